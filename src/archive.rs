@@ -0,0 +1,389 @@
+use std::io::{Read, Write};
+
+use anyhow::Context;
+use vfs::{MemoryFS, VfsPath};
+
+/// The archive formats `ingest files --archive` can mount, by file name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    TarZstd,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub fn from_file_name(file_name: &str) -> Option<ArchiveFormat> {
+        let file_name = file_name.to_lowercase();
+        if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if file_name.ends_with(".tar.zst") || file_name.ends_with(".tzst") {
+            Some(ArchiveFormat::TarZstd)
+        } else if file_name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if file_name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// An archive is loaded into memory so, unless `--max-total-bytes` is smaller,
+/// no more than this many bytes of its entries are mounted.
+pub const DEFAULT_MAX_MOUNTED_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// How much of an archive [`mount`] loads into memory.
+#[derive(Debug, Clone, Copy)]
+pub struct MountLimits {
+    // larger entries aren't loaded (`--max-content-bytes`)
+    pub max_entry_bytes: Option<u64>,
+    // once this many bytes are loaded the remaining entries aren't
+    pub max_total_bytes: u64,
+}
+
+impl Default for MountLimits {
+    fn default() -> Self {
+        MountLimits {
+            max_entry_bytes: None,
+            max_total_bytes: DEFAULT_MAX_MOUNTED_BYTES,
+        }
+    }
+}
+
+// entries escaping the archive (`../`) or absolute ones would land outside the mount
+fn enclosed_entry_path(entry_path: &str) -> Option<String> {
+    let drive_letter =
+        matches!(entry_path.as_bytes(), [drive, b':', ..] if drive.is_ascii_alphabetic());
+    if entry_path.starts_with(['/', '\\']) || drive_letter {
+        return None;
+    }
+    let components: Vec<&str> = entry_path
+        .split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect();
+    if components.is_empty() || components.contains(&"..") {
+        return None;
+    }
+    Some(components.join("/"))
+}
+
+// entries which aren't loaded are reported (as their would-be path and why)
+// rather than silently missing from the walk
+struct ArchiveLoader<'m> {
+    mount: &'m VfsPath,
+    limits: MountLimits,
+    loaded_bytes: u64,
+    unmounted: Vec<(String, String)>,
+}
+
+impl<'m> ArchiveLoader<'m> {
+    fn exceeded(&self, size: u64) -> Option<String> {
+        if let Some(max_entry_bytes) = self.limits.max_entry_bytes {
+            if size > max_entry_bytes {
+                return Some(format!(
+                    "entry is larger than --max-content-bytes {}, not loaded",
+                    max_entry_bytes
+                ));
+            }
+        }
+        if self.loaded_bytes + size > self.limits.max_total_bytes {
+            return Some(format!(
+                "archive has more than {} bytes of entries, not loaded",
+                self.limits.max_total_bytes
+            ));
+        }
+        None
+    }
+
+    fn load(&mut self, entry_path: &str, size: u64, entry: impl Read) -> anyhow::Result<()> {
+        let unmounted_path = format!("{}/{}", self.mount.as_str(), entry_path);
+        let Some(enclosed_path) = enclosed_entry_path(entry_path) else {
+            self.unmounted.push((
+                unmounted_path,
+                "entry path is absolute or escapes the archive, not loaded".to_string(),
+            ));
+            return Ok(());
+        };
+        // the recorded size is checked first but an entry is never read past the limits
+        if let Some(reason) = self.exceeded(size) {
+            self.unmounted.push((unmounted_path, reason));
+            return Ok(());
+        }
+        let max_bytes = self
+            .limits
+            .max_entry_bytes
+            .unwrap_or(u64::MAX)
+            .min(self.limits.max_total_bytes - self.loaded_bytes);
+        let mut content = Vec::new();
+        entry
+            .take(max_bytes.saturating_add(1))
+            .read_to_end(&mut content)
+            .with_context(|| format!("unable to read {}", entry_path))?;
+        if let Some(reason) = self.exceeded(content.len() as u64) {
+            self.unmounted.push((unmounted_path, reason));
+            return Ok(());
+        }
+        self.loaded_bytes += content.len() as u64;
+
+        if let Some((parent, _)) = enclosed_path.rsplit_once('/') {
+            self.mount.join(parent)?.create_dir_all()?;
+        }
+        self.mount
+            .join(&enclosed_path)?
+            .create_file()?
+            .write_all(&content)?;
+        Ok(())
+    }
+
+    fn load_tar(&mut self, reader: impl Read) -> anyhow::Result<()> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let entry = entry?;
+            // directories are created along with the files they hold, links aren't followed
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let entry_path = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+            let size = entry.size();
+            self.load(&entry_path, size, entry)?;
+        }
+        Ok(())
+    }
+
+    fn load_zip(&mut self, reader: impl Read + std::io::Seek) -> anyhow::Result<()> {
+        let mut archive = zip::ZipArchive::new(reader)?;
+        for index in 0..archive.len() {
+            let entry = archive.by_index(index)?;
+            if !entry.is_file() {
+                continue;
+            }
+            let entry_path = entry.name().to_string();
+            let size = entry.size();
+            self.load(&entry_path, size, entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads the archive at `archive_fs_path` into an in-memory VFS and returns the
+/// path its entries are mounted at, which is `archive_fs_path` itself so that an
+/// entry's URI reads like `/bundles/release.tar.gz/docs/README.md` and its nature
+/// comes from its own extension. Entries which weren't loaded (beyond `limits`,
+/// absolute or escaping the archive) are returned as their path and the reason.
+pub fn mount(
+    archive_fs_path: &str,
+    limits: MountLimits,
+) -> anyhow::Result<(VfsPath, Vec<(String, String)>)> {
+    let format = ArchiveFormat::from_file_name(archive_fs_path).ok_or_else(|| {
+        anyhow::anyhow!(
+            "[archive::mount] {} is not a .tar, .tar.gz, .tgz, .tar.zst, .tzst or .zip archive",
+            archive_fs_path
+        )
+    })?;
+    let file = std::fs::File::open(archive_fs_path)
+        .with_context(|| format!("[archive::mount] unable to open {}", archive_fs_path))?;
+    let reader = std::io::BufReader::new(file);
+
+    let mount = VfsPath::new(MemoryFS::new()).join(archive_fs_path)?;
+    mount.create_dir_all()?;
+    let mut loader = ArchiveLoader {
+        mount: &mount,
+        limits,
+        loaded_bytes: 0,
+        unmounted: Vec::new(),
+    };
+    match format {
+        ArchiveFormat::Tar => loader.load_tar(reader),
+        ArchiveFormat::TarGz => loader.load_tar(flate2::read::GzDecoder::new(reader)),
+        ArchiveFormat::TarZstd => loader.load_tar(zstd::Decoder::new(reader)?),
+        ArchiveFormat::Zip => loader.load_zip(reader),
+    }
+    .with_context(|| {
+        format!(
+            "[archive::mount] unable to load {:?} {}",
+            format, archive_fs_path
+        )
+    })?;
+    let unmounted = loader.unmounted;
+    Ok((mount, unmounted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn walked(mount: &VfsPath) -> Vec<String> {
+        let mut files: Vec<String> = mount
+            .walk_dir()
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|path| path.is_file().unwrap())
+            .map(|path| path.as_str().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_archive_format() {
+        assert_eq!(
+            ArchiveFormat::from_file_name("release-1.0.TGZ"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_file_name("bundle.tar.zst"),
+            Some(ArchiveFormat::TarZstd)
+        );
+        assert_eq!(ArchiveFormat::from_file_name("notes.gz"), None);
+    }
+
+    #[test]
+    fn test_enclosed_entry_path() {
+        assert_eq!(
+            enclosed_entry_path("./docs//README.md"),
+            Some("docs/README.md".to_string())
+        );
+        assert_eq!(enclosed_entry_path("../etc/passwd"), None);
+        assert_eq!(enclosed_entry_path("docs/../../passwd"), None);
+        assert_eq!(enclosed_entry_path("/etc/passwd"), None);
+        assert_eq!(enclosed_entry_path("C:\\Windows\\win.ini"), None);
+        assert_eq!(enclosed_entry_path("./"), None);
+    }
+
+    #[test]
+    fn test_mount_tar_gz() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_fs_path = dir.path().join("release.tar.gz");
+        let gz = flate2::write::GzEncoder::new(
+            std::fs::File::create(&archive_fs_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(gz);
+        for (entry_path, content) in [("docs/README.md", "# Release"), ("data.json", "{}")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, entry_path, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let archive_fs_path = archive_fs_path.to_string_lossy().to_string();
+        let (mounted, unmounted) = mount(&archive_fs_path, MountLimits::default()).unwrap();
+        assert!(unmounted.is_empty());
+        assert_eq!(
+            walked(&mounted),
+            vec![
+                format!("{}/data.json", archive_fs_path),
+                format!("{}/docs/README.md", archive_fs_path),
+            ]
+        );
+        assert_eq!(
+            mounted
+                .join("docs/README.md")
+                .unwrap()
+                .read_to_string()
+                .unwrap(),
+            "# Release"
+        );
+    }
+
+    #[test]
+    fn test_mount_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_fs_path = dir.path().join("bundle.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive_fs_path).unwrap());
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        writer.add_directory("src/", options).unwrap();
+        writer.start_file("src/main.rs", options).unwrap();
+        writer.write_all(b"fn main() {}").unwrap();
+        writer.finish().unwrap();
+
+        let archive_fs_path = archive_fs_path.to_string_lossy().to_string();
+        assert_eq!(
+            walked(&mount(&archive_fs_path, MountLimits::default()).unwrap().0),
+            vec![format!("{}/src/main.rs", archive_fs_path)]
+        );
+    }
+
+    fn zipped(archive_fs_path: &std::path::Path, entries: &[(&str, &str)]) -> String {
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(archive_fs_path).unwrap());
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (entry_path, content) in entries {
+            writer.start_file(*entry_path, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        archive_fs_path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_mount_unenclosed_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_fs_path = zipped(
+            &dir.path().join("evil.zip"),
+            &[
+                ("ok.txt", "ok"),
+                ("../escaped.txt", "escaped"),
+                ("/absolute.txt", "absolute"),
+            ],
+        );
+
+        let (mounted, unmounted) = mount(&archive_fs_path, MountLimits::default()).unwrap();
+        assert_eq!(
+            walked(&mounted),
+            vec![format!("{}/ok.txt", archive_fs_path)]
+        );
+        let unmounted_paths: Vec<&str> = unmounted.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            unmounted_paths,
+            vec![
+                format!("{}/../escaped.txt", archive_fs_path),
+                format!("{}//absolute.txt", archive_fs_path),
+            ]
+        );
+        assert!(unmounted[0].1.contains("escapes the archive"));
+    }
+
+    #[test]
+    fn test_mount_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_fs_path = zipped(
+            &dir.path().join("large.zip"),
+            &[
+                ("small.txt", "12345"),
+                ("large.txt", "1234567890"),
+                ("medium.txt", "1234567"),
+                ("tiny.txt", "1"),
+            ],
+        );
+
+        let (mounted, unmounted) = mount(
+            &archive_fs_path,
+            MountLimits {
+                max_entry_bytes: Some(8),
+                max_total_bytes: 12,
+            },
+        )
+        .unwrap();
+        // `large.txt` is over the entry limit and `medium.txt` would exceed the total
+        assert_eq!(
+            walked(&mounted),
+            vec![
+                format!("{}/small.txt", archive_fs_path),
+                format!("{}/tiny.txt", archive_fs_path),
+            ]
+        );
+        assert_eq!(unmounted.len(), 2);
+        assert_eq!(unmounted[0].0, format!("{}/large.txt", archive_fs_path));
+        assert!(unmounted[0].1.contains("--max-content-bytes 8"));
+        assert_eq!(unmounted[1].0, format!("{}/medium.txt", archive_fs_path));
+        assert!(unmounted[1].1.contains("more than 12 bytes"));
+    }
+}
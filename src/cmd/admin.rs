@@ -1,10 +1,178 @@
 use anyhow::Context;
+use indoc::indoc;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rusqlite::OptionalExtension;
 use serde_rusqlite::from_rows;
+use sha1::{Digest, Sha1};
 
 use super::AdminCommands;
+use super::AdminSessionsCommands;
 use super::AdminTestCommands;
 use crate::persist::*;
-use crate::resource::EncounterableResourcePathClassifier;
+use crate::resource::{
+    resolved_sample_seed, EncounterableResourcePathClassifier, EncounterableResourcePathRules,
+    PathRuleAction, ResourcesCollection, Sample,
+};
+
+// each garbage collection candidate is (description, SQL which returns rows and
+// bytes that would be reclaimed, SQL which reclaims them); soft-deleted resources
+// keep their history rows but lose their content, dependents of them are removed
+// as is content (chunks or content moved by `admin merge --dedupe-content`) which
+// no live resource refers to any more
+const GC_CANDIDATES: &[(&str, &str, &str)] = &[
+    (
+        "soft-deleted uniform_resource content",
+        r"SELECT COUNT(*), COALESCE(SUM(length(content)), 0)
+            FROM uniform_resource
+           WHERE deleted_at IS NOT NULL AND content IS NOT NULL",
+        r"UPDATE uniform_resource SET content = NULL
+           WHERE deleted_at IS NOT NULL AND content IS NOT NULL",
+    ),
+    (
+        "orphaned uniform_resource_transform",
+        r"SELECT COUNT(*), COALESCE(SUM(length(content)), 0)
+            FROM uniform_resource_transform urt
+           WHERE urt.deleted_at IS NOT NULL
+              OR NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.uniform_resource_id = urt.uniform_resource_id AND ur.deleted_at IS NULL)",
+        r"DELETE FROM uniform_resource_transform
+           WHERE deleted_at IS NOT NULL
+              OR NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.uniform_resource_id = uniform_resource_transform.uniform_resource_id AND ur.deleted_at IS NULL)",
+    ),
+    (
+        "orphaned uniform_resource_email",
+        r"SELECT COUNT(*), COALESCE(SUM(length(body_text)), 0)
+            FROM uniform_resource_email ure
+           WHERE ure.deleted_at IS NOT NULL
+              OR NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.uniform_resource_id = ure.uniform_resource_id AND ur.deleted_at IS NULL)",
+        r"DELETE FROM uniform_resource_email
+           WHERE deleted_at IS NOT NULL
+              OR NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.uniform_resource_id = uniform_resource_email.uniform_resource_id AND ur.deleted_at IS NULL)",
+    ),
+    (
+        "orphaned uniform_resource_sqlite_db",
+        r"SELECT COUNT(*), COALESCE(SUM(length(tables)), 0)
+            FROM uniform_resource_sqlite_db ursd
+           WHERE ursd.deleted_at IS NOT NULL
+              OR NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.uniform_resource_id = ursd.uniform_resource_id AND ur.deleted_at IS NULL)",
+        r"DELETE FROM uniform_resource_sqlite_db
+           WHERE deleted_at IS NOT NULL
+              OR NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.uniform_resource_id = uniform_resource_sqlite_db.uniform_resource_id AND ur.deleted_at IS NULL)",
+    ),
+    (
+        "orphaned uniform_resource_content_device",
+        r"SELECT COUNT(*), 0
+            FROM uniform_resource_content_device urcd
+           WHERE NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.uniform_resource_id = urcd.uniform_resource_id AND ur.deleted_at IS NULL)",
+        r"DELETE FROM uniform_resource_content_device
+           WHERE NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.uniform_resource_id = uniform_resource_content_device.uniform_resource_id AND ur.deleted_at IS NULL)",
+    ),
+    (
+        "orphaned uniform_resource_content",
+        r"SELECT COUNT(*), COALESCE(SUM(length(content)), 0)
+            FROM uniform_resource_content urc
+           WHERE NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.content_digest = urc.content_digest AND ur.deleted_at IS NULL)",
+        r"DELETE FROM uniform_resource_content
+           WHERE NOT EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.content_digest = uniform_resource_content.content_digest AND ur.deleted_at IS NULL)",
+    ),
+    // chunked content (`--chunk-content`) is the JSON list of its chunks' ids, in
+    // `uniform_resource` or, once deduplicated, in `uniform_resource_content`
+    (
+        "orphaned content_chunk",
+        r"SELECT COUNT(*), COALESCE(SUM(length(content)), 0)
+            FROM content_chunk
+           WHERE content_chunk_id NOT IN (SELECT chunk.value FROM uniform_resource ur, json_each(CAST(ur.content AS TEXT)) chunk WHERE ur.content_codec = 'fastcdc' AND ur.deleted_at IS NULL)
+             AND content_chunk_id NOT IN (SELECT chunk.value FROM uniform_resource_content urc, json_each(CAST(urc.content AS TEXT)) chunk WHERE urc.content_codec = 'fastcdc' AND EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.content_digest = urc.content_digest AND ur.deleted_at IS NULL))",
+        r"DELETE FROM content_chunk
+           WHERE content_chunk_id NOT IN (SELECT chunk.value FROM uniform_resource ur, json_each(CAST(ur.content AS TEXT)) chunk WHERE ur.content_codec = 'fastcdc' AND ur.deleted_at IS NULL)
+             AND content_chunk_id NOT IN (SELECT chunk.value FROM uniform_resource_content urc, json_each(CAST(urc.content AS TEXT)) chunk WHERE urc.content_codec = 'fastcdc' AND EXISTS (SELECT 1 FROM uniform_resource ur WHERE ur.content_digest = urc.content_digest AND ur.deleted_at IS NULL))",
+    ),
+];
+
+// Content is moved (not copied) out of `uniform_resource` into a single row per
+// digest (see `v017_once_urContentDedupeDDL`), the oldest resource's content and
+// codec winning; `uniform_resource_content_device` keeps which resources share it.
+// `content_digest` isn't always of the stored bytes (`--normalize-json-before-hash`
+// and `--content-text-trim` hash something else) so only resources storing exactly
+// the winning bytes are moved, the rest keep their own. Resources merged again are
+// ignored by `INSERT OR IGNORE` and already moved content is NULL so re-running is
+// a no-op.
+pub(crate) const MERGE_DEDUPE_CONTENT_SQL: &str = indoc! {"
+    INSERT OR IGNORE INTO uniform_resource_content (content_digest, content, content_codec, size_bytes)
+        SELECT content_digest, content, content_codec, size_bytes
+          FROM uniform_resource
+         WHERE content IS NOT NULL AND content_digest != '-'
+      ORDER BY content_digest, uniform_resource_id;
+    INSERT OR IGNORE INTO uniform_resource_content_device (content_digest, device_id, uniform_resource_id)
+        SELECT ur.content_digest, ur.device_id, ur.uniform_resource_id
+          FROM uniform_resource ur
+          JOIN uniform_resource_content urc ON urc.content_digest = ur.content_digest
+         WHERE ur.content IS NOT NULL AND ur.content_digest != '-'
+           AND ur.content = urc.content AND ur.content_codec IS urc.content_codec
+      ORDER BY ur.content_digest, ur.device_id, ur.uniform_resource_id;
+    UPDATE uniform_resource SET content = NULL
+     WHERE content IS NOT NULL
+       AND uniform_resource_id IN (SELECT uniform_resource_id FROM uniform_resource_content_device);
+"};
+
+// the latest version of each file this device ingested (by host name, since each
+// ingest may upsert a new device row); captured executables' output, URLs and
+// mail aren't files and content which was never hashed can't be verified
+const VERIFY_RESOURCES_SQL: &str = indoc! {"
+    SELECT ur.uri, ur.content_digest
+      FROM uniform_resource ur
+      JOIN device d ON d.device_id = ur.device_id
+     WHERE d.name = ?
+       AND ur.deleted_at IS NULL
+       AND ur.ingest_fs_path_id IS NOT NULL
+       AND ur.content_digest != '-'
+       AND ur.uri NOT LIKE '%://%'
+       AND ur.rowid = (SELECT MAX(latest.rowid) FROM uniform_resource latest WHERE latest.device_id = ur.device_id AND latest.uri = ur.uri)
+       AND NOT EXISTS (SELECT 1
+                         FROM ur_ingest_session_fs_path_entry entry
+                        WHERE entry.uniform_resource_id = ur.uniform_resource_id
+                          AND entry.captured_executable IS NOT NULL)
+  ORDER BY ur.uri"};
+
+// soft-deleted resources are never exported, of the others the last ingested wins
+const EXPORT_RESOURCE_SQL: &str = indoc! {"
+    SELECT uniform_resource_id, content_digest, json_extract(elaboration, '$.encoding')
+      FROM uniform_resource
+     WHERE uri = ?
+       AND deleted_at IS NULL
+  ORDER BY rowid DESC
+     LIMIT 1"};
+
+fn merge_source_digest(db_path: &str) -> anyhow::Result<String> {
+    let mut file = std::fs::File::open(db_path)
+        .with_context(|| format!("[AdminCommands::merge] unable to open {}", db_path))?;
+    let mut hasher = Sha1::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = std::io::Read::read(&mut file, &mut buffer)
+            .with_context(|| format!("[AdminCommands::merge] unable to read {}", db_path))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// sessions ingested before counts were recorded count the resources they stored
+pub(crate) const SESSIONS_SQL: &str = indoc! {"
+    SELECT s.ur_ingest_session_id,
+           d.name,
+           s.ingest_started_at,
+           COALESCE(s.ingest_finished_at, ''),
+           COALESCE(json_extract(s.elaboration, '$.provenance.surveilr_version'), ''),
+           COALESCE(json_extract(s.elaboration, '$.counts.uniform_resources'),
+                    (SELECT COUNT(*) FROM uniform_resource ur WHERE ur.ingest_session_id = s.ur_ingest_session_id)),
+           COALESCE(json_extract(s.elaboration, '$.provenance.args'), '')
+      FROM ur_ingest_session s
+      JOIN device d ON d.device_id = s.device_id
+  ORDER BY s.ingest_started_at DESC, s.ur_ingest_session_id DESC
+     LIMIT ?"};
 
 // Implement methods for `AdminCommands`, ensure that whether the commands
 // are called from CLI or natively within Rust, all the calls remain ergonomic.
@@ -16,14 +184,24 @@ impl AdminCommands {
                 state_db_init_sql,
                 remove_existing_first,
                 with_device,
-            } => self.init(
-                cli,
-                state_db_fs_path,
-                state_db_init_sql,
-                *remove_existing_first,
-                *with_device,
-                None,
-            ),
+                dry_run,
+                print_schema,
+            } => {
+                if *print_schema {
+                    self.init_print_schema()
+                } else if *dry_run {
+                    self.init_dry_run(state_db_init_sql)
+                } else {
+                    self.init(
+                        cli,
+                        state_db_fs_path,
+                        state_db_init_sql,
+                        *remove_existing_first,
+                        *with_device,
+                        None,
+                    )
+                }
+            }
             AdminCommands::Merge {
                 state_db_fs_path,
                 state_db_init_sql,
@@ -31,6 +209,10 @@ impl AdminCommands {
                 ignore_candidates,
                 remove_existing_first,
                 sql_only,
+                dedupe_content,
+                skip_incompatible,
+                force,
+                append,
             } => self.merge(
                 cli,
                 state_db_fs_path,
@@ -39,9 +221,75 @@ impl AdminCommands {
                 ignore_candidates,
                 *remove_existing_first,
                 *sql_only,
+                *dedupe_content,
+                *skip_incompatible,
+                *force,
+                *append,
+            ),
+            AdminCommands::Migrate {
+                state_db_fs_path,
+                dry_run,
+            } => self.migrate(cli, state_db_fs_path, *dry_run),
+            AdminCommands::Gc {
+                state_db_fs_path,
+                state_db_init_sql,
+                dry_run,
+                vacuum,
+            } => self.gc(cli, state_db_fs_path, state_db_init_sql, *dry_run, *vacuum),
+            AdminCommands::TrainDict {
+                state_db_fs_path,
+                state_db_init_sql,
+                nature,
+                max_samples,
+                sample_seed,
+                dict_size,
+                level,
+            } => self.train_dict(
+                cli,
+                state_db_fs_path,
+                state_db_init_sql,
+                nature,
+                *max_samples,
+                *sample_seed,
+                *dict_size,
+                *level,
+            ),
+            AdminCommands::Verify {
+                state_db_fs_path,
+                json,
+                sample,
+                sample_seed,
+            } => self.verify(
+                cli,
+                state_db_fs_path,
+                *json,
+                sample.map(|percent| Sample::new(percent, *sample_seed)),
+            ),
+            AdminCommands::ExportResource {
+                state_db_fs_path,
+                uri,
+                encoding,
+                out,
+            } => self.export_resource(cli, state_db_fs_path, uri, *encoding, out.as_deref()),
+            AdminCommands::ExplainPath {
+                fs_path,
+                state_db_fs_path,
+                path_rules_json,
+                ignore_fs_entry,
+                reinclude_fs_entry,
+                json,
+            } => self.explain_path(
+                cli,
+                fs_path,
+                state_db_fs_path,
+                path_rules_json.as_deref(),
+                ignore_fs_entry,
+                reinclude_fs_entry,
+                *json,
             ),
             AdminCommands::CliHelpMd => self.cli_help_markdown(),
             AdminCommands::Test(test_args) => test_args.command.execute(cli, args, test_args),
+            AdminCommands::Sessions(sessions_args) => sessions_args.command.execute(cli),
         }
     }
 
@@ -54,9 +302,7 @@ impl AdminCommands {
         with_device: bool,
         sql_script: Option<&str>,
     ) -> anyhow::Result<()> {
-        if cli.debug > 0 {
-            println!("Initializing {}", db_fs_path);
-        }
+        tracing::info!("Initializing {}", db_fs_path);
         if remove_existing_first {
             match std::fs::remove_file(db_fs_path) {
                 Ok(_) => {}
@@ -65,7 +311,12 @@ impl AdminCommands {
             }
         }
 
-        let mut dbc = DbConn::new(db_fs_path, cli.debug)
+        // initializing is migrating an existing database as needed
+        let pragmas = DbPragmas {
+            auto_migrate: true,
+            ..cli.db_pragmas()
+        };
+        let dbc = DbConn::new(db_fs_path, cli.debug, &pragmas)
             .with_context(|| format!("[AdminCommands::init] SQLite database {}", db_fs_path))?;
         let tx = dbc
             .init(Some(db_init_sql_globs))
@@ -82,12 +333,12 @@ impl AdminCommands {
                     )
                 })?;
 
-            if cli.debug > 0 {
-                println!(
-                    "Initialized {} with device {} ({})",
-                    db_fs_path, device_name, device_id
-                );
-            }
+            tracing::info!(
+                "Initialized {} with device {} ({})",
+                db_fs_path,
+                device_name,
+                device_id
+            );
         }
 
         let result = match sql_script {
@@ -102,6 +353,51 @@ impl AdminCommands {
         result
     }
 
+    // the migrations are those of a new database since the existing one (if
+    // any) isn't opened; the bootstrap SQL is loaded into memory to find them
+    fn init_dry_run(&self, db_init_sql_globs: &[String]) -> anyhow::Result<()> {
+        let conn = rusqlite::Connection::open_in_memory()
+            .with_context(|| "[AdminCommands::init_dry_run] in-memory SQLite database")?;
+        prepare_conn(&conn)
+            .with_context(|| "[AdminCommands::init_dry_run] prepare in-memory database")?;
+        bootstrap_ddl(&conn).with_context(|| "[AdminCommands::init_dry_run] bootstrap DDL")?;
+
+        println!("-- bootstrap.sql\n{}", BOOTSTRAP_SQL);
+        migratable_notebook_cells_uniq_all(
+            &conn,
+            |_index, notebook_name, cell_name, sql, _hash, _id: String| {
+                println!("-- {notebook_name}.{cell_name}\n{sql}\n");
+                Ok(())
+            },
+        )
+        .with_context(|| "[AdminCommands::init_dry_run] migratable notebook cells")?;
+
+        for path in matched_sql_batch_paths(&[".".to_string()], db_init_sql_globs) {
+            if is_capturable_sql_batch(&path)? {
+                println!(
+                    "-- {}: SQL is the output of this executable (not executed in a dry run)\n",
+                    path.display()
+                );
+            } else {
+                let sql = std::fs::read_to_string(&path).with_context(|| {
+                    format!("[AdminCommands::init_dry_run] reading {}", path.display())
+                })?;
+                println!("-- {}\n{}\n", path.display(), sql);
+            }
+        }
+        Ok(())
+    }
+
+    // the version identifies the schema since it's embedded at build time
+    fn init_print_schema(&self) -> anyhow::Result<()> {
+        println!(
+            "-- bootstrap.sql embedded in surveilr {}\n{}",
+            env!("CARGO_PKG_VERSION"),
+            BOOTSTRAP_SQL
+        );
+        Ok(())
+    }
+
     fn cli_help_markdown(&self) -> anyhow::Result<()> {
         clap_markdown::print_help_markdown::<super::Cli>();
         Ok(())
@@ -117,6 +413,10 @@ impl AdminCommands {
         ignore_candidates: &[String],
         remove_existing_first: bool,
         sql_only: bool,
+        dedupe_content: bool,
+        skip_incompatible: bool,
+        force: bool,
+        append: bool,
     ) -> Result<(), anyhow::Error> {
         let mut ignore_candidates = ignore_candidates.to_vec();
         ignore_candidates.push(state_db_fs_path.clone());
@@ -158,6 +458,59 @@ impl AdminCommands {
             }
         }
 
+        // candidates are known by their digest so that a copy under another
+        // name isn't merged twice
+        let merged = match append {
+            true => self.merged_sources(cli, state_db_fs_path)?,
+            false => std::collections::HashSet::new(),
+        };
+        let mut source_digests = std::collections::HashMap::new();
+        let mut already_merged = Vec::new();
+        for db_path in &db_paths {
+            let digest = merge_source_digest(db_path)?;
+            if merged.contains(&digest) || source_digests.values().any(|d| d == &digest) {
+                already_merged.push(db_path.clone());
+            } else {
+                source_digests.insert(db_path.clone(), digest);
+            }
+        }
+        if !already_merged.is_empty() {
+            eprintln!(
+                "[AdminCommands::merge] skipped candidates already merged into {}:\n  {}",
+                state_db_fs_path,
+                already_merged.join("\n  ")
+            );
+            db_paths.retain(|db_path| source_digests.contains_key(db_path));
+        }
+
+        // checked before the target is touched (or even removed)
+        let incompatible = self.merge_incompatible(cli, &db_paths)?;
+        if !incompatible.is_empty() {
+            let offenders = incompatible
+                .iter()
+                .map(|(db_path, reason)| format!("{}: {}", db_path, reason))
+                .collect::<Vec<_>>()
+                .join("\n  ");
+            if force {
+                eprintln!(
+                    "[AdminCommands::merge] merging incompatible candidates with --force:\n  {}",
+                    offenders
+                );
+            } else if skip_incompatible {
+                eprintln!(
+                    "[AdminCommands::merge] skipped incompatible candidates:\n  {}",
+                    offenders
+                );
+                db_paths.retain(|db_path| !incompatible.iter().any(|(path, _)| path == db_path));
+            } else {
+                anyhow::bail!(
+                    "[AdminCommands::merge] candidates incompatible with {}, pass --skip-incompatible to merge the others or --force to merge them anyway:\n  {}",
+                    state_db_fs_path,
+                    offenders
+                );
+            }
+        }
+
         let mut sql_script = String::from("");
         for db_path in &db_paths {
             let db_path_sql_identifier = crate::format::to_sql_friendly_identifier(db_path);
@@ -196,6 +549,24 @@ impl AdminCommands {
             sql_script.push('\n');
         }
 
+        // `merged_source` is created by `v018_once_mergedSourceDDL`
+        for db_path in &db_paths {
+            sql_script.push_str(
+                format!(
+                    "INSERT OR IGNORE INTO merged_source (source_digest, source_fs_path) VALUES ('{}', '{}');\n",
+                    source_digests[db_path],
+                    db_path.replace('\'', "''")
+                )
+                .as_str(),
+            );
+        }
+        sql_script.push('\n');
+
+        if dedupe_content {
+            sql_script.push_str(MERGE_DEDUPE_CONTENT_SQL);
+            sql_script.push('\n');
+        }
+
         for db_path in &db_paths {
             let db_path_sql_identifier = crate::format::to_sql_friendly_identifier(db_path);
             sql_script.push_str(format!("DETACH DATABASE {};\n", db_path_sql_identifier).as_str());
@@ -215,6 +586,689 @@ impl AdminCommands {
             )
         }
     }
+
+    // the digests of the candidates merged into an existing target before, which
+    // merged nothing yet if it doesn't exist or predates `merged_source`
+    fn merged_sources(
+        &self,
+        cli: &super::Cli,
+        state_db_fs_path: &str,
+    ) -> anyhow::Result<std::collections::HashSet<String>> {
+        let mut merged = std::collections::HashSet::new();
+        if !std::path::Path::new(state_db_fs_path).exists() {
+            return Ok(merged);
+        }
+        let dbc = DbConn::open(state_db_fs_path, cli.debug).with_context(|| {
+            format!(
+                "[AdminCommands::merged_sources] SQLite database {}",
+                state_db_fs_path
+            )
+        })?;
+        let has_merged_source: bool = dbc.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'merged_source')",
+            [],
+            |row| row.get(0),
+        )?;
+        if has_merged_source {
+            let mut stmt = dbc
+                .conn
+                .prepare("SELECT source_digest FROM merged_source")?;
+            for digest in stmt.query_map([], |row| row.get::<_, String>(0))? {
+                merged.insert(digest?);
+            }
+        }
+        Ok(merged)
+    }
+
+    // the merged tables are copied with `SELECT *` so a candidate must have
+    // applied exactly the migrations a new RSSD would have, no more and no less
+    fn merge_incompatible(
+        &self,
+        cli: &super::Cli,
+        db_paths: &[String],
+    ) -> anyhow::Result<Vec<(String, String)>> {
+        let expected = once_migrations(&migrated_in_memory_conn()?, false)
+            .with_context(|| "[AdminCommands::merge_incompatible] known migrations")?;
+        let mut incompatible = Vec::new();
+        for db_path in db_paths {
+            let applied = DbConn::open(db_path, cli.debug)
+                .and_then(|dbc| once_migrations(&dbc.conn, true).map_err(anyhow::Error::from));
+            let applied = match applied {
+                Ok(applied) => applied,
+                Err(err) => {
+                    incompatible.push((
+                        db_path.clone(),
+                        format!("no migration state, not an RSSD? ({})", err),
+                    ));
+                    continue;
+                }
+            };
+            let missing: Vec<_> = expected.difference(&applied).cloned().collect();
+            let unknown: Vec<_> = applied.difference(&expected).cloned().collect();
+            let mut reasons = Vec::new();
+            if !missing.is_empty() {
+                reasons.push(format!("older schema, missing {}", missing.join(", ")));
+            }
+            if !unknown.is_empty() {
+                reasons.push(format!("newer schema, unknown {}", unknown.join(", ")));
+            }
+            if !reasons.is_empty() {
+                incompatible.push((db_path.clone(), reasons.join("; ")));
+            }
+        }
+        Ok(incompatible)
+    }
+
+    fn export_resource(
+        &self,
+        cli: &super::Cli,
+        state_db_fs_path: &str,
+        uri: &str,
+        encoding: Option<super::ExportEncoding>,
+        out: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let dbc = DbConn::open(state_db_fs_path, cli.debug).with_context(|| {
+            format!(
+                "[AdminCommands::export_resource] SQLite database {}",
+                state_db_fs_path
+            )
+        })?;
+        let (ur_id, content_digest, text_encoding): (String, String, Option<String>) = dbc
+            .conn
+            .query_row(EXPORT_RESOURCE_SQL, [uri], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .optional()
+            .with_context(|| format!("[AdminCommands::export_resource] reading {}", uri))?
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "[AdminCommands::export_resource] no uniform resource {} in {}",
+                    uri,
+                    state_db_fs_path
+                )
+            })?;
+        let content = uniform_resource_content(&dbc.conn, &ur_id)?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "[AdminCommands::export_resource] {} has no stored content in {}",
+                uri,
+                state_db_fs_path
+            )
+        })?;
+
+        // trimmed text is stored with the raw text's digest, its own digest is
+        // kept as a transform; lossily decoded text can't match any digest
+        let algorithm = crate::resource::DigestAlgorithm::from_digest(&content_digest);
+        let digest = algorithm.digest(&content);
+        if text_encoding.as_deref() == Some("utf-8-lossy") {
+            eprintln!(
+                "[AdminCommands::export_resource] {} was lossily decoded as UTF-8, its digest {} can't be checked",
+                uri, content_digest
+            );
+        } else if digest != content_digest
+            && !dbc
+                .conn
+                .query_row(
+                    r"SELECT EXISTS (SELECT 1
+                                       FROM uniform_resource_transform
+                                      WHERE uniform_resource_id = ?
+                                        AND nature = 'text-trimmed'
+                                        AND content_digest = ?)",
+                    [&ur_id, &digest],
+                    |row| row.get::<_, bool>(0),
+                )
+                .with_context(|| {
+                    format!(
+                        "[AdminCommands::export_resource] reading transforms of {}",
+                        uri
+                    )
+                })?
+        {
+            anyhow::bail!(
+                "[AdminCommands::export_resource] stored content of {} doesn't match its digest {} (it's {})",
+                uri,
+                content_digest,
+                digest
+            );
+        }
+
+        let binary =
+            text_encoding.as_deref() == Some("binary") || std::str::from_utf8(&content).is_err();
+        let exported = match encoding.unwrap_or(if binary {
+            super::ExportEncoding::Base64
+        } else {
+            super::ExportEncoding::Raw
+        }) {
+            super::ExportEncoding::Raw => content,
+            super::ExportEncoding::Base64 => {
+                use base64::Engine;
+                let mut text = base64::engine::general_purpose::STANDARD.encode(&content);
+                text.push('\n');
+                text.into_bytes()
+            }
+            super::ExportEncoding::Base85 => {
+                let mut text = crate::format::to_base85(&content);
+                text.push('\n');
+                text.into_bytes()
+            }
+        };
+        match out {
+            Some(out) => std::fs::write(out, &exported)
+                .with_context(|| format!("[AdminCommands::export_resource] writing {}", out))?,
+            None => {
+                use std::io::Write;
+                std::io::stdout()
+                    .write_all(&exported)
+                    .with_context(|| "[AdminCommands::export_resource] writing to STDOUT")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn migrate(
+        &self,
+        cli: &super::Cli,
+        state_db_fs_path: &String,
+        dry_run: bool,
+    ) -> anyhow::Result<()> {
+        // unlike `init` this never creates a database
+        if !std::path::Path::new(state_db_fs_path).exists() {
+            anyhow::bail!(
+                "[AdminCommands::migrate] SQLite database {} does not exist",
+                state_db_fs_path
+            );
+        }
+        let pragmas = DbPragmas {
+            auto_migrate: true,
+            ..cli.db_pragmas()
+        };
+        let mut dbc = DbConn::new(state_db_fs_path, cli.debug, &pragmas).with_context(|| {
+            format!(
+                "[AdminCommands::migrate] SQLite database {}",
+                state_db_fs_path
+            )
+        })?;
+        let db_version: u32 = dbc
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .with_context(|| {
+                format!("[AdminCommands::migrate] user_version {}", state_db_fs_path)
+            })?;
+        let pending = {
+            // the bootstrap SQL pending_migrations executes isn't kept
+            let tx = dbc.conn.transaction()?;
+            pending_migrations(&tx).with_context(|| {
+                format!(
+                    "[AdminCommands::migrate] pending_migrations {}",
+                    state_db_fs_path
+                )
+            })?
+        };
+        if pending.is_empty() && db_version >= schema_version() {
+            println!(
+                "{} is at schema version {}, no migrations are pending",
+                state_db_fs_path, db_version
+            );
+            return Ok(());
+        }
+        println!(
+            "{} is at schema version {}, surveilr expects {}; pending migrations:",
+            state_db_fs_path,
+            db_version,
+            schema_version()
+        );
+        for cell_name in &pending {
+            println!("{cell_name}");
+        }
+        if dry_run {
+            return Ok(());
+        }
+
+        let tx = dbc.init(None).with_context(|| {
+            format!(
+                "[AdminCommands::migrate] init transaction {}",
+                state_db_fs_path
+            )
+        })?;
+        tx.commit().with_context(|| {
+            format!(
+                "[AdminCommands::migrate] transaction commit {}",
+                state_db_fs_path
+            )
+        })?;
+        let migrated_version: u32 = dbc
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+        println!(
+            "{} migrated from schema version {} to {}",
+            state_db_fs_path, db_version, migrated_version
+        );
+        Ok(())
+    }
+
+    fn gc(
+        &self,
+        cli: &super::Cli,
+        state_db_fs_path: &String,
+        state_db_init_sql: &[String],
+        dry_run: bool,
+        vacuum: bool,
+    ) -> anyhow::Result<()> {
+        let dbc = DbConn::new(state_db_fs_path, cli.debug, &cli.db_pragmas())
+            .with_context(|| format!("[AdminCommands::gc] SQLite database {}", state_db_fs_path))?;
+        let tx = dbc.init(Some(state_db_init_sql)).with_context(|| {
+            format!("[AdminCommands::gc] init transaction {}", state_db_fs_path)
+        })?;
+
+        let mut reclaimed_bytes: i64 = 0;
+        for (description, candidates_sql, reclaim_sql) in GC_CANDIDATES {
+            let (rows, bytes): (i64, i64) = tx
+                .query_row(candidates_sql, [], |row| Ok((row.get(0)?, row.get(1)?)))
+                .with_context(|| format!("[AdminCommands::gc] {}", candidates_sql))?;
+            if rows > 0 && !dry_run {
+                tx.execute(reclaim_sql, [])
+                    .with_context(|| format!("[AdminCommands::gc] {}", reclaim_sql))?;
+            }
+            reclaimed_bytes += bytes;
+            println!("{}: {} rows, {} bytes", description, rows, bytes);
+        }
+
+        if dry_run {
+            println!("Would reclaim {} bytes (dry run)", reclaimed_bytes);
+            return Ok(()); // dropping `tx` rolls back
+        }
+        tx.commit().with_context(|| {
+            format!(
+                "[AdminCommands::gc] transaction commit {}",
+                state_db_fs_path
+            )
+        })?;
+        println!("Reclaimed {} bytes", reclaimed_bytes);
+
+        if vacuum {
+            dbc.conn
+                .execute_batch("VACUUM")
+                .with_context(|| format!("[AdminCommands::gc] VACUUM {}", state_db_fs_path))?;
+        }
+        Ok(())
+    }
+
+    // the digest is streamed with the algorithm the stored digest was computed
+    // with, the same way ingestion's digest supplier computes it
+    // with a sample only the files it includes are re-hashed (see `resource::Sample`)
+    fn verify(
+        &self,
+        cli: &super::Cli,
+        state_db_fs_path: &str,
+        json: bool,
+        sample: Option<Sample>,
+    ) -> anyhow::Result<()> {
+        let dbc = DbConn::open(state_db_fs_path, cli.debug).with_context(|| {
+            format!(
+                "[AdminCommands::verify] SQLite database {}",
+                state_db_fs_path
+            )
+        })?;
+        let mut stmt = dbc
+            .conn
+            .prepare(VERIFY_RESOURCES_SQL)
+            .with_context(|| format!("[AdminCommands::verify] {}", VERIFY_RESOURCES_SQL))?;
+        let stored = stmt
+            .query_map([&crate::DEVICE.name], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .with_context(|| format!("[AdminCommands::verify] reading {}", state_db_fs_path))?;
+
+        let (mut matched, mut changed, mut missing) = (0, 0, 0);
+        let mut drift: Vec<Vec<String>> = Vec::new();
+        let sampled = stored
+            .into_iter()
+            .filter(|(uri, _)| sample.map_or(true, |sample| sample.includes(uri)));
+        for (uri, stored_digest) in sampled {
+            let algorithm = crate::resource::DigestAlgorithm::from_digest(&stored_digest);
+            let current_digest = match std::fs::File::open(&uri) {
+                Ok(file) => Some(
+                    algorithm
+                        .digest_reader(file)
+                        .with_context(|| format!("[AdminCommands::verify] reading {}", uri))?,
+                ),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("[AdminCommands::verify] opening {}", uri))
+                }
+            };
+            let status = match &current_digest {
+                Some(digest) if *digest == stored_digest => {
+                    matched += 1;
+                    continue;
+                }
+                Some(_) => {
+                    changed += 1;
+                    "CHANGED"
+                }
+                None => {
+                    missing += 1;
+                    "MISSING"
+                }
+            };
+            drift.push(vec![
+                uri,
+                status.to_string(),
+                stored_digest,
+                current_digest.unwrap_or_default(),
+            ]);
+        }
+
+        let output = if json {
+            super::OutputFormat::Json
+        } else {
+            cli.output
+        };
+        let mut sections = vec![
+            crate::format::OutputSection {
+                name: "summary",
+                caption: "==> verified resources",
+                headers: &["Match", "Changed", "Missing"],
+                rows: vec![vec![
+                    matched.to_string(),
+                    changed.to_string(),
+                    missing.to_string(),
+                ]],
+            },
+            crate::format::OutputSection {
+                name: "drift",
+                caption: "==> drifted resources",
+                headers: &["URI", "Status", "Stored Digest", "Current Digest"],
+                rows: drift,
+            },
+        ];
+        // the seed is reported so a random sample can be verified again with `--sample-seed`
+        if let Some(sample) = sample {
+            sections.push(crate::format::OutputSection {
+                name: "sample",
+                caption: "==> sampled resources",
+                headers: &["Percent", "Seed"],
+                rows: vec![vec![sample.percent.to_string(), sample.seed.to_string()]],
+            });
+        }
+        println!(
+            "{}",
+            crate::format::as_output_sections_text(output, &sections)
+        );
+        if changed + missing > 0 {
+            anyhow::bail!(
+                "[AdminCommands::verify] {} changed and {} missing file(s) in {}",
+                changed,
+                missing,
+                state_db_fs_path
+            )
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn explain_path(
+        &self,
+        cli: &super::Cli,
+        fs_path: &str,
+        state_db_fs_path: &str,
+        path_rules_json: Option<&str>,
+        ignore_fs_entry: &[String],
+        reinclude_fs_entry: &[String],
+        json: bool,
+    ) -> anyhow::Result<()> {
+        let rules = match path_rules_json {
+            Some(rules_fs_path) => {
+                let json_text = std::fs::read_to_string(rules_fs_path).with_context(|| {
+                    format!("[AdminCommands::explain_path] unable to read --path-rules-json {rules_fs_path}")
+                })?;
+                EncounterableResourcePathRules::from_json_text(&json_text).with_context(|| {
+                    format!(
+                        "[AdminCommands::explain_path] invalid --path-rules-json {rules_fs_path}"
+                    )
+                })?
+            }
+            // the same built-in rules a new RSSD would be bootstrapped with
+            None if !std::path::Path::new(state_db_fs_path).exists() => {
+                EncounterableResourcePathRules::default()
+            }
+            None => {
+                let dbc = DbConn::open(state_db_fs_path, cli.debug).with_context(|| {
+                    format!(
+                        "[AdminCommands::explain_path] SQLite database {}",
+                        state_db_fs_path
+                    )
+                })?;
+                EncounterableResourcePathRules::default_from_conn(&dbc.conn).with_context(|| {
+                    format!(
+                        "[AdminCommands::explain_path] classification rules in {}",
+                        state_db_fs_path
+                    )
+                })?
+            }
+        };
+        let mut classifier = EncounterableResourcePathClassifier::from_path_rules(rules)
+            .with_context(|| "[AdminCommands::explain_path] invalid path rules")?;
+        for (action, patterns) in [
+            (PathRuleAction::Exclude, ignore_fs_entry),
+            (PathRuleAction::Include, reinclude_fs_entry),
+        ] {
+            for pattern in patterns {
+                classifier.add_path_rule(action, pattern).with_context(|| {
+                    format!("[AdminCommands::explain_path] invalid path rule {pattern}")
+                })?;
+            }
+        }
+
+        // canonical like the walkers' paths when it exists, as given otherwise
+        let fs_path = std::fs::canonicalize(fs_path)
+            .map(|canonical| canonical.to_string_lossy().to_string())
+            .unwrap_or_else(|_| fs_path.to_string());
+        let explanation =
+            ResourcesCollection::new(vec![], &classifier, &None).explain_path(&fs_path);
+        if json || cli.output == super::OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&explanation)?);
+            return Ok(());
+        }
+
+        println!("path: {}", explanation.path);
+        match &explanation.path_rule {
+            Some((PathRuleAction::Exclude, regex)) => {
+                println!("1. excluded by path rule `{regex}`")
+            }
+            Some((PathRuleAction::Include, regex)) => {
+                println!("1. included by path rule `{regex}` (never ignored by a flaggable)")
+            }
+            None => println!("1. no include or exclude path rule matched"),
+        }
+        match &explanation.path_rewrite {
+            Some((regex, rewritten)) => {
+                println!("2. rewritten by `{regex}` to `{rewritten}` before matching flaggables")
+            }
+            None => println!("2. no rewrite rule matched"),
+        }
+        if explanation.flaggable_matches.is_empty() {
+            println!("3. no flaggable rule matched");
+        } else {
+            println!("3. flaggable rules matched (in evaluation order):");
+        }
+        for m in &explanation.flaggable_matches {
+            let mut flags = String::new();
+            bitflags::parser::to_writer(&m.flags, &mut flags)?;
+            let decided = explanation
+                .decided_by
+                .as_ref()
+                .is_some_and(|decided_by| decided_by.regex == m.regex);
+            println!(
+                "   {} `{}`: nature {}, flags {}",
+                if decided { "*" } else { "-" },
+                m.regex,
+                m.nature.as_deref().unwrap_or("(none)"),
+                flags
+            );
+        }
+        println!(
+            "4. ignored: {}, acquire content: {}, capturable executable: {}, capturable SQL: {}",
+            explanation.ignored,
+            explanation.acquire_content,
+            explanation.capturable_executable,
+            explanation.capturable_sql
+        );
+        println!(
+            "   nature: {}",
+            explanation.nature.as_deref().unwrap_or("(from extension)")
+        );
+        if let Some(nature_rewrite) = &explanation.nature_rewrite {
+            println!(
+                "   nature rewritten from {} by `{}`",
+                nature_rewrite
+                    .original_nature
+                    .as_deref()
+                    .unwrap_or("(none)"),
+                nature_rewrite.regex
+            );
+        }
+        if !explanation.captures.is_empty() {
+            println!(
+                "   captures: {}",
+                serde_json::to_string(&explanation.captures)?
+            );
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn train_dict(
+        &self,
+        cli: &super::Cli,
+        state_db_fs_path: &String,
+        state_db_init_sql: &[String],
+        natures: &[String],
+        max_samples: usize,
+        sample_seed: Option<u64>,
+        dict_size: usize,
+        level: i32,
+    ) -> anyhow::Result<()> {
+        let dbc =
+            DbConn::new(state_db_fs_path, cli.debug, &cli.db_pragmas()).with_context(|| {
+                format!(
+                    "[AdminCommands::train_dict] SQLite database {}",
+                    state_db_fs_path
+                )
+            })?;
+        let tx = dbc.init(Some(state_db_init_sql)).with_context(|| {
+            format!(
+                "[AdminCommands::train_dict] init transaction {}",
+                state_db_fs_path
+            )
+        })?;
+
+        // a seed is always recorded (even when not supplied) so any run can be reproduced
+        let sample_seed = resolved_sample_seed(sample_seed);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(sample_seed);
+
+        // samples are the raw bytes of text (TEXT) or binary (BLOB) content, decompressed
+        // if it was stored with `--compress-content`
+        let mut samples: Vec<Vec<u8>> = Vec::new();
+        {
+            let mut stmt = tx.prepare(
+                r"SELECT uniform_resource_id
+                    FROM uniform_resource
+                   WHERE content IS NOT NULL
+                     AND (?1 = '[]' OR nature IN (SELECT value FROM json_each(?1)))
+                   ORDER BY rowid",
+            )?;
+            let ur_ids = stmt
+                .query_map(rusqlite::params![serde_json::to_string(natures)?], |row| {
+                    row.get::<_, String>(0)
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            for ur_id in ur_ids.choose_multiple(&mut rng, max_samples) {
+                if let Some(content) = uniform_resource_content(&tx, ur_id)? {
+                    if !content.is_empty() {
+                        samples.push(content);
+                    }
+                }
+            }
+        }
+        if samples.is_empty() {
+            anyhow::bail!(
+                "[AdminCommands::train_dict] no stored content to sample in {}",
+                state_db_fs_path
+            );
+        }
+
+        let dictionary = zstd::dict::from_samples(&samples, dict_size).with_context(|| {
+            format!(
+                "[AdminCommands::train_dict] training zstd dictionary from {} samples",
+                samples.len()
+            )
+        })?;
+        let dictionary_digest = {
+            let mut hasher = Sha1::new();
+            hasher.update(&dictionary);
+            format!("{:x}", hasher.finalize())
+        };
+
+        let samples_size: usize = samples.iter().map(|s| s.len()).sum();
+        let mut compressed_size = 0;
+        let mut compressed_with_dict_size = 0;
+        let mut compressor = zstd::bulk::Compressor::with_dictionary(level, &dictionary)?;
+        for sample in &samples {
+            compressed_size += zstd::bulk::compress(sample, level)?.len();
+            compressed_with_dict_size += compressor.compress(sample)?.len();
+        }
+
+        tx.execute(
+            r"INSERT INTO uniform_resource_compression_dict (uniform_resource_compression_dict_id, algorithm, dictionary, dictionary_digest, samples_count, samples_size_bytes, compressed_size_bytes, compressed_with_dict_size_bytes, elaboration)
+                   VALUES (ulid(), 'zstd', ?, ?, ?, ?, ?, ?, json_object('sample_seed', ?))
+              ON CONFLICT (algorithm, dictionary_digest) DO NOTHING",
+            rusqlite::params![
+                dictionary,
+                dictionary_digest,
+                samples.len(),
+                samples_size,
+                compressed_size,
+                compressed_with_dict_size,
+                sample_seed as i64
+            ],
+        )
+        .with_context(|| {
+            format!(
+                "[AdminCommands::train_dict] storing dictionary {} in {}",
+                dictionary_digest, state_db_fs_path
+            )
+        })?;
+        tx.commit().with_context(|| {
+            format!(
+                "[AdminCommands::train_dict] transaction commit {}",
+                state_db_fs_path
+            )
+        })?;
+
+        let ratio = |compressed: usize| samples_size as f64 / compressed.max(1) as f64;
+        println!(
+            "Trained zstd dictionary {} ({} bytes) from {} samples ({} bytes, seed {})",
+            dictionary_digest,
+            dictionary.len(),
+            samples.len(),
+            samples_size,
+            sample_seed
+        );
+        println!(
+            "  without dictionary: {} bytes (ratio {:.2})",
+            compressed_size,
+            ratio(compressed_size)
+        );
+        println!(
+            "  with dictionary:    {} bytes (ratio {:.2})",
+            compressed_with_dict_size,
+            ratio(compressed_with_dict_size)
+        );
+        Ok(())
+    }
 }
 
 impl AdminTestCommands {
@@ -257,7 +1311,7 @@ impl AdminTestCommands {
             return Ok(());
         }
 
-        let mut dbc = DbConn::new(state_db_fs_path, cli.debug)?;
+        let dbc = DbConn::new(state_db_fs_path, cli.debug, &cli.db_pragmas())?;
         let tx = dbc.init(Some(state_db_init_sql))?;
         tx.commit()?; // in case the database was created
 
@@ -307,3 +1361,60 @@ impl AdminTestCommands {
         Ok(())
     }
 }
+
+impl AdminSessionsCommands {
+    pub fn execute(&self, cli: &super::Cli) -> anyhow::Result<()> {
+        match self {
+            AdminSessionsCommands::Ls {
+                state_db_fs_path,
+                limit,
+            } => self.ls(cli, state_db_fs_path, *limit),
+        }
+    }
+
+    fn ls(&self, cli: &super::Cli, state_db_fs_path: &str, limit: usize) -> anyhow::Result<()> {
+        let dbc = DbConn::open(state_db_fs_path, cli.debug).with_context(|| {
+            format!(
+                "[AdminSessionsCommands::ls] SQLite database {}",
+                state_db_fs_path
+            )
+        })?;
+        let mut stmt = dbc.conn.prepare(SESSIONS_SQL)?;
+        let rows = stmt
+            .query_map([limit], |row| {
+                Ok(vec![
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, i64>(5)?.to_string(),
+                    row.get::<_, String>(6)?,
+                ])
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .with_context(|| {
+                format!(
+                    "[AdminSessionsCommands::ls] reading sessions in {}",
+                    state_db_fs_path
+                )
+            })?;
+        println!(
+            "{}",
+            crate::format::as_output_text(
+                cli.output,
+                &[
+                    "Session",
+                    "Device",
+                    "Started",
+                    "Finished",
+                    "Version",
+                    "Resources",
+                    "Args"
+                ],
+                &rows
+            )
+        );
+        Ok(())
+    }
+}
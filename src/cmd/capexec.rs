@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::env;
 
+use anyhow::Context;
+use is_executable::IsExecutable;
 use serde_json::json;
 
 use super::CapturableExecCommands;
@@ -20,11 +22,22 @@ impl CapturableExecCommands {
             CapturableExecCommands::Ls {
                 root_fs_path: root_path,
                 markdown,
+                max_depth,
+                exec,
+                capture_exec_timeout_secs,
             } => {
-                if *markdown {
-                    self.ls_markdown(cli, root_path)
+                if *exec {
+                    self.ls_exec(
+                        cli,
+                        root_path,
+                        *max_depth,
+                        *capture_exec_timeout_secs,
+                        *markdown,
+                    )
+                } else if *markdown {
+                    self.ls_markdown(cli, root_path, *max_depth)
                 } else {
-                    self.ls_table(cli, root_path)
+                    self.ls_table(cli, root_path, *max_depth)
                 }
             }
             CapturableExecCommands::Test(test_args) => {
@@ -33,12 +46,19 @@ impl CapturableExecCommands {
         }
     }
 
-    fn ls_table(&self, _cli: &super::Cli, root_paths: &[String]) -> anyhow::Result<()> {
+    fn ls_table(
+        &self,
+        cli: &super::Cli,
+        root_paths: &[String],
+        max_depth: Option<usize>,
+    ) -> anyhow::Result<()> {
         let resources = ResourcesCollection::from_smart_ignore(
             root_paths,
             &Default::default(),
             &None::<HashMap<_, _>>,
             false,
+            false,
+            max_depth,
         );
 
         let mut found: Vec<Vec<String>> = vec![];
@@ -68,6 +88,7 @@ impl CapturableExecCommands {
                                 _uri,
                                 nature,
                                 is_batched_sql,
+                                _timeout,
                             ) => {
                                 if *is_batched_sql {
                                     found.push(vec![
@@ -99,23 +120,129 @@ impl CapturableExecCommands {
             }
         }
 
-        if !found.is_empty() {
-            println!(
+        // an empty JSON array is still a valid document, the others just print nothing
+        match cli.output {
+            super::OutputFormat::Table | super::OutputFormat::Ndjson if found.is_empty() => {}
+            output => println!(
                 "{}",
-                crate::format::as_ascii_table(&["Executable", "Nature", "Issue"], &found)
-            );
+                crate::format::as_output_text(output, &["Executable", "Nature", "Issue"], &found)
+            ),
+        }
+
+        Ok(())
+    }
+
+    // a preflight for `ingest files`: runs each candidate just like ingest would
+    // (with a synthetic STDIN) but nothing is persisted
+    fn ls_exec(
+        &self,
+        cli: &super::Cli,
+        root_paths: &[String],
+        max_depth: Option<usize>,
+        capture_exec_timeout_secs: u64,
+        markdown: bool,
+    ) -> anyhow::Result<()> {
+        let mut classifier: EncounterableResourcePathClassifier = Default::default();
+        classifier.capture_exec_timeout_secs = capture_exec_timeout_secs;
+        let resources = ResourcesCollection::from_smart_ignore(
+            root_paths,
+            &classifier,
+            &None::<HashMap<_, _>>,
+            false,
+            false,
+            max_depth,
+        );
+
+        let mut found: Vec<Vec<String>> = vec![];
+        for ur in resources.uniform_resources().flatten() {
+            if let crate::resource::UniformResource::CapturableExec(cer) = ur {
+                let path = cer.resource.uri.clone();
+                let nature = match &cer.executable {
+                    CapturableExecutable::UriShellExecutive(_, _, _, true, _) => "batched SQL",
+                    CapturableExecutable::UriShellExecutive(_, _, nature, false, _) => nature,
+                    CapturableExecutable::RequestedButNotExecutable(_) => {
+                        found.push(vec![
+                            path,
+                            String::from("Executable Permission Not Set"),
+                            String::from("chmod +x required (not executed)"),
+                            String::from(""),
+                            String::from(""),
+                            String::from(""),
+                        ]);
+                        continue;
+                    }
+                }
+                .to_string();
+                match cer
+                    .executable
+                    .executed_result_as_text(ShellStdIn::Json(synthetic_stdin(&path)))
+                {
+                    Ok((stdout, _, is_batched_sql)) => {
+                        let parsed_as = captured_format(&stdout, &nature, is_batched_sql);
+                        found.push(vec![
+                            path,
+                            nature,
+                            String::from(""),
+                            stdout.len().to_string(),
+                            String::from("success"),
+                            parsed_as.to_string(),
+                        ]);
+                    }
+                    Err(issue) => found.push(vec![
+                        path,
+                        nature,
+                        issue["issue"].as_str().unwrap_or_default().to_string(),
+                        issue["stdout"]
+                            .as_str()
+                            .map(|stdout| stdout.len().to_string())
+                            .unwrap_or_default(),
+                        issue["exit-status"]
+                            .as_str()
+                            .or(issue["issue"].as_str().filter(|i| *i == "timeout"))
+                            .unwrap_or_default()
+                            .to_string(),
+                        String::from(""),
+                    ]),
+                }
+            }
         }
 
+        let headers = [
+            "Executable",
+            "Nature",
+            "Issue",
+            "Stdout Bytes",
+            "Exit Status",
+            "Parsed As",
+        ];
+        if markdown {
+            println!("{}", crate::format::as_markdown_table(headers, &found));
+        } else {
+            match cli.output {
+                super::OutputFormat::Table | super::OutputFormat::Ndjson if found.is_empty() => {}
+                output => println!(
+                    "{}",
+                    crate::format::as_output_text(output, &headers, &found)
+                ),
+            }
+        }
         Ok(())
     }
 
-    fn ls_markdown(&self, _cli: &super::Cli, root_paths: &[String]) -> anyhow::Result<()> {
+    fn ls_markdown(
+        &self,
+        _cli: &super::Cli,
+        root_paths: &[String],
+        max_depth: Option<usize>,
+    ) -> anyhow::Result<()> {
         let classifier: EncounterableResourcePathClassifier = Default::default();
         let resources = ResourcesCollection::from_smart_ignore(
             root_paths,
             &classifier,
             &None::<HashMap<_, _>>,
             false,
+            false,
+            max_depth,
         );
 
         let mut markdown: Vec<String> = vec!["# `surveilr` Capturable Executables\n\n".to_string()];
@@ -178,25 +305,17 @@ impl CapturableExecCommands {
                                 _,
                                 nature,
                                 is_batched_sql,
+                                timeout,
                             ) => {
                                 markdown.push(format!("- Nature: `{}`\n", nature));
                                 markdown.push(format!("- Batched SQL?: `{}`\n", is_batched_sql));
 
-                                let synthetic_stdin = json!({
-                                    "surveilr-ingest": {
-                                        "args": { "state_db_fs_path": "synthetic" },
-                                        "env": { "current_dir": std::env::current_dir().unwrap().to_string_lossy() },
-                                        "behavior": {},
-                                        "device": { "device_id": "synthetic" },
-                                        "session": {
-                                            "walk-session-id":  "synthetic",
-                                            "walk-path-id":  "synthetic",
-                                            "entry": { "path": path },
-                                        },
-                                    }
-                                });
+                                let synthetic_stdin = synthetic_stdin(&path);
 
-                                match executive.execute(ShellStdIn::Json(synthetic_stdin.clone())) {
+                                match executive.execute(
+                                    ShellStdIn::Json(synthetic_stdin.clone()),
+                                    Some(*timeout),
+                                ) {
                                     Ok(shell_result) => {
                                         markdown.push(format!("- `{:?}`\n\n", shell_result.status));
 
@@ -250,6 +369,56 @@ impl CapturableExecCommands {
     }
 }
 
+// what `ingest files` would send as STDIN to a capturable executable
+fn synthetic_stdin(path: &str) -> serde_json::Value {
+    json!({
+        "surveilr-ingest": {
+            "args": { "state_db_fs_path": "synthetic" },
+            "env": { "current_dir": std::env::current_dir().unwrap().to_string_lossy() },
+            "behavior": {},
+            "device": { "device_id": "synthetic" },
+            "session": {
+                "walk-session-id":  "synthetic",
+                "walk-path-id":  "synthetic",
+                "entry": { "path": path },
+            },
+        }
+    })
+}
+
+// batched SQL is only checked for syntax errors since the tables it refers to
+// exist only in an actual RSSD; other output is checked for JSON
+fn captured_format(stdout: &str, nature: &str, is_batched_sql: bool) -> &'static str {
+    if is_batched_sql {
+        let conn = rusqlite::Connection::open_in_memory();
+        let mut batch = match &conn {
+            Ok(conn) => rusqlite::Batch::new(conn, stdout),
+            Err(_) => return "SQL (unchecked)",
+        };
+        loop {
+            match batch.next() {
+                Ok(Some(_)) => continue,
+                Ok(None) => return "SQL",
+                Err(err) => {
+                    let message = err.to_string();
+                    return if message.contains("syntax error")
+                        || message.contains("incomplete input")
+                    {
+                        "invalid SQL"
+                    } else {
+                        "SQL"
+                    };
+                }
+            }
+        }
+    }
+    match serde_json::from_str::<serde_json::Value>(stdout) {
+        Ok(_) => "JSON",
+        Err(_) if nature == "json" => "invalid JSON",
+        Err(_) => "text",
+    }
+}
+
 // Implement methods for `CapturableExecCommands`, ensure that whether the commands
 // are called from CLI or natively within Rust, all the calls remain ergonomic.
 impl CapturableExecTestCommands {
@@ -259,13 +428,28 @@ impl CapturableExecTestCommands {
         parent_args: &super::CapturableExecArgs,
         cmd_args: &super::CapturableExecTestArgs,
     ) -> anyhow::Result<()> {
+        let env_policy =
+            ShellEnvPolicy::from_args(&cmd_args.capture_exec_env, cmd_args.capture_exec_clean_env)
+                .with_context(|| {
+                    "[CapturableExecTestCommands.execute] invalid --capture-exec-env"
+                })?;
         match self {
-            CapturableExecTestCommands::File { fs_path } => {
-                self.test_fs_path(cli, parent_args, cmd_args, fs_path)
-            }
-            CapturableExecTestCommands::Task { stdin, task, cwd } => {
-                self.task(cli, *stdin, task, cwd.as_ref())
+            CapturableExecTestCommands::File { fs_path, json } => {
+                self.test_fs_path(cli, parent_args, cmd_args, fs_path, *json, &env_policy)
             }
+            CapturableExecTestCommands::Task {
+                stdin,
+                task,
+                task_default_nature,
+                cwd,
+            } => self.task(
+                cli,
+                *stdin,
+                task,
+                task_default_nature,
+                cwd.as_ref(),
+                &env_policy,
+            ),
         }
     }
 
@@ -275,13 +459,64 @@ impl CapturableExecTestCommands {
         _parent_args: &super::CapturableExecArgs,
         cmd_args: &super::CapturableExecTestArgs,
         fs_path: &str,
+        json_report: bool,
+        env_policy: &ShellEnvPolicy,
     ) -> anyhow::Result<()> {
         let classifier: EncounterableResourcePathClassifier = Default::default();
         let mut erc = EncounterableResourceClass {
             flags: EncounterableResourceFlags::empty(),
             nature: None,
+            digest_algorithm: classifier.digest_algorithm,
+            sniff_content_nature: false,
+            sniff_sqlite: false,
+            max_content_bytes: None,
+            text_encoding_fallback: classifier.text_encoding_fallback,
+            content_acquisition: classifier.content_acquisition,
+            capture_exec_timeout: DEFAULT_EXECUTION_TIMEOUT,
+            capture_exec_env: env_policy.clone(),
+            capture_exec_retry: RetryPolicy::default(),
+            capture_nature_from_directive: classifier.capture_nature_from_directive,
+            nature_rewrite: None,
+            captures: HashMap::new(),
+            hash_timing: None,
         };
-        if classifier.classify(fs_path, &mut erc)
+        let matches = classifier.flaggable_matches(fs_path);
+        let classified = classifier.classify(fs_path, &mut erc);
+        let is_executable = std::path::Path::new(fs_path).is_executable();
+        if json_report {
+            let report = json!({
+                "src": fs_path,
+                "executable": is_executable,
+                "classified": classified.then(|| json!({
+                    "flags": erc.flags,
+                    "nature": erc.nature,
+                    "captures": erc.captures,
+                    "capturable-executable": erc.flags.contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE),
+                    "capturable-sql": erc.flags.contains(EncounterableResourceFlags::CAPTURABLE_SQL),
+                })),
+                "matches": matches,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+
+        for m in &matches {
+            let mut flags = String::new();
+            bitflags::parser::to_writer(&m.flags, &mut flags)?;
+            println!(
+                "matched `{}`: nature {}, flags {}, capturable SQL: {}",
+                m.regex,
+                m.nature.as_deref().unwrap_or("(none)"),
+                flags,
+                m.capturable_sql
+            );
+        }
+        if !erc.captures.is_empty() {
+            println!("captures: {}", serde_json::to_string(&erc.captures)?);
+        }
+        println!("executable: {}", is_executable);
+
+        if classified
             && erc
                 .flags
                 .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
@@ -297,7 +532,7 @@ impl CapturableExecTestCommands {
                 "args": cmd_args
             }));
             let (src, nature, is_batch_sql) = match &ce {
-                CapturableExecutable::UriShellExecutive(_, uri, nature, is_batch_sql) => {
+                CapturableExecutable::UriShellExecutive(_, uri, nature, is_batch_sql, _) => {
                     (uri.clone(), nature, is_batch_sql)
                 }
                 CapturableExecutable::RequestedButNotExecutable(uri) => {
@@ -340,7 +575,9 @@ impl CapturableExecTestCommands {
         cli: &super::Cli,
         read_from_stdin: bool,
         task_cmds: &[String],
+        task_default_nature: &str,
         _cwd: Option<&String>,
+        env_policy: &ShellEnvPolicy,
     ) -> anyhow::Result<()> {
         if cli.debug > 0 {
             println!("{:?}", task_cmds);
@@ -356,9 +593,12 @@ impl CapturableExecTestCommands {
             task_cmds.to_vec()
         };
 
+        let mut classifier = EncounterableResourcePathClassifier::default();
+        classifier.capture_exec_env = env_policy.clone();
         let (_, resources) = ResourcesCollection::from_tasks_lines(
             &tasks,
-            &Default::default(),
+            task_default_nature,
+            &classifier,
             &None::<HashMap<_, _>>,
         );
         for ur in resources.uniform_resources() {
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use anyhow::Context;
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL_CONDENSED;
 use comfy_table::*;
@@ -16,58 +17,181 @@ impl IngestCommands {
             IngestCommands::Files(ifa) => {
                 if ifa.dry_run {
                     self.files_dry_run(cli, &ifa.root_fs_path, ifa)
+                } else if let Some(reference_db_fs_path) = &ifa.content_equal_to {
+                    self.files_content_equal_to(cli, ifa, reference_db_fs_path)
                 } else {
                     self.files(cli, ifa)
                 }
             }
             IngestCommands::Tasks(ifa) => self.tasks(cli, ifa),
+            IngestCommands::Urls(iua) => {
+                let ingest_session_id = crate::ingest::ingest_urls(cli, iua)?;
+                tracing::info!("Ingest Session: {ingest_session_id}");
+                Ok(())
+            }
+            IngestCommands::Imap(iia) => {
+                let ingest_session_id = crate::ingest::ingest_imap(cli, iia)?;
+                tracing::info!("Ingest Session: {ingest_session_id}");
+                Ok(())
+            }
         }
     }
 
     fn files(&self, cli: &super::Cli, args: &super::IngestFilesArgs) -> anyhow::Result<()> {
-        match crate::ingest::ingest_files(cli, args) {
-            Ok(ingest_session_id) => {
-                if args.stats || args.stats_json {
-                    // only export the path if there's more than one
-                    let sql = if args.root_fs_path.len() > 1 || args.stats_json {
-                        r"SELECT ingest_session_root_fs_path as 'Path',
-                                 file_extension as 'Extn',
-                                 total_file_count AS 'Count',
-                                 file_count_with_content AS 'Content',
-                                 file_count_with_frontmatter AS 'Frontmatter'
-                            FROM ur_ingest_session_files_stats
-                           WHERE ingest_session_id = ?"
-                    } else {
-                        r"SELECT file_extension as 'Extn',
-                                 total_file_count AS 'Count',
-                                 file_count_with_content AS 'Content',
-                                 file_count_with_frontmatter AS 'Frontmatter'
-                            FROM ur_ingest_session_files_stats
-                           WHERE ingest_session_id = ?"
-                    };
+        // with the `profile` feature the trace is set up by `Cli::init_tracing`
+        #[cfg(not(feature = "profile"))]
+        if args.profile_output.is_some() {
+            anyhow::bail!("[IngestCommands::files] --profile-output requires surveilr built with `--features profile`");
+        }
+        #[cfg(not(feature = "rayon"))]
+        if args.threads.is_some_and(|threads| threads > 1) {
+            anyhow::bail!(
+                "[IngestCommands::files] --threads requires surveilr built with `--features rayon`"
+            );
+        }
 
-                    let dbc = DbConn::open(&args.state_db_fs_path, cli.debug)?;
-                    if args.stats_json {
-                        let value = dbc.query_result_as_json_value(
-                            sql,
-                            rusqlite::params![ingest_session_id],
-                        )?;
-                        println!("{}", serde_json::to_string_pretty(&value)?);
-                    } else {
-                        let table = dbc.query_result_as_formatted_table(
-                            sql,
-                            rusqlite::params![ingest_session_id],
-                        )?;
-                        println!(
-                            "\n==> `ur_ingest_session_files_stats` for session ID '{}':\n{}",
-                            ingest_session_id, table
-                        )
-                    }
+        if args.emit_path_rules_json {
+            // an existing RSSD may have its own default rules, otherwise they're
+            // the ones a new RSSD would be seeded with (it's never created here)
+            let conn = match DbConn::open(&args.state_db_fs_path, cli.debug) {
+                Ok(dbc) => dbc.conn,
+                Err(_) => crate::persist::migrated_in_memory_conn()?,
+            };
+            let behavior = crate::ingest::IngestFilesBehavior::from_ingest_args(args, &conn)?;
+            println!(
+                "{}",
+                behavior
+                    .classifier
+                    .persistable_path_rules()
+                    .persistable_json_text()?
+            );
+            return Ok(());
+        }
+
+        let progress = crate::progress::StderrProgress;
+        let result = crate::ingest::ingest_files(cli, args, &progress)?;
+        if let Some(stats_json_fs_path) = &args.stats_json_file {
+            write_stats_json_file(&result, stats_json_fs_path)?;
+        }
+        // --stats-json is the same as `--output json --stats`
+        let stats = (args.stats || args.stats_json).then(|| stats_output(cli, args.stats_json));
+        if let Some(stats) = stats {
+            match stats {
+                super::OutputFormat::Table => self.print_ingest_result(cli, args, &result)?,
+                super::OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::to_value(&result)?)?
+                ),
+                super::OutputFormat::Ndjson => {
+                    println!("{}", serde_json::to_value(&result)?)
                 }
-                Ok(())
             }
-            Err(err) => Err(err),
         }
+        // JSON stats already include the profile
+        if let Some(profile) = &result.profile {
+            match (stats, cli.output) {
+                (Some(super::OutputFormat::Json | super::OutputFormat::Ndjson), _) => {}
+                _ if args.stats_json_file.is_some() => {}
+                (_, super::OutputFormat::Table) => self.print_ingest_profile(profile),
+                (_, super::OutputFormat::Json) => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::to_value(profile)?)?
+                ),
+                (_, super::OutputFormat::Ndjson) => {
+                    println!("{}", serde_json::to_value(profile)?)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn print_ingest_profile(&self, profile: &crate::ingest::IngestProfile) {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL_CONDENSED)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec!["Phase", "Elapsed (ms)", "Count"]);
+        for (phase, elapsed, count) in [
+            ("walk", profile.walk, profile.walked),
+            ("classify", profile.classify, profile.classified),
+            ("hash", profile.hash, profile.hashed),
+            ("persist", profile.persist, profile.classified),
+            ("total", profile.total, profile.walked),
+        ] {
+            table.add_row(vec![
+                phase.to_string(),
+                elapsed.as_millis().to_string(),
+                count.to_string(),
+            ]);
+        }
+        println!("\n==> ingest profile:\n{}", table);
+    }
+
+    fn print_ingest_result(
+        &self,
+        cli: &super::Cli,
+        args: &super::IngestFilesArgs,
+        result: &crate::ingest::IngestResult,
+    ) -> anyhow::Result<()> {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL_CONDENSED)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                "Encountered",
+                "Ignored",
+                "Not Found",
+                "Duplicates",
+                "Empty",
+                "Walk Errors",
+                "Captured Exec",
+                "Bytes Hashed",
+                "Elapsed (ms)",
+            ])
+            .add_row(vec![
+                result.encountered.to_string(),
+                result.ignored.to_string(),
+                result.not_found.to_string(),
+                result.duplicates.to_string(),
+                result.empty.to_string(),
+                result.walk_errors.to_string(),
+                result.captured_exec.to_string(),
+                result.total_bytes_hashed.to_string(),
+                result.elapsed.as_millis().to_string(),
+            ]);
+        println!(
+            "\n==> ingest session ID '{}':\n{}",
+            result.ingest_session_id, table
+        );
+
+        let mut natures: Vec<_> = result.uniform_resources_by_nature.iter().collect();
+        natures.sort();
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL_CONDENSED)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec!["Nature", "Count"]);
+        for (nature, count) in natures {
+            table.add_row(vec![nature.to_string(), count.to_string()]);
+        }
+        println!("\n==> uniform resources by nature:\n{}", table);
+
+        if args.max_total_bytes.is_some() {
+            let dbc = DbConn::open(&args.state_db_fs_path, cli.debug)?;
+            let table = dbc.query_result_as_formatted_table(
+                r"SELECT json_extract(elaboration, '$.budget.total_bytes') AS 'Bytes',
+                         json_extract(elaboration, '$.budget.max_total_bytes') AS 'Budget',
+                         json_extract(elaboration, '$.budget.skipped') AS 'Skipped'
+                    FROM ur_ingest_session
+                   WHERE ur_ingest_session_id = ?",
+                rusqlite::params![result.ingest_session_id],
+            )?;
+            println!("\n==> content bytes budget:\n{}", table)
+        }
+        Ok(())
     }
 
     fn tasks(&self, cli: &super::Cli, args: &super::IngestTasksArgs) -> anyhow::Result<()> {
@@ -84,21 +208,30 @@ impl IngestCommands {
                         WHERE ingest_session_id = ?"#;
 
                     let dbc = DbConn::open(&args.state_db_fs_path, cli.debug)?;
-                    if args.stats_json {
-                        let value = dbc.query_result_as_json_value(
-                            sql,
-                            rusqlite::params![ingest_session_id],
-                        )?;
-                        println!("{}", serde_json::to_string_pretty(&value)?);
-                    } else {
-                        let table = dbc.query_result_as_formatted_table(
-                            sql,
-                            rusqlite::params![ingest_session_id],
-                        )?;
-                        println!(
-                            "\n==> `ur_ingest_session_tasks_stats` for session ID '{}':\n{}",
-                            ingest_session_id, table
-                        )
+                    match stats_output(cli, args.stats_json) {
+                        super::OutputFormat::Table => {
+                            let table = dbc.query_result_as_formatted_table(
+                                sql,
+                                rusqlite::params![ingest_session_id],
+                            )?;
+                            println!(
+                                "\n==> `ur_ingest_session_tasks_stats` for session ID '{}':\n{}",
+                                ingest_session_id, table
+                            )
+                        }
+                        output => {
+                            let value = dbc.query_result_as_json_value(
+                                sql,
+                                rusqlite::params![ingest_session_id],
+                            )?;
+                            if output == super::OutputFormat::Json {
+                                println!("{}", serde_json::to_string_pretty(&value)?);
+                            } else if let Some(rows) = value.as_array() {
+                                for row in rows {
+                                    println!("{}", row);
+                                }
+                            }
+                        }
                     }
                 }
                 Ok(())
@@ -107,20 +240,53 @@ impl IngestCommands {
         }
     }
 
+    fn files_content_equal_to(
+        &self,
+        cli: &super::Cli,
+        args: &super::IngestFilesArgs,
+        reference_db_fs_path: &str,
+    ) -> anyhow::Result<()> {
+        let divergences = crate::ingest::content_equal_to(cli, args, reference_db_fs_path)?;
+        if divergences.is_empty() {
+            tracing::info!("All files match {}", reference_db_fs_path);
+            return Ok(());
+        }
+
+        for divergence in &divergences {
+            match divergence {
+                crate::ingest::ContentDivergence::Added(path) => println!("ADDED   {}", path),
+                crate::ingest::ContentDivergence::Missing(path) => println!("MISSING {}", path),
+                crate::ingest::ContentDivergence::Changed(path) => println!("CHANGED {}", path),
+            }
+        }
+        anyhow::bail!(
+            "[IngestCommands::files_content_equal_to] {} file(s) diverge from {}",
+            divergences.len(),
+            reference_db_fs_path
+        )
+    }
+
     fn files_dry_run(
         &self,
         _cli: &super::Cli,
         root_fs_path: &[String],
-        _args: &super::IngestFilesArgs,
+        args: &super::IngestFilesArgs,
     ) -> anyhow::Result<()> {
         let classifier: EncounterableResourcePathClassifier = Default::default();
-        let wd_resources =
-            ResourcesCollection::from_walk_dir(root_fs_path, &classifier, &None::<HashMap<_, _>>);
+        let wd_resources = ResourcesCollection::from_walk_dir(
+            root_fs_path,
+            &classifier,
+            &None::<HashMap<_, _>>,
+            args.follow_symlinks,
+            args.max_depth,
+        );
         let si_resources = ResourcesCollection::from_smart_ignore(
             root_fs_path,
             &classifier,
             &None::<HashMap<_, _>>,
             false,
+            args.follow_symlinks,
+            args.max_depth,
         );
         let vfs_pfs_resources = ResourcesCollection::from_vfs_physical_fs(
             root_fs_path,
@@ -154,6 +320,21 @@ impl IngestCommands {
             Cell::new(vfs_pfs_resources.encounterable.len().to_string()),
             Cell::new("Files surveilr could potentially handle"),
         ]);
+        let walk_errors = |resources: &ResourcesCollection| {
+            resources
+                .encounterable
+                .iter()
+                .filter(|er| matches!(er, EncounterableResource::WalkError(_, _)))
+                .count()
+                .to_string()
+        };
+        table.add_row(vec![
+            Cell::new("Walk Errors"),
+            Cell::new(walk_errors(&wd_resources)),
+            Cell::new(walk_errors(&si_resources)),
+            Cell::new(walk_errors(&vfs_pfs_resources)),
+            Cell::new("Paths which couldn't be read (e.g. permission denied)"),
+        ]);
         table.add_row(vec![
             Cell::new("Ignored via filename Regex"),
             Cell::new(wd_resources.ignored().count().to_string()),
@@ -406,3 +587,27 @@ impl IngestCommands {
         Ok(())
     }
 }
+
+// written alongside the target and then renamed over it so that whatever
+// watches the file never reads a partially written one
+fn write_stats_json_file(
+    result: &crate::ingest::IngestResult,
+    stats_json_fs_path: &str,
+) -> anyhow::Result<()> {
+    let partial_fs_path = format!("{stats_json_fs_path}.partial");
+    std::fs::write(&partial_fs_path, serde_json::to_string_pretty(result)?).with_context(|| {
+        format!("[IngestCommands::files] unable to write stats JSON to {partial_fs_path}")
+    })?;
+    std::fs::rename(&partial_fs_path, stats_json_fs_path).with_context(|| {
+        format!("[IngestCommands::files] unable to move stats JSON to {stats_json_fs_path}")
+    })
+}
+
+// the older `--stats-json` flag takes precedence over the global `--output`
+fn stats_output(cli: &super::Cli, stats_json: bool) -> super::OutputFormat {
+    if stats_json {
+        super::OutputFormat::Json
+    } else {
+        cli.output
+    }
+}
@@ -1,10 +1,12 @@
-use clap::{Args, Parser, Subcommand};
-use serde::Serialize;
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
 
 pub mod admin;
 pub mod capexec;
 pub mod ingest;
 pub mod notebooks;
+pub mod shell;
 
 const DEFAULT_STATEDB_FS_PATH: &str = "resource-surveillance.sqlite.db";
 const DEFAULT_MERGED_STATEDB_FS_PATH: &str = "resource-surveillance-aggregated.sqlite.db";
@@ -12,18 +14,246 @@ const DEFAULT_MERGED_STATEDB_FS_PATH: &str = "resource-surveillance-aggregated.s
 #[derive(Debug, Serialize, Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
-    /// How to identify this device
-    #[arg(long, num_args = 0..=1, default_value = super::DEVICE.name(), default_missing_value = "always", env="SURVEILR_DEVICE_NAME")]
+    /// How to identify this device (defaults to `SURVEILR_DEVICE_NAME`, then `/etc/machine-id`, then the hostname)
+    #[arg(long)]
     pub device_name: Option<String>,
 
-    /// Turn debugging information on (repeat for higher levels)
+    /// Turn debugging information on (repeat for higher levels, same as `--log-level info`, `debug` then `trace`)
     #[arg(short, long, action = clap::ArgAction::Count, env="SURVEILR_DEBUG")]
     pub debug: u8,
 
+    /// the most verbose log lines written to STDERR (`warn` unless `--debug` is given)
+    #[arg(long, value_enum, global = true, env = "SURVEILR_LOG_LEVEL")]
+    pub log_level: Option<LogLevel>,
+
+    /// only log errors, overrides `--log-level` and `--debug`
+    #[arg(short, long, global = true, env = "SURVEILR_QUIET")]
+    pub quiet: bool,
+
+    /// how list-style commands (`capturable-exec ls`, `notebooks ls`, ingest stats) emit their results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, global = true, env = "SURVEILR_OUTPUT")]
+    pub output: OutputFormat,
+
+    /// set the state DB's `journal_mode` (e.g. `wal` so others can read while ingesting); unchanged by default
+    #[arg(long, value_enum, global = true, env = "SURVEILR_DB_JOURNAL_MODE")]
+    pub db_journal_mode: Option<DbJournalMode>,
+
+    /// set the state DB's `synchronous` pragma (`normal` is safe with `wal`); unchanged by default
+    #[arg(long, value_enum, global = true, env = "SURVEILR_DB_SYNCHRONOUS")]
+    pub db_synchronous: Option<DbSynchronous>,
+
+    /// wait this many milliseconds for a locked state DB before failing with `database is locked` (5000 by default)
+    #[arg(long, global = true, env = "SURVEILR_DB_BUSY_TIMEOUT_MS")]
+    pub db_busy_timeout_ms: Option<u64>,
+
+    /// apply the migrations an existing state DB is missing instead of failing (see `admin migrate`)
+    #[arg(long, global = true, env = "SURVEILR_AUTO_MIGRATE")]
+    pub auto_migrate: bool,
+
     #[command(subcommand)]
     pub command: CliCommands,
 }
 
+impl Cli {
+    /// The log level from `--quiet`, `--log-level` or the `--debug` count, in
+    /// that order of precedence.
+    pub fn log_level(&self) -> LogLevel {
+        match (self.quiet, self.log_level) {
+            (true, _) => LogLevel::Error,
+            (false, Some(log_level)) => log_level,
+            (false, None) => match self.debug {
+                0 => LogLevel::Warn,
+                1 => LogLevel::Info,
+                2 => LogLevel::Debug,
+                _ => LogLevel::Trace,
+            },
+        }
+    }
+
+    /// Sets `debug` from `--quiet` or `--log-level` so that output which
+    /// depends on the `--debug` count follows them too.
+    pub fn apply_log_level(&mut self) {
+        self.debug = match self.log_level() {
+            LogLevel::Error | LogLevel::Warn => 0,
+            LogLevel::Info => 1,
+            LogLevel::Debug => 2,
+            LogLevel::Trace => 3,
+        };
+    }
+
+    /// Writes log lines at `log_level()` to STDERR so that STDOUT stays clean
+    /// for `--output json`; with `ingest files --profile-output` spans are also
+    /// written as a chrome trace until the returned guard is dropped.
+    pub fn init_tracing(&self) -> TracingGuard {
+        use tracing_subscriber::prelude::*;
+        let log_layer = tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_target(false)
+            .without_time()
+            .with_filter(self.log_level().level_filter());
+
+        #[cfg(feature = "profile")]
+        if let CliCommands::Ingest(IngestArgs {
+            command: IngestCommands::Files(files_args),
+        }) = &self.command
+        {
+            if let Some(profile_output) = &files_args.profile_output {
+                let (chrome_layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+                    .file(profile_output)
+                    .include_args(true)
+                    .build();
+                tracing_subscriber::registry()
+                    .with(log_layer)
+                    .with(chrome_layer)
+                    .init();
+                return TracingGuard {
+                    _chrome: Some(guard),
+                };
+            }
+        }
+
+        tracing_subscriber::registry().with(log_layer).init();
+        TracingGuard {
+            #[cfg(feature = "profile")]
+            _chrome: None,
+        }
+    }
+
+    /// The `--db-*` pragmas to apply whenever the state DB is opened.
+    pub fn db_pragmas(&self) -> crate::persist::DbPragmas {
+        crate::persist::DbPragmas {
+            // the clap value names are the same as SQLite's pragma values
+            journal_mode: self
+                .db_journal_mode
+                .and_then(|mode| mode.to_possible_value())
+                .map(|value| value.get_name().to_string()),
+            synchronous: self
+                .db_synchronous
+                .and_then(|sync| sync.to_possible_value())
+                .map(|value| value.get_name().to_string()),
+            busy_timeout: self
+                .db_busy_timeout_ms
+                .map(std::time::Duration::from_millis),
+            auto_migrate: self.auto_migrate,
+        }
+    }
+
+    /// Replaces `@file` values of the arguments which accept them with the
+    /// entries listed in those files (see `expanded_at_files`).
+    pub fn expand_at_files(&mut self) -> anyhow::Result<()> {
+        if let CliCommands::Ingest(args) = &mut self.command {
+            match &mut args.command {
+                IngestCommands::Files(files_args) => {
+                    files_args.root_fs_path = expanded_at_files(&files_args.root_fs_path)?;
+                }
+                IngestCommands::Tasks(tasks_args) => {
+                    tasks_args.task = expanded_at_files(&tasks_args.task)?;
+                }
+                IngestCommands::Urls(urls_args) => {
+                    urls_args.url = expanded_at_files(&urls_args.url)?;
+                }
+                IngestCommands::Imap(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Expands each value starting with `@` into the lines of the named file,
+/// skipping blank lines and `#` comments the same way task lines are; other
+/// values are kept as-is. Lines in the file are not expanded again.
+pub fn expanded_at_files(values: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut expanded = Vec::with_capacity(values.len());
+    for value in values {
+        match value.strip_prefix('@') {
+            Some(fs_path) => {
+                let text = std::fs::read_to_string(fs_path).with_context(|| {
+                    format!("[expanded_at_files] unable to read {fs_path} given as {value}")
+                })?;
+                expanded.extend(
+                    text.lines()
+                        .filter(|line| !line.starts_with('#'))
+                        .filter(|line| !line.trim().is_empty())
+                        .map(String::from),
+                );
+            }
+            None => expanded.push(value.clone()),
+        }
+    }
+    Ok(expanded)
+}
+
+/// Parses a `KEY=VALUE` pair; a `/regex/` key must be a valid regular expression.
+fn parse_key_val(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .rsplit_once('=')
+        .ok_or_else(|| format!("invalid KEY=VALUE: no `=` found in `{s}`"))?;
+    if let Some(pattern) = crate::resource::nature_alias_regex(key) {
+        regex::Regex::new(pattern).map_err(|err| format!("invalid regex in `{s}`: {err}"))?;
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn parse_nature_bytes(s: &str) -> Result<(String, u64), String> {
+    let (nature, bytes) = s
+        .rsplit_once('=')
+        .ok_or_else(|| format!("invalid NATURE=BYTES: no `=` found in `{s}`"))?;
+    let bytes = bytes
+        .parse()
+        .map_err(|err| format!("invalid byte count in `{s}`: {err}"))?;
+    Ok((nature.to_string(), bytes))
+}
+
+// the name comes before the first `=` since CSS attribute selectors contain `=`
+fn parse_html_select(s: &str) -> Result<(String, String), String> {
+    let (name, select) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid NAME=SELECTOR: no `=` found in `{s}`"))?;
+    crate::html::HtmlSelect::parse(name, select)?;
+    Ok((name.to_string(), select.to_string()))
+}
+
+// the name comes before the first `=` like `--html-select`
+fn parse_xml_select(s: &str) -> Result<(String, String), String> {
+    let (name, select) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid NAME=PATH: no `=` found in `{s}`"))?;
+    crate::xml::XmlSelect::parse(name, select)?;
+    Ok((name.to_string(), select.to_string()))
+}
+
+fn parse_json_schema(s: &str) -> Result<(String, String), String> {
+    let (name, fs_path) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid NAME=PATH: no `=` found in `{s}`"))?;
+    Ok((name.to_string(), fs_path.to_string()))
+}
+
+// the name comes after the last `=` since regular expressions may contain `=`
+fn parse_json_schema_apply(s: &str) -> Result<(String, String), String> {
+    let (pattern, name) = s
+        .rsplit_once('=')
+        .ok_or_else(|| format!("invalid REGEX=NAME: no `=` found in `{s}`"))?;
+    regex::Regex::new(pattern).map_err(|err| format!("invalid regex in `{s}`: {err}"))?;
+    Ok((pattern.to_string(), name.to_string()))
+}
+
+fn parse_sample_percent(s: &str) -> Result<f64, String> {
+    let percent: f64 = s
+        .parse()
+        .map_err(|err| format!("invalid percentage `{s}`: {err}"))?;
+    if !(percent > 0.0 && percent <= 100.0) {
+        return Err(format!("`{s}` isn't a percentage above 0 and up to 100"));
+    }
+    Ok(percent)
+}
+
+// the value is kept as given so that saved behaviors stay relative to each run
+fn parse_since(s: &str) -> Result<String, String> {
+    crate::format::parsed_since(s, chrono::Utc::now())?;
+    Ok(s.to_string())
+}
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, Subcommand)]
 pub enum CliCommands {
@@ -31,6 +261,7 @@ pub enum CliCommands {
     CapturableExec(CapturableExecArgs),
     Ingest(IngestArgs),
     Notebooks(NotebooksArgs),
+    Shell(ShellArgs),
 }
 
 /// Admin / maintenance utilities
@@ -42,7 +273,7 @@ pub struct AdminArgs {
 
 #[derive(Debug, Serialize, Subcommand)]
 pub enum AdminCommands {
-    /// initialize an empty database with bootstrap.sql
+    /// initialize an empty database with the bootstrap SQL embedded in `surveilr`
     Init {
         /// target SQLite database
         #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
@@ -59,6 +290,14 @@ pub enum AdminCommands {
         /// add the current device in the empty database's device table
         #[arg(long)]
         with_device: bool,
+
+        /// only emit the SQL that would be executed to STDOUT (no database is opened or removed)
+        #[arg(long)]
+        dry_run: bool,
+
+        /// only emit the embedded bootstrap DDL to STDOUT (no database is opened or removed)
+        #[arg(long, conflicts_with = "dry_run")]
+        print_schema: bool,
     },
 
     /// merge multiple surveillance state databases into a single one
@@ -86,6 +325,148 @@ pub enum AdminCommands {
         /// only generate SQL and emit to STDOUT (no actual merge)
         #[arg(long)]
         sql_only: bool,
+
+        /// store content shared by several resources (e.g. across devices) once, keyed by digest
+        #[arg(long)]
+        dedupe_content: bool,
+
+        /// merge the candidates whose schema is compatible and report the others as skipped
+        #[arg(long)]
+        skip_incompatible: bool,
+
+        /// merge the candidates even if their schemas aren't compatible
+        #[arg(long, conflicts_with = "skip_incompatible")]
+        force: bool,
+
+        /// merge into the existing target only the candidates not merged before (by digest, see `merged_source`)
+        #[arg(long, conflicts_with = "remove_existing_first")]
+        append: bool,
+    },
+
+    /// apply the migrations an existing state DB is missing (its schema predates this `surveilr`)
+    Migrate {
+        /// target SQLite database
+        #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+        state_db_fs_path: String,
+
+        /// only list the pending migrations, don't apply them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// remove content no longer referenced by any live uniform resource
+    Gc {
+        /// target SQLite database
+        #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+        state_db_fs_path: String,
+
+        /// one or more globs to match as SQL files and batch execute them in alpha order
+        #[arg(short = 'I', long)]
+        state_db_init_sql: Vec<String>,
+
+        /// only report what would be reclaimed, don't remove anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// run VACUUM after collecting so the database file actually shrinks
+        #[arg(long)]
+        vacuum: bool,
+    },
+
+    /// train a zstd dictionary from stored content and save it in the database
+    TrainDict {
+        /// target SQLite database
+        #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+        state_db_fs_path: String,
+
+        /// one or more globs to match as SQL files and batch execute them in alpha order
+        #[arg(short = 'I', long)]
+        state_db_init_sql: Vec<String>,
+
+        /// only sample uniform resources with these natures (default is all)
+        #[arg(short, long)]
+        nature: Vec<String>,
+
+        /// maximum number of stored content samples to train with
+        #[arg(long, default_value = "1000")]
+        max_samples: usize,
+
+        /// seed for the sampling RNG so that a training run can be reproduced (default is random)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(..=i64::MAX as u64))]
+        sample_seed: Option<u64>,
+
+        /// maximum size of the trained dictionary in bytes
+        #[arg(long, default_value = "112640")]
+        dict_size: usize,
+
+        /// zstd compression level used to report the compression ratio
+        #[arg(long, default_value = "3")]
+        level: i32,
+    },
+
+    /// re-hash this device's files stored in the database and report which changed or are missing
+    Verify {
+        /// target SQLite database
+        #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+        state_db_fs_path: String,
+
+        /// emit the report as JSON (same as `--output json`)
+        #[arg(long)]
+        json: bool,
+
+        /// only re-hash about this percentage (`10`, `0.5`) of the files
+        #[arg(long, value_parser = parse_sample_percent)]
+        sample: Option<f64>,
+
+        /// seed deciding which files `--sample` re-hashes so a sampled verify can be reproduced (default is random, and reported)
+        #[arg(long, requires = "sample", value_parser = clap::value_parser!(u64).range(..=i64::MAX as u64))]
+        sample_seed: Option<u64>,
+    },
+
+    /// write the stored content of a uniform resource, after checking its digest, to STDOUT or a file
+    ExportResource {
+        /// target SQLite database
+        #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+        state_db_fs_path: String,
+
+        /// URI of the uniform resource (its most recently ingested version is exported)
+        #[arg(short, long)]
+        uri: String,
+
+        /// how to encode the content (default is `raw` for text and `base64` for binary content)
+        #[arg(short, long, value_enum)]
+        encoding: Option<ExportEncoding>,
+
+        /// write the content to this file instead of STDOUT
+        #[arg(short, long)]
+        out: Option<String>,
+    },
+
+    /// explain, step by step, how `ingest files` would classify a path (ignored, content acquired, capturable executable or SQL)
+    ExplainPath {
+        /// the path to explain (it doesn't need to exist)
+        #[arg(short, long)]
+        fs_path: String,
+
+        /// SQLite database whose default classification rules are used
+        #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+        state_db_fs_path: String,
+
+        /// read the classification rules from this JSON file instead of the RSSD's defaults
+        #[arg(long)]
+        path_rules_json: Option<String>,
+
+        /// regular expression of paths to ignore, as with `ingest files`
+        #[arg(long)]
+        ignore_fs_entry: Vec<String>,
+
+        /// regular expression of paths to include again even if ignored, as with `ingest files`
+        #[arg(long)]
+        reinclude_fs_entry: Vec<String>,
+
+        /// emit the explanation as JSON (same as `--output json`)
+        #[arg(long)]
+        json: bool,
     },
 
     /// generate CLI help markdown
@@ -93,6 +474,30 @@ pub enum AdminCommands {
 
     /// generate CLI help markdown
     Test(AdminTestArgs),
+
+    /// ingest sessions with the arguments and `surveilr` version each ran with
+    Sessions(AdminSessionsArgs),
+}
+
+/// Ingest sessions' provenance
+#[derive(Debug, Serialize, Args)]
+pub struct AdminSessionsArgs {
+    #[command(subcommand)]
+    pub command: AdminSessionsCommands,
+}
+
+#[derive(Debug, Serialize, Subcommand)]
+pub enum AdminSessionsCommands {
+    /// list the most recent ingest sessions
+    Ls {
+        /// target SQLite database
+        #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+        state_db_fs_path: String,
+
+        /// how many sessions to list, most recent first
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
 }
 
 /// Capturable Executables (CE) assurance tools
@@ -138,6 +543,18 @@ pub enum CapturableExecCommands {
         /// emit the results as markdown, not a simple table
         #[arg(long)]
         markdown: bool,
+
+        /// only descend this many directories below each root path (0 is just the root path itself)
+        #[arg(long)]
+        max_depth: Option<usize>,
+
+        /// execute each candidate (without persisting anything) and show its output size, exit status and format
+        #[arg(long)]
+        exec: bool,
+
+        /// with --exec, kill candidates that run longer than this many seconds
+        #[arg(long, default_value_t = 60)]
+        capture_exec_timeout_secs: u64,
     },
 
     /// test capturable executables files
@@ -149,6 +566,15 @@ pub enum CapturableExecCommands {
 pub struct CapturableExecTestArgs {
     #[command(subcommand)]
     pub command: CapturableExecTestCommands,
+
+    /// set KEY=VAL (or, given just KEY, pass the parent's KEY) in capturable executables' environment
+    #[arg(long, global = true)]
+    #[serde(skip)] // injected values are often secrets
+    pub capture_exec_env: Vec<String>,
+
+    /// run capturable executables with only `--capture-exec-env` variables (otherwise the environment is inherited)
+    #[arg(long, global = true)]
+    pub capture_exec_clean_env: bool,
 }
 
 #[derive(Debug, Serialize, Subcommand)]
@@ -157,6 +583,10 @@ pub enum CapturableExecTestCommands {
     File {
         #[arg(short, long)]
         fs_path: String,
+
+        /// only emit the classification report (matching rules, nature, SQL and executable flags) as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Execute a task string as if it was run by `ingest tasks` and show the output
@@ -169,6 +599,10 @@ pub enum CapturableExecTestCommands {
         #[arg(short, long)]
         task: Vec<String>,
 
+        /// nature of the output of task lines which are plain commands rather than JSON objects
+        #[arg(long, default_value = "json")]
+        task_default_nature: String,
+
         /// use this as the current working directory (CWD)
         #[arg(long)]
         cwd: Option<String>,
@@ -182,6 +616,116 @@ pub struct IngestArgs {
     pub command: IngestCommands,
 }
 
+/// How stored text content should be trimmed (the digest is always of the raw text)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentTextTrim {
+    /// trailing whitespace on each line
+    Lines,
+    /// trailing blank lines at the end of the content
+    End,
+    /// both trailing whitespace on each line and trailing blank lines
+    All,
+}
+
+/// SQLite `PRAGMA journal_mode` values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DbJournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    Wal,
+    Off,
+}
+
+/// SQLite `PRAGMA synchronous` values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DbSynchronous {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+/// How verbose the log lines written to STDERR are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn level_filter(self) -> tracing_subscriber::filter::LevelFilter {
+        use tracing_subscriber::filter::LevelFilter;
+        match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Keeps tracing output (e.g. the `--profile-output` chrome trace) flowing
+/// until it's dropped, see `Cli::init_tracing`.
+pub struct TracingGuard {
+    #[cfg(feature = "profile")]
+    _chrome: Option<tracing_chrome::FlushGuard>,
+}
+
+/// How list-style commands emit their results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// a pretty table for humans
+    #[default]
+    Table,
+    /// a single JSON document
+    Json,
+    /// newline-delimited JSON, one object per row
+    Ndjson,
+}
+
+/// How stored content should be compressed (the digest is always of the uncompressed content)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentCodec {
+    /// Zstandard, recorded as `zstd` in `uniform_resource.content_codec` (or
+    /// `zstd:{dictionary id}` once `admin train-dict` has trained a dictionary)
+    Zstd,
+}
+
+/// How `admin export-resource` encodes the stored content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportEncoding {
+    /// the content's bytes as they were ingested
+    Raw,
+    /// standard (padded) base64 text
+    Base64,
+    /// base85 text using the RFC 1924 alphabet (as git and Python's `b85encode` do)
+    Base85,
+}
+
+/// What capturable executables encountered while ingesting files receive on STDIN
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureExecStdin {
+    /// JSON describing the ingest session, device and behavior (`surveilr-ingest`)
+    #[default]
+    Context,
+    /// the executable's own content, the same bytes its uniform resource was hashed from
+    Content,
+}
+
 /// Ingest content from device file system and other sources
 #[derive(Debug, Serialize, Args)]
 pub struct IngestFilesArgs {
@@ -189,14 +733,22 @@ pub struct IngestFilesArgs {
     #[arg(long)]
     pub dry_run: bool,
 
+    /// don't run the ingestion, compare file digests to the latest ingestion in this reference database
+    #[arg(long)]
+    pub content_equal_to: Option<String>,
+
     /// the behavior name in `behavior` table
     #[arg(short, long, env = "SURVEILR_INGEST_BEHAVIOR_NAME")]
     pub behavior: Option<String>,
 
-    /// one or more root paths to ingest
+    /// one or more root paths to ingest (`@file` reads the paths, one per line, from a file; `-` ingests the file paths listed on STDIN without walking them)
     #[arg(short, long, default_value = ".", default_missing_value = "always")]
     pub root_fs_path: Vec<String>,
 
+    /// one or more `.tar`, `.tar.gz`/`.tgz`, `.tar.zst`/`.tzst` or `.zip` archives to walk, without extracting them, instead of root paths
+    #[arg(long, conflicts_with = "root_fs_path")]
+    pub archive: Vec<String>,
+
     /// target SQLite database
     #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
     pub state_db_fs_path: String,
@@ -205,6 +757,10 @@ pub struct IngestFilesArgs {
     #[arg(short = 'I', long)]
     pub state_db_init_sql: Vec<String>,
 
+    /// one or more globs to match as SQL files and batch execute them in alpha order, in their own transaction, after the ingestion is committed
+    #[arg(long)]
+    pub after_ingest_sql: Vec<String>,
+
     /// include the surveil database in the ingestion candidates
     #[arg(long)]
     pub include_state_db_in_ingestion: bool,
@@ -217,9 +773,254 @@ pub struct IngestFilesArgs {
     #[arg(long)]
     pub stats_json: bool,
 
+    /// write the stats JSON (the same as `--stats-json`) to this file instead of STDOUT, replacing it atomically
+    #[arg(long)]
+    pub stats_json_file: Option<String>,
+
+    /// for JSON natures, also store a digest of the canonical (sorted keys, compact) content
+    #[arg(long)]
+    pub normalize_json_before_hash: bool,
+
+    /// store trimmed text content (raw text is still hashed, the trimmed digest is kept as a transform)
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "all")]
+    pub content_text_trim: Option<ContentTextTrim>,
+
+    /// compress stored content (reads must decompress according to `uniform_resource.content_codec`)
+    #[arg(long, value_enum)]
+    pub compress_content: Option<ContentCodec>,
+
+    /// store binary content as content-defined (FastCDC) chunks shared across resources and versions
+    #[arg(long)]
+    pub chunk_content: bool,
+
+    /// skip content acquisition for files whose size and last modified time match a prior ingest
+    #[arg(long)]
+    pub incremental: bool,
+
+    /// fail (without storing anything) if any path can't be walked, e.g. permission denied
+    #[arg(long)]
+    pub fail_on_walk_error: bool,
+
+    /// re-acquire and re-hash all content even if `--incremental` is passed
+    #[arg(long)]
+    pub force_rehash: bool,
+
+    /// only ingest files modified within this duration (`24h`, `7d`) or since an RFC3339 timestamp
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<String>,
+
+    /// only ingest about this percentage (`10`, `0.5`) of the files and capturable executables
+    #[arg(long, value_parser = parse_sample_percent)]
+    pub sample: Option<f64>,
+
+    /// seed deciding which resources `--sample` keeps so a sampled ingest can be reproduced (default is random, recorded in the session's `elaboration.sample.seed`)
+    #[arg(long, requires = "sample", value_parser = clap::value_parser!(u64).range(..=i64::MAX as u64))]
+    pub sample_seed: Option<u64>,
+
+    /// stop acquiring content once this many bytes have been ingested (the rest are skipped)
+    #[arg(long)]
+    pub max_total_bytes: Option<u64>,
+
+    /// only store the (streamed) digest, not the content, of files larger than this many bytes
+    #[arg(long)]
+    pub max_content_bytes: Option<u64>,
+
+    /// store only the digest and metadata, not the content, of resources larger than this many bytes
+    #[arg(long)]
+    pub max_store_bytes: Option<u64>,
+
+    /// override `--max-store-bytes` for a nature (`png=0` stores no images, `md=1048576` caps Markdown)
+    #[arg(long, value_parser = parse_nature_bytes)]
+    pub max_store_bytes_for_nature: Vec<(String, u64)>,
+
+    /// commit every this many resources instead of once at the end (0, the default, keeps the session all or nothing)
+    #[arg(long, default_value_t = 0)]
+    pub commit_batch_size: usize,
+
+    /// kill capturable executables that run longer than this many seconds
+    #[arg(long, default_value_t = 60)]
+    pub capture_exec_timeout_secs: u64,
+
+    /// run up to this many capturable executables at once, ahead of storing their output in walk order
+    #[arg(long, default_value_t = 1, conflicts_with = "ordered_capture")]
+    pub capture_exec_concurrency: usize,
+
+    /// set KEY=VAL (or, given just KEY, pass the parent's KEY) in capturable executables' environment
+    #[arg(long)]
+    #[serde(skip)] // injected values are often secrets
+    pub capture_exec_env: Vec<String>,
+
+    /// retry failed capturable executables (but not missing or non-executable ones) and busy database writes this many times, with exponential backoff
+    #[arg(long, default_value_t = 0)]
+    pub max_retries: u32,
+
+    /// run capturable executables with only `--capture-exec-env` variables (otherwise the environment is inherited)
+    #[arg(long)]
+    pub capture_exec_clean_env: bool,
+
+    /// what capturable executables receive on STDIN
+    #[arg(long, value_enum, default_value = "context")]
+    pub capture_exec_stdin: CaptureExecStdin,
+
+    /// don't record each capturable executable's exit status, STDERR and STDOUT size (`capturable_exec_run`)
+    #[arg(long)]
+    pub no_capture_exec_diagnostics: bool,
+
+    /// open encountered SQLite databases (recognized by their header, whatever the extension) read-only and record their tables and row counts
+    #[arg(long)]
+    pub inspect_sqlite: bool,
+
+    /// don't apply the RSSD's default (or `--path-rules-json`) ignore rules (e.g. `.git` and `node_modules`), only `--ignore-*` ones
+    #[arg(long)]
+    pub no_default_ignores: bool,
+
+    /// don't apply the RSSD's default (or `--path-rules-json`) content acquisition rules (e.g. `.md`, `.json`), only `--acquire-glob` ones
+    #[arg(long)]
+    pub no_default_acquire: bool,
+
+    /// don't apply the RSSD's default (or `--path-rules-json`) capturable executable rules (e.g. `surveilr[json]` in the name)
+    #[arg(long)]
+    pub no_default_capture: bool,
+
+    /// regular expression of paths to ignore (evaluated in order, the last matching rule wins)
+    #[arg(long)]
+    pub ignore_fs_entry: Vec<String>,
+
+    /// regular expression of paths to include again even if ignored (evaluated after `--ignore-fs-entry`)
+    #[arg(long)]
+    pub reinclude_fs_entry: Vec<String>,
+
+    /// glob (e.g. `target/` or `**/*.min.js`) of paths relative to the walk root to ignore, gitignore-style
+    #[arg(long)]
+    pub ignore_glob: Vec<String>,
+
+    /// glob of paths relative to the walk root whose content to acquire (an `--ignore-glob` match wins)
+    #[arg(long)]
+    pub acquire_glob: Vec<String>,
+
+    /// follow symbolic links (e.g. a symlinked `current/` directory) while walking, guarding against cycles
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// classify symbolic links by the path they resolve to (e.g. its extension) rather than their own name
+    #[arg(long)]
+    pub resolve_symlink_nature: bool,
+
+    /// take a capturable executable's output nature from a `# surveilr-nature: json` comment on its first line (or after its shebang)
+    #[arg(long)]
+    pub capture_nature_from_directive: bool,
+
+    /// only ingest resources of this nature (after rewrites and `--nature-bind`), may be repeated
+    #[arg(long, conflicts_with = "exclude_nature")]
+    pub include_nature: Vec<String>,
+
+    /// don't ingest resources of this nature (after rewrites and `--nature-bind`), may be repeated
+    #[arg(long)]
+    pub exclude_nature: Vec<String>,
+
+    /// don't record which resources had their nature changed by path rewrite rules (`ur_ingest_session_nature_rewrite`)
+    #[arg(long)]
+    pub no_nature_rewrite_audit: bool,
+
+    /// walk each file only once even if hard links (or followed symlinks) lead to it again (unix only)
+    #[arg(long)]
+    pub dedupe_inodes: bool,
+
+    /// how to find files under the root paths
+    #[arg(long, value_enum, default_value = "smart")]
+    pub walker: crate::resource::Walker,
+
+    /// only descend this many directories below each root path (0 is just the root path itself)
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// number of threads used to classify and hash resources (defaults to the number of CPUs, requires `rayon` feature)
+    #[arg(long)]
+    pub threads: Option<usize>,
+
+    /// bind a nature to another (`text=text/plain`) or a URI regex to a nature (`/\.log\.\d+$/=text/plain`)
+    #[arg(short = 'N', long, value_parser = parse_key_val)]
+    pub nature_bind: Vec<(String, String)>,
+
+    /// extract `name=css-selector` (or `name=css-selector::attr(attribute)`) from HTML into `elaboration.html_select`
+    #[arg(long, value_parser = parse_html_select)]
+    pub html_select: Vec<(String, String)>,
+
+    /// don't store zero-byte files (directories aren't affected), they're only counted as `Empty` in `--stats`
+    #[arg(long)]
+    pub skip_empty_files: bool,
+
+    /// also store a digest of each resource's path relative to its root path in `uniform_resource.path_digest`
+    #[arg(long)]
+    pub hash_filenames: bool,
+
+    /// run capturable executables in the order of their `order` named capture (or leading file name number)
+    #[arg(long)]
+    pub ordered_capture: bool,
+
+    /// extract `name=/root/elem` (or `name=/root/elem/@attribute`) from XML into `elaboration.xml_select`
+    #[arg(long, value_parser = parse_xml_select)]
+    pub xml_select: Vec<(String, String)>,
+
+    /// load the JSON Schema in a file as `name=path.schema.json` (see `--json-schema-apply`)
+    #[arg(long, value_parser = parse_json_schema)]
+    pub json_schema: Vec<(String, String)>,
+
+    /// validate JSON resources whose URI matches `regex=name` with that `--json-schema`, recording problems in `validation_issue`
+    #[arg(long, value_parser = parse_json_schema_apply)]
+    pub json_schema_apply: Vec<(String, String)>,
+
+    /// detect the nature of binary formats from their leading bytes, preferring it over the extension
+    #[arg(long)]
+    pub sniff_content_nature: bool,
+
+    /// when a root path is inside a git repository, record each tracked file's last commit in its elaboration
+    #[arg(long)]
+    pub git_metadata: bool,
+
+    /// algorithm for content digests (non-SHA-1 digests are stored as `<algo>:<hex>`)
+    #[arg(long, value_enum, default_value = "sha1")]
+    pub digest_algo: crate::resource::DigestAlgorithm,
+
+    /// how to acquire text content which isn't valid UTF-8
+    #[arg(long, value_enum, default_value = "error")]
+    pub text_encoding_fallback: crate::resource::TextEncodingFallback,
+
+    /// only acquire content as text (binary content suppliers aren't created)
+    #[arg(long, conflicts_with = "content_binary_only")]
+    pub content_text_only: bool,
+
+    /// only acquire content as binary, storing text natures as binary too (text isn't decoded)
+    #[arg(long)]
+    pub content_binary_only: bool,
+
+    /// catalog resources' metadata (URI, nature, size, timestamps) without reading, hashing or storing content
+    #[arg(long, conflicts_with_all = ["content_text_only", "content_binary_only"])]
+    pub manifest_only: bool,
+
+    /// also write the ingested resources' metadata (no content) to this Parquet file
+    #[arg(long)]
+    pub parquet_out: Option<String>,
+
+    /// read the ignore/acquire/capture classification rules from this JSON file instead of the RSSD's defaults (other flags still apply on top)
+    #[arg(long)]
+    pub path_rules_json: Option<String>,
+
+    /// print the effective classification rules (defaults, --path-rules-json and flags) as JSON and exit without ingesting
+    #[arg(long)]
+    pub emit_path_rules_json: bool,
+
     /// save the options as a new behavior
     #[arg(long)]
     pub save_behavior: Option<String>,
+
+    /// write a chrome-trace (flamegraph-friendly) JSON profile of the ingest phases (requires `profile` feature)
+    #[arg(long)]
+    pub profile_output: Option<String>,
+
+    /// show how long walking, classifying, hashing and persisting took after completion (JSON with `--output json`)
+    #[arg(long)]
+    pub profile: bool,
 }
 
 /// Notebooks maintenance utilities
@@ -237,6 +1038,14 @@ pub struct IngestTasksArgs {
     #[arg(long)]
     pub stdin: bool,
 
+    /// one or more task lines (`@file` reads the lines from a file); STDIN is read when none are given
+    #[arg(short, long)]
+    pub task: Vec<String>,
+
+    /// nature of the output of task lines which are plain commands rather than JSON objects
+    #[arg(long, default_value = "json")]
+    pub task_default_nature: String,
+
     /// show session stats after completion
     #[arg(long)]
     pub stats: bool,
@@ -246,12 +1055,71 @@ pub struct IngestTasksArgs {
     pub stats_json: bool,
 }
 
+/// Ingest content from HTTP/HTTPS URLs
+#[derive(Debug, Serialize, Args)]
+pub struct IngestUrlsArgs {
+    /// target SQLite database
+    #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+    pub state_db_fs_path: String,
+
+    /// one or more globs to match as SQL files and batch execute them in alpha order
+    #[arg(short = 'I', long)]
+    pub state_db_init_sql: Vec<String>,
+
+    /// one or more URLs to fetch (`@file` reads the URLs from a file); STDIN is read when none are given
+    #[arg(short, long)]
+    pub url: Vec<String>,
+}
+
+/// Ingest messages (and their attachments) from an IMAP mailbox
+#[derive(Debug, Serialize, Args)]
+pub struct IngestImapArgs {
+    /// target SQLite database
+    #[arg(short='d', long, default_value = DEFAULT_STATEDB_FS_PATH, default_missing_value = "always", env="SURVEILR_STATEDB_FS_PATH")]
+    pub state_db_fs_path: String,
+
+    /// one or more globs to match as SQL files and batch execute them in alpha order
+    #[arg(short = 'I', long)]
+    pub state_db_init_sql: Vec<String>,
+
+    /// IMAP server host name
+    #[arg(long)]
+    pub host: String,
+
+    /// IMAP server port
+    #[arg(long, default_value_t = 993)]
+    pub port: u16,
+
+    /// connect without TLS (e.g. to a local bridge)
+    #[arg(long)]
+    pub no_tls: bool,
+
+    /// user to log in as
+    #[arg(short, long)]
+    pub username: String,
+
+    /// password of the user, best given through the environment
+    #[arg(long, env = "SURVEILR_IMAP_PASSWORD", hide_env_values = true)]
+    #[serde(skip)]
+    pub password: String,
+
+    /// the mailbox to ingest
+    #[arg(short, long, default_value = "INBOX")]
+    pub mailbox: String,
+
+    /// only ingest messages which arrived on or after this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub since: Option<chrono::NaiveDate>,
+}
+
 /// Ingest uniform resources content from multiple sources
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, Subcommand)]
 pub enum IngestCommands {
     Files(IngestFilesArgs),
     Tasks(IngestTasksArgs),
+    Urls(IngestUrlsArgs),
+    Imap(IngestImapArgs),
 }
 
 /// Notebooks maintenance utilities
@@ -284,6 +1152,37 @@ pub enum NotebooksCommands {
         /// add separators before each cell
         #[arg(short, long)]
         seps: bool,
+
+        /// write each cell to `DIR/{notebook}/{cell}.sql` instead of STDOUT
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<String>,
+    },
+
+    /// execute a single notebook cell and record its state transition
+    Exec {
+        /// the notebook which contains the cell
+        #[arg(short, long)]
+        notebook: String,
+
+        /// the cell to execute (its most recent version)
+        #[arg(short, long)]
+        cell: String,
+
+        /// the state the cell is transitioning from
+        #[arg(long, default_value = "NONE")]
+        from_state: String,
+
+        /// the state the cell is transitioning to, a cell already in this state is not executed again
+        #[arg(long, default_value = "EXECUTED")]
+        to_state: String,
+
+        /// why the cell is being executed (recorded with the state transition)
+        #[arg(long, default_value = "notebooks exec")]
+        reason: String,
+
+        /// print the SQL instead of executing it
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// list all notebooks
@@ -291,6 +1190,47 @@ pub enum NotebooksCommands {
         /// list all SQL cells that will be handled by execute_migrations
         #[arg(short, long)]
         migratable: bool,
+
+        /// list only the migratable cells which are pending (or failed)
+        #[arg(long)]
+        pending_only: bool,
+    },
+
+    /// warn about migratable cells whose SQL is likely not idempotent (e.g. `CREATE TABLE` without `IF NOT EXISTS`)
+    Lint {
+        /// exit with an error if any cell is flagged
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+/// Deno Task Shell utilities
+#[derive(Debug, Serialize, Args)]
+pub struct ShellArgs {
+    #[command(subcommand)]
+    pub command: ShellCommands,
+}
+
+#[derive(Debug, Serialize, Subcommand)]
+pub enum ShellCommands {
+    /// execute a Deno Task Shell command (`&&`, `||`, `;`, pipes, `VAR=val cmd`, etc.) and emit the result as JSON
+    Json {
+        /// the Deno Task Shell command text to execute
+        #[arg(short, long)]
+        command: String,
+
+        /// use this as the current working directory (CWD)
+        #[arg(long)]
+        cwd: Option<String>,
+
+        /// set KEY=VAL (repeatable) in the command's environment
+        #[arg(short, long)]
+        #[serde(skip)] // injected values are often secrets
+        env: Vec<String>,
+
+        /// only emit STDOUT (parsed as JSON), not the status and STDERR
+        #[arg(long)]
+        stdout_only: bool,
     },
 }
 
@@ -301,6 +1241,7 @@ impl CliCommands {
             CliCommands::CapturableExec(args) => args.command.execute(cli, args),
             CliCommands::Ingest(args) => args.command.execute(cli, args),
             CliCommands::Notebooks(args) => args.command.execute(cli, args),
+            CliCommands::Shell(args) => args.command.execute(cli, args),
         }
     }
 }
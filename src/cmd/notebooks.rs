@@ -1,3 +1,7 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Context;
 use rusqlite::{Connection, OpenFlags};
 
 use super::NotebooksCommands;
@@ -7,20 +11,35 @@ use crate::persist::*;
 // Implement methods for `NotebooksCommands`, ensure that whether the commands
 // are called from CLI or natively within Rust, all the calls remain ergonomic.
 impl NotebooksCommands {
-    pub fn execute(&self, _cli: &super::Cli, args: &super::NotebooksArgs) -> anyhow::Result<()> {
+    pub fn execute(&self, cli: &super::Cli, args: &super::NotebooksArgs) -> anyhow::Result<()> {
         match self {
             NotebooksCommands::Cat {
                 notebook,
                 cell,
                 seps,
-            } => self.cat(args, notebook, cell, *seps),
-            NotebooksCommands::Ls { migratable } => {
-                if *migratable {
-                    self.ls_migrations(args)
+                out_dir,
+            } => self.cat(args, notebook, cell, *seps, out_dir.as_deref()),
+            NotebooksCommands::Exec {
+                notebook,
+                cell,
+                from_state,
+                to_state,
+                reason,
+                dry_run,
+            } => self.exec(args, notebook, cell, from_state, to_state, reason, *dry_run),
+            NotebooksCommands::Ls {
+                migratable,
+                pending_only,
+            } => {
+                if *pending_only {
+                    self.ls_pending(args, cli.output)
+                } else if *migratable {
+                    self.ls_migrations(args, cli.output)
                 } else {
-                    self.ls(args)
+                    self.ls(args, cli.output)
                 }
             }
+            NotebooksCommands::Lint { strict } => self.lint(args, cli.output, *strict),
         }
     }
 
@@ -30,21 +49,25 @@ impl NotebooksCommands {
         notebooks: &Vec<String>,
         cells: &Vec<String>,
         seps: bool,
+        out_dir: Option<&str>,
     ) -> anyhow::Result<()> {
         if let Some(db_fs_path) = args.state_db_fs_path.as_deref() {
             if let Ok(conn) =
                 Connection::open_with_flags(db_fs_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
             {
                 match select_notebooks_and_cells(&conn, notebooks, cells) {
-                    Ok(matched) => {
-                        for row in matched {
-                            let (notebook, kernel, cell, code) = row;
-                            if seps {
-                                println!("-- {notebook}::{cell} ({kernel})");
+                    Ok(matched) => match out_dir {
+                        Some(out_dir) => self.cat_to_files(matched, out_dir, seps)?,
+                        None => {
+                            for row in matched {
+                                let (notebook, kernel, cell, code) = row;
+                                if seps {
+                                    println!("-- {notebook}::{cell} ({kernel})");
+                                }
+                                println!("{code}");
                             }
-                            println!("{code}");
                         }
-                    }
+                    },
                     Err(err) => println!("Notebooks cells command error: {}", err),
                 }
             } else {
@@ -57,7 +80,142 @@ impl NotebooksCommands {
         Ok(())
     }
 
-    fn ls(&self, args: &super::NotebooksArgs) -> anyhow::Result<()> {
+    // cells which share a name (e.g. several versions of the same cell) would
+    // overwrite each other so all but the first get their index in the file name
+    fn cat_to_files(
+        &self,
+        matched: Vec<(String, String, String, String)>,
+        out_dir: &str,
+        seps: bool,
+    ) -> anyhow::Result<()> {
+        let mut written = HashSet::new();
+        for (index, (notebook, kernel, cell, code)) in matched.into_iter().enumerate() {
+            let nb_dir = Path::new(out_dir).join(to_file_name_safe(&notebook));
+            std::fs::create_dir_all(&nb_dir).with_context(|| {
+                format!("[NotebooksCommands::cat] creating {}", nb_dir.display())
+            })?;
+            let cell_name = to_file_name_safe(&cell);
+            let mut path = nb_dir.join(format!("{cell_name}.sql"));
+            if !written.insert(path.clone()) {
+                path = nb_dir.join(format!("{cell_name}-{index}.sql"));
+                written.insert(path.clone());
+            }
+            let content = if seps {
+                format!("-- {notebook}::{cell} ({kernel})\n{code}\n")
+            } else {
+                format!("{code}\n")
+            };
+            std::fs::write(&path, content)
+                .with_context(|| format!("[NotebooksCommands::cat] writing {}", path.display()))?;
+            println!("{}", path.display());
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn exec(
+        &self,
+        args: &super::NotebooksArgs,
+        notebook: &str,
+        cell: &str,
+        from_state: &str,
+        to_state: &str,
+        reason: &str,
+        dry_run: bool,
+    ) -> anyhow::Result<()> {
+        let Some(db_fs_path) = args.state_db_fs_path.as_deref() else {
+            return Ok(());
+        };
+        let conn = Connection::open_with_flags(db_fs_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+            .with_context(|| format!("[NotebooksCommands::exec] opening {}", db_fs_path))?;
+        prepare_conn(&conn)
+            .with_context(|| format!("[NotebooksCommands::exec] prepare_conn {}", db_fs_path))?;
+
+        let (_id, code) = match select_notebook_cell_code_latest(&conn, notebook, cell) {
+            Ok(found) => found,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                anyhow::bail!(
+                    "[NotebooksCommands::exec] no cell {notebook}::{cell} in {db_fs_path}"
+                )
+            }
+            Err(err) => Err(err).with_context(|| {
+                format!("[NotebooksCommands::exec] reading {notebook}::{cell} in {db_fs_path}")
+            })?,
+        };
+        let already = !matches!(
+            is_notebook_cell_state(&conn, notebook, cell, from_state, to_state),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        );
+
+        if dry_run {
+            if already {
+                println!("-- {notebook}::{cell} is already {to_state}, it would not be executed");
+            }
+            println!("{code}");
+            return Ok(());
+        }
+
+        let ec = ExecutableCode::NotebookCell {
+            notebook_name: notebook.to_string(),
+            cell_name: cell.to_string(),
+        };
+        match execute_batch_stateful(&conn, &ec, from_state, to_state, reason) {
+            None => println!("{notebook}::{cell} is already {to_state}, not executed"),
+            Some(Ok(_)) => println!("{notebook}::{cell} executed ({from_state} -> {to_state})"),
+            Some(Err(err)) => Err(err).with_context(|| {
+                format!("[NotebooksCommands::exec] executing {notebook}::{cell} in {db_fs_path}")
+            })?,
+        }
+        Ok(())
+    }
+
+    // a heuristic: a migration which failed half-way is re-executed from the
+    // start so each statement must tolerate having been applied already
+    fn lint(
+        &self,
+        args: &super::NotebooksArgs,
+        output: super::OutputFormat,
+        strict: bool,
+    ) -> anyhow::Result<()> {
+        let Some(db_fs_path) = args.state_db_fs_path.as_deref() else {
+            anyhow::bail!("[NotebooksCommands::lint] requires a database (-d)");
+        };
+        let conn = Connection::open_with_flags(db_fs_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+            .with_context(|| format!("[NotebooksCommands::lint] opening {}", db_fs_path))?;
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        migratable_notebook_cells_uniq_all(
+            &conn,
+            |_index, notebook_name, cell_name, sql, _hash, _id: String| {
+                for warning in non_idempotent_statements(&sql) {
+                    rows.push(vec![notebook_name.clone(), cell_name.clone(), warning]);
+                }
+                Ok(())
+            },
+        )
+        .with_context(|| format!("[NotebooksCommands::lint] reading cells in {}", db_fs_path))?;
+
+        if rows.is_empty() {
+            if output == super::OutputFormat::Table {
+                println!("No likely non-idempotent statements in migratable cells");
+            }
+            return Ok(());
+        }
+        println!(
+            "{}",
+            as_output_text(output, &["Notebook", "Cell", "Warning"], &rows)
+        );
+        if strict {
+            anyhow::bail!(
+                "[NotebooksCommands::lint] {} likely non-idempotent statements in {}",
+                rows.len(),
+                db_fs_path
+            );
+        }
+        Ok(())
+    }
+
+    fn ls(&self, args: &super::NotebooksArgs, output: super::OutputFormat) -> anyhow::Result<()> {
         if let Some(db_fs_path) = args.state_db_fs_path.as_deref() {
             if let Ok(conn) =
                 Connection::open_with_flags(db_fs_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
@@ -70,7 +228,11 @@ impl NotebooksCommands {
                 .unwrap();
                 println!(
                     "{}",
-                    as_ascii_table(&["Notebook", "Kernel", "Cell", "Versions", "ID"], &rows)
+                    as_output_text(
+                        output,
+                        &["Notebook", "Kernel", "Cell", "Versions", "ID"],
+                        &rows
+                    )
                 );
             } else {
                 println!("Notebooks command requires a database: {}", db_fs_path);
@@ -79,11 +241,77 @@ impl NotebooksCommands {
         Ok(())
     }
 
-    fn ls_migrations(&self, args: &super::NotebooksArgs) -> anyhow::Result<()> {
+    fn ls_pending(
+        &self,
+        args: &super::NotebooksArgs,
+        output: super::OutputFormat,
+    ) -> anyhow::Result<()> {
+        if let Some(db_fs_path) = args.state_db_fs_path.as_deref() {
+            if let Ok(conn) =
+                Connection::open_with_flags(db_fs_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+            {
+                let status = self.migrations_status_section(&conn, db_fs_path, true)?;
+                if output == super::OutputFormat::Table {
+                    println!("{}", status.caption);
+                }
+                println!("{}", as_output_text(output, status.headers, &status.rows));
+            } else {
+                println!("Notebooks command requires a database: {}", db_fs_path);
+            };
+        }
+        Ok(())
+    }
+
+    // `applied` and `failed` cells have a state recorded by execute_migrations,
+    // `always` cells are executed in every session so they are never pending
+    fn migrations_status_section(
+        &self,
+        conn: &Connection,
+        db_fs_path: &str,
+        pending_only: bool,
+    ) -> anyhow::Result<OutputSection<'static>> {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        migratable_notebook_cells_status(
+            conn,
+            |_index, notebook_name, cell_name, hash, status, transitioned_at, id| {
+                if !pending_only || status == "pending" || status == "failed" {
+                    rows.push(vec![
+                        notebook_name,
+                        cell_name,
+                        status,
+                        transitioned_at.unwrap_or_default(),
+                        hash,
+                        id,
+                    ]);
+                }
+                Ok(())
+            },
+        )
+        .with_context(|| {
+            format!(
+                "[NotebooksCommands::migrations_status_section] in {}",
+                db_fs_path
+            )
+        })?;
+        Ok(OutputSection {
+            name: "status",
+            caption: "Migration status of all migratable cells (unique rows)",
+            headers: &["Notebook", "Cell", "Status", "When", "Code Hash", "ID"],
+            rows,
+        })
+    }
+
+    fn ls_migrations(
+        &self,
+        args: &super::NotebooksArgs,
+        output: super::OutputFormat,
+    ) -> anyhow::Result<()> {
         if let Some(db_fs_path) = args.state_db_fs_path.as_deref() {
             if let Ok(conn) =
                 Connection::open_with_flags(db_fs_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
             {
+                let mut sections: Vec<OutputSection> = Vec::new();
+
                 let mut rows: Vec<Vec<String>> = Vec::new(); // Declare the rows as a vector of vectors of strings
                 migratable_notebook_cells_all_with_versions(
                     &conn,
@@ -93,11 +321,12 @@ impl NotebooksCommands {
                     },
                 )
                 .unwrap();
-                println!("All cells that are candidates for migration (including duplicates)");
-                println!(
-                    "{}",
-                    as_ascii_table(&["Notebook", "Cell", "Code Hash", "ID"], &rows)
-                );
+                sections.push(OutputSection {
+                    name: "candidates",
+                    caption: "All cells that are candidates for migration (including duplicates)",
+                    headers: &["Notebook", "Cell", "Code Hash", "ID"],
+                    rows,
+                });
 
                 let mut rows: Vec<Vec<String>> = Vec::new(); // Declare the rows as a vector of vectors of strings
                 migratable_notebook_cells_uniq_all(
@@ -108,11 +337,14 @@ impl NotebooksCommands {
                     },
                 )
                 .unwrap();
-                println!("All cells deemed to be migratable (unique rows)");
-                println!(
-                    "{}",
-                    as_ascii_table(&["Notebook", "Cell", "Code Hash", "ID"], &rows)
-                );
+                sections.push(OutputSection {
+                    name: "migratable",
+                    caption: "All cells deemed to be migratable (unique rows)",
+                    headers: &["Notebook", "Cell", "Code Hash", "ID"],
+                    rows,
+                });
+
+                sections.push(self.migrations_status_section(&conn, db_fs_path, false)?);
 
                 let mut rows: Vec<Vec<String>> = Vec::new(); // Declare the rows as a vector of vectors of strings
                 migratable_notebook_cells_not_executed(
@@ -123,11 +355,13 @@ impl NotebooksCommands {
                     },
                 )
                 .unwrap();
-                println!("All cells that should be migrated because they have not been executed");
-                println!(
-                    "{}",
-                    as_ascii_table(&["Notebook", "Cell", "Code Hash", "ID"], &rows)
-                );
+                sections.push(OutputSection {
+                    name: "not_executed",
+                    caption:
+                        "All cells that should be migrated because they have not been executed",
+                    headers: &["Notebook", "Cell", "Code Hash", "ID"],
+                    rows,
+                });
 
                 let mut rows: Vec<Vec<String>> = Vec::new(); // Declare the rows as a vector of vectors of strings
                 notebook_cell_states(
@@ -156,17 +390,16 @@ impl NotebooksCommands {
                     },
                 )
                 .unwrap();
-                println!("code_notebook_state");
-                println!(
-                    "{}",
-                    as_ascii_table(
-                        &[
-                            "Notebook", "Kernel", "Cell", "From", "To", "Remarks", "When",
-                            "Cell ID"
-                        ],
-                        &rows
-                    )
-                );
+                sections.push(OutputSection {
+                    name: "code_notebook_state",
+                    caption: "code_notebook_state",
+                    headers: &[
+                        "Notebook", "Kernel", "Cell", "From", "To", "Remarks", "When", "Cell ID",
+                    ],
+                    rows,
+                });
+
+                println!("{}", as_output_sections_text(output, &sections));
             } else {
                 println!("Notebooks command requires a database: {}", db_fs_path);
             };
@@ -174,3 +407,193 @@ impl NotebooksCommands {
         Ok(())
     }
 }
+
+// comments and the content of string literals are dropped so that neither
+// keywords nor `;` in them are mistaken for SQL
+fn sql_without_comments_and_literals(sql: &str) -> String {
+    let mut stripped = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        stripped.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                stripped.push(' ');
+            }
+            '\'' => {
+                // `''` inside a literal is an escaped quote, the literal goes on
+                while let Some(c) = chars.next() {
+                    if c == '\'' && chars.peek() != Some(&'\'') {
+                        break;
+                    }
+                    if c == '\'' {
+                        chars.next();
+                    }
+                }
+                stripped.push_str("''");
+            }
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+const CREATE_MODIFIERS: &[&str] = &["TEMP", "TEMPORARY", "UNIQUE", "VIRTUAL"];
+
+/// Statements which would likely fail (or duplicate rows) if executed again:
+/// `CREATE TABLE`, `INDEX`, `VIEW` or `TRIGGER` without `IF NOT EXISTS` and
+/// `INSERT` without `OR IGNORE`, `OR REPLACE`, `ON CONFLICT` or a `NOT EXISTS`
+/// guard. Statements in trigger bodies are executed by the trigger, not the
+/// cell, so aren't flagged.
+pub fn non_idempotent_statements(sql: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut in_trigger = false;
+    for statement in sql_without_comments_and_literals(sql).split(';') {
+        let words: Vec<String> = statement
+            .split(|c: char| c.is_whitespace() || "(),".contains(c))
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_uppercase())
+            .collect();
+        if words.is_empty() {
+            continue;
+        }
+        if in_trigger {
+            in_trigger = words.last().map(String::as_str) != Some("END");
+            continue;
+        }
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match words.as_slice() {
+            ["CREATE", rest @ ..] => {
+                let rest: Vec<&str> = rest
+                    .iter()
+                    .copied()
+                    .skip_while(|word| CREATE_MODIFIERS.contains(word))
+                    .collect();
+                let Some(kind) = rest.first() else {
+                    continue;
+                };
+                if *kind == "TRIGGER" && words.contains(&"BEGIN") {
+                    in_trigger = words.last() != Some(&"END");
+                }
+                if matches!(*kind, "TABLE" | "INDEX" | "VIEW" | "TRIGGER")
+                    && rest.get(1..4) != Some(&["IF", "NOT", "EXISTS"][..])
+                {
+                    warnings.push(format!(
+                        "CREATE {} {} without IF NOT EXISTS",
+                        kind,
+                        rest.get(1).unwrap_or(&"")
+                    ));
+                }
+            }
+            ["INSERT", rest @ ..] => {
+                if !matches!(rest, ["OR", "IGNORE" | "REPLACE", ..])
+                    && !words.windows(2).any(|pair| pair == ["ON", "CONFLICT"])
+                    && !words.windows(2).any(|pair| pair == ["NOT", "EXISTS"])
+                {
+                    let table = rest
+                        .iter()
+                        .position(|word| *word == "INTO")
+                        .and_then(|into| rest.get(into + 1))
+                        .unwrap_or(&"");
+                    warnings.push(format!("INSERT INTO {} without ON CONFLICT", table));
+                }
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_non_idempotent_create() {
+        assert_eq!(
+            non_idempotent_statements(
+                "CREATE TABLE a (id TEXT); CREATE UNIQUE INDEX a_id ON a(id); CREATE VIEW v AS SELECT 1"
+            ),
+            vec![
+                "CREATE TABLE A without IF NOT EXISTS",
+                "CREATE INDEX A_ID without IF NOT EXISTS",
+                "CREATE VIEW V without IF NOT EXISTS",
+            ]
+        );
+        assert!(non_idempotent_statements(
+            "CREATE TABLE IF NOT EXISTS a (id TEXT);
+             CREATE UNIQUE INDEX IF NOT EXISTS a_id ON a(id);
+             CREATE TEMP VIEW IF NOT EXISTS v AS SELECT 1;"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_non_idempotent_insert() {
+        assert_eq!(
+            non_idempotent_statements("INSERT INTO a (id) VALUES ('x')"),
+            vec!["INSERT INTO A without ON CONFLICT"]
+        );
+        assert!(non_idempotent_statements(
+            "INSERT OR IGNORE INTO a (id) VALUES ('x');
+             INSERT OR REPLACE INTO a (id) VALUES ('x');
+             INSERT INTO a (id) VALUES ('x') ON CONFLICT (id) DO NOTHING;
+             INSERT INTO a (id) SELECT 'x' WHERE NOT EXISTS (SELECT 1 FROM a WHERE id = 'x');"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_non_idempotent_trigger_body() {
+        assert!(non_idempotent_statements(
+            "CREATE TRIGGER IF NOT EXISTS a_audit AFTER INSERT ON a BEGIN
+                INSERT INTO a_log (id) VALUES (NEW.id);
+                INSERT INTO a_log (id) VALUES (NEW.id);
+             END;"
+        )
+        .is_empty());
+        assert_eq!(
+            non_idempotent_statements(
+                "CREATE TRIGGER a_audit AFTER INSERT ON a BEGIN
+                    INSERT INTO a_log (id) VALUES (NEW.id);
+                 END;
+                 INSERT INTO b (id) VALUES (1);"
+            ),
+            vec![
+                "CREATE TRIGGER A_AUDIT without IF NOT EXISTS",
+                "INSERT INTO B without ON CONFLICT",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_idempotent_ignores_literals_and_comments() {
+        assert!(non_idempotent_statements(
+            "-- CREATE TABLE a (id TEXT);
+             /* INSERT INTO a VALUES (1); */
+             SELECT 'CREATE TABLE b (id TEXT); INSERT INTO b VALUES (''x'')';"
+        )
+        .is_empty());
+        assert_eq!(
+            non_idempotent_statements(
+                "INSERT INTO a (id) VALUES ('ON CONFLICT'); -- ON CONFLICT DO NOTHING"
+            ),
+            vec!["INSERT INTO A without ON CONFLICT"]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use serde_json::json;
+
+use super::ShellCommands;
+use crate::shell::*;
+
+// Implement methods for `ShellCommands`, ensure that whether the commands
+// are called from CLI or natively within Rust, all the calls remain ergonomic.
+impl ShellCommands {
+    pub fn execute(&self, _cli: &super::Cli, _args: &super::ShellArgs) -> anyhow::Result<()> {
+        match self {
+            ShellCommands::Json {
+                command,
+                cwd,
+                env,
+                stdout_only,
+            } => self.json(command, cwd.as_deref(), env, *stdout_only),
+        }
+    }
+
+    // the Deno Task Shell only reports the exit code of the whole command so a
+    // sequence like `a && b` has a single (aggregate) status; failures are also
+    // emitted as JSON (never as an error) so the output can always be piped
+    fn json(
+        &self,
+        command: &str,
+        cwd: Option<&str>,
+        env: &[String],
+        stdout_only: bool,
+    ) -> anyhow::Result<()> {
+        match self.executed(command, cwd, env) {
+            Ok(result) if result.success() && stdout_only => {
+                println!("{}", result.stdout_json_text(None))
+            }
+            Ok(result) if result.success() => println!("{}", result.json_text(None)),
+            Ok(result) => {
+                let mut failure = result.json();
+                failure["command"] = json!(command);
+                // the shell reports commands it can't parse without an exit status
+                let (issue, remediation) = match result.status {
+                    subprocess::ExitStatus::Undetermined => (
+                        "[ShellCommands::json] unable to parse command",
+                        "ensure that the command is valid Deno Task Shell syntax",
+                    ),
+                    _ => (
+                        "[ShellCommands::json] invalid exit status",
+                        "ensure that the command is called with proper arguments and input formats",
+                    ),
+                };
+                failure["issue"] = json!(issue);
+                failure["remediation"] = json!(remediation);
+                println!("{}", serde_json::to_string_pretty(&failure)?);
+            }
+            Err(failure) => println!("{}", serde_json::to_string_pretty(&failure)?),
+        }
+        Ok(())
+    }
+
+    fn executed(
+        &self,
+        command: &str,
+        cwd: Option<&str>,
+        env: &[String],
+    ) -> Result<ShellResult, serde_json::Value> {
+        let env_policy = ShellEnvPolicy::from_args(env, false).map_err(|err| {
+            json!({
+                "command": command,
+                "issue": "[ShellCommands::json] invalid --env",
+                "remediation": "pass each variable as KEY=VAL (or just KEY)",
+                "rust-err": format!("{:?}", err),
+            })
+        })?;
+        let mut executive =
+            DenoTaskShellExecutive::new(command.to_string(), None).env_policy(&env_policy);
+        if let Some(cwd) = cwd {
+            let cwd = std::fs::canonicalize(cwd).map_err(|err| {
+                json!({
+                    "command": command,
+                    "issue": "[ShellCommands::json] invalid --cwd",
+                    "remediation": "make sure that the --cwd directory exists",
+                    "cwd": cwd,
+                    "rust-err": format!("{:?}", err),
+                })
+            })?;
+            executive = executive.cwd(&cwd);
+        }
+        executive.execute(ShellStdIn::None, None).map_err(|err| {
+            json!({
+                "command": command,
+                "issue": "[ShellCommands::json] execution error",
+                "rust-err": format!("{:?}", err),
+            })
+        })
+    }
+}
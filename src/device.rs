@@ -1,13 +1,69 @@
+use serde::Serialize;
 use serde_json::json;
 use sysinfo::{System, SystemExt};
 
+pub const DEVICE_NAME_ENV_VAR: &str = "SURVEILR_DEVICE_NAME";
+pub const MACHINE_ID_FS_PATH: &str = "/etc/machine-id";
+
+/// Where a device's name came from, recorded in `device.elaboration`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceNameSource {
+    Flag,
+    Env,
+    MachineId,
+    Hostname,
+}
+
 pub struct Device {
     pub name: String,
     pub boundary: Option<String>,
+    pub name_source: DeviceNameSource,
 }
 
 impl Device {
-    pub fn new(boundary: Option<String>) -> Device {
+    /// Resolves the name from, in order, `name` (`--device-name`), the
+    /// `SURVEILR_DEVICE_NAME` environment variable, the machine-id and the
+    /// hostname; hostnames are often random per container, machine-ids aren't.
+    pub fn new(name: Option<String>, boundary: Option<String>) -> Device {
+        if let Some(name) = name.filter(|name| !name.trim().is_empty()) {
+            return Device {
+                name,
+                boundary,
+                name_source: DeviceNameSource::Flag,
+            };
+        }
+        if let Some(name) = std::env::var(DEVICE_NAME_ENV_VAR)
+            .ok()
+            .filter(|name| !name.trim().is_empty())
+        {
+            return Device {
+                name,
+                boundary,
+                name_source: DeviceNameSource::Env,
+            };
+        }
+        match Device::from_machine_id(boundary.clone()) {
+            Some(device) => device,
+            None => Device::from_hostname(boundary),
+        }
+    }
+
+    /// The device named by `/etc/machine-id`, if it exists and isn't empty.
+    pub fn from_machine_id(boundary: Option<String>) -> Option<Device> {
+        let machine_id = std::fs::read_to_string(MACHINE_ID_FS_PATH).ok()?;
+        let name = machine_id.trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some(Device {
+            name: name.to_owned(),
+            boundary,
+            name_source: DeviceNameSource::MachineId,
+        })
+    }
+
+    pub fn from_hostname(boundary: Option<String>) -> Device {
         let name = hostname::get()
             .map(|os_str| {
                 os_str
@@ -16,11 +72,11 @@ impl Device {
             })
             .unwrap_or_else(|_| "unknown".to_owned());
 
-        Device { name, boundary }
-    }
-
-    pub fn name(&self) -> &str {
-        &self.name
+        Device {
+            name,
+            boundary,
+            name_source: DeviceNameSource::Hostname,
+        }
     }
 
     pub fn state_json(&self) -> String {
@@ -34,4 +90,8 @@ impl Device {
 
         serde_json::to_string_pretty(&sys).unwrap()
     }
+
+    pub fn elaboration_json(&self) -> String {
+        serde_json::to_string(&json!({ "name_source": self.name_source })).unwrap()
+    }
 }
@@ -0,0 +1,148 @@
+use mail_parser::{Address, MessageParser, MimeHeaders};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmailMessage {
+    pub message_id: Option<String>,
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub subject: Option<String>,
+    pub sent_at: Option<String>,
+    pub body_text: Option<String>,
+}
+
+fn addresses(address: Option<&Address>) -> Vec<String> {
+    match address {
+        Some(address) => address
+            .clone()
+            .into_list()
+            .iter()
+            .filter_map(|addr| addr.address.as_ref().map(|a| a.to_string()))
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Parses an RFC 822 (`.eml`) message and returns the commonly queried headers
+/// (From, To, Subject, Date, Message-ID) along with the first text body part.
+/// Returns `None` if the raw bytes cannot be parsed as a message at all.
+pub fn email_message(raw: &[u8]) -> Option<EmailMessage> {
+    let message = MessageParser::default().parse(raw)?;
+    Some(EmailMessage {
+        message_id: message.message_id().map(String::from),
+        from: addresses(message.from()).into_iter().next(),
+        to: addresses(message.to()),
+        subject: message.subject().map(String::from),
+        sent_at: message.date().map(|date| date.to_rfc3339()),
+        body_text: message.body_text(0).map(|body| body.to_string()),
+    })
+}
+
+/// A part of a message which is an attachment rather than its body.
+#[derive(Debug, Clone)]
+pub struct EmailAttachment {
+    pub name: String,
+    pub content_type: Option<String>,
+    pub content: Vec<u8>,
+}
+
+/// The attachments of an RFC 822 message; unnamed attachments are named by
+/// their position (`attachment-1`, ...).
+pub fn email_attachments(raw: &[u8]) -> Vec<EmailAttachment> {
+    let Some(message) = MessageParser::default().parse(raw) else {
+        return vec![];
+    };
+    message
+        .attachments()
+        .enumerate()
+        .map(|(index, part)| EmailAttachment {
+            name: part
+                .attachment_name()
+                .map(String::from)
+                .unwrap_or_else(|| format!("attachment-{}", index + 1)),
+            content_type: part.content_type().map(|ct| match ct.subtype() {
+                Some(subtype) => format!("{}/{}", ct.ctype(), subtype),
+                None => ct.ctype().to_string(),
+            }),
+            content: part.contents().to_vec(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_email_message() {
+        let raw = "From: Alice Example <alice@example.com>\r\n\
+                   To: bob@example.com, carol@example.com\r\n\
+                   Subject: Quarterly report\r\n\
+                   Date: Mon, 4 Dec 2023 10:15:00 +0000\r\n\
+                   Message-ID: <report-1@example.com>\r\n\
+                   \r\n\
+                   Please see the attached numbers.\r\n";
+        let email = email_message(raw.as_bytes()).unwrap();
+        assert_eq!(email.message_id.as_deref(), Some("report-1@example.com"));
+        assert_eq!(email.from.as_deref(), Some("alice@example.com"));
+        assert_eq!(email.to, vec!["bob@example.com", "carol@example.com"]);
+        assert_eq!(email.subject.as_deref(), Some("Quarterly report"));
+        assert_eq!(email.sent_at.as_deref(), Some("2023-12-04T10:15:00Z"));
+        assert_eq!(
+            email.body_text.as_deref().map(str::trim_end),
+            Some("Please see the attached numbers.")
+        );
+    }
+
+    #[test]
+    fn test_email_message_fixture() {
+        let raw = include_bytes!("../support/test-fixtures/email-message.eml");
+        let email = email_message(raw).unwrap();
+        assert_eq!(email.message_id.as_deref(), Some("x1@example.com"));
+        assert_eq!(email.from.as_deref(), Some("alice@example.com"));
+        assert_eq!(email.to, vec!["bob@example.com"]);
+        assert_eq!(email.subject.as_deref(), Some("Hello"));
+        assert_eq!(email.sent_at.as_deref(), Some("2023-12-04T10:15:00Z"));
+        assert_eq!(
+            email.body_text.as_deref().map(str::trim_end),
+            Some("Hi Bob.")
+        );
+    }
+
+    #[test]
+    fn test_email_message_without_headers() {
+        let email = email_message(b"just a body without any headers").unwrap();
+        assert_eq!(email.from, None);
+        assert!(email.to.is_empty());
+        assert_eq!(email.subject, None);
+    }
+
+    #[test]
+    fn test_email_attachments() {
+        let raw = "From: alice@example.com\r\n\
+                   Subject: Numbers\r\n\
+                   MIME-Version: 1.0\r\n\
+                   Content-Type: multipart/mixed; boundary=\"b\"\r\n\
+                   \r\n\
+                   --b\r\n\
+                   Content-Type: text/plain\r\n\
+                   \r\n\
+                   See attached.\r\n\
+                   --b\r\n\
+                   Content-Type: text/csv\r\n\
+                   Content-Disposition: attachment; filename=\"q4.csv\"\r\n\
+                   \r\n\
+                   a,b\r\n\
+                   --b--\r\n";
+        let attachments = email_attachments(raw.as_bytes());
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].name, "q4.csv");
+        assert_eq!(attachments[0].content_type.as_deref(), Some("text/csv"));
+        assert_eq!(
+            String::from_utf8_lossy(&attachments[0].content).trim_end(),
+            "a,b"
+        );
+        assert!(email_attachments(b"no attachments here").is_empty());
+    }
+}
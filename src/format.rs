@@ -5,6 +5,8 @@ use comfy_table::presets::ASCII_MARKDOWN;
 use comfy_table::presets::UTF8_FULL_CONDENSED;
 use comfy_table::*;
 
+use crate::cmd::OutputFormat;
+
 pub fn as_ascii_table<T, U, V, W>(headers: T, rows: U) -> String
 where
     T: IntoIterator,
@@ -37,7 +39,6 @@ where
     table
 }
 
-#[allow(dead_code)]
 pub fn as_markdown_table<T, U, V, W>(headers: T, rows: U) -> String
 where
     T: IntoIterator,
@@ -55,6 +56,95 @@ where
     table.to_string()
 }
 
+/// The rows of a table as JSON objects keyed by the SQL-friendly form of each
+/// header (e.g. `Code Hash` becomes `code_hash`).
+pub fn rows_as_json_objects(headers: &[&str], rows: &[Vec<String>]) -> Vec<serde_json::Value> {
+    let keys: Vec<String> = headers
+        .iter()
+        .map(|header| to_sql_friendly_identifier(header))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            serde_json::Value::Object(
+                keys.iter()
+                    .cloned()
+                    .zip(row.iter().map(|value| serde_json::json!(value)))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Renders the rows as a table, a single JSON array or newline-delimited JSON
+/// (one object per row) depending on the `--output` format.
+pub fn as_output_text(output: OutputFormat, headers: &[&str], rows: &[Vec<String>]) -> String {
+    match output {
+        OutputFormat::Table => as_ascii_table(headers, rows),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&rows_as_json_objects(headers, rows)).unwrap_or_default()
+        }
+        OutputFormat::Ndjson => rows_as_json_objects(headers, rows)
+            .iter()
+            .map(|row| row.to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// A named table in the output of commands which list more than one table.
+pub struct OutputSection<'a> {
+    pub name: &'a str,
+    pub caption: &'a str,
+    pub headers: &'a [&'a str],
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Like `as_output_text` for several tables: captioned tables, a single JSON
+/// object with an array per section name or newline-delimited JSON where each
+/// row also carries its `section` name.
+pub fn as_output_sections_text(output: OutputFormat, sections: &[OutputSection]) -> String {
+    match output {
+        OutputFormat::Table => sections
+            .iter()
+            .map(|section| {
+                format!(
+                    "{}\n{}",
+                    section.caption,
+                    as_ascii_table(section.headers, &section.rows)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Json => serde_json::to_string_pretty(&serde_json::Value::Object(
+            sections
+                .iter()
+                .map(|section| {
+                    (
+                        section.name.to_string(),
+                        serde_json::Value::Array(rows_as_json_objects(
+                            section.headers,
+                            &section.rows,
+                        )),
+                    )
+                })
+                .collect(),
+        ))
+        .unwrap_or_default(),
+        OutputFormat::Ndjson => sections
+            .iter()
+            .flat_map(|section| {
+                rows_as_json_objects(section.headers, &section.rows)
+                    .into_iter()
+                    .map(|mut row| {
+                        row["section"] = serde_json::json!(section.name);
+                        row.to_string()
+                    })
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
 /// Converts a string to a SQL-friendly identifier following SQLite identifier rules.
 ///
 /// SQLite identifier rules:
@@ -86,6 +176,225 @@ pub fn to_sql_friendly_identifier(input: &str) -> String {
     result.to_string()
 }
 
+/// Removes trailing whitespace from each line (`lines`) and/or trailing blank
+/// lines from the end of the text (`end`); a final newline is kept if the text
+/// had any content ending in one so that files still end "properly".
+pub fn trimmed_text(text: &str, lines: bool, end: bool) -> String {
+    let mut trimmed = if lines {
+        text.split_inclusive('\n')
+            .map(|line| {
+                let newline = if line.ends_with("\r\n") {
+                    "\r\n"
+                } else if line.ends_with('\n') {
+                    "\n"
+                } else {
+                    ""
+                };
+                format!("{}{}", line.trim_end(), newline)
+            })
+            .collect::<String>()
+    } else {
+        text.to_string()
+    };
+    if end {
+        let content_len = trimmed.trim_end().len();
+        let rest = &trimmed[content_len..];
+        let newline = match rest.find('\n') {
+            Some(nl) if rest[..nl].ends_with('\r') => "\r\n",
+            Some(_) => "\n",
+            None => "",
+        };
+        trimmed = format!("{}{}", &trimmed[..content_len], newline);
+    }
+    trimmed
+}
+
+/// Re-serializes JSON text in a canonical form (sorted object keys, no
+/// insignificant whitespace) so that semantically identical documents produce
+/// identical text; returns `None` if `json_text` is not valid JSON.
+pub fn to_canonical_json_text(json_text: &str) -> Option<String> {
+    // serde_json's `Map` is a `BTreeMap` (we don't enable `preserve_order`) so
+    // keys are emitted in sorted order and `to_string` is always compact
+    serde_json::from_str::<serde_json::Value>(json_text)
+        .ok()
+        .map(|value| value.to_string())
+}
+
+/// Parses the test points (`ok 1 - description`, `not ok 2 description`) of
+/// Test Anything Protocol output into a JSON array of `{description, ok}`;
+/// indented (subtest or YAML diagnostic) lines and comments are skipped. Test
+/// numbers out of sequence or a plan (`1..N`) which doesn't match the number of
+/// test points are reported with the offending line.
+pub fn tap_test_points(tap: &str) -> Result<serde_json::Value, String> {
+    let mut points = vec![];
+    let mut plan: Option<(usize, usize, &str)> = None;
+    for (index, line) in tap.lines().enumerate() {
+        let line_num = index + 1;
+        if let Some(count) = line.strip_prefix("1..") {
+            let count = count.split_whitespace().next().unwrap_or_default();
+            match count.parse::<usize>() {
+                Ok(count) => plan = Some((line_num, count, line)),
+                Err(_) => return Err(format!("line {line_num}: invalid TAP plan `{line}`")),
+            }
+            continue;
+        }
+        let (ok, rest) = if let Some(rest) = line.strip_prefix("not ok") {
+            (false, rest)
+        } else if let Some(rest) = line.strip_prefix("ok") {
+            (true, rest)
+        } else {
+            continue;
+        };
+        if !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+            continue; // e.g. `okay`, not a test point
+        }
+        let rest = rest.trim_start();
+        let number_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if number_len > 0 {
+            let expected = points.len() + 1;
+            if rest[..number_len].parse::<usize>() != Ok(expected) {
+                return Err(format!(
+                    "line {line_num}: expected TAP test point {expected} in `{line}`"
+                ));
+            }
+        }
+        let description = rest[number_len..].trim_start();
+        let description = description.strip_prefix('-').unwrap_or(description).trim();
+        points.push(serde_json::json!({ "description": description, "ok": ok }));
+    }
+    if let Some((line_num, count, line)) = plan {
+        if count != points.len() {
+            return Err(format!(
+                "line {line_num}: TAP plan `{line}` expects {count} test points but found {}",
+                points.len()
+            ));
+        }
+    }
+    Ok(serde_json::Value::Array(points))
+}
+
+/// Orders text the way people expect numbered names to sort (`file2` before
+/// `file10`): runs of ASCII digits compare by their value and everything else
+/// compares by code point, so the order doesn't depend on the locale or the
+/// platform. Ties (e.g. `01` and `1`) fall back to plain byte ordering.
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let (mut a_chars, mut b_chars) = (a.char_indices().peekable(), b.char_indices().peekable());
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some((a_start, a_char)), Some((b_start, b_char)))
+                if a_char.is_ascii_digit() && b_char.is_ascii_digit() =>
+            {
+                let digits =
+                    |chars: &mut std::iter::Peekable<std::str::CharIndices>, text: &str, start| {
+                        let mut end = start;
+                        while let Some((index, ch)) = chars.peek().copied() {
+                            if !ch.is_ascii_digit() {
+                                break;
+                            }
+                            end = index + 1;
+                            chars.next();
+                        }
+                        text[start..end].trim_start_matches('0').to_string()
+                    };
+                let a_digits = digits(&mut a_chars, a, a_start);
+                let b_digits = digits(&mut b_chars, b, b_start);
+                // without leading zeros the longer run is the larger number
+                let ordering = a_digits
+                    .len()
+                    .cmp(&b_digits.len())
+                    .then_with(|| a_digits.cmp(&b_digits));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some((_, a_char)), Some((_, b_char))) => {
+                if a_char != b_char {
+                    return a_char.cmp(&b_char);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Makes a name safe to use as a single file or directory name on any platform:
+/// path separators, reserved and control characters become `_`, and names which
+/// are empty or only dots (`.`, `..`) become `_`.
+pub fn to_file_name_safe(name: &str) -> String {
+    let safe: String = name
+        .trim()
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            ch if ch.is_control() => '_',
+            ch => ch,
+        })
+        .collect();
+    if safe.chars().all(|ch| ch == '.') {
+        "_".to_string()
+    } else {
+        safe
+    }
+}
+
+/// Resolves a `--since` value, either a duration back from `now` (a number
+/// followed by `s`, `m`, `h`, `d` or `w`, e.g. `24h`) or an RFC3339 timestamp.
+pub fn parsed_since(
+    since: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let since = since.trim();
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(since) {
+        return Ok(at.with_timezone(&chrono::Utc));
+    }
+    let invalid = || format!("invalid duration or RFC3339 timestamp `{since}` (e.g. `24h`, `7d`)");
+    let unit_at = since
+        .find(|ch: char| !ch.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (count, unit) = since.split_at(unit_at);
+    let count: i64 = count.parse().map_err(|_| invalid())?;
+    let duration = match unit {
+        "s" => chrono::Duration::try_seconds(count),
+        "m" => chrono::Duration::try_minutes(count),
+        "h" => chrono::Duration::try_hours(count),
+        "d" => chrono::Duration::try_days(count),
+        "w" => chrono::Duration::try_weeks(count),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    now.checked_sub_signed(duration).ok_or_else(invalid)
+}
+
+// the RFC 1924 alphabet, also used by git binary patches and Python's `b85encode`
+const BASE85_ALPHABET: &[u8; 85] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+/// Encodes `bytes` as base85 text (RFC 1924 alphabet) without padding, so
+/// a final partial group of `n` bytes becomes `n + 1` characters.
+pub fn to_base85(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(4) * 5);
+    for group in bytes.chunks(4) {
+        let mut padded = [0u8; 4];
+        padded[..group.len()].copy_from_slice(group);
+        let mut value = u32::from_be_bytes(padded);
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE85_ALPHABET[(value % 85) as usize];
+            value /= 85;
+        }
+        text.extend(digits[..group.len() + 1].iter().map(|&digit| digit as char));
+    }
+    text
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
@@ -126,4 +435,161 @@ mod tests {
         let result = to_sql_friendly_identifier(input_string);
         assert_eq!(result, "");
     }
+
+    #[test]
+    fn test_trimmed_text() {
+        let text = "first  \nsecond\t\r\n\nthird \n\n \n";
+        assert_eq!(
+            trimmed_text(text, true, false),
+            "first\nsecond\r\n\nthird\n\n\n"
+        );
+        assert_eq!(
+            trimmed_text(text, false, true),
+            "first  \nsecond\t\r\n\nthird\n"
+        );
+        assert_eq!(trimmed_text(text, true, true), "first\nsecond\r\n\nthird\n");
+        assert_eq!(trimmed_text("no newline  ", true, true), "no newline");
+    }
+
+    #[test]
+    fn test_to_canonical_json_text() {
+        let a = to_canonical_json_text("{\"b\": [1, 2], \"a\": {\"y\": true, \"x\": null}}");
+        let b =
+            to_canonical_json_text("{\n  \"a\": {\"x\": null, \"y\": true},\n  \"b\": [1,2]\n}");
+        assert_eq!(a.as_deref(), Some(r#"{"a":{"x":null,"y":true},"b":[1,2]}"#));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_to_canonical_json_text_invalid() {
+        assert_eq!(to_canonical_json_text("{ not json"), None);
+    }
+
+    #[test]
+    fn test_tap_test_points() {
+        let tap = "TAP version 13\n1..3\nok 1 - first\nnot ok 2 second # TODO later\n  ---\n  message: x\n  ...\n# comment\nok - third\n";
+        assert_eq!(
+            tap_test_points(tap),
+            Ok(serde_json::json!([
+                { "description": "first", "ok": true },
+                { "description": "second # TODO later", "ok": false },
+                { "description": "third", "ok": true }
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_tap_test_points_invalid() {
+        assert_eq!(
+            tap_test_points("ok 1 - a\nok 3 - b\n"),
+            Err("line 2: expected TAP test point 2 in `ok 3 - b`".to_string())
+        );
+        assert_eq!(
+            tap_test_points("1..2\nok 1 - a\n"),
+            Err("line 1: TAP plan `1..2` expects 2 test points but found 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "file10.sql",
+            "file2.sql",
+            "file1.sql",
+            "002_views.sql",
+            "010_seed.sql",
+            "001_tables.sql",
+            "file02.sql",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "001_tables.sql",
+                "002_views.sql",
+                "010_seed.sql",
+                "file1.sql",
+                "file02.sql",
+                "file2.sql",
+                "file10.sql",
+            ]
+        );
+        assert_eq!(natural_cmp("a", "a"), std::cmp::Ordering::Equal);
+        assert_eq!(natural_cmp("a", "ab"), std::cmp::Ordering::Less);
+    }
+
+    #[test]
+    fn test_to_file_name_safe() {
+        assert_eq!(
+            to_file_name_safe("v001_once_initialDDL"),
+            "v001_once_initialDDL"
+        );
+        assert_eq!(to_file_name_safe("a/b\\c:d*e?"), "a_b_c_d_e_");
+        assert_eq!(to_file_name_safe(" spaced name "), "spaced name");
+        assert_eq!(to_file_name_safe(".."), "_");
+        assert_eq!(to_file_name_safe(""), "_");
+    }
+
+    #[test]
+    fn test_as_output_text() {
+        let rows = vec![
+            vec!["nb".to_string(), "a1".to_string()],
+            vec!["nb".to_string(), "b2".to_string()],
+        ];
+        assert_eq!(
+            as_output_text(OutputFormat::Ndjson, &["Notebook", "Code Hash"], &rows),
+            "{\"code_hash\":\"a1\",\"notebook\":\"nb\"}\n{\"code_hash\":\"b2\",\"notebook\":\"nb\"}"
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&as_output_text(
+                OutputFormat::Json,
+                &["Notebook", "Code Hash"],
+                &rows
+            ))
+            .unwrap(),
+            serde_json::json!([
+                { "notebook": "nb", "code_hash": "a1" },
+                { "notebook": "nb", "code_hash": "b2" }
+            ])
+        );
+        assert_eq!(
+            as_output_sections_text(
+                OutputFormat::Ndjson,
+                &[OutputSection {
+                    name: "status",
+                    caption: "Status",
+                    headers: &["Cell"],
+                    rows: vec![vec!["c".to_string()]],
+                }]
+            ),
+            "{\"cell\":\"c\",\"section\":\"status\"}"
+        );
+    }
+
+    #[test]
+    fn test_parsed_since() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-03-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let at = |text: &str| parsed_since(text, now).map(|at| at.to_rfc3339());
+        assert_eq!(at("90m"), Ok("2024-03-10T10:30:00+00:00".to_string()));
+        assert_eq!(at("24h"), Ok("2024-03-09T12:00:00+00:00".to_string()));
+        assert_eq!(at("7d"), Ok("2024-03-03T12:00:00+00:00".to_string()));
+        assert_eq!(
+            at("2024-01-01T00:00:00+02:00"),
+            Ok("2023-12-31T22:00:00+00:00".to_string())
+        );
+        assert!(at("7").is_err());
+        assert!(at("d").is_err());
+        assert!(at("3 days").is_err());
+    }
+
+    #[test]
+    fn test_to_base85() {
+        assert_eq!(to_base85(b""), "");
+        assert_eq!(to_base85(b"hello"), "Xk~0{Zv");
+        assert_eq!(to_base85(&[0, 0, 0, 0]), "00000");
+        assert_eq!(to_base85(&[255, 255, 255, 255]), "|NsC0");
+        assert_eq!(to_base85(&[1]), "0R");
+    }
 }
@@ -16,18 +16,26 @@ pub type FrontmatterComponents = (
     String,
 );
 
-pub fn frontmatter(text: &str) -> FrontmatterComponents {
+lazy_static::lazy_static! {
     // Define regex patterns for YAML, TOML, and JSON frontmatter
-    // The ending delimiter must be alone on its line and followed by a newline;
+    // The opening delimiter may follow a BOM and leading whitespace, lines may
+    // end with LF or CRLF and the ending delimiter must be alone on its line;
     // - `[\s\S]` is a character class that matches any whitespace character (\s)
     //    and any non-whitespace character (\S), which effectively matches any
     //    character, including newlines.
     // - `*?` is a non-greedy quantifier that matches as few characters as possible
     //    to satisfy the pattern.
-    let yaml_regex = Regex::new(r"^---\n([\s\S]*?)\n---\n").unwrap();
-    let toml_regex = Regex::new(r"^\+\+\+\n([\s\S]*?)\n\+\+\+\n").unwrap();
-    let json_regex = Regex::new(r"^\{\n([\s\S]*?)\n\}\n").unwrap();
+    static ref YAML_REGEX: Regex =
+        Regex::new(r"^\x{FEFF}?\s*---\r?\n([\s\S]*?)\r?\n---(?:\r?\n|$)").unwrap();
+    static ref TOML_REGEX: Regex =
+        Regex::new(r"^\x{FEFF}?\s*\+\+\+\r?\n([\s\S]*?)\r?\n\+\+\+(?:\r?\n|$)").unwrap();
+    static ref JSON_DELIMITED_REGEX: Regex =
+        Regex::new(r"^\x{FEFF}?\s*;;;\r?\n([\s\S]*?)\r?\n;;;(?:\r?\n|$)").unwrap();
+    static ref JSON_REGEX: Regex =
+        Regex::new(r"^\x{FEFF}?\s*(\{\r?\n[\s\S]*?\r?\n\})(?:\r?\n|$)").unwrap();
+}
 
+pub fn frontmatter(text: &str) -> FrontmatterComponents {
     let nature: FrontmatterNature;
     let content;
     let mut frontmatter_raw = None;
@@ -35,26 +43,38 @@ pub fn frontmatter(text: &str) -> FrontmatterComponents {
         Err("No frontmatter found".into());
 
     // Check for YAML frontmatter
-    if let Some(caps) = yaml_regex.captures(text) {
+    if let Some(caps) = YAML_REGEX.captures(text) {
         let fm = caps.get(1).unwrap().as_str();
         frontmatter_raw = Some(caps.get(0).unwrap().as_str().to_string());
-        content = yaml_regex.replace(text, "").to_string();
+        content = YAML_REGEX.replace(text, "").to_string();
         frontmatter_json = serde_yaml::from_str(fm).map_err(Into::into);
         nature = FrontmatterNature::YamlFM;
     }
     // Check for TOML frontmatter
-    else if let Some(caps) = toml_regex.captures(text) {
+    else if let Some(caps) = TOML_REGEX.captures(text) {
         let fm = caps.get(1).unwrap().as_str();
         frontmatter_raw = Some(caps.get(0).unwrap().as_str().to_string());
-        content = toml_regex.replace(text, "").to_string();
+        content = TOML_REGEX.replace(text, "").to_string();
         frontmatter_json = toml::from_str(fm).map_err(Into::into);
         nature = FrontmatterNature::TomlFM;
     }
+    // Check for `;;;` delimited JSON frontmatter, the braces of the object are optional
+    else if let Some(caps) = JSON_DELIMITED_REGEX.captures(text) {
+        let fm = caps.get(1).unwrap().as_str();
+        frontmatter_raw = Some(caps.get(0).unwrap().as_str().to_string());
+        content = JSON_DELIMITED_REGEX.replace(text, "").to_string();
+        frontmatter_json = if fm.trim_start().starts_with('{') {
+            serde_json::from_str(fm).map_err(Into::into)
+        } else {
+            serde_json::from_str(&format!("{{{fm}}}")).map_err(Into::into)
+        };
+        nature = FrontmatterNature::JsonFM;
+    }
     // Check for JSON frontmatter
-    else if let Some(caps) = json_regex.captures(text) {
-        let fm = caps.get(0).unwrap().as_str();
+    else if let Some(caps) = JSON_REGEX.captures(text) {
+        let fm = caps.get(1).unwrap().as_str();
         frontmatter_raw = Some(caps.get(0).unwrap().as_str().to_string());
-        content = json_regex.replace(text, "").to_string();
+        content = JSON_REGEX.replace(text, "").to_string();
         frontmatter_json = serde_json::from_str(fm).map_err(Into::into);
         nature = FrontmatterNature::JsonFM;
     }
@@ -109,6 +129,56 @@ mod tests {
         assert_eq!(content, "Content goes here.");
     }
 
+    #[test]
+    fn test_json_delimited_frontmatter() {
+        let text = ";;;\n\"title\": \"Example\",\n\"draft\": true\n;;;\nContent goes here.";
+        let (nature, fm, fm_json, content) = frontmatter(text);
+        assert!(matches!(nature, FrontmatterNature::JsonFM));
+        assert_eq!(
+            fm,
+            Some(";;;\n\"title\": \"Example\",\n\"draft\": true\n;;;\n".to_string())
+        );
+        assert_eq!(fm_json.unwrap(), json!({"title": "Example", "draft": true}));
+        assert_eq!(content, "Content goes here.");
+
+        let text = ";;;\n{ \"title\": \"Example\" }\n;;;\nContent goes here.";
+        let (_, _, fm_json, _) = frontmatter(text);
+        assert_eq!(fm_json.unwrap(), json!({"title": "Example"}));
+    }
+
+    #[test]
+    fn test_bom_whitespace_and_crlf_frontmatter() {
+        let text = "\u{feff}\r\n---\r\ntitle: Example\r\n---\r\nContent goes here.";
+        let (nature, fm, fm_json, content) = frontmatter(text);
+        assert!(matches!(nature, FrontmatterNature::YamlFM));
+        assert_eq!(
+            fm,
+            Some("\u{feff}\r\n---\r\ntitle: Example\r\n---\r\n".to_string())
+        );
+        assert_eq!(fm_json.unwrap(), json!({"title": "Example"}));
+        assert_eq!(content, "Content goes here.");
+
+        let text = "  \n+++\r\ntitle = \"Example\"\r\n+++\r\nContent goes here.";
+        let (nature, _, fm_json, content) = frontmatter(text);
+        assert!(matches!(nature, FrontmatterNature::TomlFM));
+        assert_eq!(fm_json.unwrap(), json!({"title": "Example"}));
+        assert_eq!(content, "Content goes here.");
+
+        let text = "\u{feff}{\r\n\"title\": \"Example\"\r\n}\r\nContent goes here.";
+        let (nature, _, fm_json, content) = frontmatter(text);
+        assert!(matches!(nature, FrontmatterNature::JsonFM));
+        assert_eq!(fm_json.unwrap(), json!({"title": "Example"}));
+        assert_eq!(content, "Content goes here.");
+    }
+
+    #[test]
+    fn test_frontmatter_without_content() {
+        let (nature, _, fm_json, content) = frontmatter("---\ntitle: Example\n---");
+        assert!(matches!(nature, FrontmatterNature::YamlFM));
+        assert_eq!(fm_json.unwrap(), json!({"title": "Example"}));
+        assert_eq!(content, "");
+    }
+
     #[test]
     fn test_no_frontmatter() {
         let text = "Content goes here.";
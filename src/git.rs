@@ -0,0 +1,147 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+
+/// The most recent commit which changed a file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GitCommitMetadata {
+    pub commit: String,
+    pub author: String,
+    pub committed_at: DateTime<Utc>,
+}
+
+impl GitCommitMetadata {
+    fn from_commit(commit: &git2::Commit) -> Option<Self> {
+        let author = commit.author();
+        Some(GitCommitMetadata {
+            commit: commit.id().to_string(),
+            author: match (author.name(), author.email()) {
+                (Some(name), Some(email)) => format!("{name} <{email}>"),
+                (Some(name), None) => name.to_string(),
+                (None, email) => email.unwrap_or_default().to_string(),
+            },
+            committed_at: Utc.timestamp_opt(commit.time().seconds(), 0).single()?,
+        })
+    }
+}
+
+/// The last commit of every file tracked in a repository's `HEAD`. History is
+/// walked once, newest first, until each tracked file has been seen since
+/// asking per file would walk the history once per file.
+pub struct GitCommits {
+    workdir: PathBuf,
+    by_path: HashMap<PathBuf, GitCommitMetadata>,
+}
+
+impl GitCommits {
+    /// `None` unless `root` is inside a repository with a working directory and
+    /// at least one commit (bare and empty repositories have no metadata). In
+    /// shallow clones the files last changed before the shallow boundary are
+    /// attributed to the oldest available commit.
+    pub fn discover(root: &Path) -> Option<GitCommits> {
+        let repo = git2::Repository::discover(root).ok()?;
+        let workdir = repo.workdir()?.canonicalize().ok()?;
+        let head = repo.head().ok()?.peel_to_commit().ok()?;
+
+        let mut pending: HashSet<PathBuf> = HashSet::new();
+        head.tree()
+            .ok()?
+            .walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+                if entry.kind() == Some(git2::ObjectType::Blob) {
+                    if let Some(name) = entry.name() {
+                        pending.insert(Path::new(dir).join(name));
+                    }
+                }
+                git2::TreeWalkResult::Ok
+            })
+            .ok()?;
+
+        let mut by_path = HashMap::new();
+        let mut revwalk = repo.revwalk().ok()?;
+        revwalk.push(head.id()).ok()?;
+        for oid in revwalk {
+            if pending.is_empty() {
+                break;
+            }
+            // a missing (e.g. shallow) ancestor ends the walk
+            let Some(commit) = oid.ok().and_then(|oid| repo.find_commit(oid).ok()) else {
+                break;
+            };
+            let Ok(tree) = commit.tree() else { break };
+            let parent_tree = commit.parent(0).ok().and_then(|parent| parent.tree().ok());
+            let Ok(diff) = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None) else {
+                break;
+            };
+            let Some(metadata) = GitCommitMetadata::from_commit(&commit) else {
+                continue;
+            };
+            for delta in diff.deltas() {
+                if let Some(path) = delta.new_file().path() {
+                    if pending.remove(path) {
+                        by_path.insert(path.to_path_buf(), metadata.clone());
+                    }
+                }
+            }
+        }
+
+        Some(GitCommits { workdir, by_path })
+    }
+
+    /// `None` for files which aren't tracked or are outside the working directory.
+    pub fn last_commit(&self, fs_path: &Path) -> Option<&GitCommitMetadata> {
+        self.by_path.get(fs_path.strip_prefix(&self.workdir).ok()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn commit_file(repo: &git2::Repository, name: &str, text: &str, seconds: i64) -> String {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(name), text).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature =
+            git2::Signature::new("Tester", "tester@example.com", &git2::Time::new(seconds, 0))
+                .unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, name, &tree, &parents)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_last_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let first = commit_file(&repo, "a.md", "a", 1_700_000_000);
+        let second = commit_file(&repo, "b.md", "b", 1_700_000_100);
+        std::fs::write(dir.path().join("untracked.md"), "u").unwrap();
+
+        let root = dir.path().canonicalize().unwrap();
+        let commits = GitCommits::discover(&root).unwrap();
+        let a = commits.last_commit(&root.join("a.md")).unwrap();
+        assert_eq!(a.commit, first);
+        assert_eq!(a.author, "Tester <tester@example.com>");
+        assert_eq!(a.committed_at.timestamp(), 1_700_000_000);
+        assert_eq!(
+            commits.last_commit(&root.join("b.md")).unwrap().commit,
+            second
+        );
+        assert_eq!(commits.last_commit(&root.join("untracked.md")), None);
+    }
+
+    #[test]
+    fn test_bare_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let bare = git2::Repository::init_bare(dir.path().join("bare.git")).unwrap();
+        assert!(GitCommits::discover(bare.path()).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-/**
+/*
  * These are helper macros for creating type-safe functions that wrap SQLite SQL
  * statements in Rusqlite accessors. When you need convenience, use these helpers
  * but if you need high performance (like in a loop or batch), it's probably best
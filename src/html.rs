@@ -0,0 +1,126 @@
+use scraper::{Html, Selector};
+use serde_json::{Map, Value as JsonValue};
+
+/// A named CSS selector from `--html-select name=selector`; a selector which ends
+/// with `::attr(name)` selects that attribute of the matching elements instead of
+/// their text.
+pub struct HtmlSelect {
+    pub name: String,
+    selector: Selector,
+    attr: Option<String>,
+}
+
+impl HtmlSelect {
+    pub fn parse(name: &str, select: &str) -> Result<HtmlSelect, String> {
+        let (css, attr) = match select
+            .trim()
+            .strip_suffix(')')
+            .and_then(|s| s.rsplit_once("::attr("))
+        {
+            Some((css, attr)) => (css, Some(attr.trim().to_string())),
+            None => (select, None),
+        };
+        let selector = Selector::parse(css)
+            .map_err(|err| format!("invalid CSS selector `{css}` for `{name}`: {err:?}"))?;
+        Ok(HtmlSelect {
+            name: name.to_string(),
+            selector,
+            attr,
+        })
+    }
+
+    // text is whitespace-collapsed since it's usually indented markup
+    fn values(&self, document: &Html) -> Vec<String> {
+        document
+            .select(&self.selector)
+            .filter_map(|element| match &self.attr {
+                Some(attr) => element.value().attr(attr).map(String::from),
+                None => Some(
+                    element
+                        .text()
+                        .flat_map(str::split_whitespace)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+            })
+            .collect()
+    }
+}
+
+/// Parses the HTML leniently (like browsers do) and returns each selection by
+/// name: `null` if nothing matched, a string for a single match or an array of
+/// strings when several elements matched.
+pub fn selected_values(html: &str, selects: &[HtmlSelect]) -> Map<String, JsonValue> {
+    let document = Html::parse_document(html);
+    selects
+        .iter()
+        .map(|select| {
+            let mut values = select.values(&document);
+            let value = match values.len() {
+                0 => JsonValue::Null,
+                1 => JsonValue::String(values.remove(0)),
+                _ => JsonValue::from(values),
+            };
+            (select.name.clone(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn selects(pairs: &[(&str, &str)]) -> Vec<HtmlSelect> {
+        pairs
+            .iter()
+            .map(|(name, select)| HtmlSelect::parse(name, select).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_selected_values() {
+        let html = r#"<html><head>
+            <title>
+                Hello   World
+            </title>
+            <link rel="canonical" href="https://example.com/hello">
+            </head><body><ul><li>one</li><li>two <b>2</b></li></ul></body></html>"#;
+        let selected = selected_values(
+            html,
+            &selects(&[
+                ("title", "head>title"),
+                ("canonical", "link[rel=canonical]::attr(href)"),
+                ("items", "li"),
+                ("missing", "meta[name=description]::attr(content)"),
+            ]),
+        );
+        assert_eq!(
+            JsonValue::Object(selected),
+            json!({
+                "title": "Hello World",
+                "canonical": "https://example.com/hello",
+                "items": ["one", "two 2"],
+                "missing": null
+            })
+        );
+    }
+
+    #[test]
+    fn test_malformed_html() {
+        let selected = selected_values(
+            "<div><p>one<p>two</span></b><h1>three",
+            &selects(&[("paragraphs", "div > p"), ("heading", "h1")]),
+        );
+        assert_eq!(
+            JsonValue::Object(selected),
+            json!({ "paragraphs": ["one", "two"], "heading": "three" })
+        );
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        assert!(HtmlSelect::parse("bad", "head>>>").is_err());
+    }
+}
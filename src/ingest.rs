@@ -2,11 +2,14 @@ use std::collections::HashMap;
 
 use anyhow::{Context, Result};
 use indoc::indoc;
-use rusqlite::{params, Connection};
+use rusqlite::types::{ToSqlOutput, Value, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::git::GitCommits;
 use crate::persist::*;
+use crate::progress::ProgressObserver;
 use crate::resource::*;
 use crate::shell::*;
 
@@ -20,14 +23,84 @@ const INS_UR_INGEST_SESSION_FINISH_SQL: &str = indoc! {"
            SET ingest_finished_at = CURRENT_TIMESTAMP 
          WHERE ur_ingest_session_id = ?"};
 
+const UPD_UR_INGEST_SESSION_BUDGET_SQL: &str = indoc! {"
+        UPDATE ur_ingest_session
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.budget', json_object('max_total_bytes', ?, 'total_bytes', ?, 'budget_limited', json(?), 'skipped', ?))
+         WHERE ur_ingest_session_id = ?"};
+
+const UPD_UR_INGEST_SESSION_SAMPLE_SQL: &str = indoc! {"
+        UPDATE ur_ingest_session
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.sample', json_object('percent', ?, 'seed', ?))
+         WHERE ur_ingest_session_id = ?"};
+
+const UPD_UR_INGEST_SESSION_FAILED_SQL: &str = indoc! {"
+        UPDATE ur_ingest_session
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.failed', json_object('uri', ?, 'committed', ?, 'error', ?))
+         WHERE ur_ingest_session_id = ?"};
+
+const UPD_UR_INGEST_SESSION_AFTER_SQL_SQL: &str = indoc! {"
+        UPDATE ur_ingest_session
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.after_ingest_sql', json_object('committed', json(?), 'sql_files', json(?)))
+         WHERE ur_ingest_session_id = ?"};
+
+// secrets (e.g. `--capture-exec-env` values, IMAP passwords) are never part
+// of the args since they're `#[serde(skip)]` in `crate::cmd`
+const UPD_UR_INGEST_SESSION_PROVENANCE_SQL: &str = indoc! {"
+        UPDATE ur_ingest_session
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.provenance', json_object('surveilr_version', ?, 'args', json(?)))
+         WHERE ur_ingest_session_id = ?"};
+
+const UPD_UR_INGEST_SESSION_COUNTS_SQL: &str = indoc! {"
+        UPDATE ur_ingest_session
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.counts', json(?))
+         WHERE ur_ingest_session_id = ?"};
+
 const INS_UR_ISFSP_SQL: &str = indoc! {"
         INSERT INTO ur_ingest_session_fs_path (ur_ingest_session_fs_path_id, ingest_session_id, root_path) 
                                   VALUES (ulid(), ?, ?) RETURNING ur_ingest_session_fs_path_id"};
 
+/// Records which `surveilr` ran the session and with what arguments so that
+/// two sessions can be compared (see `admin sessions ls`).
+fn record_session_provenance(
+    conn: &Connection,
+    cli: &crate::cmd::Cli,
+    ingest_session_id: &str,
+) -> anyhow::Result<()> {
+    conn.execute(
+        UPD_UR_INGEST_SESSION_PROVENANCE_SQL,
+        params![
+            env!("CARGO_PKG_VERSION"),
+            serde_json::to_string(cli)?,
+            ingest_session_id
+        ],
+    )?;
+    Ok(())
+}
+
+/// Records what the session encountered and stored so sessions of every kind
+/// (files, URLs, mail, tasks) can be summarized alike (see `admin sessions ls`).
+fn record_session_counts(
+    conn: &Connection,
+    ingest_session_id: &str,
+    counts: &serde_json::Value,
+) -> anyhow::Result<()> {
+    conn.execute(
+        UPD_UR_INGEST_SESSION_COUNTS_SQL,
+        params![counts.to_string(), ingest_session_id],
+    )
+    .with_context(|| {
+        format!(
+            "[record_session_counts] {}",
+            UPD_UR_INGEST_SESSION_COUNTS_SQL
+        )
+    })?;
+    Ok(())
+}
+
 // in INS_UR_SQL the `DO UPDATE SET size_bytes = EXCLUDED.size_bytes` is a workaround to allow RETURNING uniform_resource_id when the row already exists
 const INS_UR_SQL: &str = indoc! {"
-        INSERT INTO uniform_resource (uniform_resource_id, device_id, ingest_session_id, ingest_fs_path_id, uri, nature, content, content_digest, size_bytes, last_modified_at, content_fm_body_attrs, frontmatter)
-                              VALUES (ulid(), ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) 
+        INSERT INTO uniform_resource (uniform_resource_id, device_id, ingest_session_id, ingest_fs_path_id, uri, nature, content, content_digest, size_bytes, last_modified_at, content_fm_body_attrs, frontmatter, frontmatter_error, content_codec)
+                              VALUES (ulid(), ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) 
                          ON CONFLICT (device_id, content_digest, uri, size_bytes, last_modified_at) 
                            DO UPDATE SET size_bytes = EXCLUDED.size_bytes
                            RETURNING uniform_resource_id"};
@@ -39,6 +112,107 @@ const INS_UR_TRANSFORM_SQL: &str = indoc! {"
                                  DO UPDATE SET size_bytes = EXCLUDED.size_bytes
                                      RETURNING uniform_resource_transform_id"};
 
+const INS_UR_SQLITE_DB_SQL: &str = indoc! {"
+        INSERT INTO uniform_resource_sqlite_db (uniform_resource_sqlite_db_id, uniform_resource_id, tables_count, tables, inspection_error)
+                                        VALUES (ulid(), ?, ?, ?, ?)
+                                   ON CONFLICT (uniform_resource_id) DO NOTHING"};
+
+const INS_UR_NATURE_REWRITE_SQL: &str = indoc! {"
+        INSERT INTO ur_ingest_session_nature_rewrite (ur_ingest_session_nature_rewrite_id, ingest_session_id, uri, original_nature, rewritten_nature, rewrite_regex, rewrite_replace)
+                                              VALUES (ulid(), ?, ?, ?, ?, ?, ?)
+                                         ON CONFLICT (ingest_session_id, uri) DO NOTHING"};
+
+const INS_CONTENT_CHUNK_SQL: &str = indoc! {"
+        INSERT INTO content_chunk (content_chunk_id, size_bytes, content, content_codec)
+                           VALUES (?, ?, ?, ?)
+                      ON CONFLICT (content_chunk_id) DO NOTHING"};
+
+const INS_VALIDATION_ISSUE_SQL: &str = indoc! {"
+        INSERT INTO validation_issue (validation_issue_id, content_digest, uri, schema_name, issue_type, instance_path, message)
+                              VALUES (ulid(), ?, ?, ?, ?, ?, ?)
+                         ON CONFLICT (content_digest, schema_name, issue_type, instance_path, message) DO NOTHING"};
+
+const INS_CAPTURABLE_EXEC_RUN_SQL: &str = indoc! {"
+        INSERT INTO capturable_exec_run (capturable_exec_run_id, ingest_session_id, uri, exit_status, exit_code, stderr, stdout_bytes)
+                                 VALUES (ulid(), ?, ?, ?, ?, ?, ?)"};
+
+const INS_UR_EMAIL_SQL: &str = indoc! {"
+        INSERT INTO uniform_resource_email (uniform_resource_email_id, uniform_resource_id, message_id, from_address, to_addresses, subject, sent_at, body_text)
+                                    VALUES (ulid(), ?, ?, ?, ?, ?, ?, ?)
+                               ON CONFLICT (uniform_resource_id) DO NOTHING"};
+
+// served by the `idx_uniform_resource__device_id__uri` index so the rows written
+// by any prior (full) ingest are all that `--incremental` needs
+const SEL_UR_PRIOR_FINGERPRINT_SQL: &str = indoc! {"
+        SELECT uniform_resource_id, content_digest
+          FROM uniform_resource
+         WHERE device_id = ? AND uri = ? AND size_bytes = ? AND last_modified_at = ? AND deleted_at IS NULL
+      ORDER BY created_at DESC
+         LIMIT 1"};
+
+const UPD_UR_SNIFFED_NATURE_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.nature', json_object('declared', ?, 'detected', ?))
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_GIT_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.git', json(?))
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_SYMLINK_TARGET_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET symlink_target = ?
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_PATH_DIGEST_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET path_digest = ?
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_WALK_ROOT_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET walk_root = ?, path_rel = ?
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_CAPTURES_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.captures', json(?))
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_HTML_SELECT_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.html_select', json(?))
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_XML_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), ?, json(?))
+         WHERE uniform_resource_id = ?"};
+
+const SEL_UR_CONTENT_DIGEST_SQL: &str = indoc! {"
+SELECT content_digest FROM uniform_resource WHERE uniform_resource_id = ?"};
+
+const UPD_UR_TABULAR_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.tabular', json_object('delimiter', ?, 'header', json(?)))
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_PDF_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.pdf', json_object('page_count', ?))
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_TEXT_ENCODING_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.encoding', ?)
+         WHERE uniform_resource_id = ?"};
+
+const UPD_UR_CONTENT_TRUNCATED_SQL: &str = indoc! {"
+        UPDATE uniform_resource
+           SET elaboration = json_set(COALESCE(elaboration, '{}'), '$.content_truncated', json('true'))
+         WHERE uniform_resource_id = ?"};
+
 const INS_UR_ISFSP_ENTRY_SQL: &str = indoc! {"
         INSERT INTO ur_ingest_session_fs_path_entry (ur_ingest_session_fs_path_entry_id, ingest_session_id, ingest_fs_path_id, uniform_resource_id, file_path_abs, file_path_rel_parent, file_path_rel, file_basename, file_extn, ur_status, ur_diagnostics, captured_executable) 
                                            VALUES (ulid(), ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"};
@@ -53,8 +227,32 @@ pub struct IngestContext<'conn> {
     ins_ur_isfsp_stmt: rusqlite::Statement<'conn>,
     ins_ur_stmt: rusqlite::Statement<'conn>,
     ins_ur_transform_stmt: rusqlite::Statement<'conn>,
+    ins_ur_email_stmt: rusqlite::Statement<'conn>,
+    ins_ur_sqlite_db_stmt: rusqlite::Statement<'conn>,
+    ins_ur_nature_rewrite_stmt: rusqlite::Statement<'conn>,
+    ins_content_chunk_stmt: rusqlite::Statement<'conn>,
+    ins_validation_issue_stmt: rusqlite::Statement<'conn>,
+    ins_capturable_exec_run_stmt: rusqlite::Statement<'conn>,
+    upd_ur_tabular_stmt: rusqlite::Statement<'conn>,
+    upd_ur_pdf_stmt: rusqlite::Statement<'conn>,
+    upd_ur_text_encoding_stmt: rusqlite::Statement<'conn>,
+    upd_ur_content_truncated_stmt: rusqlite::Statement<'conn>,
+    upd_ur_sniffed_nature_stmt: rusqlite::Statement<'conn>,
+    upd_ur_git_stmt: rusqlite::Statement<'conn>,
+    upd_ur_symlink_target_stmt: rusqlite::Statement<'conn>,
+    upd_ur_path_digest_stmt: rusqlite::Statement<'conn>,
+    upd_ur_walk_root_stmt: rusqlite::Statement<'conn>,
+    upd_ur_html_select_stmt: rusqlite::Statement<'conn>,
+    upd_ur_xml_stmt: rusqlite::Statement<'conn>,
+    upd_ur_captures_stmt: rusqlite::Statement<'conn>,
+    sel_ur_prior_fingerprint_stmt: rusqlite::Statement<'conn>,
+    sel_ur_content_digest_stmt: rusqlite::Statement<'conn>,
     ins_ur_isfsp_entry_stmt: rusqlite::Statement<'conn>,
     ins_ur_is_task_stmt: rusqlite::Statement<'conn>,
+    // uniform resource upserts are retried while the database is busy
+    retry_policy: RetryPolicy,
+    // `--compress-content zstd` compresses with the latest `admin train-dict` dictionary
+    compression_dict: Option<CompressionDict>,
 }
 
 impl<'conn> IngestContext<'conn> {
@@ -77,6 +275,132 @@ impl<'conn> IngestContext<'conn> {
                 INS_UR_TRANSFORM_SQL, db_fs_path
             )
         })?;
+        let ins_ur_email_stmt = conn.prepare(INS_UR_EMAIL_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `ins_ur_email_stmt` SQL {} in {}",
+                INS_UR_EMAIL_SQL, db_fs_path
+            )
+        })?;
+        let ins_ur_sqlite_db_stmt = conn.prepare(INS_UR_SQLITE_DB_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `ins_ur_sqlite_db_stmt` SQL {} in {}",
+                INS_UR_SQLITE_DB_SQL, db_fs_path
+            )
+        })?;
+        let ins_ur_nature_rewrite_stmt =
+            conn.prepare(INS_UR_NATURE_REWRITE_SQL).with_context(|| {
+                format!(
+                    "[IngestContext::from_conn] unable to create `ins_ur_nature_rewrite_stmt` SQL {} in {}",
+                    INS_UR_NATURE_REWRITE_SQL, db_fs_path
+                )
+            })?;
+        let ins_content_chunk_stmt = conn.prepare(INS_CONTENT_CHUNK_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `ins_content_chunk_stmt` SQL {} in {}",
+                INS_CONTENT_CHUNK_SQL, db_fs_path
+            )
+        })?;
+        let ins_validation_issue_stmt =
+            conn.prepare(INS_VALIDATION_ISSUE_SQL).with_context(|| {
+                format!(
+                    "[IngestContext::from_conn] unable to create `ins_validation_issue_stmt` SQL {} in {}",
+                    INS_VALIDATION_ISSUE_SQL, db_fs_path
+                )
+            })?;
+        let ins_capturable_exec_run_stmt =
+            conn.prepare(INS_CAPTURABLE_EXEC_RUN_SQL).with_context(|| {
+                format!(
+                    "[IngestContext::from_conn] unable to create `ins_capturable_exec_run_stmt` SQL {} in {}",
+                    INS_CAPTURABLE_EXEC_RUN_SQL, db_fs_path
+                )
+            })?;
+        let upd_ur_tabular_stmt = conn.prepare(UPD_UR_TABULAR_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_tabular_stmt` SQL {} in {}",
+                UPD_UR_TABULAR_SQL, db_fs_path
+            )
+        })?;
+        let upd_ur_pdf_stmt = conn.prepare(UPD_UR_PDF_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_pdf_stmt` SQL {} in {}",
+                UPD_UR_PDF_SQL, db_fs_path
+            )
+        })?;
+        let upd_ur_text_encoding_stmt =
+            conn.prepare(UPD_UR_TEXT_ENCODING_SQL).with_context(|| {
+                format!(
+                    "[IngestContext::from_conn] unable to create `upd_ur_text_encoding_stmt` SQL {} in {}",
+                    UPD_UR_TEXT_ENCODING_SQL, db_fs_path
+                )
+            })?;
+        let upd_ur_content_truncated_stmt =
+            conn.prepare(UPD_UR_CONTENT_TRUNCATED_SQL).with_context(|| {
+                format!(
+                    "[IngestContext::from_conn] unable to create `upd_ur_content_truncated_stmt` SQL {} in {}",
+                    UPD_UR_CONTENT_TRUNCATED_SQL, db_fs_path
+                )
+            })?;
+        let upd_ur_sniffed_nature_stmt = conn.prepare(UPD_UR_SNIFFED_NATURE_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_sniffed_nature_stmt` SQL {} in {}",
+                UPD_UR_SNIFFED_NATURE_SQL, db_fs_path
+            )
+        })?;
+        let upd_ur_git_stmt = conn.prepare(UPD_UR_GIT_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_git_stmt` SQL {} in {}",
+                UPD_UR_GIT_SQL, db_fs_path
+            )
+        })?;
+        let upd_ur_symlink_target_stmt =
+            conn.prepare(UPD_UR_SYMLINK_TARGET_SQL).with_context(|| {
+                format!(
+                    "[IngestContext::from_conn] unable to create `upd_ur_symlink_target_stmt` SQL {} in {}",
+                    UPD_UR_SYMLINK_TARGET_SQL, db_fs_path
+                )
+            })?;
+        let upd_ur_path_digest_stmt = conn.prepare(UPD_UR_PATH_DIGEST_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_path_digest_stmt` SQL {} in {}",
+                UPD_UR_PATH_DIGEST_SQL, db_fs_path
+            )
+        })?;
+        let upd_ur_walk_root_stmt = conn.prepare(UPD_UR_WALK_ROOT_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_walk_root_stmt` SQL {} in {}",
+                UPD_UR_WALK_ROOT_SQL, db_fs_path
+            )
+        })?;
+        let upd_ur_html_select_stmt = conn.prepare(UPD_UR_HTML_SELECT_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_html_select_stmt` SQL {} in {}",
+                UPD_UR_HTML_SELECT_SQL, db_fs_path
+            )
+        })?;
+        let upd_ur_xml_stmt = conn.prepare(UPD_UR_XML_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_xml_stmt` SQL {} in {}",
+                UPD_UR_XML_SQL, db_fs_path
+            )
+        })?;
+        let upd_ur_captures_stmt = conn.prepare(UPD_UR_CAPTURES_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `upd_ur_captures_stmt` SQL {} in {}",
+                UPD_UR_CAPTURES_SQL, db_fs_path
+            )
+        })?;
+        let sel_ur_prior_fingerprint_stmt = conn.prepare(SEL_UR_PRIOR_FINGERPRINT_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `sel_ur_prior_fingerprint_stmt` SQL {} in {}",
+                SEL_UR_PRIOR_FINGERPRINT_SQL, db_fs_path
+            )
+        })?;
+        let sel_ur_content_digest_stmt = conn.prepare(SEL_UR_CONTENT_DIGEST_SQL).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to create `sel_ur_content_digest_stmt` SQL {} in {}",
+                SEL_UR_CONTENT_DIGEST_SQL, db_fs_path
+            )
+        })?;
         let ins_ur_isfsp_entry_stmt = conn.prepare(INS_UR_ISFSP_ENTRY_SQL).with_context(|| {
             format!(
                 "[IngestContext::from_conn] unable to create `ins_ur_isfsp_entry_stmt` SQL {} in {}",
@@ -89,16 +413,103 @@ impl<'conn> IngestContext<'conn> {
                 INS_UR_ISFSP_ENTRY_SQL, db_fs_path
             )
         })?;
+        let compression_dict = latest_compression_dict(conn).with_context(|| {
+            format!(
+                "[IngestContext::from_conn] unable to read the latest compression dictionary in {}",
+                db_fs_path
+            )
+        })?;
         Ok(IngestContext {
             ins_ur_isfsp_stmt,
             ins_ur_stmt,
             ins_ur_transform_stmt,
+            ins_ur_email_stmt,
+            ins_ur_sqlite_db_stmt,
+            ins_ur_nature_rewrite_stmt,
+            ins_content_chunk_stmt,
+            ins_validation_issue_stmt,
+            ins_capturable_exec_run_stmt,
+            upd_ur_tabular_stmt,
+            upd_ur_pdf_stmt,
+            upd_ur_text_encoding_stmt,
+            upd_ur_content_truncated_stmt,
+            upd_ur_sniffed_nature_stmt,
+            upd_ur_git_stmt,
+            upd_ur_symlink_target_stmt,
+            upd_ur_path_digest_stmt,
+            upd_ur_walk_root_stmt,
+            upd_ur_html_select_stmt,
+            upd_ur_xml_stmt,
+            upd_ur_captures_stmt,
+            sel_ur_prior_fingerprint_stmt,
+            sel_ur_content_digest_stmt,
             ins_ur_isfsp_entry_stmt,
             ins_ur_is_task_stmt: ins_ur_istask_entry_stmt,
+            retry_policy: RetryPolicy::default(),
+            compression_dict,
         })
     }
 }
 
+// another connection holds the lock longer than the busy timeout (if any)
+fn is_database_busy(err: &rusqlite::Error) -> bool {
+    matches!(
+        err.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
+}
+
+impl<'conn> IngestContext<'conn> {
+    /// Upserts a uniform resource (see `INS_UR_SQL`) and returns its id, retrying
+    /// with backoff while the database is busy or locked (see `--max-retries`).
+    fn upserted_ur(&mut self, params: &[&dyn rusqlite::ToSql]) -> Result<String> {
+        let retry_policy = self.retry_policy;
+        let (upserted, retries) = retry_policy.retried(
+            || {
+                self.ins_ur_stmt
+                    .query_row(params, |row| row.get::<_, String>(0))
+            },
+            |upserted| upserted.as_ref().is_err_and(is_database_busy),
+        );
+        match upserted {
+            Ok(ur_id) => {
+                if retries > 0 {
+                    tracing::info!("upserted uniform resource {ur_id} after {retries} retries");
+                }
+                Ok(ur_id)
+            }
+            Err(err) if retries > 0 => {
+                let message = format!("{} (gave up after {} retries)", err, retries);
+                Err(anyhow::Error::new(err).context(message))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Finds the most recent `uniform_resource` already stored for the same
+    /// uri, size and last modification time, returning its id and digest.
+    pub fn prior_resource_fingerprint(
+        &mut self,
+        device_id: &str,
+        resource: &ContentResource,
+    ) -> rusqlite::Result<Option<(String, String)>> {
+        let Some(last_modified_at) = resource.last_modified_at else {
+            return Ok(None);
+        };
+        self.sel_ur_prior_fingerprint_stmt
+            .query_row(
+                params![
+                    device_id,
+                    resource.uri,
+                    resource.size,
+                    persistable_timestamp(&last_modified_at)
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+    }
+}
+
 pub struct UniformResourceWriterState<'a, 'conn> {
     state_db_fs_path: &'a String,
     env_current_dir: &'a String,
@@ -108,12 +519,15 @@ pub struct UniformResourceWriterState<'a, 'conn> {
     ingest_stmts: &'a mut IngestContext<'conn>,
     ingest_files_behavior: Option<&'a IngestFilesBehavior>,
     ingest_fs_path_id: Option<&'a String>,
+    json_schemas: Option<&'a crate::json_schema::JsonSchemas>,
+    // capturable executables already run by `execute_capturable_ahead`, by URI
+    executed_ahead: HashMap<String, (ShellStdIn, anyhow::Result<ShellResult>)>,
 }
 
 impl<'a, 'conn> UniformResourceWriterState<'a, 'conn> {
     fn capturable_exec_ctx(&self, entry: &mut UniformResourceWriterEntry) -> ShellStdIn {
-        let path = if entry.path.is_some() {
-            json!({ "path": entry.path.unwrap() })
+        let path = if let Some(path) = entry.path {
+            json!({ "path": path })
         } else {
             json!(null)
         };
@@ -132,6 +546,283 @@ impl<'a, 'conn> UniformResourceWriterState<'a, 'conn> {
         });
         ShellStdIn::Json(ctx)
     }
+
+    // recorded whether or not the run succeeded since scripts often warn on
+    // STDERR but still exit 0
+    fn record_capturable_exec_run(
+        &mut self,
+        uri: &str,
+        shell_result: &ShellResult,
+    ) -> rusqlite::Result<()> {
+        let diagnostics = self
+            .ingest_files_behavior
+            .map(|behavior| !behavior.no_capture_exec_diagnostics)
+            .unwrap_or(true);
+        if !diagnostics {
+            return Ok(());
+        }
+        let exit_code = match shell_result.status {
+            subprocess::ExitStatus::Exited(code) => Some(code as i64),
+            _ => None,
+        };
+        self.ingest_stmts
+            .ins_capturable_exec_run_stmt
+            .execute(params![
+                self.ingest_session_id,
+                uri,
+                format!("{:?}", shell_result.status),
+                exit_code,
+                shell_result.stderr,
+                shell_result.stdout.len(),
+            ])?;
+        Ok(())
+    }
+
+    // the executable's own uniform_resource is inserted (and hashed) before it's
+    // executed so with `--capture-exec-stdin content` it reads exactly those bytes
+    fn capturable_exec_stdin(
+        &self,
+        resource: &ContentResource,
+        entry: &mut UniformResourceWriterEntry,
+    ) -> Result<ShellStdIn> {
+        match self
+            .ingest_files_behavior
+            .map(|behavior| behavior.capture_exec_stdin)
+            .unwrap_or_default()
+        {
+            crate::cmd::CaptureExecStdin::Context => Ok(self.capturable_exec_ctx(entry)),
+            crate::cmd::CaptureExecStdin::Content => {
+                let binary_supplier = resource.content_binary_supplier.as_ref().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "[UniformResourceWriterState::capturable_exec_stdin] content of {} is not acquirable",
+                        resource.uri
+                    )
+                })?;
+                let binary = binary_supplier().map_err(|err| {
+                    anyhow::anyhow!(
+                        "[UniformResourceWriterState::capturable_exec_stdin] reading {}: {}",
+                        resource.uri,
+                        err
+                    )
+                })?;
+                Ok(ShellStdIn::Binary(binary.content_binary().to_owned()))
+            }
+        }
+    }
+
+    // with `--capture-exec-concurrency` the capturable executables are run across
+    // up to `concurrency` threads before any resource is persisted; their results
+    // are then taken by `CapturableExecResource::insert` in walk order so what's
+    // stored doesn't depend on which execution finished first
+    fn execute_capturable_ahead(
+        &mut self,
+        capturable: &[CapturableExecResource<ContentResource>],
+        concurrency: usize,
+    ) {
+        let mut pending: Vec<(&str, ShellStdIn)> = Vec::new();
+        let mut executions: Vec<(&dyn ShellExecutive, ShellStdIn, std::time::Duration)> =
+            Vec::new();
+        for cer in capturable {
+            let CapturableExecutable::UriShellExecutive(executive, _, _, _, timeout) =
+                &cer.executable
+            else {
+                continue;
+            };
+            let mut entry = UniformResourceWriterEntry {
+                path: Some(cer.resource.uri.as_str()),
+                tried_alternate_nature: None,
+            };
+            // an unavailable stdin is reported when the executable is persisted
+            if let Ok(stdin) = self.capturable_exec_stdin(&cer.resource, &mut entry) {
+                pending.push((cer.resource.uri.as_str(), stdin.clone()));
+                executions.push((executive.as_ref(), stdin, *timeout));
+            }
+        }
+
+        // executables of a worker that panicked are run again when persisted
+        let executed = execute_concurrently(&executions, concurrency);
+        for ((uri, stdin), shell_result) in pending.into_iter().zip(executed) {
+            if let Some(shell_result) = shell_result {
+                self.executed_ahead
+                    .insert(uri.to_string(), (stdin, shell_result));
+            }
+        }
+    }
+
+    fn digest_algorithm(&self) -> DigestAlgorithm {
+        self.ingest_files_behavior
+            .map(|b| b.classifier.digest_algorithm)
+            .unwrap_or_default()
+    }
+
+    // when `--content-text-trim` is active this is the text that should be stored
+    fn content_text_trimmed(&self, text: &str) -> Option<String> {
+        let (lines, end) = match self.ingest_files_behavior?.content_text_trim? {
+            crate::cmd::ContentTextTrim::Lines => (true, false),
+            crate::cmd::ContentTextTrim::End => (false, true),
+            crate::cmd::ContentTextTrim::All => (true, true),
+        };
+        Some(crate::format::trimmed_text(text, lines, end))
+    }
+
+    // content larger than its nature's `--max-store-bytes-for-nature` (or the
+    // `--max-store-bytes` default) is not stored, only its digest and metadata
+    fn exceeds_max_store_bytes(&self, nature: Option<&str>, content: &[u8]) -> bool {
+        let Some(behavior) = self.ingest_files_behavior else {
+            return false;
+        };
+        nature
+            .and_then(|nature| behavior.max_store_bytes_for_nature.get(nature))
+            .or(behavior.max_store_bytes.as_ref())
+            .is_some_and(|max_store_bytes| content.len() as u64 > *max_store_bytes)
+    }
+
+    // content is compressed after the supplier's digest of the raw content so
+    // the same content has the same digest whether compressed or not
+    fn stored_content<'c>(
+        &self,
+        content: ValueRef<'c>,
+    ) -> Result<(ToSqlOutput<'c>, Option<String>)> {
+        match self
+            .ingest_files_behavior
+            .and_then(|behavior| behavior.compress_content)
+        {
+            Some(codec) => {
+                let (compressed, content_codec) = compressed_content(
+                    codec,
+                    self.ingest_stmts.compression_dict.as_ref(),
+                    content.as_bytes()?,
+                )?;
+                Ok((
+                    ToSqlOutput::Owned(Value::Blob(compressed)),
+                    Some(content_codec),
+                ))
+            }
+            None => Ok((ToSqlOutput::Borrowed(content), None)),
+        }
+    }
+
+    // the (`--content-text-trim`med) text as it's stored: not at all when larger
+    // than `--max-store-bytes` (the last element is whether it was truncated),
+    // otherwise compressed by `stored_content`
+    fn stored_text_content<'c>(
+        &self,
+        nature: Option<&str>,
+        stored_text: &'c str,
+    ) -> Result<(ToSqlOutput<'c>, Option<String>, bool)> {
+        let stored_text = stored_text.as_bytes();
+        if self.exceeds_max_store_bytes(nature, stored_text) {
+            return Ok((ToSqlOutput::Owned(Value::Null), None, true));
+        }
+        let (content, content_codec) = self.stored_content(ValueRef::Text(stored_text))?;
+        Ok((content, content_codec, false))
+    }
+
+    // records how the text stored by `stored_text_content` differs from the
+    // acquired content: trimmed, decoded by a fallback encoding or truncated
+    fn record_text_elaboration(
+        &mut self,
+        uniform_resource_id: &str,
+        uri: &str,
+        trimmed: Option<&str>,
+        encoding: Option<&str>,
+        truncated: bool,
+    ) -> rusqlite::Result<()> {
+        if let Some(trimmed) = trimmed {
+            self.insert_content_text_trimmed_digest(uniform_resource_id, uri, trimmed)?;
+        }
+        self.record_text_encoding(uniform_resource_id, encoding)?;
+        self.record_content_truncated(uniform_resource_id, truncated)
+    }
+
+    // with `--chunk-content` binary content is split into content-defined chunks
+    // which are stored once in `content_chunk` (compressed if `--compress-content`)
+    // and uniform_resource.content becomes the ordered list of chunk ids
+    fn chunked_content(&mut self, content: &[u8]) -> Result<Option<(Vec<u8>, String)>> {
+        let Some(behavior) = self.ingest_files_behavior.filter(|b| b.chunk_content) else {
+            return Ok(None);
+        };
+        let digest_algorithm = behavior.classifier.digest_algorithm;
+        let mut chunk_ids = Vec::new();
+        for chunk in crate::persist::content_chunks(content) {
+            let chunk_id = digest_algorithm.digest(chunk);
+            let (stored, chunk_codec) = match behavior.compress_content {
+                Some(codec) => {
+                    let (compressed, chunk_codec) = compressed_content(
+                        codec,
+                        self.ingest_stmts.compression_dict.as_ref(),
+                        chunk,
+                    )?;
+                    (compressed, Some(chunk_codec))
+                }
+                None => (chunk.to_vec(), None),
+            };
+            self.ingest_stmts
+                .ins_content_chunk_stmt
+                .execute(params![chunk_id, chunk.len(), stored, chunk_codec])
+                .with_context(|| {
+                    format!(
+                        "[UniformResourceWriterState::chunked_content] storing chunk {}",
+                        chunk_id
+                    )
+                })?;
+            chunk_ids.push(chunk_id);
+        }
+        Ok(Some((
+            serde_json::to_vec(&chunk_ids)?,
+            crate::persist::CHUNKED_CONTENT_CODEC.to_string(),
+        )))
+    }
+
+    // text which isn't exactly the acquired content (see `--text-encoding-fallback`)
+    // records how it was decoded in `elaboration.encoding`
+    fn record_text_encoding(
+        &mut self,
+        uniform_resource_id: &str,
+        encoding: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        if let Some(encoding) = encoding {
+            self.ingest_stmts
+                .upd_ur_text_encoding_stmt
+                .execute(params![encoding, uniform_resource_id])?;
+        }
+        Ok(())
+    }
+
+    fn record_content_truncated(
+        &mut self,
+        uniform_resource_id: &str,
+        truncated: bool,
+    ) -> rusqlite::Result<()> {
+        if truncated {
+            self.ingest_stmts
+                .upd_ur_content_truncated_stmt
+                .execute(params![uniform_resource_id])?;
+        }
+        Ok(())
+    }
+
+    // the raw text's digest stays on uniform_resource, the trimmed text's digest
+    // is recorded as a transform (without duplicating the already stored content)
+    fn insert_content_text_trimmed_digest(
+        &mut self,
+        uniform_resource_id: &str,
+        uri: &str,
+        trimmed: &str,
+    ) -> rusqlite::Result<String> {
+        let hash = self.digest_algorithm().digest(trimmed);
+        self.ingest_stmts.ins_ur_transform_stmt.query_row(
+            params![
+                uniform_resource_id,
+                uri,
+                "text-trimmed",
+                hash,
+                &None::<String>,
+                trimmed.len()
+            ],
+            |row| row.get(0),
+        )
+    }
 }
 
 pub struct UniformResourceWriterEntry<'a> {
@@ -144,12 +835,16 @@ pub enum UniformResourceWriterAction {
     Inserted(String, Option<String>),
     InsertedExecutableOutput(String, Option<String>, serde_json::Value),
     CapturedExecutableSqlOutput(String, serde_json::Value),
+    #[allow(dead_code)] // the diagnostics already carry the result's exit status and stderr
     CapturedExecutableNonZeroExit(ShellResult, serde_json::Value),
     ContentSupplierError(Box<dyn std::error::Error>),
     ContentUnavailable(),
     CapturableExecNotExecutable(),
     CapturableExecError(anyhow::Error),
     CapturableExecUrCreateError(Box<dyn std::error::Error>),
+    SkippedByBudget(u64),
+    DigestOnly(String, u64),
+    Unchanged(String),
     Error(anyhow::Error),
 }
 
@@ -174,12 +869,18 @@ impl UniformResourceWriterAction {
             | UniformResourceWriterAction::CapturableExecNotExecutable() => {
                 Some(String::from("ISSUE"))
             }
+            UniformResourceWriterAction::SkippedByBudget(_) => {
+                Some(String::from("SKIPPED_BY_BUDGET"))
+            }
+            UniformResourceWriterAction::DigestOnly(_, _) => Some(String::from("DIGEST_ONLY")),
+            UniformResourceWriterAction::Unchanged(_) => Some(String::from("UNCHANGED")),
         }
     }
 
     fn ur_diagnostics(&self) -> Option<String> {
         match self {
             UniformResourceWriterAction::Inserted(_, _) => None,
+            UniformResourceWriterAction::Unchanged(_) => None,
             UniformResourceWriterAction::InsertedExecutableOutput(_, _, _) => None,
             UniformResourceWriterAction::CapturedExecutableSqlOutput(_, _) => None,
             UniformResourceWriterAction::CapturedExecutableNonZeroExit(_, diags) => {
@@ -206,6 +907,13 @@ impl UniformResourceWriterAction {
                     "instance": "UniformResourceWriterAction::CapturableExecNotExecutable",
                     "message": "File matched as a potential capturable executable but the file permissions do not allow execution",
                 })).unwrap()),
+            UniformResourceWriterAction::CapturableExecError(err) if err.is::<ExecutionTimeout>() =>
+                Some(serde_json::to_string_pretty(&json!({
+                    "instance": "UniformResourceWriterAction::CapturableExecError",
+                    "message": "File matched as a potential capturable executable but was killed for running too long",
+                    "issue": "timeout",
+                    "error": err.to_string()
+                })).unwrap()),
             UniformResourceWriterAction::CapturableExecError(err) =>
                 Some(serde_json::to_string_pretty(&json!({
                     "instance": "UniformResourceWriterAction::CapturableExecError",
@@ -218,6 +926,18 @@ impl UniformResourceWriterAction {
                     "message": "File matched as a potential capturable executable and was executed but could create a new uniform resource",
                     "error": err.to_string()
                 })).unwrap()),
+            UniformResourceWriterAction::DigestOnly(_, max_content_bytes) =>
+                Some(serde_json::to_string_pretty(&json!({
+                    "instance": "UniformResourceWriterAction::DigestOnly",
+                    "message": "Resource is larger than the maximum content size so only its digest was stored",
+                    "max-content-bytes": max_content_bytes
+                })).unwrap()),
+            UniformResourceWriterAction::SkippedByBudget(max_total_bytes) =>
+                Some(serde_json::to_string_pretty(&json!({
+                    "instance": "UniformResourceWriterAction::SkippedByBudget",
+                    "message": "Resource was not processed because the ingest content bytes budget was reached",
+                    "max-total-bytes": max_total_bytes
+                })).unwrap()),
             UniformResourceWriterAction::Error(err) =>
                 Some(serde_json::to_string_pretty(&json!({
                     "message": "UniformResourceWriterAction::Error(err)",
@@ -244,84 +964,290 @@ pub trait UniformResourceWriter<Resource> {
         &self,
         urw_state: &mut UniformResourceWriterState<'_, '_>,
         resource: &ContentResource,
-        _entry: &mut UniformResourceWriterEntry,
+        entry: &mut UniformResourceWriterEntry,
     ) -> UniformResourceWriterResult {
         let uri = resource.uri.clone();
         match resource.content_text_supplier.as_ref() {
             Some(text_supplier) => match text_supplier() {
-                Ok(text) => match urw_state.ingest_stmts.ins_ur_stmt.query_row(
-                    params![
+                Ok(text) => {
+                    let trimmed = urw_state.content_text_trimmed(text.content_text());
+                    let (content, content_codec, truncated) = match urw_state.stored_text_content(
+                        resource.nature.as_deref(),
+                        trimmed.as_deref().unwrap_or(text.content_text()),
+                    ) {
+                        Ok(stored) => stored,
+                        Err(err) => {
+                            return UniformResourceWriterResult {
+                                uri,
+                                action: UniformResourceWriterAction::Error(err),
+                            }
+                        }
+                    };
+                    match urw_state.ingest_stmts.upserted_ur(params![
                         urw_state.device_id,
                         urw_state.ingest_session_id,
                         urw_state.ingest_fs_path_id,
                         resource.uri,
                         resource.nature,
-                        text.content_text(),
+                        content,
                         text.content_digest_hash(),
                         resource.size,
-                        resource.last_modified_at.unwrap().to_string(),
+                        resource
+                            .last_modified_at
+                            .as_ref()
+                            .map(persistable_timestamp),
                         &None::<String>, // content_fm_body_attrs
                         &None::<String>, // frontmatter
-                    ],
-                    |row| row.get(0),
-                ) {
-                    Ok(new_or_existing_ur_id) => UniformResourceWriterResult {
-                        uri,
-                        action: UniformResourceWriterAction::Inserted(new_or_existing_ur_id, None),
-                    },
+                        &None::<String>, // frontmatter_error
+                        content_codec,
+                    ]) {
+                        Ok(new_or_existing_ur_id) => {
+                            if let Err(err) = urw_state.record_text_elaboration(
+                                &new_or_existing_ur_id,
+                                &resource.uri,
+                                trimmed.as_deref(),
+                                text.content_text_encoding(),
+                                truncated,
+                            ) {
+                                return UniformResourceWriterResult {
+                                    uri,
+                                    action: UniformResourceWriterAction::Error(err.into()),
+                                };
+                            }
+                            UniformResourceWriterResult {
+                                uri,
+                                action: UniformResourceWriterAction::Inserted(
+                                    new_or_existing_ur_id,
+                                    None,
+                                ),
+                            }
+                        }
+                        Err(err) => UniformResourceWriterResult {
+                            uri,
+                            action: UniformResourceWriterAction::Error(err),
+                        },
+                    }
+                }
+                Err(err) if err.is::<TextEncodingSkipped>() => {
+                    self.insert_skipped_text(urw_state, resource, entry, err)
+                }
+                Err(err) => UniformResourceWriterResult {
+                    uri,
+                    action: UniformResourceWriterAction::ContentSupplierError(err),
+                },
+            },
+            // with `--content-binary-only` text natures are stored as binary
+            None => match resource.content_binary_supplier.as_ref() {
+                Some(binary_supplier) => match binary_supplier() {
+                    Ok(bc) => self.insert_binary(urw_state, resource, bc, entry),
                     Err(err) => UniformResourceWriterResult {
                         uri,
-                        action: UniformResourceWriterAction::Error(err.into()),
+                        action: UniformResourceWriterAction::ContentSupplierError(err),
                     },
                 },
-                Err(err) => UniformResourceWriterResult {
+                None => UniformResourceWriterResult {
                     uri,
-                    action: UniformResourceWriterAction::ContentSupplierError(err),
+                    action: UniformResourceWriterAction::ContentUnavailable(),
                 },
             },
-            None => UniformResourceWriterResult {
-                uri,
-                action: UniformResourceWriterAction::ContentUnavailable(),
-            },
         }
     }
 
-    fn insert_binary(
+    // with `--text-encoding-fallback skip` content which isn't valid UTF-8 is
+    // stored as binary (and its `elaboration.encoding` recorded as `binary`)
+    fn insert_skipped_text(
         &self,
         urw_state: &mut UniformResourceWriterState<'_, '_>,
         resource: &ContentResource,
-        bc: Box<dyn BinaryContent>,
-        _entry: &mut UniformResourceWriterEntry,
+        entry: &mut UniformResourceWriterEntry,
+        err: Box<dyn std::error::Error>,
     ) -> UniformResourceWriterResult {
         let uri = resource.uri.clone();
-        match urw_state.ingest_stmts.ins_ur_stmt.query_row(
-            params![
-                urw_state.device_id,
-                urw_state.ingest_session_id,
-                urw_state.ingest_fs_path_id,
-                resource.uri,
-                resource.nature,
-                bc.content_binary(),
-                bc.content_digest_hash(),
-                resource.size,
-                resource.last_modified_at.unwrap().to_string(),
-                &None::<String>, // content_fm_body_attrs
-                &None::<String>, // frontmatter
-            ],
-            |row| row.get(0),
-        ) {
-            Ok(new_or_existing_ur_id) => UniformResourceWriterResult {
-                uri,
-                action: UniformResourceWriterAction::Inserted(new_or_existing_ur_id, None),
-            },
+        let bc = match resource.content_binary_supplier.as_ref() {
+            Some(binary_supplier) => match binary_supplier() {
+                Ok(bc) => bc,
+                Err(err) => {
+                    return UniformResourceWriterResult {
+                        uri,
+                        action: UniformResourceWriterAction::ContentSupplierError(err),
+                    }
+                }
+            },
+            None => {
+                return UniformResourceWriterResult {
+                    uri,
+                    action: UniformResourceWriterAction::ContentSupplierError(err),
+                }
+            }
+        };
+        let inserted = self.insert_binary(urw_state, resource, bc, entry);
+        if let UniformResourceWriterAction::Inserted(ur_id, _) = &inserted.action {
+            if let Err(err) = urw_state.record_text_encoding(ur_id, Some("binary")) {
+                return UniformResourceWriterResult {
+                    uri,
+                    action: UniformResourceWriterAction::Error(err.into()),
+                };
+            }
+        }
+        inserted
+    }
+
+    fn insert_binary(
+        &self,
+        urw_state: &mut UniformResourceWriterState<'_, '_>,
+        resource: &ContentResource,
+        bc: Box<dyn BinaryContent>,
+        _entry: &mut UniformResourceWriterEntry,
+    ) -> UniformResourceWriterResult {
+        let uri = resource.uri.clone();
+        let truncated =
+            urw_state.exceeds_max_store_bytes(resource.nature.as_deref(), bc.content_binary());
+        let stored = match truncated {
+            true => Ok((ToSqlOutput::Owned(Value::Null), None)),
+            false => match urw_state.chunked_content(bc.content_binary()) {
+                Ok(Some((chunk_ids, content_codec))) => Ok((
+                    ToSqlOutput::Owned(Value::Blob(chunk_ids)),
+                    Some(content_codec),
+                )),
+                Ok(None) => urw_state.stored_content(ValueRef::Blob(bc.content_binary())),
+                Err(err) => Err(err),
+            },
+        };
+        let (content, content_codec) = match stored {
+            Ok(stored) => stored,
+            Err(err) => {
+                return UniformResourceWriterResult {
+                    uri,
+                    action: UniformResourceWriterAction::Error(err),
+                }
+            }
+        };
+        match urw_state.ingest_stmts.upserted_ur(params![
+            urw_state.device_id,
+            urw_state.ingest_session_id,
+            urw_state.ingest_fs_path_id,
+            resource.uri,
+            resource.nature,
+            content,
+            bc.content_digest_hash(),
+            resource.size,
+            resource
+                .last_modified_at
+                .as_ref()
+                .map(persistable_timestamp),
+            &None::<String>, // content_fm_body_attrs
+            &None::<String>, // frontmatter
+            &None::<String>, // frontmatter_error
+            content_codec,
+        ]) {
+            Ok(new_or_existing_ur_id) => {
+                if let Err(err) =
+                    urw_state.record_content_truncated(&new_or_existing_ur_id, truncated)
+                {
+                    return UniformResourceWriterResult {
+                        uri,
+                        action: UniformResourceWriterAction::Error(err.into()),
+                    };
+                }
+                UniformResourceWriterResult {
+                    uri,
+                    action: UniformResourceWriterAction::Inserted(new_or_existing_ur_id, None),
+                }
+            }
             Err(err) => UniformResourceWriterResult {
                 uri,
-                action: UniformResourceWriterAction::Error(err.into()),
+                action: UniformResourceWriterAction::Error(err),
             },
         }
     }
 }
 
+// resources over the classifier's `max_content_bytes` have no content suppliers,
+// their digest is streamed from the source and no content is stored
+fn insert_digest_only(
+    urw_state: &mut UniformResourceWriterState<'_, '_>,
+    resource: &ContentResource,
+    digest_supplier: &DigestContentSupplier,
+    max_content_bytes: u64,
+) -> UniformResourceWriterResult {
+    let uri = resource.uri.clone();
+    let digest = match digest_supplier() {
+        Ok(digest) => digest,
+        Err(err) => {
+            return UniformResourceWriterResult {
+                uri,
+                action: UniformResourceWriterAction::ContentSupplierError(err),
+            }
+        }
+    };
+    match urw_state.ingest_stmts.upserted_ur(params![
+        urw_state.device_id,
+        urw_state.ingest_session_id,
+        urw_state.ingest_fs_path_id,
+        resource.uri,
+        resource.nature,
+        &None::<String>, // not storing content
+        digest,
+        resource.size,
+        resource
+            .last_modified_at
+            .as_ref()
+            .map(persistable_timestamp),
+        &None::<String>, // content_fm_body_attrs
+        &None::<String>, // frontmatter
+        &None::<String>, // frontmatter_error
+        &None::<String>, // content_codec
+    ]) {
+        Ok(new_or_existing_ur_id) => UniformResourceWriterResult {
+            uri,
+            action: UniformResourceWriterAction::DigestOnly(
+                new_or_existing_ur_id,
+                max_content_bytes,
+            ),
+        },
+        Err(err) => UniformResourceWriterResult {
+            uri,
+            action: UniformResourceWriterAction::Error(err),
+        },
+    }
+}
+
+// with `--manifest-only` no content suppliers are created so only the metadata
+// is stored, whatever the nature, and nothing is hashed
+fn insert_manifest_only(
+    urw_state: &mut UniformResourceWriterState<'_, '_>,
+    resource: &ContentResource,
+) -> UniformResourceWriterResult {
+    let uri = resource.uri.clone();
+    match urw_state.ingest_stmts.upserted_ur(params![
+        urw_state.device_id,
+        urw_state.ingest_session_id,
+        urw_state.ingest_fs_path_id,
+        resource.uri,
+        resource.nature,
+        &None::<String>,   // not storing content
+        String::from("-"), // no hash being computed
+        resource.size,
+        resource
+            .last_modified_at
+            .as_ref()
+            .map(persistable_timestamp),
+        &None::<String>, // content_fm_body_attrs
+        &None::<String>, // frontmatter
+        &None::<String>, // frontmatter_error
+        &None::<String>, // content_codec
+    ]) {
+        Ok(new_or_existing_ur_id) => UniformResourceWriterResult {
+            uri,
+            action: UniformResourceWriterAction::Inserted(new_or_existing_ur_id, None),
+        },
+        Err(err) => UniformResourceWriterResult {
+            uri,
+            action: UniformResourceWriterAction::Error(err),
+        },
+    }
+}
+
 // this is the unknown resource content handler
 impl UniformResourceWriter<ContentResource> for ContentResource {
     fn insert(
@@ -330,22 +1256,21 @@ impl UniformResourceWriter<ContentResource> for ContentResource {
         entry: &mut UniformResourceWriterEntry,
     ) -> UniformResourceWriterResult {
         let uri = self.uri.clone();
-        match urw_state.ingest_stmts.ins_ur_stmt.query_row(
-            params![
-                urw_state.device_id,
-                urw_state.ingest_session_id,
-                urw_state.ingest_fs_path_id,
-                self.uri,
-                self.nature,
-                &None::<String>,   // not storing content
-                String::from("-"), // no hash being computed
-                self.size,
-                self.last_modified_at.unwrap().to_string(),
-                &None::<String>, // content_fm_body_attrs
-                &None::<String>, // frontmatter
-            ],
-            |row| row.get(0),
-        ) {
+        match urw_state.ingest_stmts.upserted_ur(params![
+            urw_state.device_id,
+            urw_state.ingest_session_id,
+            urw_state.ingest_fs_path_id,
+            self.uri,
+            self.nature,
+            &None::<String>,   // not storing content
+            String::from("-"), // no hash being computed
+            self.size,
+            self.last_modified_at.as_ref().map(persistable_timestamp),
+            &None::<String>, // content_fm_body_attrs
+            &None::<String>, // frontmatter
+            &None::<String>, // frontmatter_error
+            &None::<String>, // content_codec
+        ]) {
             Ok(new_or_existing_ur_id) => UniformResourceWriterResult {
                 uri,
                 action: UniformResourceWriterAction::Inserted(
@@ -362,12 +1287,60 @@ impl UniformResourceWriter<ContentResource> for ContentResource {
             },
             Err(err) => UniformResourceWriterResult {
                 uri,
-                action: UniformResourceWriterAction::Error(err.into()),
+                action: UniformResourceWriterAction::Error(err),
             },
         }
     }
 }
 
+impl CapturableExecResource<ContentResource> {
+    // the captured output stands in for a file of the declared nature so that it
+    // becomes the same uniform resource variant (JSON, Markdown, images, etc.)
+    // with the same treatment as a file-sourced resource of that nature
+    fn captured_output_resource(
+        &self,
+        nature: &str,
+        stdout: String,
+        digest_algorithm: DigestAlgorithm,
+    ) -> ContentResource {
+        let hash = digest_algorithm.digest(&stdout);
+        let size = stdout.len() as u64;
+        let stdout = std::sync::Arc::new(stdout);
+        let (text_stdout, text_hash) = (stdout.clone(), hash.clone());
+        let (binary_stdout, binary_hash) = (stdout, hash.clone());
+        ContentResource {
+            flags: self.resource.flags,
+            uri: self.resource.uri.clone(),
+            nature: Some(nature.to_string()),
+            size: Some(size),
+            created_at: Some(chrono::Utc::now()),
+            last_modified_at: Some(chrono::Utc::now()),
+            declared_nature: None,
+            git: None,
+            symlink_target: None,
+            content_binary_supplier: Some(Box::new(
+                move || -> Result<Box<dyn BinaryContent>, Box<dyn std::error::Error>> {
+                    Ok(Box::new(ResourceBinaryContent {
+                        hash: binary_hash.clone(),
+                        binary: binary_stdout.as_bytes().to_vec(),
+                    }))
+                },
+            )),
+            content_digest_supplier: Some(Box::new(
+                move || -> Result<String, Box<dyn std::error::Error>> { Ok(hash.clone()) },
+            )),
+            content_text_supplier: Some(Box::new(
+                move || -> Result<Box<dyn TextContent>, Box<dyn std::error::Error>> {
+                    Ok(Box::new(ResourceTextContent {
+                        text: text_stdout.to_string(),
+                        hash: text_hash.clone(),
+                    }))
+                },
+            )),
+        }
+    }
+}
+
 impl UniformResourceWriter<ContentResource> for CapturableExecResource<ContentResource> {
     fn insert(
         &self,
@@ -384,16 +1357,42 @@ impl UniformResourceWriter<ContentResource> for CapturableExecResource<ContentRe
                 interpretable_code,
                 nature,
                 is_batched_sql,
+                timeout,
             ) => {
-                let stdin = urw_state.capturable_exec_ctx(entry);
-                match executive.execute(stdin.clone()) {
+                let (stdin, executed) = match urw_state.executed_ahead.remove(&self.resource.uri) {
+                    Some(executed_ahead) => executed_ahead,
+                    None => {
+                        let stdin = match urw_state.capturable_exec_stdin(&self.resource, entry) {
+                            Ok(stdin) => stdin,
+                            Err(err) => {
+                                return UniformResourceWriterResult {
+                                    uri: self.resource.uri.clone(),
+                                    action: UniformResourceWriterAction::CapturableExecError(err),
+                                }
+                            }
+                        };
+                        let executed = executive.execute(stdin.clone(), Some(*timeout));
+                        (stdin, executed)
+                    }
+                };
+                match executed {
                     Ok(shell_result) => {
+                        if let Err(err) =
+                            urw_state.record_capturable_exec_run(&self.resource.uri, &shell_result)
+                        {
+                            return UniformResourceWriterResult {
+                                uri: self.resource.uri.clone(),
+                                action: UniformResourceWriterAction::Error(err.into()),
+                            };
+                        }
                         let captured_executable_diags = json!({
                             "args": [],
                             "interpretable-code": interpretable_code,
                             "stdin": stdin.json(),
                             "exit-status": format!("{:?}", shell_result.status),
                             "stderr": shell_result.stderr,
+                            "captures": self.captures,
+                            "retries": shell_result.retries,
                         });
 
                         if shell_result.success() {
@@ -410,24 +1409,11 @@ impl UniformResourceWriter<ContentResource> for CapturableExecResource<ContentRe
                                 };
                             }
 
-                            let hash = shell_result.stdout_hash();
-                            let output_res = ContentResource {
-                                flags: self.resource.flags,
-                                uri: self.resource.uri.clone(),
-                                nature: Some(nature.clone()),
-                                size: Some(shell_result.stdout.len().try_into().unwrap()),
-                                created_at: Some(chrono::Utc::now()),
-                                last_modified_at: Some(chrono::Utc::now()),
-                                content_binary_supplier: None,
-                                content_text_supplier: Some(Box::new(
-                                    move || -> Result<Box<dyn TextContent>, Box<dyn std::error::Error>> {
-                                        // TODO: do we really need to make clone these, can't we just
-                                        // pass in self.executable.capturable_exec_text_supplier!?!?
-                                        Ok(Box::new(ResourceTextContent { text: shell_result.stdout.clone(), hash: hash.clone() })
-                                            as Box<dyn TextContent>)
-                                    },
-                                )),
-                            };
+                            let output_res = self.captured_output_resource(
+                                nature,
+                                shell_result.stdout,
+                                urw_state.digest_algorithm(),
+                            );
 
                             match urw_state.resources.uniform_resource(output_res) {
                                 Ok(output_ur) => {
@@ -435,13 +1421,31 @@ impl UniformResourceWriter<ContentResource> for CapturableExecResource<ContentRe
                                     let inserted_output = ur.insert(urw_state, entry);
                                     match inserted_output.action {
                                         UniformResourceWriterAction::Inserted(ur_id, ur_status) => {
+                                            if !self.captures.is_empty() {
+                                                if let Err(err) = urw_state
+                                                    .ingest_stmts
+                                                    .upd_ur_captures_stmt
+                                                    .execute(params![
+                                                        serde_json::to_string(&self.captures)
+                                                            .unwrap(),
+                                                        ur_id,
+                                                    ])
+                                                {
+                                                    return UniformResourceWriterResult {
+                                                        uri: inserted_output.uri,
+                                                        action: UniformResourceWriterAction::Error(
+                                                            err.into(),
+                                                        ),
+                                                    };
+                                                }
+                                            }
                                             UniformResourceWriterResult {
                                                 uri: inserted_output.uri,
                                                 action: UniformResourceWriterAction::InsertedExecutableOutput(ur_id, ur_status,
                                                     captured_executable_diags),
                                             }
-                                        },
-                                        _ => inserted_output
+                                        }
+                                        _ => inserted_output,
                                     }
                                 }
                                 Err(err) => UniformResourceWriterResult {
@@ -476,13 +1480,101 @@ impl UniformResourceWriter<ContentResource> for CapturableExecResource<ContentRe
     }
 }
 
+impl UniformResourceWriter<ContentResource> for EmailResource<ContentResource> {
+    fn insert(
+        &self,
+        urw_state: &mut UniformResourceWriterState<'_, '_>,
+        entry: &mut UniformResourceWriterEntry,
+    ) -> UniformResourceWriterResult {
+        let uri = self.resource.uri.clone();
+        let message = match self.resource.content_binary_supplier.as_ref() {
+            Some(message_supplier) => match message_supplier() {
+                Ok(message) => message,
+                Err(err) => {
+                    return UniformResourceWriterResult {
+                        uri,
+                        action: UniformResourceWriterAction::ContentSupplierError(err),
+                    }
+                }
+            },
+            None => {
+                return UniformResourceWriterResult {
+                    uri,
+                    action: UniformResourceWriterAction::ContentUnavailable(),
+                }
+            }
+        };
+
+        // store the raw message as the content, then (for RFC 822 only) the parsed
+        // headers and body in the `uniform_resource_email` side table; Outlook
+        // `.msg` files are kept as binary without any parsing for now
+        let parsed = match self.format {
+            EmailFormat::Rfc822 => crate::email::email_message(message.content_binary()),
+            EmailFormat::OutlookMsg | EmailFormat::Unknown => None,
+        };
+        let inserted = self.insert_binary(urw_state, &self.resource, message, entry);
+        if let (UniformResourceWriterAction::Inserted(ur_id, _), Some(email)) =
+            (&inserted.action, parsed)
+        {
+            if let Err(err) = urw_state.ingest_stmts.ins_ur_email_stmt.execute(params![
+                ur_id,
+                email.message_id,
+                email.from,
+                serde_json::to_string(&email.to).unwrap(),
+                email.subject,
+                email.sent_at,
+                email.body_text,
+            ]) {
+                return UniformResourceWriterResult {
+                    uri,
+                    action: UniformResourceWriterAction::Error(err.into()),
+                };
+            }
+        }
+        inserted
+    }
+}
+
+// selectors which don't match are recorded as null, only unreadable content
+// (already reported by the insert) keeps the selections from being recorded
 impl UniformResourceWriter<ContentResource> for HtmlResource<ContentResource> {
     fn insert(
         &self,
         urw_state: &mut UniformResourceWriterState<'_, '_>,
         entry: &mut UniformResourceWriterEntry,
     ) -> UniformResourceWriterResult {
-        self.insert_text(urw_state, &self.resource, entry)
+        let inserted = self.insert_text(urw_state, &self.resource, entry);
+        let html_select = match urw_state.ingest_files_behavior {
+            Some(behavior) if !behavior.html_select.is_empty() => &behavior.html_select,
+            _ => return inserted,
+        };
+        if let UniformResourceWriterAction::Inserted(ur_id, _) = &inserted.action {
+            let Some(Ok(html)) = self
+                .resource
+                .content_text_supplier
+                .as_ref()
+                .map(|supplier| supplier())
+            else {
+                return inserted;
+            };
+            // the selectors were validated when the arguments were parsed
+            let selects: Vec<_> = html_select
+                .iter()
+                .filter_map(|(name, select)| crate::html::HtmlSelect::parse(name, select).ok())
+                .collect();
+            let selected = crate::html::selected_values(html.content_text(), &selects);
+            if let Err(err) = urw_state
+                .ingest_stmts
+                .upd_ur_html_select_stmt
+                .execute(params![serde_json::to_string(&selected).unwrap(), ur_id,])
+            {
+                return UniformResourceWriterResult {
+                    uri: inserted.uri,
+                    action: UniformResourceWriterAction::Error(err.into()),
+                };
+            }
+        }
+        inserted
     }
 }
 
@@ -515,17 +1607,112 @@ impl UniformResourceWriter<ContentResource> for JsonResource<ContentResource> {
         urw_state: &mut UniformResourceWriterState<'_, '_>,
         entry: &mut UniformResourceWriterEntry,
     ) -> UniformResourceWriterResult {
-        self.insert_text(urw_state, &self.resource, entry)
+        let inserted = self.insert_text(urw_state, &self.resource, entry);
+        let normalize = urw_state
+            .ingest_files_behavior
+            .map(|behavior| behavior.normalize_json_before_hash)
+            .unwrap_or(false);
+        let json_schemas = urw_state
+            .json_schemas
+            .filter(|json_schemas| !json_schemas.is_empty());
+        if !normalize && json_schemas.is_none() {
+            return inserted;
+        }
+
+        // the raw-bytes digest stays on uniform_resource, the canonical content and
+        // its digest are stored as a transform; invalid JSON is simply not normalized
+        if let (
+            UniformResourceWriterAction::Inserted(uniform_resource_id, _),
+            Some(text_supplier),
+        ) = (
+            &inserted.action,
+            self.resource.content_text_supplier.as_ref(),
+        ) {
+            if let Ok(text) = text_supplier() {
+                // issues are keyed by digest so re-ingesting the same content adds none
+                if let Some(json_schemas) = json_schemas {
+                    for issue in json_schemas.issues(&self.resource.uri, text.content_text()) {
+                        if let Err(err) =
+                            urw_state
+                                .ingest_stmts
+                                .ins_validation_issue_stmt
+                                .execute(params![
+                                    text.content_digest_hash(),
+                                    self.resource.uri,
+                                    issue.schema_name,
+                                    issue.issue_type,
+                                    issue.instance_path,
+                                    issue.message
+                                ])
+                        {
+                            return UniformResourceWriterResult {
+                                uri: inserted.uri,
+                                action: UniformResourceWriterAction::Error(err.into()),
+                            };
+                        }
+                    }
+                }
+                if !normalize {
+                    return inserted;
+                }
+                if let Some(canonical) = crate::format::to_canonical_json_text(text.content_text())
+                {
+                    let hash = urw_state.digest_algorithm().digest(&canonical);
+                    if let Err(err) = urw_state.ingest_stmts.ins_ur_transform_stmt.query_row(
+                        params![
+                            uniform_resource_id,
+                            self.resource.uri,
+                            "json-canonical",
+                            hash,
+                            canonical,
+                            canonical.len()
+                        ],
+                        |row| row.get::<_, String>(0),
+                    ) {
+                        return UniformResourceWriterResult {
+                            uri: inserted.uri,
+                            action: UniformResourceWriterAction::Error(err.into()),
+                        };
+                    }
+                }
+            }
+        }
+        inserted
     }
 }
 
+// the raw text remains the content, the parsed (normalized) JSON is stored as a
+// transform; text which doesn't parse is stored without one
 impl UniformResourceWriter<ContentResource> for JsonableTextResource<ContentResource> {
     fn insert(
         &self,
         urw_state: &mut UniformResourceWriterState<'_, '_>,
         entry: &mut UniformResourceWriterEntry,
     ) -> UniformResourceWriterResult {
-        self.insert_text(urw_state, &self.resource, entry)
+        let inserted = self.insert_text(urw_state, &self.resource, entry);
+        if let UniformResourceWriterAction::Inserted(uniform_resource_id, _) = &inserted.action {
+            if let Ok(value) = self.as_json() {
+                let json = value.to_string();
+                let hash = urw_state.digest_algorithm().digest(&json);
+                if let Err(err) = urw_state.ingest_stmts.ins_ur_transform_stmt.query_row(
+                    params![
+                        uniform_resource_id,
+                        self.resource.uri,
+                        "json",
+                        hash,
+                        json,
+                        json.len()
+                    ],
+                    |row| row.get::<_, String>(0),
+                ) {
+                    return UniformResourceWriterResult {
+                        uri: inserted.uri,
+                        action: UniformResourceWriterAction::Error(err.into()),
+                    };
+                }
+            }
+        }
+        inserted
     }
 }
 
@@ -533,7 +1720,7 @@ impl UniformResourceWriter<ContentResource> for MarkdownResource<ContentResource
     fn insert(
         &self,
         urw_state: &mut UniformResourceWriterState<'_, '_>,
-        _entry: &mut UniformResourceWriterEntry,
+        entry: &mut UniformResourceWriterEntry,
     ) -> UniformResourceWriterResult {
         let uri = self.resource.uri.clone();
         match self.resource.content_text_supplier.as_ref() {
@@ -541,8 +1728,12 @@ impl UniformResourceWriter<ContentResource> for MarkdownResource<ContentResource
                 Ok(markdown_src) => {
                     let mut fm_attrs = None::<String>;
                     let mut fm_json: Option<String> = None::<String>;
+                    let mut fm_error: Option<String> = None::<String>;
                     let (_, fm_raw, fm_json_value, fm_body) = markdown_src.frontmatter();
-                    if fm_json_value.is_ok() {
+                    if let (Some(_), Err(err)) = (&fm_raw, &fm_json_value) {
+                        // frontmatter was delimited but could not be parsed
+                        fm_error = Some(err.to_string());
+                    } else if fm_json_value.is_ok() {
                         fm_json = Some(serde_json::to_string_pretty(&fm_json_value.ok()).unwrap());
                         // this needs to be JSON parse'd first so that stringify later will work properly
                         let attrs_json: serde_json::Value =
@@ -555,44 +1746,73 @@ impl UniformResourceWriter<ContentResource> for MarkdownResource<ContentResource
                         fm_attrs = Some(serde_json::to_string_pretty(&fm_attrs_value).unwrap());
                     }
                     let uri = self.resource.uri.to_string();
-                    match urw_state.ingest_stmts.ins_ur_stmt.query_row(
-                        params![
-                            urw_state.device_id,
-                            urw_state.ingest_session_id,
-                            urw_state.ingest_fs_path_id,
-                            self.resource.uri,
-                            self.resource.nature,
-                            markdown_src.content_text(),
-                            markdown_src.content_digest_hash(),
-                            self.resource.size,
-                            self.resource.last_modified_at.unwrap().to_string(),
-                            fm_attrs,
-                            fm_json,
-                        ],
-                        |row| row.get(0),
+                    let trimmed = urw_state.content_text_trimmed(markdown_src.content_text());
+                    let (content, content_codec, truncated) = match urw_state.stored_text_content(
+                        self.resource.nature.as_deref(),
+                        trimmed.as_deref().unwrap_or(markdown_src.content_text()),
                     ) {
-                        Ok(new_or_existing_ur_id) => UniformResourceWriterResult {
-                            uri,
-                            action: UniformResourceWriterAction::Inserted(
-                                new_or_existing_ur_id,
-                                None,
-                            ),
-                        },
+                        Ok(stored) => stored,
+                        Err(err) => {
+                            return UniformResourceWriterResult {
+                                uri,
+                                action: UniformResourceWriterAction::Error(err),
+                            }
+                        }
+                    };
+                    match urw_state.ingest_stmts.upserted_ur(params![
+                        urw_state.device_id,
+                        urw_state.ingest_session_id,
+                        urw_state.ingest_fs_path_id,
+                        self.resource.uri,
+                        self.resource.nature,
+                        content,
+                        markdown_src.content_digest_hash(),
+                        self.resource.size,
+                        self.resource
+                            .last_modified_at
+                            .as_ref()
+                            .map(persistable_timestamp),
+                        fm_attrs,
+                        fm_json,
+                        fm_error,
+                        content_codec,
+                    ]) {
+                        Ok(new_or_existing_ur_id) => {
+                            if let Err(err) = urw_state.record_text_elaboration(
+                                &new_or_existing_ur_id,
+                                &self.resource.uri,
+                                trimmed.as_deref(),
+                                markdown_src.content_text_encoding(),
+                                truncated,
+                            ) {
+                                return UniformResourceWriterResult {
+                                    uri,
+                                    action: UniformResourceWriterAction::Error(err.into()),
+                                };
+                            }
+                            UniformResourceWriterResult {
+                                uri,
+                                action: UniformResourceWriterAction::Inserted(
+                                    new_or_existing_ur_id,
+                                    None,
+                                ),
+                            }
+                        }
                         Err(err) => UniformResourceWriterResult {
                             uri,
-                            action: UniformResourceWriterAction::Error(err.into()),
+                            action: UniformResourceWriterAction::Error(err),
                         },
                     }
                 }
+                Err(err) if err.is::<TextEncodingSkipped>() => {
+                    self.insert_skipped_text(urw_state, &self.resource, entry, err)
+                }
                 Err(err) => UniformResourceWriterResult {
                     uri,
                     action: UniformResourceWriterAction::ContentSupplierError(err),
                 },
             },
-            None => UniformResourceWriterResult {
-                uri,
-                action: UniformResourceWriterAction::ContentUnavailable(),
-            },
+            None => self.insert_text(urw_state, &self.resource, entry),
         }
     }
 }
@@ -617,13 +1837,206 @@ impl UniformResourceWriter<ContentResource> for SourceCodeResource<ContentResour
     }
 }
 
+impl UniformResourceWriter<ContentResource> for SqliteDbResource<ContentResource> {
+    fn insert(
+        &self,
+        urw_state: &mut UniformResourceWriterState<'_, '_>,
+        entry: &mut UniformResourceWriterEntry,
+    ) -> UniformResourceWriterResult {
+        let inspect = urw_state
+            .ingest_files_behavior
+            .map(|behavior| behavior.inspect_sqlite)
+            .unwrap_or(false);
+        if !inspect {
+            // without inspection SQLite files are treated like any unknown nature
+            return self.resource.insert(urw_state, entry);
+        }
+
+        let uri = self.resource.uri.clone();
+        let inserted = match self.resource.content_binary_supplier.as_ref() {
+            Some(db_supplier) => match db_supplier() {
+                Ok(db) => self.insert_binary(urw_state, &self.resource, db, entry),
+                Err(err) => {
+                    return UniformResourceWriterResult {
+                        uri,
+                        action: UniformResourceWriterAction::ContentSupplierError(err),
+                    }
+                }
+            },
+            None => match urw_state.ingest_stmts.upserted_ur(params![
+                urw_state.device_id,
+                urw_state.ingest_session_id,
+                urw_state.ingest_fs_path_id,
+                self.resource.uri,
+                self.resource.nature,
+                &None::<String>,   // not storing content
+                String::from("-"), // no hash being computed
+                self.resource.size,
+                self.resource
+                    .last_modified_at
+                    .as_ref()
+                    .map(persistable_timestamp),
+                &None::<String>, // content_fm_body_attrs
+                &None::<String>, // frontmatter
+                &None::<String>, // frontmatter_error
+                &None::<String>, // content_codec
+            ]) {
+                Ok(new_or_existing_ur_id) => UniformResourceWriterResult {
+                    uri: uri.clone(),
+                    action: UniformResourceWriterAction::Inserted(new_or_existing_ur_id, None),
+                },
+                Err(err) => {
+                    return UniformResourceWriterResult {
+                        uri,
+                        action: UniformResourceWriterAction::Error(err),
+                    }
+                }
+            },
+        };
+
+        if let UniformResourceWriterAction::Inserted(ur_id, _) = &inserted.action {
+            // never open the state database itself (it's in the middle of our transaction)
+            let is_state_db = match (
+                std::fs::canonicalize(&uri),
+                std::fs::canonicalize(urw_state.state_db_fs_path),
+            ) {
+                (Ok(candidate), Ok(state_db)) => candidate == state_db,
+                _ => false,
+            };
+            let (tables_count, tables, inspection_error) = if is_state_db {
+                (
+                    None,
+                    None,
+                    Some(String::from("state database is not inspected")),
+                )
+            } else {
+                match sqlite_db_tables_row_counts(&uri) {
+                    Ok(tables) => (
+                        Some(tables.len()),
+                        Some(
+                            serde_json::to_string(
+                                &tables
+                                    .iter()
+                                    .map(|(name, row_count)| json!({ "name": name, "row_count": row_count }))
+                                    .collect::<Vec<_>>(),
+                            )
+                            .unwrap(),
+                        ),
+                        None,
+                    ),
+                    Err(err) => (None, None, Some(format!("{:#}", err))),
+                }
+            };
+            if let Err(err) = urw_state
+                .ingest_stmts
+                .ins_ur_sqlite_db_stmt
+                .execute(params![ur_id, tables_count, tables, inspection_error])
+            {
+                return UniformResourceWriterResult {
+                    uri,
+                    action: UniformResourceWriterAction::Error(err.into()),
+                };
+            }
+        }
+        inserted
+    }
+}
+
+// the text is stored as-is, the delimiter and header are kept in `elaboration`
+// so that views can use the header as column metadata
+impl UniformResourceWriter<ContentResource> for TabularResource<ContentResource> {
+    fn insert(
+        &self,
+        urw_state: &mut UniformResourceWriterState<'_, '_>,
+        entry: &mut UniformResourceWriterEntry,
+    ) -> UniformResourceWriterResult {
+        let inserted = self.insert_text(urw_state, &self.resource, entry);
+        if let UniformResourceWriterAction::Inserted(ur_id, _) = &inserted.action {
+            if let Err(err) = urw_state.ingest_stmts.upd_ur_tabular_stmt.execute(params![
+                (self.delimiter as char).to_string(),
+                serde_json::to_string(&self.header).unwrap(),
+                ur_id,
+            ]) {
+                return UniformResourceWriterResult {
+                    uri: inserted.uri,
+                    action: UniformResourceWriterAction::Error(err.into()),
+                };
+            }
+        }
+        inserted
+    }
+}
+
+// the extracted page text is stored as the content so it can be searched like
+// any other text; encrypted or malformed PDFs become supplier errors
+impl UniformResourceWriter<ContentResource> for PdfResource<ContentResource> {
+    fn insert(
+        &self,
+        urw_state: &mut UniformResourceWriterState<'_, '_>,
+        entry: &mut UniformResourceWriterEntry,
+    ) -> UniformResourceWriterResult {
+        let inserted = self.insert_text(urw_state, &self.resource, entry);
+        if let UniformResourceWriterAction::Inserted(ur_id, _) = &inserted.action {
+            if let Err(err) = urw_state
+                .ingest_stmts
+                .upd_ur_pdf_stmt
+                .execute(params![self.page_count, ur_id])
+            {
+                return UniformResourceWriterResult {
+                    uri: inserted.uri,
+                    action: UniformResourceWriterAction::Error(err.into()),
+                };
+            }
+        }
+        inserted
+    }
+}
+
+// malformed XML is still stored, the parse error is recorded in its elaboration
 impl UniformResourceWriter<ContentResource> for XmlResource<ContentResource> {
     fn insert(
         &self,
         urw_state: &mut UniformResourceWriterState<'_, '_>,
         entry: &mut UniformResourceWriterEntry,
     ) -> UniformResourceWriterResult {
-        self.insert_text(urw_state, &self.resource, entry)
+        let inserted = self.insert_text(urw_state, &self.resource, entry);
+        let svg = matches!(self.schema, XmlSchema::Svg);
+        let xml_select = match urw_state.ingest_files_behavior {
+            Some(behavior) => behavior.xml_select.as_slice(),
+            None => &[],
+        };
+        if !svg && xml_select.is_empty() {
+            return inserted;
+        }
+        if let UniformResourceWriterAction::Inserted(ur_id, _) = &inserted.action {
+            let Some(Ok(xml)) = self
+                .resource
+                .content_text_supplier
+                .as_ref()
+                .map(|supplier| supplier())
+            else {
+                return inserted;
+            };
+            // the paths were validated when the arguments were parsed
+            let selects: Vec<_> = xml_select
+                .iter()
+                .filter_map(|(name, select)| crate::xml::XmlSelect::parse(name, select).ok())
+                .collect();
+            let elaboration = crate::xml::elaboration(xml.content_text(), svg, &selects);
+            for (key, value) in elaboration {
+                if let Err(err) = urw_state.ingest_stmts.upd_ur_xml_stmt.execute(params![
+                    format!("$.{key}"),
+                    serde_json::to_string(&value).unwrap(),
+                    ur_id,
+                ]) {
+                    return UniformResourceWriterResult {
+                        uri: inserted.uri,
+                        action: UniformResourceWriterAction::Error(err.into()),
+                    };
+                }
+            }
+        }
+        inserted
     }
 }
 
@@ -633,15 +2046,39 @@ impl UniformResource<ContentResource> {
         urw_state: &mut UniformResourceWriterState<'_, '_>,
         entry: &mut UniformResourceWriterEntry,
     ) -> UniformResourceWriterResult {
+        let cr = self.content_resource();
+        let manifest_only = urw_state.ingest_files_behavior.is_some_and(|behavior| {
+            behavior.classifier.content_acquisition == ContentAcquisition::ManifestOnly
+        });
+        if manifest_only && !matches!(self, UniformResource::CapturableExec(_)) {
+            return insert_manifest_only(urw_state, cr);
+        }
+        if let (None, None, Some(digest_supplier), Some(max_content_bytes)) = (
+            &cr.content_binary_supplier,
+            &cr.content_text_supplier,
+            &cr.content_digest_supplier,
+            urw_state
+                .ingest_files_behavior
+                .and_then(|behavior| behavior.classifier.max_content_bytes),
+        ) {
+            if !matches!(self, UniformResource::CapturableExec(_)) {
+                return insert_digest_only(urw_state, cr, digest_supplier, max_content_bytes);
+            }
+        }
+
         match self {
             UniformResource::CapturableExec(capturable) => capturable.insert(urw_state, entry),
+            UniformResource::Email(email) => email.insert(urw_state, entry),
             UniformResource::Html(html) => html.insert(urw_state, entry),
             UniformResource::Json(json) => json.insert(urw_state, entry),
             UniformResource::JsonableText(jtr) => jtr.insert(urw_state, entry),
             UniformResource::Image(img) => img.insert(urw_state, entry),
             UniformResource::Markdown(md) => md.insert(urw_state, entry),
+            UniformResource::Pdf(pdf) => pdf.insert(urw_state, entry),
             UniformResource::PlainText(txt) => txt.insert(urw_state, entry),
             UniformResource::SourceCode(sc) => sc.insert(urw_state, entry),
+            UniformResource::SqliteDb(sqlite) => sqlite.insert(urw_state, entry),
+            UniformResource::Tabular(tabular) => tabular.insert(urw_state, entry),
             UniformResource::Xml(xml) => xml.insert(urw_state, entry),
             UniformResource::Unknown(unknown, tried_alternate_nature) => {
                 if let Some(tried_alternate_nature) = tried_alternate_nature {
@@ -657,6 +2094,66 @@ impl UniformResource<ContentResource> {
 pub struct IngestFilesBehavior {
     pub classifier: EncounterableResourcePathClassifier,
     pub root_fs_paths: Vec<String>,
+    #[serde(default)]
+    pub archives: Vec<String>,
+    #[serde(default)]
+    pub normalize_json_before_hash: bool,
+    #[serde(default)]
+    pub inspect_sqlite: bool,
+    #[serde(default)]
+    pub content_text_trim: Option<crate::cmd::ContentTextTrim>,
+    #[serde(default)]
+    pub compress_content: Option<crate::cmd::ContentCodec>,
+    #[serde(default)]
+    pub chunk_content: bool,
+    #[serde(default)]
+    pub capture_exec_stdin: crate::cmd::CaptureExecStdin,
+    #[serde(default)]
+    pub no_capture_exec_diagnostics: bool,
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    #[serde(default)]
+    pub dedupe_inodes: bool,
+    #[serde(default)]
+    pub no_nature_rewrite_audit: bool,
+    #[serde(default)]
+    pub nature_filter: Option<NatureFilter>,
+    // resolved when each ingest starts so `24h` is relative to that ingest
+    #[serde(default)]
+    pub since: Option<String>,
+    // without a seed each ingest samples with a random one (recorded on its session)
+    #[serde(default)]
+    pub sample: Option<f64>,
+    #[serde(default)]
+    pub sample_seed: Option<u64>,
+    #[serde(default)]
+    pub nature_bind: HashMap<String, String>,
+    #[serde(default)]
+    pub html_select: Vec<(String, String)>,
+    #[serde(default)]
+    pub xml_select: Vec<(String, String)>,
+    #[serde(default)]
+    pub max_store_bytes: Option<u64>,
+    #[serde(default)]
+    pub ordered_capture: bool,
+    #[serde(default)]
+    pub hash_filenames: bool,
+    #[serde(default)]
+    pub skip_empty_files: bool,
+    #[serde(default)]
+    pub capture_exec_concurrency: usize,
+    #[serde(default)]
+    pub max_store_bytes_for_nature: HashMap<String, u64>,
+    #[serde(default)]
+    pub json_schema: Vec<(String, String)>,
+    #[serde(default)]
+    pub json_schema_apply: Vec<(String, String)>,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub git_metadata: bool,
+    #[serde(default)]
+    pub walker: Walker,
 }
 
 impl IngestFilesBehavior {
@@ -698,6 +2195,25 @@ impl IngestFilesBehavior {
         }
     }
 
+    /// The classification rules the CLI flags are layered on: those in
+    /// `--path-rules-json`, otherwise the defaults stored in the RSSD.
+    pub fn path_rules(
+        args: &crate::cmd::IngestFilesArgs,
+        conn: &Connection,
+    ) -> anyhow::Result<EncounterableResourcePathRules> {
+        match &args.path_rules_json {
+            Some(fs_path) => {
+                let json_text = std::fs::read_to_string(fs_path).with_context(|| {
+                    format!("[IngestFilesBehavior.path_rules] unable to read --path-rules-json {fs_path}")
+                })?;
+                EncounterableResourcePathRules::from_json_text(&json_text).with_context(|| {
+                    format!("[IngestFilesBehavior.path_rules] invalid --path-rules-json {fs_path}")
+                })
+            }
+            None => Ok(EncounterableResourcePathRules::default_from_conn(conn)?),
+        }
+    }
+
     pub fn from_ingest_args(
         args: &crate::cmd::IngestFilesArgs,
         conn: &Connection,
@@ -705,24 +2221,130 @@ impl IngestFilesBehavior {
         // the names in `args` are convenient for CLI usage but the struct
         // field names in IngestBehavior should be longer and more descriptive
         // since IngestBehavior is stored as activity in the database.
-        Ok(IngestFilesBehavior {
-            classifier: EncounterableResourcePathClassifier::default_from_conn(conn)?,
-            root_fs_paths: args.root_fs_path.clone(),
-        })
-    }
-
-    pub fn from_json(json_text: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(json_text)
-    }
-
-    pub fn persistable_json_text(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string_pretty(self)
-    }
-
-    pub fn save(
-        &self,
-        conn: &Connection,
-        device_id: &String,
+        let mut classifier =
+            EncounterableResourcePathClassifier::from_path_rules(Self::path_rules(args, conn)?)
+                .with_context(|| "[IngestFilesBehavior.from_ingest_args] invalid path rules")?;
+        // the defaults are dropped before any of the flags' own rules are added
+        let mut without_defaults = EncounterableResourceFlags::empty();
+        without_defaults.set(
+            EncounterableResourceFlags::IGNORE_RESOURCE,
+            args.no_default_ignores,
+        );
+        without_defaults.set(
+            EncounterableResourceFlags::CONTENT_ACQUIRABLE,
+            args.no_default_acquire,
+        );
+        without_defaults.set(
+            EncounterableResourceFlags::CAPTURABLE_EXECUTABLE,
+            args.no_default_capture,
+        );
+        classifier.remove_flaggables(without_defaults);
+        classifier.digest_algorithm = args.digest_algo;
+        classifier.sniff_content_nature = args.sniff_content_nature;
+        classifier.max_content_bytes = args.max_content_bytes;
+        classifier.text_encoding_fallback = args.text_encoding_fallback;
+        classifier.content_acquisition = ContentAcquisition::from_args(
+            args.content_text_only,
+            args.content_binary_only,
+            args.manifest_only,
+        );
+        classifier.acquire_capturable_content =
+            args.capture_exec_stdin == crate::cmd::CaptureExecStdin::Content;
+        classifier.capture_exec_timeout_secs = args.capture_exec_timeout_secs;
+        classifier.max_retries = args.max_retries;
+        // SQLite databases are inspected by their header, whatever their extension
+        classifier.sniff_sqlite = args.inspect_sqlite;
+        classifier.resolve_symlink_nature = args.resolve_symlink_nature;
+        classifier.capture_nature_from_directive = args.capture_nature_from_directive;
+        classifier.capture_exec_env =
+            ShellEnvPolicy::from_args(&args.capture_exec_env, args.capture_exec_clean_env)
+                .with_context(|| {
+                    "[IngestFilesBehavior.from_ingest_args] invalid --capture-exec-env"
+                })?;
+        for pattern in &args.ignore_fs_entry {
+            classifier
+                .add_path_rule(PathRuleAction::Exclude, pattern)
+                .with_context(|| {
+                    format!("[IngestFilesBehavior.from_ingest_args] invalid --ignore-fs-entry {pattern}")
+                })?;
+        }
+        for pattern in &args.reinclude_fs_entry {
+            classifier
+                .add_path_rule(PathRuleAction::Include, pattern)
+                .with_context(|| {
+                    format!("[IngestFilesBehavior.from_ingest_args] invalid --reinclude-fs-entry {pattern}")
+                })?;
+        }
+        for pattern in &args.ignore_glob {
+            classifier
+                .add_path_glob(PathGlobAction::Ignore, pattern)
+                .with_context(|| {
+                    format!(
+                        "[IngestFilesBehavior.from_ingest_args] invalid --ignore-glob {pattern}"
+                    )
+                })?;
+        }
+        for pattern in &args.acquire_glob {
+            classifier
+                .add_path_glob(PathGlobAction::Acquire, pattern)
+                .with_context(|| {
+                    format!(
+                        "[IngestFilesBehavior.from_ingest_args] invalid --acquire-glob {pattern}"
+                    )
+                })?;
+        }
+        Ok(IngestFilesBehavior {
+            classifier,
+            // the default `.` root path isn't walked along with archives
+            root_fs_paths: if args.archive.is_empty() {
+                args.root_fs_path.clone()
+            } else {
+                Vec::new()
+            },
+            archives: args.archive.clone(),
+            normalize_json_before_hash: args.normalize_json_before_hash,
+            inspect_sqlite: args.inspect_sqlite,
+            content_text_trim: args.content_text_trim,
+            compress_content: args.compress_content,
+            chunk_content: args.chunk_content,
+            capture_exec_stdin: args.capture_exec_stdin,
+            no_capture_exec_diagnostics: args.no_capture_exec_diagnostics,
+            follow_symlinks: args.follow_symlinks,
+            dedupe_inodes: args.dedupe_inodes,
+            no_nature_rewrite_audit: args.no_nature_rewrite_audit,
+            nature_filter: NatureFilter::from_args(&args.include_nature, &args.exclude_nature),
+            since: args.since.clone(),
+            sample: args.sample,
+            sample_seed: args.sample_seed,
+            nature_bind: args.nature_bind.iter().cloned().collect(),
+            html_select: args.html_select.clone(),
+            xml_select: args.xml_select.clone(),
+            max_store_bytes: args.max_store_bytes,
+            ordered_capture: args.ordered_capture,
+            hash_filenames: args.hash_filenames,
+            skip_empty_files: args.skip_empty_files,
+            capture_exec_concurrency: args.capture_exec_concurrency,
+            max_store_bytes_for_nature: args.max_store_bytes_for_nature.iter().cloned().collect(),
+            json_schema: args.json_schema.clone(),
+            json_schema_apply: args.json_schema_apply.clone(),
+            max_depth: args.max_depth,
+            git_metadata: args.git_metadata,
+            walker: args.walker,
+        })
+    }
+
+    pub fn from_json(json_text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json_text)
+    }
+
+    pub fn persistable_json_text(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn save(
+        &self,
+        conn: &Connection,
+        device_id: &String,
         behavior_name: &String,
     ) -> anyhow::Result<String> {
         let behavior_id: String = conn
@@ -751,16 +2373,242 @@ impl IngestFilesBehavior {
     }
 }
 
+/// The `schema_version` of `--stats-json` output, incremented whenever a field
+/// is renamed or removed (adding one doesn't change it).
+pub const INGEST_RESULT_SCHEMA_VERSION: u32 = 1;
+
+/// What happened during an `ingest_files` session, for callers which need the
+/// counts programmatically rather than as `--stats` output. It's also what
+/// `--stats-json` (and `--stats-json-file`) serializes, see the README.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct IngestResult {
+    pub schema_version: u32,
+    pub ingest_session_id: String,
+    pub encountered: usize,
+    pub ignored: usize,
+    pub not_found: usize,
+    pub duplicates: usize,
+    pub empty: usize,
+    pub walk_errors: usize,
+    pub captured_exec: usize,
+    pub uniform_resources_by_nature: HashMap<String, usize>,
+    pub total_bytes_hashed: u64,
+    #[serde(rename = "elapsed_ms", serialize_with = "serialize_duration_ms")]
+    pub elapsed: std::time::Duration,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<IngestProfile>,
+}
+
+/// Where the time of an `ingest_files` session went, only recorded with
+/// `--profile`. Hashing is summed across threads so with `--threads` it can be
+/// more than the wall clock time; the other phases exclude any hashing done
+/// while they ran.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct IngestProfile {
+    #[serde(rename = "walk_ms", serialize_with = "serialize_duration_ms")]
+    pub walk: std::time::Duration,
+    #[serde(rename = "classify_ms", serialize_with = "serialize_duration_ms")]
+    pub classify: std::time::Duration,
+    #[serde(rename = "hash_ms", serialize_with = "serialize_duration_ms")]
+    pub hash: std::time::Duration,
+    #[serde(rename = "persist_ms", serialize_with = "serialize_duration_ms")]
+    pub persist: std::time::Duration,
+    #[serde(rename = "total_ms", serialize_with = "serialize_duration_ms")]
+    pub total: std::time::Duration,
+    pub walked: usize,
+    pub classified: usize,
+    pub hashed: usize,
+}
+
+// times how long the resources iterator takes to produce each resource and how
+// much of that was hashing (the parallel walker hashes ahead of time)
+struct TimedResources<'a, I> {
+    resources: I,
+    hash_timing: Option<&'a HashTiming>,
+    elapsed: std::time::Duration,
+    hashing: std::time::Duration,
+    count: usize,
+}
+
+impl<'a, I> TimedResources<'a, I> {
+    fn new(resources: I, hash_timing: Option<&'a HashTiming>) -> Self {
+        TimedResources {
+            resources,
+            hash_timing,
+            elapsed: std::time::Duration::ZERO,
+            hashing: std::time::Duration::ZERO,
+            count: 0,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for TimedResources<'_, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(hash_timing) = self.hash_timing else {
+            return self.resources.next();
+        };
+        let started_at = std::time::Instant::now();
+        let hashed = hash_timing.elapsed();
+        let item = self.resources.next();
+        self.elapsed += started_at.elapsed();
+        self.hashing += hash_timing.elapsed().saturating_sub(hashed);
+        if item.is_some() {
+            self.count += 1;
+        }
+        item
+    }
+}
+
+// `--commit-batch-size` commits every so many resources instead of only once
+// at the end (0, the default, so that a session is all or nothing); the
+// resource being written is kept so a failure can say which one caused it
+struct CommitBatches<'conn> {
+    conn: &'conn Connection,
+    tx: Option<rusqlite::Transaction<'conn>>,
+    batch_size: usize,
+    uncommitted: usize,
+    committed: usize,
+    writing: Option<String>,
+}
+
+impl<'conn> CommitBatches<'conn> {
+    fn new(
+        conn: &'conn Connection,
+        tx: rusqlite::Transaction<'conn>,
+        batch_size: usize,
+    ) -> CommitBatches<'conn> {
+        CommitBatches {
+            conn,
+            tx: Some(tx),
+            batch_size,
+            uncommitted: 0,
+            committed: 0,
+            writing: None,
+        }
+    }
+
+    fn writing(&mut self, uri: &str) {
+        self.writing = Some(uri.to_string());
+    }
+
+    // SQLite rolls the transaction back by itself on some errors (e.g. a full
+    // disk or a `RAISE(ROLLBACK)` trigger) after which each statement would be
+    // committed on its own
+    fn ensure_open(&self) -> Result<()> {
+        if self.conn.is_autocommit() {
+            anyhow::bail!("[CommitBatches::ensure_open] the transaction was rolled back by SQLite");
+        }
+        Ok(())
+    }
+
+    // counts the resource just written and, at the end of a batch, commits it
+    // and begins the next one
+    fn written(&mut self) -> Result<()> {
+        self.ensure_open()?;
+        self.uncommitted += 1;
+        if self.batch_size > 0 && self.uncommitted >= self.batch_size {
+            self.commit()?;
+            self.tx = Some(
+                begin_transaction(self.conn)
+                    .context("[CommitBatches::written] unable to begin the next batch")?,
+            );
+        }
+        self.writing = None;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        if let Some(tx) = self.tx.take() {
+            tx.commit().with_context(|| {
+                format!(
+                    "[CommitBatches::commit] unable to commit a batch of {} resources",
+                    self.uncommitted
+                )
+            })?;
+        }
+        self.committed += std::mem::take(&mut self.uncommitted);
+        Ok(())
+    }
+
+    // rolls back the open batch and, since earlier batches can't be, records
+    // on the (already committed) session that it failed and where
+    fn failed(mut self, ingest_session_id: &str, err: anyhow::Error) -> anyhow::Error {
+        // dropping the transaction rolls it back (unless SQLite already did)
+        drop(self.tx.take());
+        let err = match &self.writing {
+            Some(uri) => err.context(format!(
+                "[ingest_files] failed writing {}, rolled back the {} resources since the last commit",
+                uri, self.uncommitted
+            )),
+            None => err.context(format!(
+                "[ingest_files] rolled back the {} resources since the last commit",
+                self.uncommitted
+            )),
+        };
+        if self.committed == 0 {
+            return err;
+        }
+        match self.conn.execute(
+            UPD_UR_INGEST_SESSION_FAILED_SQL,
+            params![
+                self.writing,
+                self.committed,
+                format!("{:#}", err),
+                ingest_session_id
+            ],
+        ) {
+            Ok(_) => err,
+            Err(record_err) => err.context(format!(
+                "[ingest_files] unable to mark session {} as failed: {}",
+                ingest_session_id, record_err
+            )),
+        }
+    }
+}
+
+fn serialize_duration_ms<S: serde::Serializer>(
+    duration: &std::time::Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_millis())
+}
+
+/// The `--root-fs-path` which stands for the file paths listed on STDIN, they're
+/// ingested as-is instead of being walked.
+pub const STDIN_ROOT_FS_PATH: &str = "-";
+
+// the deepest directory holding all of `fs_paths`, recorded as their root path
+fn common_root_fs_path(fs_paths: &[String]) -> Option<String> {
+    let mut common = std::path::Path::new(fs_paths.first()?)
+        .parent()?
+        .to_path_buf();
+    for fs_path in &fs_paths[1..] {
+        while !std::path::Path::new(fs_path).starts_with(&common) {
+            if !common.pop() {
+                return None;
+            }
+        }
+    }
+    Some(common.to_string_lossy().to_string())
+}
+
 pub fn ingest_files(
     cli: &crate::cmd::Cli,
     ingest_args: &crate::cmd::IngestFilesArgs,
-) -> Result<String> {
-    let mut dbc = DbConn::new(&ingest_args.state_db_fs_path, cli.debug).with_context(|| {
-        format!(
-            "[ingest_files] SQLite transaction in {}",
-            ingest_args.state_db_fs_path
-        )
-    })?;
+    progress: &dyn ProgressObserver,
+) -> Result<IngestResult> {
+    let _span = tracing::info_span!("ingest_files").entered();
+    let started_at = std::time::Instant::now();
+
+    let mut dbc = DbConn::new(&ingest_args.state_db_fs_path, cli.debug, &cli.db_pragmas())
+        .with_context(|| {
+            format!(
+                "[ingest_files] SQLite transaction in {}",
+                ingest_args.state_db_fs_path
+            )
+        })?;
     let db_fs_path = dbc.db_fs_path.clone();
 
     // putting everything inside a transaction improves performance significantly
@@ -779,6 +2627,13 @@ pub fn ingest_files(
     let (mut behavior, mut behavior_id) = IngestFilesBehavior::new(&device_id, ingest_args, &tx)
         .with_context(|| format!("[ingest_files] behavior issue {}", db_fs_path))?;
 
+    // `--nature-bind` takes precedence over the RSSD's `nature_bind` table
+    nature_binds(&tx, |_, nature, bound_nature| {
+        behavior.nature_bind.entry(nature).or_insert(bound_nature);
+        Ok(())
+    })
+    .with_context(|| format!("[ingest_files] nature_binds in {}", db_fs_path))?;
+
     if !ingest_args.include_state_db_in_ingestion {
         let canonical_db_fs_path = std::fs::canonicalize(std::path::Path::new(&db_fs_path))
             .with_context(|| format!("[ingest_files] unable to canonicalize in {}", db_fs_path))?;
@@ -798,51 +2653,1203 @@ pub fn ingest_files(
             .add_ignore_exact(db_journal_path.to_string_lossy().to_string().as_str());
     }
 
-    if let Some(save_behavior_name) = &ingest_args.save_behavior {
-        let saved_bid = behavior
-            .save(&tx, &device_id, save_behavior_name)
-            .with_context(|| {
-                format!(
-                    "[ingest_files] saving {} in {}",
-                    save_behavior_name, db_fs_path
-                )
-            })?;
-        if cli.debug > 0 {
-            println!("Saved behavior: {} ({})", save_behavior_name, saved_bid);
-        }
-        behavior_id = Some(saved_bid);
-    }
-    if cli.debug > 0 {
-        println!(
-            "Behavior: {}",
-            behavior_id.clone().unwrap_or(String::from("custom"))
-        );
-    }
+    if let Some(save_behavior_name) = &ingest_args.save_behavior {
+        let saved_bid = behavior
+            .save(&tx, &device_id, save_behavior_name)
+            .with_context(|| {
+                format!(
+                    "[ingest_files] saving {} in {}",
+                    save_behavior_name, db_fs_path
+                )
+            })?;
+        progress.on_behavior_saved(save_behavior_name, &saved_bid);
+        behavior_id = Some(saved_bid);
+    }
+    progress.on_behavior(behavior_id.as_deref());
+
+    let ingest_session_id: String = tx
+        .query_row(
+            INS_UR_INGEST_SESSION_SQL,
+            params![
+                device_id,
+                behavior_id,
+                match behavior.persistable_json_text() {
+                    Ok(json_text) => json_text,
+                    Err(_err) =>
+                        String::from("JSON serialization error, TODO: convert err to string"),
+                }
+            ],
+            |row| row.get(0),
+        )
+        .with_context(|| {
+            format!(
+                "[ingest_files] inserting UR walk session using {} in {}",
+                INS_UR_INGEST_SESSION_SQL, db_fs_path
+            )
+        })?;
+    record_session_provenance(&tx, cli, &ingest_session_id).with_context(|| {
+        format!(
+            "[ingest_files] unable to record provenance of session {} in {}",
+            ingest_session_id, db_fs_path
+        )
+    })?;
+
+    progress.on_session_started(&ingest_session_id);
+    let mut result = IngestResult {
+        schema_version: INGEST_RESULT_SCHEMA_VERSION,
+        ingest_session_id: ingest_session_id.clone(),
+        ..Default::default()
+    };
+    // only coarse phase boundaries are timed unless profiling
+    let hash_timing = ingest_args
+        .profile
+        .then(|| std::sync::Arc::new(HashTiming::default()));
+    let mut profile = ingest_args.profile.then(IngestProfile::default);
+    let seen_inodes: Option<SeenInodes> = behavior.dedupe_inodes.then(Default::default);
+
+    // a thread count of 0 lets rayon pick the number of CPUs
+    #[cfg(feature = "rayon")]
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(ingest_args.threads.unwrap_or(0))
+        .build()
+        .with_context(|| {
+            format!(
+                "[ingest_files] unable to build thread pool in {}",
+                db_fs_path
+            )
+        })?;
+
+    let incremental = ingest_args.incremental && !ingest_args.force_rehash;
+    let modified_since = behavior
+        .since
+        .as_deref()
+        .map(|since| crate::format::parsed_since(since, chrono::Utc::now()))
+        .transpose()
+        .map_err(anyhow::Error::msg)
+        .with_context(|| format!("[ingest_files] invalid since in {}", db_fs_path))?;
+    let sample = behavior
+        .sample
+        .map(|percent| Sample::new(percent, behavior.sample_seed));
+    if let Some(sample) = sample {
+        tx.execute(
+            UPD_UR_INGEST_SESSION_SAMPLE_SQL,
+            params![sample.percent, sample.seed as i64, ingest_session_id],
+        )
+        .with_context(|| {
+            format!(
+                "[ingest_files] unable to execute SQL {} in {}",
+                UPD_UR_INGEST_SESSION_SAMPLE_SQL, db_fs_path
+            )
+        })?;
+    }
+    let json_schemas = crate::json_schema::JsonSchemas::from_args(
+        &behavior.json_schema,
+        &behavior.json_schema_apply,
+    )
+    .with_context(|| format!("[ingest_files] invalid JSON schemas in {}", db_fs_path))?;
+    let mut parquet_rows = ingest_args
+        .parquet_out
+        .as_ref()
+        .map(|_| crate::parquet_out::ParquetResourceRows::new(&ingest_session_id));
+
+    // cumulative bytes of resources whose content was acquired (and hashed)
+    let mut total_bytes: u64 = 0;
+    let mut skipped_by_budget: u64 = 0;
+    let mut batches = CommitBatches::new(&dbc.conn, tx, ingest_args.commit_batch_size);
+    // a failure rolls back whatever wasn't committed, which is the whole session
+    // unless `--commit-batch-size` already committed some of it
+    let ingested = (|| -> Result<()> {
+        let env_current_dir = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let mut ingest_stmts =
+            IngestContext::from_conn(&dbc.conn, &ingest_args.state_db_fs_path)
+                .with_context(|| format!("[ingest_files] ingest_stmts in {}", db_fs_path))?;
+        ingest_stmts.retry_policy = RetryPolicy::new(behavior.classifier.max_retries);
+
+        // skipping blank lines and comments the same way task lines are
+        let stdin_fs_paths: Vec<String> = if behavior
+            .root_fs_paths
+            .iter()
+            .any(|root_path| root_path == STDIN_ROOT_FS_PATH)
+        {
+            std::io::stdin()
+                .lines()
+                .map_while(Result::ok)
+                .filter(|line| !line.starts_with('#'))
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| {
+                    std::path::Path::new(&env_current_dir)
+                        .join(line)
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let root_paths = behavior
+            .root_fs_paths
+            .iter()
+            .map(|root_path| (root_path, false))
+            .chain(behavior.archives.iter().map(|archive| (archive, true)));
+        // every root is walked before anything is ingested so that
+        // `--fail-on-walk-error` can fail without storing anything
+        let mut walked_roots = Vec::new();
+        for (root_path, archive) in root_paths {
+            let canonical_path = if root_path == STDIN_ROOT_FS_PATH {
+                common_root_fs_path(&stdin_fs_paths).unwrap_or_else(|| env_current_dir.clone())
+            } else {
+                let canonical_path_buf = std::fs::canonicalize(std::path::Path::new(&root_path))
+                    .with_context(|| {
+                        format!(
+                            "[ingest_files] unable to canonicalize {} in {}",
+                            root_path, db_fs_path
+                        )
+                    })?;
+                // lossy rather than panicking on a non UTF-8 (or Windows verbatim) root
+                canonical_path_buf.to_string_lossy().to_string()
+            };
+
+            let rp: Vec<String> = vec![canonical_path.clone()];
+            let walk_started_at = std::time::Instant::now();
+            let resources = {
+                let _span = tracing::info_span!("walk", root_path = %canonical_path).entered();
+                let resources = if root_path == STDIN_ROOT_FS_PATH {
+                    ResourcesCollection::from_fs_paths(
+                        &stdin_fs_paths,
+                        &behavior.classifier,
+                        &Some(behavior.nature_bind.clone()),
+                        behavior.follow_symlinks,
+                    )
+                } else if archive {
+                    ResourcesCollection::from_vfs_archive(
+                        &canonical_path,
+                        crate::archive::MountLimits {
+                            max_entry_bytes: behavior.classifier.max_content_bytes,
+                            max_total_bytes: ingest_args
+                                .max_total_bytes
+                                .unwrap_or(crate::archive::DEFAULT_MAX_MOUNTED_BYTES)
+                                .min(crate::archive::DEFAULT_MAX_MOUNTED_BYTES),
+                        },
+                        &behavior.classifier,
+                        &Some(behavior.nature_bind.clone()),
+                    )
+                } else {
+                    ResourcesCollection::from_walker(
+                        behavior.walker,
+                        &rp,
+                        &behavior.classifier,
+                        &Some(behavior.nature_bind.clone()),
+                        behavior.follow_symlinks,
+                        behavior.max_depth,
+                    )
+                };
+                resources
+                    .with_git_commits(if behavior.git_metadata {
+                        GitCommits::discover(std::path::Path::new(&canonical_path))
+                    } else {
+                        None
+                    })
+                    .with_seen_inodes(seen_inodes.clone())
+                    .with_nature_rewrites_audit(!behavior.no_nature_rewrite_audit)
+                    .with_nature_filter(behavior.nature_filter.clone())
+                    .with_modified_since(modified_since)
+                    .with_sample(sample)
+                    .with_skip_empty_files(behavior.skip_empty_files)
+                    .with_walk_roots(&rp)
+                    .with_ordered_capture(behavior.ordered_capture)
+                    .with_hash_timing(hash_timing.clone())
+            };
+            if let Some(profile) = profile.as_mut() {
+                profile.walk += walk_started_at.elapsed();
+            }
+            walked_roots.push((root_path, canonical_path, resources));
+        }
+
+        if ingest_args.fail_on_walk_error {
+            let walk_errors: Vec<_> = walked_roots
+                .iter()
+                .flat_map(|(_, _, resources)| resources.walk_errors())
+                .collect();
+            if let Some((uri, reason)) = walk_errors.first() {
+                anyhow::bail!(
+                    "[ingest_files] unable to walk {} ({} walk errors in total) with --fail-on-walk-error: {}",
+                    uri,
+                    walk_errors.len(),
+                    reason
+                );
+            }
+        }
+
+        for (root_path, canonical_path, resources) in walked_roots {
+            let ins_ur_wsp_params = params![ingest_session_id, canonical_path];
+            let ingest_fs_path_id: String = ingest_stmts
+                .ins_ur_isfsp_stmt
+                .query_row(ins_ur_wsp_params, |row| row.get(0))
+                .with_context(|| {
+                    format!(
+                        "[ingest_files] ins_ur_wsp_stmt {} with {} in {}",
+                        INS_UR_ISFSP_SQL, "TODO: ins_ur_wsp_params.join()", db_fs_path
+                    )
+                })?;
+
+            progress.on_root_path(root_path, &ingest_fs_path_id);
+
+            let walked_at = std::time::Instant::now();
+            let hashed_before_walked = hash_timing.as_deref().map(HashTiming::elapsed);
+
+            let mut urw_state = UniformResourceWriterState {
+                state_db_fs_path: &db_fs_path,
+                ingest_files_behavior: Some(&behavior),
+                env_current_dir: &env_current_dir,
+                device_id: &device_id,
+                ingest_session_id: &ingest_session_id,
+                ingest_fs_path_id: Some(&ingest_fs_path_id),
+                json_schemas: Some(&json_schemas),
+                resources: &resources,
+                ingest_stmts: &mut ingest_stmts,
+                executed_ahead: HashMap::new(),
+            };
+
+            // the parallel walker reads content ahead of time, which is exactly
+            // what `--incremental` tries to avoid for unchanged resources
+            #[cfg(feature = "rayon")]
+            let uniform_resources: Box<dyn Iterator<Item = _>> = if incremental {
+                Box::new(resources.uniform_resources())
+            } else {
+                Box::new(resources.par_uniform_resources(&pool))
+            };
+            #[cfg(not(feature = "rayon"))]
+            let uniform_resources = resources.uniform_resources();
+            let mut uniform_resources =
+                TimedResources::new(uniform_resources, hash_timing.as_deref());
+
+            // `--ordered-capture` executables may depend on each other so they're
+            // always run one at a time, as they're persisted
+            if behavior.capture_exec_concurrency > 1 && !behavior.ordered_capture {
+                let capturable: Vec<_> = resources.capturable_exec_resources().collect();
+                urw_state.execute_capturable_ahead(&capturable, behavior.capture_exec_concurrency);
+            }
+
+            for resource_result in &mut uniform_resources {
+                match resource_result {
+                    Ok(resource) => {
+                        let _span = tracing::info_span!("persist", uri = %resource.uri()).entered();
+                        progress.on_resource_encountered(resource.uri());
+                        batches.writing(resource.uri());
+                        let mut urw_entry = UniformResourceWriterEntry {
+                            path: Some(resource.uri()),
+                            tried_alternate_nature: None,
+                        };
+                        let inserted = match ingest_args.max_total_bytes {
+                            Some(max_total_bytes) if total_bytes >= max_total_bytes => {
+                                skipped_by_budget += 1;
+                                UniformResourceWriterResult {
+                                    uri: resource.uri().clone(),
+                                    action: UniformResourceWriterAction::SkippedByBudget(
+                                        max_total_bytes,
+                                    ),
+                                }
+                            }
+                            _ => {
+                                let cr = resource.content_resource();
+                                let acquirable = cr.content_binary_supplier.is_some()
+                                    || cr.content_text_supplier.is_some();
+                                // captured executables are always run since their output
+                                // can change even if the executable itself has not
+                                let prior = if incremental
+                                    && (acquirable || cr.content_digest_supplier.is_some())
+                                    && !matches!(resource, UniformResource::CapturableExec(_))
+                                {
+                                    match urw_state
+                                        .ingest_stmts
+                                        .prior_resource_fingerprint(&device_id, cr)
+                                    {
+                                        Ok(Some((prior_ur_id, prior_digest)))
+                                            if behavior
+                                                .classifier
+                                                .digest_algorithm
+                                                .is_digest_of(&prior_digest) =>
+                                        {
+                                            Some(prior_ur_id)
+                                        }
+                                        Ok(_) => None,
+                                        Err(err) => {
+                                            progress.on_error(&format!(
+                                                "[ingest_files] unable to read prior fingerprint of {} in {}: {}",
+                                                cr.uri, db_fs_path, err
+                                            ));
+                                            None
+                                        }
+                                    }
+                                } else {
+                                    None
+                                };
+                                if let Some(prior_ur_id) = prior {
+                                    UniformResourceWriterResult {
+                                        uri: resource.uri().clone(),
+                                        action: UniformResourceWriterAction::Unchanged(prior_ur_id),
+                                    }
+                                } else {
+                                    if acquirable {
+                                        total_bytes += cr.size.unwrap_or(0);
+                                    }
+                                    let inserted = resource.insert(&mut urw_state, &mut urw_entry);
+                                    batches.ensure_open()?;
+                                    match inserted.action {
+                                        UniformResourceWriterAction::Inserted(_, _)
+                                            if acquirable =>
+                                        {
+                                            progress.on_content_hashed(&inserted.uri, cr.size);
+                                            result.total_bytes_hashed += cr.size.unwrap_or(0);
+                                        }
+                                        UniformResourceWriterAction::DigestOnly(_, _) => {
+                                            result.total_bytes_hashed += cr.size.unwrap_or(0);
+                                        }
+                                        _ => {}
+                                    }
+                                    inserted
+                                }
+                            }
+                        };
+                        if matches!(resource, UniformResource::CapturableExec(_)) {
+                            result.captured_exec += 1;
+                        }
+                        if matches!(
+                            inserted.action,
+                            UniformResourceWriterAction::Inserted(_, _)
+                                | UniformResourceWriterAction::InsertedExecutableOutput(_, _, _)
+                                | UniformResourceWriterAction::DigestOnly(_, _)
+                                | UniformResourceWriterAction::Unchanged(_)
+                        ) {
+                            *result
+                                .uniform_resources_by_nature
+                                .entry(
+                                    resource
+                                        .content_resource()
+                                        .nature
+                                        .clone()
+                                        .unwrap_or_default(),
+                                )
+                                .or_default() += 1;
+                        }
+
+                        let mut ur_status = inserted.action.ur_status();
+                        let mut ur_diagnostics = inserted.action.ur_diagnostics();
+                        let mut captured_exec_diags: Option<String> = None;
+
+                        let uniform_resource_id = match &inserted.action {
+                            UniformResourceWriterAction::Inserted(
+                                ref uniform_resource_id,
+                                None,
+                            )
+                            | UniformResourceWriterAction::DigestOnly(ref uniform_resource_id, _)
+                            | UniformResourceWriterAction::Unchanged(ref uniform_resource_id) => {
+                                Some(uniform_resource_id)
+                            }
+                            UniformResourceWriterAction::InsertedExecutableOutput(
+                                ref uniform_resource_id,
+                                None,
+                                diags,
+                            ) => {
+                                captured_exec_diags =
+                                    Some(serde_json::to_string_pretty(&diags).unwrap());
+                                Some(uniform_resource_id)
+                            }
+                            UniformResourceWriterAction::CapturedExecutableSqlOutput(
+                                ref sql_script,
+                                diags,
+                            ) => {
+                                captured_exec_diags =
+                                    Some(serde_json::to_string_pretty(&diags).unwrap());
+                                match dbc.conn.execute_batch(sql_script) {
+                                    Ok(_) => {
+                                        ur_status = Some(String::from("EXECUTED_CAPTURED_SQL"));
+                                        ur_diagnostics = Some(serde_json::to_string_pretty(&json!({
+                                            "instance": "UniformResourceWriterAction::CapturedExecutableSqlOutput(err)",
+                                            "SQL": sql_script
+                                        })).unwrap());
+                                        None
+                                    }
+                                    Err(err) => {
+                                        ur_status = Some(String::from("ERROR"));
+                                        ur_diagnostics = Some(serde_json::to_string_pretty(&json!({
+                                            "instance": "UniformResourceWriterAction::CapturedExecutableSqlOutput(err)",
+                                            "message": "Error executing batched SQL",
+                                            "error": err.to_string(),
+                                            "SQL": sql_script
+                                        })).unwrap());
+                                        None
+                                    }
+                                }
+                            }
+                            _ => None,
+                        };
+
+                        // keep what the path declared when the content said otherwise, on
+                        // the resource if one was stored or else on the walk entry
+                        if let Some(declared_nature) = &resource.content_resource().declared_nature
+                        {
+                            let detected_nature = &resource.content_resource().nature;
+                            match uniform_resource_id {
+                                Some(ur_id) => {
+                                    if let Err(err) = urw_state
+                                        .ingest_stmts
+                                        .upd_ur_sniffed_nature_stmt
+                                        .execute(params![declared_nature, detected_nature, ur_id])
+                                    {
+                                        progress.on_error(&format!(
+                                            "[ingest_files] unable to record sniffed nature of {} in {}: {}",
+                                            inserted.uri, db_fs_path, err
+                                        ));
+                                    }
+                                }
+                                None => {
+                                    let mut diagnostics = ur_diagnostics
+                                        .as_deref()
+                                        .and_then(|diags| serde_json::from_str(diags).ok())
+                                        .unwrap_or_else(|| json!({}));
+                                    diagnostics["nature"] = json!({
+                                        "declared": declared_nature,
+                                        "detected": detected_nature
+                                    });
+                                    ur_diagnostics =
+                                        Some(serde_json::to_string_pretty(&diagnostics).unwrap());
+                                }
+                            }
+                        }
+
+                        if let (Some(ur_id), Some(git)) =
+                            (uniform_resource_id, &resource.content_resource().git)
+                        {
+                            if let Err(err) = urw_state
+                                .ingest_stmts
+                                .upd_ur_git_stmt
+                                .execute(params![serde_json::to_string(git).unwrap(), ur_id])
+                            {
+                                progress.on_error(&format!(
+                                    "[ingest_files] unable to record git commit of {} in {}: {}",
+                                    inserted.uri, db_fs_path, err
+                                ));
+                            }
+                        }
+
+                        if let (Some(ur_id), Some(symlink_target)) = (
+                            uniform_resource_id,
+                            &resource.content_resource().symlink_target,
+                        ) {
+                            if let Err(err) = urw_state
+                                .ingest_stmts
+                                .upd_ur_symlink_target_stmt
+                                .execute(params![symlink_target, ur_id])
+                            {
+                                progress.on_error(&format!(
+                                    "[ingest_files] unable to record symlink target of {} in {}: {}",
+                                    inserted.uri, db_fs_path, err
+                                ));
+                            }
+                        }
+
+                        if let Some(parquet_rows) = parquet_rows.as_mut() {
+                            let cr = resource.content_resource();
+                            let content_digest = match uniform_resource_id {
+                                Some(ur_id) => urw_state
+                                    .ingest_stmts
+                                    .sel_ur_content_digest_stmt
+                                    .query_row(params![ur_id], |row| row.get(0))
+                                    .optional()
+                                    .unwrap_or(None),
+                                None => None,
+                            };
+                            parquet_rows.push(crate::parquet_out::ParquetResourceRow {
+                                uri: inserted.uri.clone(),
+                                nature: cr.nature.clone(),
+                                size_bytes: cr.size,
+                                created_at: cr.created_at,
+                                last_modified_at: cr.last_modified_at,
+                                content_digest,
+                                ur_status: ur_status.clone(),
+                            });
+                        }
+
+                        let walk_root = resources
+                            .walk_root(&inserted.uri)
+                            .unwrap_or(canonical_path.as_str());
+                        match extract_path_info(
+                            std::path::Path::new(walk_root),
+                            std::path::Path::new(&inserted.uri),
+                        ) {
+                            Some((
+                                file_path_abs,
+                                file_path_rel_parent,
+                                file_path_rel,
+                                file_basename,
+                                file_extn,
+                            )) => {
+                                // with the content digest this tells moved files from edited ones
+                                if let (true, Some(ur_id)) =
+                                    (behavior.hash_filenames, uniform_resource_id)
+                                {
+                                    let path_digest = behavior
+                                        .classifier
+                                        .digest_algorithm
+                                        .digest(file_path_rel.to_string_lossy().as_bytes());
+                                    if let Err(err) = urw_state
+                                        .ingest_stmts
+                                        .upd_ur_path_digest_stmt
+                                        .execute(params![path_digest, ur_id])
+                                    {
+                                        progress.on_error(&format!(
+                                            "[ingest_files] unable to record path digest of {} in {}: {}",
+                                            inserted.uri, db_fs_path, err
+                                        ));
+                                    }
+                                }
+                                // the uri stays as is, these group resources by root
+                                if let Some(ur_id) = uniform_resource_id {
+                                    if let Err(err) = urw_state
+                                        .ingest_stmts
+                                        .upd_ur_walk_root_stmt
+                                        .execute(params![
+                                            walk_root,
+                                            file_path_rel.to_string_lossy().to_string(),
+                                            ur_id
+                                        ])
+                                    {
+                                        progress.on_error(&format!(
+                                            "[ingest_files] unable to record walk root of {} in {}: {}",
+                                            inserted.uri, db_fs_path, err
+                                        ));
+                                    }
+                                }
+                                match urw_state.ingest_stmts.ins_ur_isfsp_entry_stmt.execute(
+                                    params![
+                                        ingest_session_id,
+                                        ingest_fs_path_id,
+                                        uniform_resource_id,
+                                        file_path_abs.into_os_string().into_string().unwrap(),
+                                        file_path_rel_parent
+                                            .into_os_string()
+                                            .into_string()
+                                            .unwrap(),
+                                        file_path_rel.into_os_string().into_string().unwrap(),
+                                        file_basename,
+                                        file_extn.unwrap_or_default(),
+                                        ur_status,
+                                        ur_diagnostics,
+                                        captured_exec_diags
+                                    ],
+                                ) {
+                                    Ok(_) => progress.on_db_upsert(&inserted.uri, ur_status.as_deref()),
+                                    Err(err) => progress.on_error(&format!(
+                                        "[ingest_files] unable to insert UR walk session path file system entry for {} in {}: {} ({})",
+                                        &inserted.uri, db_fs_path, err, INS_UR_ISFSP_ENTRY_SQL
+                                    )),
+                                }
+                            }
+                            None => progress.on_error(&format!(
+                                "[ingest_files] error extracting path info for {} in {}",
+                                canonical_path, db_fs_path
+                            )),
+                        }
+
+                        batches.written()?;
+                    }
+                    Err(e) => {
+                        progress.on_error(&format!(
+                            "[ingest_files] Error processing a resource: {}",
+                            e
+                        ));
+                    }
+                }
+            }
+            for (uri, nature_rewrite) in resources.take_nature_rewrites() {
+                ingest_stmts
+                    .ins_ur_nature_rewrite_stmt
+                    .execute(params![
+                        ingest_session_id,
+                        uri,
+                        nature_rewrite.original_nature,
+                        nature_rewrite.rewritten_nature,
+                        nature_rewrite.regex,
+                        nature_rewrite.replace
+                    ])
+                    .with_context(|| {
+                        format!(
+                            "[ingest_files] unable to record the nature rewrite of {} in {}",
+                            uri, db_fs_path
+                        )
+                    })?;
+            }
+            result.encountered += resources.encounter_counts.encountered();
+            result.ignored += resources.encounter_counts.ignored();
+            result.not_found += resources.encounter_counts.not_found();
+            result.duplicates += resources.encounter_counts.duplicates();
+            result.empty += resources.encounter_counts.empty();
+            let walk_errors = resources.encounter_counts.take_walk_errors();
+            for (uri, reason) in &walk_errors {
+                progress.on_walk_error(uri, reason);
+            }
+            result.walk_errors += walk_errors.len();
+            // dangling symlinks have no resource, their entry keeps the target
+            for (uri, symlink_target) in resources.encounter_counts.take_dangling_symlinks() {
+                let Some((
+                    file_path_abs,
+                    file_path_rel_parent,
+                    file_path_rel,
+                    file_basename,
+                    file_extn,
+                )) = extract_path_info(
+                    std::path::Path::new(&canonical_path),
+                    std::path::Path::new(&uri),
+                )
+                else {
+                    continue;
+                };
+                if let Err(err) = ingest_stmts.ins_ur_isfsp_entry_stmt.execute(params![
+                    ingest_session_id,
+                    ingest_fs_path_id,
+                    None::<String>,
+                    file_path_abs.into_os_string().into_string().unwrap(),
+                    file_path_rel_parent.into_os_string().into_string().unwrap(),
+                    file_path_rel.into_os_string().into_string().unwrap(),
+                    file_basename,
+                    file_extn.unwrap_or_default(),
+                    "NOT_FOUND",
+                    serde_json::to_string_pretty(&json!({
+                        "instance": "EncounteredResource::NotFound",
+                        "message": "dangling symbolic link",
+                        "symlink_target": symlink_target,
+                    }))?,
+                    None::<String>,
+                ]) {
+                    progress.on_error(&format!(
+                        "[ingest_files] unable to insert UR walk session path file system entry for {} in {}: {} ({})",
+                        uri, db_fs_path, err, INS_UR_ISFSP_ENTRY_SQL
+                    ));
+                }
+            }
+            // whatever wasn't spent producing resources, or hashing them, went
+            // into storing them
+            if let (Some(profile), Some(hash_timing), Some(hashed_before_walked)) = (
+                profile.as_mut(),
+                hash_timing.as_deref(),
+                hashed_before_walked,
+            ) {
+                let hashing = hash_timing.elapsed().saturating_sub(hashed_before_walked);
+                profile.classify += uniform_resources
+                    .elapsed
+                    .saturating_sub(uniform_resources.hashing);
+                profile.persist += walked_at
+                    .elapsed()
+                    .saturating_sub(uniform_resources.elapsed)
+                    .saturating_sub(hashing.saturating_sub(uniform_resources.hashing));
+                profile.classified += uniform_resources.count;
+            }
+        }
+        if let Some(max_total_bytes) = ingest_args.max_total_bytes {
+            dbc.conn
+                .execute(
+                    UPD_UR_INGEST_SESSION_BUDGET_SQL,
+                    params![
+                        max_total_bytes,
+                        total_bytes,
+                        (skipped_by_budget > 0).to_string(),
+                        skipped_by_budget,
+                        ingest_session_id
+                    ],
+                )
+                .with_context(|| {
+                    format!(
+                        "[ingest_files] unable to execute SQL {} in {}",
+                        UPD_UR_INGEST_SESSION_BUDGET_SQL, db_fs_path
+                    )
+                })?;
+        }
+        let counts = json!({
+            "encountered": result.encountered,
+            "ignored": result.ignored,
+            "not_found": result.not_found,
+            "duplicates": result.duplicates,
+            "empty": result.empty,
+            "walk_errors": result.walk_errors,
+            "captured_exec": result.captured_exec,
+            "uniform_resources": result.uniform_resources_by_nature.values().sum::<usize>(),
+            "total_bytes_hashed": result.total_bytes_hashed,
+        });
+        record_session_counts(&dbc.conn, &ingest_session_id, &counts).with_context(|| {
+            format!(
+                "[ingest_files] unable to record counts of session {} in {}",
+                ingest_session_id, db_fs_path
+            )
+        })?;
+        match dbc
+            .conn
+            .execute(INS_UR_INGEST_SESSION_FINISH_SQL, params![ingest_session_id])
+        {
+            Ok(_) => {}
+            Err(err) => progress.on_error(&format!(
+                "[ingest_files] unable to execute SQL {} in {}: {}",
+                INS_UR_INGEST_SESSION_FINISH_SQL, db_fs_path, err
+            )),
+        }
+        // putting everything inside a transaction improves performance significantly
+        let commit_started_at = std::time::Instant::now();
+        batches.commit().with_context(|| {
+            format!(
+                "[ingest_files] unable to perform final commit in {}",
+                db_fs_path
+            )
+        })?;
+        if let Some(profile) = profile.as_mut() {
+            profile.persist += commit_started_at.elapsed();
+        }
+        Ok(())
+    })();
+    ingested.map_err(|err| batches.failed(&ingest_session_id, err))?;
+
+    // failures are reported but never undo the (already committed) ingestion
+    if !ingest_args.after_ingest_sql.is_empty() {
+        if let Err(err) =
+            execute_after_ingest_sql(&mut dbc, &ingest_args.after_ingest_sql, &ingest_session_id)
+        {
+            progress.on_error(&format!("{:?}", err));
+        }
+    }
+
+    // the state database remains the system of record, the Parquet file is an
+    // additional metadata-only export of the same session
+    if let (Some(parquet_out), Some(parquet_rows)) = (&ingest_args.parquet_out, &parquet_rows) {
+        parquet_rows
+            .write(parquet_out)
+            .with_context(|| format!("[ingest_files] unable to write {}", parquet_out))?;
+    }
+
+    result.elapsed = started_at.elapsed();
+    if let (Some(mut profile), Some(hash_timing)) = (profile, hash_timing) {
+        profile.hash = hash_timing.elapsed();
+        profile.hashed = hash_timing.count();
+        profile.walked = result.encountered;
+        profile.total = result.elapsed;
+        result.profile = Some(profile);
+    }
+    Ok(result)
+}
+
+// all of the SQL files are committed together, or none of them if any fails;
+// either way which files ran is recorded in the session's elaboration
+fn execute_after_ingest_sql(
+    dbc: &mut DbConn,
+    after_ingest_sql: &[String],
+    ingest_session_id: &str,
+) -> anyhow::Result<()> {
+    let db_fs_path = dbc.db_fs_path.clone();
+    let verbose_level = dbc.vebose_level;
+    let tx = dbc.conn.transaction().with_context(|| {
+        format!(
+            "[execute_after_ingest_sql] SQLite transaction in {}",
+            db_fs_path
+        )
+    })?;
+    let executed = execute_globs_batch(
+        &tx,
+        &[".".to_string()],
+        after_ingest_sql,
+        "ingest_files --after-ingest-sql",
+        verbose_level,
+    )
+    .with_context(|| {
+        format!(
+            "[execute_after_ingest_sql] execute_globs_batch {} in {}",
+            after_ingest_sql.join(", "),
+            db_fs_path
+        )
+    })?;
+    let failed: Vec<_> = executed
+        .iter()
+        .filter(|(_, sql, _)| sql.is_none())
+        .map(|(uri, _, _)| uri.as_str())
+        .collect();
+    let sql_files = json!(executed
+        .iter()
+        .map(|(uri, sql, _)| json!({ "uri": uri, "executed": sql.is_some() }))
+        .collect::<Vec<_>>());
+    let committed = failed.is_empty();
+    let record = |conn: &Connection| {
+        conn.execute(
+            UPD_UR_INGEST_SESSION_AFTER_SQL_SQL,
+            params![
+                committed.to_string(),
+                sql_files.to_string(),
+                ingest_session_id
+            ],
+        )
+        .with_context(|| {
+            format!(
+                "[execute_after_ingest_sql] unable to execute SQL {} in {}",
+                UPD_UR_INGEST_SESSION_AFTER_SQL_SQL, db_fs_path
+            )
+        })
+    };
+    if committed {
+        record(&tx)?;
+        return tx.commit().with_context(|| {
+            format!(
+                "[execute_after_ingest_sql] unable to commit in {}",
+                db_fs_path
+            )
+        });
+    }
+
+    tx.rollback().with_context(|| {
+        format!(
+            "[execute_after_ingest_sql] unable to roll back in {}",
+            db_fs_path
+        )
+    })?;
+    record(&dbc.conn)?;
+    anyhow::bail!(
+        "[execute_after_ingest_sql] {} failed so none of the --after-ingest-sql was committed in {} (the ingestion was)",
+        failed.join(", "),
+        db_fs_path
+    )
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IngestUrlsBehavior {
+    pub urls: Vec<String>,
+}
+
+// the URL is stored as the absolute path, its parent is everything up to the
+// last `/` of the URL's path and the basename and extension come from the path
+fn url_path_info(url: &str) -> (String, String, Option<String>) {
+    let path = reqwest::Url::parse(url)
+        .map(|parsed| parsed.path().to_string())
+        .unwrap_or_default();
+    let basename = path.rsplit('/').next().unwrap_or_default().to_string();
+    let parent = url
+        .strip_suffix(basename.as_str())
+        .unwrap_or(url)
+        .to_string();
+    let extn = basename.rsplit_once('.').map(|(_, extn)| extn.to_string());
+    (parent, basename, extn)
+}
+
+// writes a resource which wasn't found by walking the file system (e.g. a URL
+// or a message), returning its ID (if it was stored), status and diagnostics
+fn written_uniform_resource(
+    cr: ContentResource,
+    path: &str,
+    urw_state: &mut UniformResourceWriterState<'_, '_>,
+) -> Result<(Option<String>, Option<String>, Option<String>)> {
+    match urw_state.resources.uniform_resource(cr) {
+        Ok(resource) => {
+            let mut urw_entry = UniformResourceWriterEntry {
+                path: Some(path),
+                tried_alternate_nature: None,
+            };
+            let inserted = resource.insert(urw_state, &mut urw_entry);
+            let uniform_resource_id = match &inserted.action {
+                UniformResourceWriterAction::Inserted(ur_id, _) => Some(ur_id.clone()),
+                _ => None,
+            };
+            Ok((
+                uniform_resource_id,
+                inserted.action.ur_status(),
+                inserted.action.ur_diagnostics(),
+            ))
+        }
+        Err(err) => Ok((
+            None,
+            Some(String::from("ERROR")),
+            Some(serde_json::to_string_pretty(&json!({
+                "instance": "ResourcesCollection::uniform_resource",
+                "message": "Unable to create a uniform resource",
+                "error": err.to_string()
+            }))?),
+        )),
+    }
+}
+
+pub fn ingest_urls(
+    cli: &crate::cmd::Cli,
+    ingest_args: &crate::cmd::IngestUrlsArgs,
+) -> Result<String> {
+    let dbc = DbConn::new(&ingest_args.state_db_fs_path, cli.debug, &cli.db_pragmas())
+        .with_context(|| {
+            format!(
+                "[ingest_urls] SQLite transaction in {}",
+                ingest_args.state_db_fs_path
+            )
+        })?;
+    let db_fs_path = dbc.db_fs_path.clone();
+
+    // putting everything inside a transaction improves performance significantly
+    let tx = dbc.init(Some(&ingest_args.state_db_init_sql))?;
+    let (device_id, _device_name) = upserted_device(&tx, &crate::DEVICE).with_context(|| {
+        format!(
+            "[ingest_urls] upserted_device {} in {}",
+            crate::DEVICE.name,
+            db_fs_path
+        )
+    })?;
+
+    let behavior = IngestUrlsBehavior {
+        urls: if ingest_args.url.is_empty() {
+            std::io::stdin().lines().map_while(Result::ok).collect()
+        } else {
+            ingest_args.url.clone()
+        },
+    };
+    let classifier = EncounterableResourcePathClassifier::default_from_conn(&tx)?;
+    let resources =
+        ResourcesCollection::from_urls(&behavior.urls, &classifier, &None::<HashMap<_, _>>);
+
+    let ingest_session_id: String = tx
+        .query_row(
+            INS_UR_INGEST_SESSION_SQL,
+            params![
+                device_id,
+                None::<String>,
+                serde_json::to_string_pretty(&behavior)?
+            ],
+            |row| row.get(0),
+        )
+        .with_context(|| {
+            format!(
+                "[ingest_urls] inserting UR walk session using {} in {}",
+                INS_UR_INGEST_SESSION_SQL, db_fs_path
+            )
+        })?;
+    record_session_provenance(&tx, cli, &ingest_session_id).with_context(|| {
+        format!(
+            "[ingest_urls] unable to record provenance of session {} in {}",
+            ingest_session_id, db_fs_path
+        )
+    })?;
+    tracing::info!("Walk Session: {ingest_session_id}");
+
+    let (mut encountered, mut not_found, mut uniform_resources) = (0, 0, 0);
+    {
+        let env_current_dir = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let mut ingest_stmts = IngestContext::from_conn(&tx, &ingest_args.state_db_fs_path)
+            .with_context(|| format!("[ingest_urls] ingest_stmts in {}", db_fs_path))?;
+
+        // each URL is its own "root path" so its entry can record what happened
+        for er in resources.encountered() {
+            encountered += 1;
+            let (url, inserted, http_status) = match er {
+                EncounteredResource::Resource(cr, _) => (cr.uri.clone(), Some(cr), None),
+                EncounteredResource::NotFound(url, _, _) => {
+                    not_found += 1;
+                    (url, None, None)
+                }
+                EncounteredResource::HttpNotOk(url, status, _) => {
+                    not_found += 1;
+                    (url, None, Some(status))
+                }
+                EncounteredResource::Ignored(_, _)
+                | EncounteredResource::NotFile(_, _)
+                | EncounteredResource::Duplicate(_, _, _)
+                | EncounteredResource::Empty(_, _)
+                | EncounteredResource::WalkError(_, _, _)
+                | EncounteredResource::CapturableExec(_, _, _) => continue,
+            };
+
+            let ingest_fs_path_id: String = ingest_stmts
+                .ins_ur_isfsp_stmt
+                .query_row(params![ingest_session_id, url], |row| row.get(0))
+                .with_context(|| {
+                    format!(
+                        "[ingest_urls] ins_ur_isfsp_stmt {} with {} in {}",
+                        INS_UR_ISFSP_SQL, url, db_fs_path
+                    )
+                })?;
+
+            let (uniform_resource_id, ur_status, ur_diagnostics) = match inserted {
+                Some(cr) => {
+                    let mut urw_state = UniformResourceWriterState {
+                        state_db_fs_path: &db_fs_path,
+                        ingest_files_behavior: None,
+                        env_current_dir: &env_current_dir,
+                        device_id: &device_id,
+                        ingest_session_id: &ingest_session_id,
+                        ingest_fs_path_id: Some(&ingest_fs_path_id),
+                        json_schemas: None,
+                        resources: &resources,
+                        ingest_stmts: &mut ingest_stmts,
+                        executed_ahead: HashMap::new(),
+                    };
+                    written_uniform_resource(cr, &url, &mut urw_state)?
+                }
+                None => (
+                    None,
+                    Some(String::from("NOT_FOUND")),
+                    Some(serde_json::to_string_pretty(&json!({
+                        "instance": "EncounteredResource::HttpNotOk",
+                        "message": match http_status {
+                            Some(_) => "URL did not return a 2xx status (after redirects)",
+                            None => "URL could not be requested",
+                        },
+                        "http-status": http_status,
+                    }))?),
+                ),
+            };
+
+            if uniform_resource_id.is_some() {
+                uniform_resources += 1;
+            }
+
+            let (file_path_rel_parent, file_basename, file_extn) = url_path_info(&url);
+            if let Err(err) = ingest_stmts.ins_ur_isfsp_entry_stmt.execute(params![
+                ingest_session_id,
+                ingest_fs_path_id,
+                uniform_resource_id,
+                url,
+                file_path_rel_parent,
+                url,
+                file_basename,
+                file_extn.unwrap_or_default(),
+                ur_status,
+                ur_diagnostics,
+                None::<String>,
+            ]) {
+                eprintln!(
+                    "[ingest_urls] unable to insert UR entry for {} in {}: {} ({})",
+                    url, db_fs_path, err, INS_UR_ISFSP_ENTRY_SQL
+                )
+            }
+        }
+    }
+
+    let counts = json!({
+        "encountered": encountered,
+        "not_found": not_found,
+        "uniform_resources": uniform_resources,
+    });
+    record_session_counts(&tx, &ingest_session_id, &counts).with_context(|| {
+        format!(
+            "[ingest_urls] unable to record counts of session {} in {}",
+            ingest_session_id, db_fs_path
+        )
+    })?;
+    match tx.execute(INS_UR_INGEST_SESSION_FINISH_SQL, params![ingest_session_id]) {
+        Ok(_) => {}
+        Err(err) => {
+            eprintln!(
+                "[ingest_urls] unable to execute SQL {} in {}: {}",
+                INS_UR_INGEST_SESSION_FINISH_SQL, db_fs_path, err
+            )
+        }
+    }
+
+    tx.commit().with_context(|| {
+        format!(
+            "[ingest_urls] unable to perform final commit in {}",
+            db_fs_path
+        )
+    })?;
+
+    Ok(ingest_session_id)
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IngestImapBehavior {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub username: String,
+    pub mailbox: String,
+    pub since: Option<chrono::NaiveDate>,
+}
+
+pub fn ingest_imap(
+    cli: &crate::cmd::Cli,
+    ingest_args: &crate::cmd::IngestImapArgs,
+) -> Result<String> {
+    let mailbox = crate::mailbox::ImapMailbox {
+        host: ingest_args.host.clone(),
+        port: ingest_args.port,
+        tls: !ingest_args.no_tls,
+        username: ingest_args.username.clone(),
+        password: ingest_args.password.clone(),
+        mailbox: ingest_args.mailbox.clone(),
+    };
+    // fetch before opening the transaction so a slow server doesn't hold it open
+    let messages = mailbox.messages(ingest_args.since)?;
+
+    let dbc = DbConn::new(&ingest_args.state_db_fs_path, cli.debug, &cli.db_pragmas())
+        .with_context(|| {
+            format!(
+                "[ingest_imap] SQLite transaction in {}",
+                ingest_args.state_db_fs_path
+            )
+        })?;
+    let db_fs_path = dbc.db_fs_path.clone();
+
+    let tx = dbc.init(Some(&ingest_args.state_db_init_sql))?;
+    let (device_id, _device_name) = upserted_device(&tx, &crate::DEVICE).with_context(|| {
+        format!(
+            "[ingest_imap] upserted_device {} in {}",
+            crate::DEVICE.name,
+            db_fs_path
+        )
+    })?;
+
+    let behavior = IngestImapBehavior {
+        host: mailbox.host.clone(),
+        port: mailbox.port,
+        tls: mailbox.tls,
+        username: mailbox.username.clone(),
+        mailbox: mailbox.mailbox.clone(),
+        since: ingest_args.since,
+    };
+    let classifier = EncounterableResourcePathClassifier::default_from_conn(&tx)?;
+    let resources = ResourcesCollection::from_imap(
+        messages
+            .into_iter()
+            .flat_map(|message| {
+                ImapResource::from_message(&mailbox.message_uri(message.uid), message)
+            })
+            .collect(),
+        &classifier,
+        &None::<HashMap<_, _>>,
+    );
 
     let ingest_session_id: String = tx
         .query_row(
             INS_UR_INGEST_SESSION_SQL,
             params![
                 device_id,
-                behavior_id,
-                match behavior.persistable_json_text() {
-                    Ok(json_text) => json_text,
-                    Err(_err) =>
-                        String::from("JSON serialization error, TODO: convert err to string"),
-                }
+                None::<String>,
+                serde_json::to_string_pretty(&behavior)?
             ],
             |row| row.get(0),
         )
         .with_context(|| {
             format!(
-                "[ingest_files] inserting UR walk session using {} in {}",
+                "[ingest_imap] inserting UR walk session using {} in {}",
                 INS_UR_INGEST_SESSION_SQL, db_fs_path
             )
         })?;
+    record_session_provenance(&tx, cli, &ingest_session_id).with_context(|| {
+        format!(
+            "[ingest_imap] unable to record provenance of session {} in {}",
+            ingest_session_id, db_fs_path
+        )
+    })?;
+    tracing::info!("Walk Session: {ingest_session_id}");
 
-    if cli.debug > 0 {
-        println!("Walk Session: {ingest_session_id}");
-    }
+    // messages and their attachments
+    let (mut encountered, mut uniform_resources) = (0, 0);
     {
         let env_current_dir = std::env::current_dir()
             .unwrap()
@@ -850,177 +3857,93 @@ pub fn ingest_files(
             .to_string();
 
         let mut ingest_stmts = IngestContext::from_conn(&tx, &ingest_args.state_db_fs_path)
-            .with_context(|| format!("[ingest_files] ingest_stmts in {}", db_fs_path))?;
-
-        for root_path in &behavior.root_fs_paths {
-            let canonical_path_buf = std::fs::canonicalize(std::path::Path::new(&root_path))
-                .with_context(|| {
-                    format!(
-                        "[ingest_files] unable to canonicalize {} in {}",
-                        root_path, db_fs_path
-                    )
-                })?;
-            let canonical_path = canonical_path_buf.into_os_string().into_string().unwrap();
-
-            let ins_ur_wsp_params = params![ingest_session_id, canonical_path];
-            let ingest_fs_path_id: String = ingest_stmts
-                .ins_ur_isfsp_stmt
-                .query_row(ins_ur_wsp_params, |row| row.get(0))
-                .with_context(|| {
-                    format!(
-                        "[ingest_files] ins_ur_wsp_stmt {} with {} in {}",
-                        INS_UR_ISFSP_SQL, "TODO: ins_ur_wsp_params.join()", db_fs_path
-                    )
-                })?;
-
-            if cli.debug > 0 {
-                println!("  Walk Session Path: {root_path} ({ingest_fs_path_id})");
-            }
+            .with_context(|| format!("[ingest_imap] ingest_stmts in {}", db_fs_path))?;
 
-            let rp: Vec<String> = vec![canonical_path.clone()];
-            let resources = ResourcesCollection::from_smart_ignore(
-                &rp,
-                &behavior.classifier,
-                &None::<HashMap<_, _>>,
-                false,
-            );
+        // the mailbox is the "root path" of its messages and their attachments
+        let mailbox_uri = mailbox.mailbox_uri();
+        let ingest_fs_path_id: String = ingest_stmts
+            .ins_ur_isfsp_stmt
+            .query_row(params![ingest_session_id, mailbox_uri], |row| row.get(0))
+            .with_context(|| {
+                format!(
+                    "[ingest_imap] ins_ur_isfsp_stmt {} with {} in {}",
+                    INS_UR_ISFSP_SQL, mailbox_uri, db_fs_path
+                )
+            })?;
 
+        for er in resources.encountered() {
+            let EncounteredResource::Resource(cr, _) = er else {
+                continue;
+            };
+            encountered += 1;
+            let uri = cr.uri.clone();
             let mut urw_state = UniformResourceWriterState {
                 state_db_fs_path: &db_fs_path,
-                ingest_files_behavior: Some(&behavior),
+                ingest_files_behavior: None,
                 env_current_dir: &env_current_dir,
                 device_id: &device_id,
                 ingest_session_id: &ingest_session_id,
                 ingest_fs_path_id: Some(&ingest_fs_path_id),
+                json_schemas: None,
                 resources: &resources,
                 ingest_stmts: &mut ingest_stmts,
+                executed_ahead: HashMap::new(),
             };
+            let (uniform_resource_id, ur_status, ur_diagnostics) =
+                written_uniform_resource(cr, &uri, &mut urw_state)?;
+            if uniform_resource_id.is_some() {
+                uniform_resources += 1;
+            }
 
-            for resource_result in resources.uniform_resources() {
-                match resource_result {
-                    Ok(resource) => {
-                        let mut urw_entry = UniformResourceWriterEntry {
-                            path: Some(resource.uri()),
-                            tried_alternate_nature: None,
-                        };
-                        let inserted = resource.insert(&mut urw_state, &mut urw_entry);
-                        let mut ur_status = inserted.action.ur_status();
-                        let mut ur_diagnostics = inserted.action.ur_diagnostics();
-                        let mut captured_exec_diags: Option<String> = None;
-
-                        let uniform_resource_id = match &inserted.action {
-                            UniformResourceWriterAction::Inserted(
-                                ref uniform_resource_id,
-                                None,
-                            ) => Some(uniform_resource_id),
-                            UniformResourceWriterAction::InsertedExecutableOutput(
-                                ref uniform_resource_id,
-                                None,
-                                diags,
-                            ) => {
-                                captured_exec_diags =
-                                    Some(serde_json::to_string_pretty(&diags).unwrap());
-                                Some(uniform_resource_id)
-                            }
-                            UniformResourceWriterAction::CapturedExecutableSqlOutput(
-                                ref sql_script,
-                                diags,
-                            ) => {
-                                captured_exec_diags =
-                                    Some(serde_json::to_string_pretty(&diags).unwrap());
-                                match tx.execute_batch(sql_script) {
-                                    Ok(_) => {
-                                        ur_status = Some(String::from("EXECUTED_CAPTURED_SQL"));
-                                        ur_diagnostics = Some(serde_json::to_string_pretty(&json!({
-                                            "instance": "UniformResourceWriterAction::CapturedExecutableSqlOutput(err)",
-                                            "SQL": sql_script
-                                        })).unwrap());
-                                        None
-                                    }
-                                    Err(err) => {
-                                        ur_status = Some(String::from("ERROR"));
-                                        ur_diagnostics = Some(serde_json::to_string_pretty(&json!({
-                                            "instance": "UniformResourceWriterAction::CapturedExecutableSqlOutput(err)",
-                                            "message": "Error executing batched SQL",
-                                            "error": err.to_string(),
-                                            "SQL": sql_script
-                                        })).unwrap());
-                                        None
-                                    }
-                                }
-                            }
-                            _ => None,
-                        };
-
-                        match extract_path_info(
-                            std::path::Path::new(&canonical_path),
-                            std::path::Path::new(&inserted.uri),
-                        ) {
-                            Some((
-                                file_path_abs,
-                                file_path_rel_parent,
-                                file_path_rel,
-                                file_basename,
-                                file_extn,
-                            )) => {
-                                match urw_state.ingest_stmts.ins_ur_isfsp_entry_stmt.execute(
-                                    params![
-                                        ingest_session_id,
-                                        ingest_fs_path_id,
-                                        uniform_resource_id,
-                                        file_path_abs.into_os_string().into_string().unwrap(),
-                                        file_path_rel_parent
-                                            .into_os_string()
-                                            .into_string()
-                                            .unwrap(),
-                                        file_path_rel.into_os_string().into_string().unwrap(),
-                                        file_basename,
-                                        if let Some(file_extn) = file_extn {
-                                            file_extn
-                                        } else {
-                                            String::from("")
-                                        },
-                                        ur_status,
-                                        ur_diagnostics,
-                                        captured_exec_diags
-                                    ],
-                                ) {
-                                    Ok(_) => {}
-                                    Err(err) => {
-                                        eprintln!( "[ingest_files] unable to insert UR walk session path file system entry for {} in {}: {} ({})",
-                                        &inserted.uri, db_fs_path, err, INS_UR_ISFSP_ENTRY_SQL
-                                        )
-                                    }
-                                }
-                            }
-                            None => {
-                                eprintln!(
-                                    "[ingest_files] error extracting path info for {} in {}",
-                                    canonical_path, db_fs_path
-                                )
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("[ingest_files] Error processing a resource: {}", e);
-                    }
-                }
+            let (file_path_rel_parent, file_basename) = uri.rsplit_once('/').unwrap_or_default();
+            let file_extn = file_basename
+                .rsplit_once('.')
+                .map(|(_, extn)| extn)
+                .unwrap_or_default();
+            if let Err(err) = ingest_stmts.ins_ur_isfsp_entry_stmt.execute(params![
+                ingest_session_id,
+                ingest_fs_path_id,
+                uniform_resource_id,
+                uri,
+                file_path_rel_parent,
+                uri.strip_prefix(&format!("{mailbox_uri}/")).unwrap_or(&uri),
+                file_basename,
+                file_extn,
+                ur_status,
+                ur_diagnostics,
+                None::<String>,
+            ]) {
+                eprintln!(
+                    "[ingest_imap] unable to insert UR entry for {} in {}: {} ({})",
+                    uri, db_fs_path, err, INS_UR_ISFSP_ENTRY_SQL
+                )
             }
         }
     }
+
+    let counts = json!({
+        "encountered": encountered,
+        "uniform_resources": uniform_resources,
+    });
+    record_session_counts(&tx, &ingest_session_id, &counts).with_context(|| {
+        format!(
+            "[ingest_imap] unable to record counts of session {} in {}",
+            ingest_session_id, db_fs_path
+        )
+    })?;
     match tx.execute(INS_UR_INGEST_SESSION_FINISH_SQL, params![ingest_session_id]) {
         Ok(_) => {}
         Err(err) => {
             eprintln!(
-                "[ingest_files] unable to execute SQL {} in {}: {}",
+                "[ingest_imap] unable to execute SQL {} in {}: {}",
                 INS_UR_INGEST_SESSION_FINISH_SQL, db_fs_path, err
             )
         }
     }
-    // putting everything inside a transaction improves performance significantly
+
     tx.commit().with_context(|| {
         format!(
-            "[ingest_files] unable to perform final commit in {}",
+            "[ingest_imap] unable to perform final commit in {}",
             db_fs_path
         )
     })?;
@@ -1041,6 +3964,10 @@ impl IngestTasksBehavior {
             .map(Result::ok)
             .map(|t| t.unwrap())
             .collect();
+        Self::from_lines(lines)
+    }
+
+    pub fn from_lines(lines: Vec<String>) -> Self {
         IngestTasksBehavior {
             lines: lines.clone(),
             encounterable: lines,
@@ -1052,16 +3979,130 @@ impl IngestTasksBehavior {
     }
 }
 
-pub fn ingest_tasks(
+// the reference set is the most recent walk of the same root path, leaving out
+// captured executables since their digests are of the output not the file;
+// entries without a uniform resource (e.g. unknown natures) only need to exist
+const SEL_UR_CONTENT_EQUAL_TO_SQL: &str = indoc! {"
+        SELECT e.file_path_abs, ur.content_digest, ur.size_bytes
+          FROM ur_ingest_session_fs_path_entry e
+     LEFT JOIN uniform_resource ur ON ur.uniform_resource_id = e.uniform_resource_id
+         WHERE e.captured_executable IS NULL
+           AND e.ingest_fs_path_id = (
+                SELECT ur_ingest_session_fs_path_id
+                  FROM ur_ingest_session_fs_path
+                 WHERE root_path = ?
+              ORDER BY ur_ingest_session_fs_path_id DESC
+                 LIMIT 1)"};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentDivergence {
+    Added(String),
+    Missing(String),
+    Changed(String),
+}
+
+/// Walks and hashes the files in `root_fs_path` just like `ingest_files` but,
+/// instead of persisting anything, compares each file's digest and size to
+/// the latest ingestion of the same root path in the reference database.
+/// Returns an empty list when the tree matches the reference exactly.
+pub fn content_equal_to(
     cli: &crate::cmd::Cli,
-    ingest_args: &crate::cmd::IngestTasksArgs,
-) -> Result<String> {
-    let mut dbc = DbConn::new(&ingest_args.state_db_fs_path, cli.debug).with_context(|| {
+    ingest_args: &crate::cmd::IngestFilesArgs,
+    reference_db_fs_path: &str,
+) -> Result<Vec<ContentDivergence>> {
+    let dbc = DbConn::open(reference_db_fs_path, cli.debug).with_context(|| {
         format!(
-            "[ingest_tasks] SQLite transaction in {}",
-            ingest_args.state_db_fs_path
+            "[content_equal_to] opening reference database {}",
+            reference_db_fs_path
         )
     })?;
+
+    let mut behavior =
+        IngestFilesBehavior::from_ingest_args(ingest_args, &dbc.conn).with_context(|| {
+            format!(
+                "[content_equal_to] behavior from reference {}",
+                reference_db_fs_path
+            )
+        })?;
+    if let Ok(canonical_db_fs_path) = std::fs::canonicalize(reference_db_fs_path) {
+        let canonical_db_fs_path = canonical_db_fs_path.to_string_lossy().to_string();
+        behavior
+            .classifier
+            .add_ignore_exact(canonical_db_fs_path.as_str());
+        behavior
+            .classifier
+            .add_ignore_exact(format!("{}-journal", canonical_db_fs_path).as_str());
+    }
+
+    let mut divergences: Vec<ContentDivergence> = Vec::new();
+    let mut stmt = dbc.conn.prepare(SEL_UR_CONTENT_EQUAL_TO_SQL)?;
+    for root_path in &behavior.root_fs_paths {
+        let canonical_path = std::fs::canonicalize(std::path::Path::new(&root_path))
+            .with_context(|| format!("[content_equal_to] unable to canonicalize {}", root_path))?
+            .to_string_lossy()
+            .to_string();
+
+        let mut reference: HashMap<String, (Option<String>, Option<i64>)> = HashMap::new();
+        let mut rows = stmt.query([&canonical_path])?;
+        while let Some(row) = rows.next()? {
+            reference.insert(row.get(0)?, (row.get(1)?, row.get(2)?));
+        }
+
+        let resources = ResourcesCollection::from_walker(
+            behavior.walker,
+            &[canonical_path],
+            &behavior.classifier,
+            &None::<HashMap<_, _>>,
+            behavior.follow_symlinks,
+            behavior.max_depth,
+        );
+        for encountered in resources.encountered() {
+            let cr = match encountered {
+                EncounteredResource::Resource(cr, _) => cr,
+                EncounteredResource::CapturableExec(cr, _, _) => {
+                    reference.remove(&cr.uri);
+                    continue;
+                }
+                _ => continue,
+            };
+            // only the digest is needed so the content is streamed, not loaded
+            let digest = if let Some(digest_supplier) = &cr.content_digest_supplier {
+                match digest_supplier() {
+                    Ok(digest) => digest,
+                    Err(err) => anyhow::bail!("[content_equal_to] reading {}: {}", cr.uri, err),
+                }
+            } else {
+                String::from("-") // same as content that isn't acquired during ingestion
+            };
+            let size = cr.size.map(|size| size as i64);
+            match reference.remove(&cr.uri) {
+                Some((None, _)) => {}
+                Some((Some(expected_digest), expected_size))
+                    if expected_digest == digest && expected_size == size => {}
+                Some(_) => divergences.push(ContentDivergence::Changed(cr.uri)),
+                None => divergences.push(ContentDivergence::Added(cr.uri)),
+            }
+        }
+
+        let mut missing: Vec<String> = reference.into_keys().collect();
+        missing.sort();
+        divergences.extend(missing.into_iter().map(ContentDivergence::Missing));
+    }
+
+    Ok(divergences)
+}
+
+pub fn ingest_tasks(
+    cli: &crate::cmd::Cli,
+    ingest_args: &crate::cmd::IngestTasksArgs,
+) -> Result<String> {
+    let dbc = DbConn::new(&ingest_args.state_db_fs_path, cli.debug, &cli.db_pragmas())
+        .with_context(|| {
+            format!(
+                "[ingest_tasks] SQLite transaction in {}",
+                ingest_args.state_db_fs_path
+            )
+        })?;
     let db_fs_path = dbc.db_fs_path.clone();
 
     // putting everything inside a transaction improves performance significantly
@@ -1074,10 +4115,18 @@ pub fn ingest_tasks(
         )
     })?;
 
-    let mut behavior = IngestTasksBehavior::from_stdin();
+    let mut behavior = if ingest_args.task.is_empty() {
+        IngestTasksBehavior::from_stdin()
+    } else {
+        IngestTasksBehavior::from_lines(ingest_args.task.clone())
+    };
     let classifier = EncounterableResourcePathClassifier::default_from_conn(&tx)?;
-    let (encounterable, resources) =
-        ResourcesCollection::from_tasks_lines(&behavior.lines, &classifier, &None::<HashMap<_, _>>);
+    let (encounterable, resources) = ResourcesCollection::from_tasks_lines(
+        &behavior.lines,
+        &ingest_args.task_default_nature,
+        &classifier,
+        &None::<HashMap<_, _>>,
+    );
     behavior.encounterable = encounterable;
 
     let ingest_session_id: String = tx
@@ -1100,10 +4149,15 @@ pub fn ingest_tasks(
                 INS_UR_INGEST_SESSION_SQL, db_fs_path
             )
         })?;
-    if cli.debug > 0 {
-        println!("Walk Session: {ingest_session_id}");
-    }
+    record_session_provenance(&tx, cli, &ingest_session_id).with_context(|| {
+        format!(
+            "[ingest_tasks] unable to record provenance of session {} in {}",
+            ingest_session_id, db_fs_path
+        )
+    })?;
+    tracing::info!("Walk Session: {ingest_session_id}");
 
+    let (mut encountered, mut captured_exec, mut uniform_resources, mut errors) = (0, 0, 0, 0);
     {
         let env_current_dir = std::env::current_dir()
             .unwrap()
@@ -1120,20 +4174,21 @@ pub fn ingest_tasks(
             device_id: &device_id,
             ingest_session_id: &ingest_session_id,
             ingest_fs_path_id: None,
+            json_schemas: None,
             resources: &resources,
             ingest_stmts: &mut ingest_stmts,
+            executed_ahead: HashMap::new(),
         };
 
         for resource_result in resources.uniform_resources() {
+            encountered += 1;
             match resource_result {
                 Ok(resource) => {
                     let mut urw_entry = UniformResourceWriterEntry {
                         path: Some(resource.uri()),
                         tried_alternate_nature: None,
                     };
-                    if cli.debug > 0 {
-                        println!("{:?}", urw_entry.path);
-                    }
+                    tracing::debug!("{:?}", urw_entry.path);
 
                     let inserted = resource.insert(&mut urw_state, &mut urw_entry);
                     let mut ur_status = inserted.action.ur_status();
@@ -1186,6 +4241,13 @@ pub fn ingest_tasks(
                             None
                         }
                     };
+                    captured_exec += 1;
+                    if uniform_resource_id.is_some() {
+                        uniform_resources += 1;
+                    }
+                    if ur_status.as_deref() == Some("ERROR") {
+                        errors += 1;
+                    }
 
                     match urw_state.ingest_stmts.ins_ur_is_task_stmt.execute(params![
                         ingest_session_id,
@@ -1203,12 +4265,25 @@ pub fn ingest_tasks(
                     }
                 }
                 Err(e) => {
+                    errors += 1;
                     eprintln!("Error processing a ingest_tasks resource: {}", e);
                 }
             }
         }
     }
 
+    let counts = json!({
+        "encountered": encountered,
+        "captured_exec": captured_exec,
+        "uniform_resources": uniform_resources,
+        "errors": errors,
+    });
+    record_session_counts(&tx, &ingest_session_id, &counts).with_context(|| {
+        format!(
+            "[ingest_tasks] unable to record counts of session {} in {}",
+            ingest_session_id, db_fs_path
+        )
+    })?;
     match tx.execute(INS_UR_INGEST_SESSION_FINISH_SQL, params![ingest_session_id]) {
         Ok(_) => {}
         Err(err) => {
@@ -1229,3 +4304,224 @@ pub fn ingest_tasks(
 
     Ok(ingest_session_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_ingest_files_session_provenance_and_counts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("hello.md"), "# hello").unwrap();
+        let db_fs_path = dir.path().join("ingest.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+
+        let cli = crate::cmd::Cli::parse_from([
+            "surveilr",
+            "ingest",
+            "files",
+            "-d",
+            &db_fs_path,
+            "-r",
+            &root.to_string_lossy(),
+            "--capture-exec-env",
+            "TOKEN=shh",
+        ]);
+        let crate::cmd::CliCommands::Ingest(crate::cmd::IngestArgs {
+            command: crate::cmd::IngestCommands::Files(args),
+        }) = &cli.command
+        else {
+            panic!("expected `ingest files` command");
+        };
+        let result = ingest_files(&cli, args, &crate::progress::NoopProgress).unwrap();
+
+        let conn = Connection::open(&*db_fs_path).unwrap();
+        let (provenance_args, uniform_resources): (String, i64) = conn
+            .query_row(
+                "SELECT json_extract(elaboration, '$.provenance.args'),
+                        json_extract(elaboration, '$.counts.uniform_resources')
+                   FROM ur_ingest_session WHERE ur_ingest_session_id = ?",
+                [&result.ingest_session_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(provenance_args.contains("root_fs_path"));
+        assert!(!provenance_args.contains("capture_exec_env"));
+        assert!(!provenance_args.contains("shh"));
+        assert_eq!(uniform_resources, 1);
+
+        let sessions: Vec<(String, i64)> = conn
+            .prepare(crate::cmd::admin::SESSIONS_SQL)
+            .unwrap()
+            .query_map([10], |row| Ok((row.get(0)?, row.get(5)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(sessions, vec![(result.ingest_session_id, 1)]);
+    }
+
+    #[test]
+    fn test_ingest_files_commit_batch_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        for i in 0..5 {
+            std::fs::write(root.join(format!("{}.txt", i)), format!("file {}", i)).unwrap();
+        }
+        let db_fs_path = dir.path().join("ingest.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+
+        // writing the third resource rolls back the transaction, after the
+        // first batch of two was committed
+        let dbc = DbConn::new(&db_fs_path, 0, &DbPragmas::default()).unwrap();
+        dbc.init(None).unwrap().commit().unwrap();
+        dbc.conn
+            .execute_batch(
+                "CREATE TRIGGER inject_failure BEFORE INSERT ON uniform_resource
+                   WHEN (SELECT COUNT(*) FROM uniform_resource) >= 2
+                 BEGIN SELECT RAISE(ROLLBACK, 'injected failure'); END;",
+            )
+            .unwrap();
+        drop(dbc);
+
+        let cli = crate::cmd::Cli::parse_from([
+            "surveilr",
+            "ingest",
+            "files",
+            "-d",
+            &db_fs_path,
+            "-r",
+            &root.to_string_lossy(),
+            "--commit-batch-size",
+            "2",
+        ]);
+        let crate::cmd::CliCommands::Ingest(crate::cmd::IngestArgs {
+            command: crate::cmd::IngestCommands::Files(args),
+        }) = &cli.command
+        else {
+            panic!("expected `ingest files` command");
+        };
+        let err = ingest_files(&cli, args, &crate::progress::NoopProgress).unwrap_err();
+        let err = format!("{:#}", err);
+        assert!(err.contains("rolled back"));
+
+        let conn = Connection::open(&*db_fs_path).unwrap();
+        let stored: Vec<String> = conn
+            .prepare("SELECT uri FROM uniform_resource")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+
+        let (finished_at, failed_uri, committed): (Option<String>, String, i64) = conn
+            .query_row(
+                "SELECT ingest_finished_at,
+                        json_extract(elaboration, '$.failed.uri'),
+                        json_extract(elaboration, '$.failed.committed')
+                   FROM ur_ingest_session",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(finished_at, None);
+        assert_eq!(committed, 2);
+        assert!(failed_uri.ends_with(".txt"));
+        assert!(!stored.contains(&failed_uri));
+        assert!(err.contains(&failed_uri));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ingest_files_fail_on_walk_error() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (dir.path().join("first"), dir.path().join("second"));
+        let locked = second.join("locked");
+        std::fs::create_dir(&first).unwrap();
+        std::fs::create_dir_all(&locked).unwrap();
+        std::fs::write(first.join("readable.md"), "# readable").unwrap();
+        std::fs::write(second.join("readable.md"), "# readable").unwrap();
+        std::fs::write(locked.join("secret.md"), "# secret").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        let unlock =
+            || std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        // e.g. root in a container can read it anyway
+        if std::fs::read_dir(&locked).is_ok() {
+            unlock();
+            return;
+        }
+
+        let db_fs_path = dir.path().join("ingest.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+        DbConn::new(&db_fs_path, 0, &DbPragmas::default())
+            .unwrap()
+            .init(None)
+            .unwrap()
+            .commit()
+            .unwrap();
+
+        let cli = crate::cmd::Cli::parse_from([
+            "surveilr",
+            "ingest",
+            "files",
+            "-d",
+            &db_fs_path,
+            "-r",
+            &first.to_string_lossy(),
+            "-r",
+            &second.to_string_lossy(),
+            "--fail-on-walk-error",
+            "--commit-batch-size",
+            "1",
+        ]);
+        let crate::cmd::CliCommands::Ingest(crate::cmd::IngestArgs {
+            command: crate::cmd::IngestCommands::Files(args),
+        }) = &cli.command
+        else {
+            panic!("expected `ingest files` command");
+        };
+        let result = ingest_files(&cli, args, &crate::progress::NoopProgress);
+        unlock();
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("--fail-on-walk-error"));
+        assert!(err.contains("locked"));
+
+        // not even the first root's resources were stored
+        let conn = Connection::open(&*db_fs_path).unwrap();
+        let stored: (i64, i64, i64) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM ur_ingest_session),
+                        (SELECT COUNT(*) FROM ur_ingest_session_fs_path),
+                        (SELECT COUNT(*) FROM uniform_resource)",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(stored, (0, 0, 0));
+    }
+
+    #[test]
+    fn test_imap_password_not_serialized() {
+        let cli = crate::cmd::Cli::parse_from([
+            "surveilr",
+            "ingest",
+            "imap",
+            "--host",
+            "mail.example.com",
+            "-u",
+            "someone",
+            "--password",
+            "hunter2",
+        ]);
+        let args = serde_json::to_string(&cli).unwrap();
+        assert!(args.contains("mail.example.com"));
+        assert!(!args.contains("password"));
+        assert!(!args.contains("hunter2"));
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::Context;
+use jsonschema::JSONSchema;
+use regex::Regex;
+use serde_json::Value as JsonValue;
+
+/// Issue type of content which doesn't parse as JSON at all (so it couldn't be
+/// validated against any schema).
+pub const MALFORMED_JSON: &str = "malformed-json";
+/// Issue type of JSON which parses but violates a schema.
+pub const SCHEMA_VIOLATION: &str = "schema-violation";
+
+/// A problem found when validating a JSON resource; `instance_path` is the JSON
+/// pointer of the offending value (empty for the whole document).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub schema_name: String,
+    pub issue_type: &'static str,
+    pub instance_path: String,
+    pub message: String,
+}
+
+struct NamedSchema {
+    name: String,
+    schema: JSONSchema,
+}
+
+/// Schemas from `--json-schema name=path` applied to the JSON resources whose
+/// URI matches a `--json-schema-apply regex=name` mapping.
+pub struct JsonSchemas {
+    schemas: Vec<NamedSchema>,
+    applies: Vec<(Regex, usize)>,
+}
+
+impl JsonSchemas {
+    pub fn from_args(
+        schemas: &[(String, String)],
+        applies: &[(String, String)],
+    ) -> anyhow::Result<JsonSchemas> {
+        let mut named = Vec::new();
+        for (name, path) in schemas {
+            let schema_text = std::fs::read_to_string(path).with_context(|| {
+                format!(
+                    "[JsonSchemas::from_args] reading schema {} from {}",
+                    name, path
+                )
+            })?;
+            let schema_json: JsonValue = serde_json::from_str(&schema_text).with_context(|| {
+                format!(
+                    "[JsonSchemas::from_args] schema {} in {} is not JSON",
+                    name, path
+                )
+            })?;
+            let schema = JSONSchema::compile(&schema_json).map_err(|err| {
+                anyhow::anyhow!(
+                    "[JsonSchemas::from_args] invalid schema {} in {}: {}",
+                    name,
+                    path,
+                    err
+                )
+            })?;
+            named.push(NamedSchema {
+                name: name.clone(),
+                schema,
+            });
+        }
+        Self::from_compiled(named, applies)
+    }
+
+    fn from_compiled(
+        schemas: Vec<NamedSchema>,
+        applies: &[(String, String)],
+    ) -> anyhow::Result<JsonSchemas> {
+        let mut compiled_applies = Vec::new();
+        for (regex, name) in applies {
+            let index = schemas
+                .iter()
+                .position(|schema| schema.name == *name)
+                .with_context(|| {
+                    format!(
+                        "[JsonSchemas::from_args] `{}={}` names an unknown schema (see --json-schema)",
+                        regex, name
+                    )
+                })?;
+            let regex = Regex::new(regex)
+                .with_context(|| format!("[JsonSchemas::from_args] invalid regex {}", regex))?;
+            compiled_applies.push((regex, index));
+        }
+        Ok(JsonSchemas {
+            schemas,
+            applies: compiled_applies,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.applies.is_empty()
+    }
+
+    /// Validates `json_text` against every schema applied to `uri`; content
+    /// which doesn't parse is reported once per schema as malformed.
+    pub fn issues(&self, uri: &str, json_text: &str) -> Vec<ValidationIssue> {
+        let mut applied: Vec<&NamedSchema> = self
+            .applies
+            .iter()
+            .filter(|(regex, _)| regex.is_match(uri))
+            .map(|(_, index)| &self.schemas[*index])
+            .collect();
+        applied.dedup_by(|a, b| a.name == b.name);
+        if applied.is_empty() {
+            return vec![];
+        }
+
+        let instance: JsonValue = match serde_json::from_str(json_text) {
+            Ok(instance) => instance,
+            Err(err) => {
+                return applied
+                    .iter()
+                    .map(|named| ValidationIssue {
+                        schema_name: named.name.clone(),
+                        issue_type: MALFORMED_JSON,
+                        instance_path: String::new(),
+                        message: err.to_string(),
+                    })
+                    .collect()
+            }
+        };
+
+        let mut issues = Vec::new();
+        for named in applied {
+            if let Err(errors) = named.schema.validate(&instance) {
+                issues.extend(errors.map(|error| ValidationIssue {
+                    schema_name: named.name.clone(),
+                    issue_type: SCHEMA_VIOLATION,
+                    instance_path: error.instance_path.to_string(),
+                    message: error.to_string(),
+                }));
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn config_schemas() -> JsonSchemas {
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": { "port": { "type": "integer" } }
+        });
+        JsonSchemas::from_compiled(
+            vec![NamedSchema {
+                name: "config".to_string(),
+                schema: JSONSchema::compile(&schema).unwrap(),
+            }],
+            &[(r"config/.*\.json$".to_string(), "config".to_string())],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_valid_and_unmatched() {
+        let schemas = config_schemas();
+        assert_eq!(schemas.issues("/config/a.json", r#"{"name": "a"}"#), vec![]);
+        assert_eq!(schemas.issues("/other/a.json", r#"{"port": "x"}"#), vec![]);
+    }
+
+    #[test]
+    fn test_schema_violation() {
+        let issues = config_schemas().issues("/config/a.json", r#"{"name": "a", "port": "x"}"#);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].issue_type, SCHEMA_VIOLATION);
+        assert_eq!(issues[0].instance_path, "/port");
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let issues = config_schemas().issues("/config/a.json", r#"{"name": "#);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].issue_type, MALFORMED_JSON);
+        assert_eq!(issues[0].instance_path, "");
+    }
+
+    #[test]
+    fn test_unknown_schema_name() {
+        assert!(
+            JsonSchemas::from_compiled(vec![], &[(".*".to_string(), "x".to_string())]).is_err()
+        );
+    }
+}
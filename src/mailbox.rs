@@ -0,0 +1,122 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// A message as fetched from an IMAP mailbox, identified by its `UID`.
+#[derive(Debug, Clone)]
+pub struct ImapMessage {
+    pub uid: u32,
+    pub internal_date: Option<DateTime<Utc>>,
+    pub raw: Vec<u8>,
+}
+
+pub struct ImapMailbox {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub username: String,
+    pub password: String,
+    pub mailbox: String,
+}
+
+trait ImapStream: Read + Write {}
+impl<T: Read + Write> ImapStream for T {}
+
+impl ImapMailbox {
+    /// `imap://user@host:port/mailbox` (RFC 5092) identifies the mailbox.
+    pub fn mailbox_uri(&self) -> String {
+        format!(
+            "imap://{}@{}:{}/{}",
+            self.username, self.host, self.port, self.mailbox
+        )
+    }
+
+    /// `imap://user@host:port/mailbox/;UID=uid` identifies a message.
+    pub fn message_uri(&self, uid: u32) -> String {
+        format!("{}/;UID={}", self.mailbox_uri(), uid)
+    }
+
+    fn connect(&self) -> anyhow::Result<imap::Client<Box<dyn ImapStream>>> {
+        let tcp = TcpStream::connect((self.host.as_str(), self.port))
+            .with_context(|| format!("[ImapMailbox::connect] {}:{}", self.host, self.port))?;
+        let stream: Box<dyn ImapStream> = if self.tls {
+            let root_store = rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            let config = rustls::ClientConfig::builder()
+                .with_root_certificates(root_store)
+                .with_no_client_auth();
+            let server_name = rustls::pki_types::ServerName::try_from(self.host.clone())
+                .with_context(|| format!("[ImapMailbox::connect] invalid host {}", self.host))?;
+            let conn = rustls::ClientConnection::new(Arc::new(config), server_name)
+                .with_context(|| format!("[ImapMailbox::connect] TLS with {}", self.host))?;
+            Box::new(rustls::StreamOwned::new(conn, tcp))
+        } else {
+            Box::new(tcp)
+        };
+        let mut client = imap::Client::new(stream);
+        client
+            .read_greeting()
+            .with_context(|| format!("[ImapMailbox::connect] greeting from {}", self.host))?;
+        Ok(client)
+    }
+
+    /// Fetches every message in the mailbox (or only those with an internal
+    /// date on or after `since`) without marking them as seen.
+    pub fn messages(&self, since: Option<NaiveDate>) -> anyhow::Result<Vec<ImapMessage>> {
+        let mut session = self
+            .connect()?
+            .login(&self.username, &self.password)
+            .map_err(|(err, _)| err)
+            .with_context(|| {
+                format!(
+                    "[ImapMailbox::messages] login as {} to {}",
+                    self.username, self.host
+                )
+            })?;
+        session
+            .examine(&self.mailbox)
+            .with_context(|| format!("[ImapMailbox::messages] examine {}", self.mailbox))?;
+
+        let query = match since {
+            Some(since) => format!("SINCE {}", since.format("%d-%b-%Y")),
+            None => String::from("ALL"),
+        };
+        let mut uids: Vec<u32> = session
+            .uid_search(&query)
+            .with_context(|| format!("[ImapMailbox::messages] UID SEARCH {query}"))?
+            .into_iter()
+            .collect();
+        uids.sort_unstable();
+
+        let mut messages = Vec::with_capacity(uids.len());
+        if !uids.is_empty() {
+            let uid_set = uids
+                .iter()
+                .map(|uid| uid.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            let fetches = session
+                .uid_fetch(&uid_set, "(UID INTERNALDATE BODY.PEEK[])")
+                .with_context(|| {
+                    format!("[ImapMailbox::messages] UID FETCH in {}", self.mailbox)
+                })?;
+            for fetch in fetches.iter() {
+                if let (Some(uid), Some(raw)) = (fetch.uid, fetch.body()) {
+                    messages.push(ImapMessage {
+                        uid,
+                        internal_date: fetch.internal_date().map(|date| date.with_timezone(&Utc)),
+                        raw: raw.to_vec(),
+                    });
+                }
+            }
+        }
+
+        // the messages are already fetched so failing to say goodbye isn't fatal
+        let _ = session.logout();
+        Ok(messages)
+    }
+}
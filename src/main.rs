@@ -5,39 +5,51 @@ use clap::Parser;
 extern crate lazy_static;
 
 mod device;
+
+// set from `--device-name` before DEVICE is first dereferenced
+static DEVICE_NAME_FLAG: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
 lazy_static! {
-    static ref DEVICE: device::Device = device::Device::new(None);
+    static ref DEVICE: device::Device = device::Device::new(DEVICE_NAME_FLAG.get().cloned(), None);
 }
 
 #[macro_use]
 mod helpers;
 
+mod archive;
 mod cmd;
+mod email;
 mod format;
 mod frontmatter;
+mod git;
+mod html;
 mod ingest;
+mod json_schema;
+mod mailbox;
+#[allow(dead_code)] // generated from support/sql-aide/models.ts
 mod models_polygenix;
+mod parquet_out;
+mod pdf;
 mod persist;
+mod progress;
 mod resource;
 mod shell;
+mod tabular;
+mod xml;
 
 fn main() -> anyhow::Result<()> {
-    let cli = cmd::Cli::parse();
-
-    // --debug can be passed more than once to increase level
-    match cli.debug {
-        0 => {}
-        1 => println!("Debug mode is kind of on"),
-        2 => println!("Debug mode is on"),
-        _ => println!("Don't be crazy"),
+    let mut cli = cmd::Cli::parse();
+    cli.expand_at_files().with_context(|| "main")?;
+    if let Some(name) = &cli.device_name {
+        DEVICE_NAME_FLAG.get_or_init(|| name.clone());
     }
 
-    if cli.debug > 0 {
-        // You can check the value provided by positional arguments, or option arguments
-        if let Some(name) = cli.device_name.as_deref() {
-            println!("Device: {name}");
-        }
-    }
+    // --debug can be passed more than once to increase level, --quiet and
+    // --log-level take precedence over it
+    cli.apply_log_level();
+    let _tracing_guard = cli.init_tracing();
+    tracing::debug!("Log level: {:?}", cli.log_level());
+    tracing::info!("Device: {} ({:?})", DEVICE.name, DEVICE.name_source);
 
     cli.command.execute(&cli).with_context(|| "main")?;
     Ok(())
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampMillisecondArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+
+/// The metadata of one ingested resource (content itself is not exported).
+pub struct ParquetResourceRow {
+    pub uri: String,
+    pub nature: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_modified_at: Option<DateTime<Utc>>,
+    pub content_digest: Option<String>,
+    pub ur_status: Option<String>,
+}
+
+/// Buffers the rows of an ingest session so they can be written as a single
+/// Parquet file once the session is complete.
+pub struct ParquetResourceRows {
+    ingest_session_id: String,
+    rows: Vec<ParquetResourceRow>,
+}
+
+impl ParquetResourceRows {
+    pub fn new(ingest_session_id: &str) -> Self {
+        ParquetResourceRows {
+            ingest_session_id: ingest_session_id.to_string(),
+            rows: vec![],
+        }
+    }
+
+    pub fn push(&mut self, row: ParquetResourceRow) {
+        self.rows.push(row);
+    }
+
+    fn schema() -> Schema {
+        let timestamp = DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()));
+        Schema::new(vec![
+            Field::new("ingest_session_id", DataType::Utf8, false),
+            Field::new("uri", DataType::Utf8, false),
+            Field::new("nature", DataType::Utf8, true),
+            Field::new("size_bytes", DataType::UInt64, true),
+            Field::new("created_at", timestamp.clone(), true),
+            Field::new("last_modified_at", timestamp, true),
+            Field::new("content_digest", DataType::Utf8, true),
+            Field::new("ur_status", DataType::Utf8, true),
+        ])
+    }
+
+    /// Writes (or replaces) `fs_path` and returns the number of rows written.
+    pub fn write(&self, fs_path: &str) -> anyhow::Result<usize> {
+        let schema = Arc::new(Self::schema());
+        let millis = |at: &Option<DateTime<Utc>>| at.map(|at| at.timestamp_millis());
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec![
+                self.ingest_session_id.as_str();
+                self.rows.len()
+            ])),
+            Arc::new(StringArray::from_iter_values(
+                self.rows.iter().map(|r| r.uri.as_str()),
+            )),
+            Arc::new(StringArray::from_iter(
+                self.rows.iter().map(|r| r.nature.as_deref()),
+            )),
+            Arc::new(UInt64Array::from_iter(
+                self.rows.iter().map(|r| r.size_bytes),
+            )),
+            Arc::new(
+                TimestampMillisecondArray::from_iter(
+                    self.rows.iter().map(|r| millis(&r.created_at)),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(
+                TimestampMillisecondArray::from_iter(
+                    self.rows.iter().map(|r| millis(&r.last_modified_at)),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(StringArray::from_iter(
+                self.rows.iter().map(|r| r.content_digest.as_deref()),
+            )),
+            Arc::new(StringArray::from_iter(
+                self.rows.iter().map(|r| r.ur_status.as_deref()),
+            )),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns)
+            .with_context(|| format!("[ParquetResourceRows.write] record batch for {fs_path}"))?;
+
+        let file = std::fs::File::create(fs_path)
+            .with_context(|| format!("[ParquetResourceRows.write] unable to create {fs_path}"))?;
+        let mut writer = ArrowWriter::try_new(file, schema, None)
+            .with_context(|| format!("[ParquetResourceRows.write] writer for {fs_path}"))?;
+        writer
+            .write(&batch)
+            .with_context(|| format!("[ParquetResourceRows.write] unable to write {fs_path}"))?;
+        writer
+            .close()
+            .with_context(|| format!("[ParquetResourceRows.write] unable to close {fs_path}"))?;
+        Ok(self.rows.len())
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+
+/// Why text could not be extracted from a PDF; kept as data (rather than a
+/// panic or an opaque string) so it can be stored as a diagnostic.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdfExtractError {
+    Unreadable(String),
+    Malformed(String),
+    Encrypted,
+    TextUnavailable { page_count: usize, message: String },
+}
+
+impl fmt::Display for PdfExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PdfExtractError::Unreadable(message) => {
+                write!(f, "PDF content could not be read: {message}")
+            }
+            PdfExtractError::Malformed(message) => write!(f, "PDF is malformed: {message}"),
+            PdfExtractError::Encrypted => write!(f, "PDF is encrypted, text not extracted"),
+            PdfExtractError::TextUnavailable {
+                page_count,
+                message,
+            } => write!(
+                f,
+                "unable to extract text from PDF with {page_count} page(s): {message}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PdfExtractError {}
+
+/// The text of a PDF, one page after another, and its number of pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfText {
+    pub page_count: usize,
+    pub text: String,
+}
+
+pub fn extracted_text(bytes: &[u8]) -> Result<PdfText, PdfExtractError> {
+    let document = lopdf::Document::load_mem(bytes)
+        .map_err(|err| PdfExtractError::Malformed(err.to_string()))?;
+    if document.is_encrypted() {
+        return Err(PdfExtractError::Encrypted);
+    }
+
+    let page_numbers: Vec<u32> = document.get_pages().keys().cloned().collect();
+    let page_count = page_numbers.len();
+    let text =
+        document
+            .extract_text(&page_numbers)
+            .map_err(|err| PdfExtractError::TextUnavailable {
+                page_count,
+                message: err.to_string(),
+            })?;
+    Ok(PdfText { page_count, text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Document, Object, Stream};
+    use pretty_assertions::assert_eq;
+
+    fn pdf_with_pages(pages_text: &[&str]) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let kids: Vec<Object> = pages_text
+            .iter()
+            .map(|text| {
+                let content = Content {
+                    operations: vec![
+                        Operation::new("BT", vec![]),
+                        Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                        Operation::new("Td", vec![72.into(), 720.into()]),
+                        Operation::new("Tj", vec![Object::string_literal(*text)]),
+                        Operation::new("ET", vec![]),
+                    ],
+                };
+                let content_id =
+                    doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                })
+                .into()
+            })
+            .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+
+        let mut bytes = vec![];
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_extracted_text() {
+        let extracted = extracted_text(&pdf_with_pages(&["Hello", "World"])).unwrap();
+        assert_eq!(extracted.page_count, 2);
+        assert!(extracted.text.contains("Hello"));
+        assert!(extracted.text.contains("World"));
+    }
+
+    #[test]
+    fn test_extracted_text_malformed() {
+        assert!(matches!(
+            extracted_text(b"%PDF-1.5 not really a PDF"),
+            Err(PdfExtractError::Malformed(_))
+        ));
+    }
+}
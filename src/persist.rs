@@ -6,7 +6,7 @@ use comfy_table::*;
 use globset::Glob;
 use is_executable::IsExecutable; // adds path.is_executable
 use rusqlite::functions::FunctionFlags;
-use rusqlite::{types::ValueRef, Connection, Result as RusqliteResult, ToSql};
+use rusqlite::{types::ValueRef, Connection, OptionalExtension, Result as RusqliteResult, ToSql};
 use serde_json::{json, Value as JsonValue};
 use ulid::Ulid;
 
@@ -26,31 +26,80 @@ pub fn declare_ulid_function(db: &Connection) -> RusqliteResult<()> {
     })
 }
 
+/// Pragmas applied right after the state DB is opened, `None` keeps SQLite's
+/// (or the database file's) current setting.
+#[derive(Debug, Default, Clone)]
+pub struct DbPragmas {
+    pub journal_mode: Option<String>,
+    pub synchronous: Option<String>,
+    pub busy_timeout: Option<std::time::Duration>,
+    // not a pragma: whether `DbConn::init` may apply the migrations an existing
+    // RSSD is missing (otherwise it fails with `SchemaMismatch`)
+    pub auto_migrate: bool,
+}
+
+impl DbPragmas {
+    pub fn apply(&self, conn: &Connection) -> Result<()> {
+        if let Some(busy_timeout) = self.busy_timeout {
+            conn.busy_timeout(busy_timeout)
+                .with_context(|| format!("[DbPragmas::apply] busy_timeout {:?}", busy_timeout))?;
+        }
+        if let Some(journal_mode) = &self.journal_mode {
+            // SQLite answers with the resulting mode, which differs when the mode
+            // can't be changed (e.g. `wal` for in-memory databases)
+            let applied: String = conn
+                .pragma_update_and_check(None, "journal_mode", journal_mode, |row| row.get(0))
+                .with_context(|| format!("[DbPragmas::apply] journal_mode {}", journal_mode))?;
+            if !applied.eq_ignore_ascii_case(journal_mode) {
+                anyhow::bail!(
+                    "[DbPragmas::apply] journal_mode {} requested but SQLite kept {}",
+                    journal_mode,
+                    applied
+                );
+            }
+        }
+        if let Some(synchronous) = &self.synchronous {
+            conn.pragma_update(None, "synchronous", synchronous)
+                .with_context(|| format!("[DbPragmas::apply] synchronous {}", synchronous))?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct DbConn {
     pub db_fs_path: String,
     pub conn: Connection,
     pub vebose_level: u8,
+    pub auto_migrate: bool,
+}
+
+/// Begins a (deferred) transaction which, unlike `Connection::transaction`,
+/// only borrows `conn`; there must not be one already.
+pub fn begin_transaction(conn: &Connection) -> rusqlite::Result<rusqlite::Transaction<'_>> {
+    rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Deferred)
 }
 
 impl DbConn {
     // open an existing database or create a new one if it doesn't exist
-    pub fn new(db_fs_path: &str, vebose_level: u8) -> Result<DbConn> {
+    pub fn new(db_fs_path: &str, vebose_level: u8, pragmas: &DbPragmas) -> Result<DbConn> {
         let db_fs_path = db_fs_path.to_string();
         let conn = Connection::open(db_fs_path.clone())
             .with_context(|| format!("[DbConn::new] SQLite database {}", db_fs_path))?;
+        pragmas
+            .apply(&conn)
+            .with_context(|| format!("[DbConn::new] pragmas for {}", db_fs_path))?;
         prepare_conn(&conn).with_context(|| {
             format!("[DbConn::new] prepare SQLite connection for {}", db_fs_path)
         })?;
 
-        if vebose_level > 0 {
-            println!("RSSD: {}", db_fs_path);
-        }
+        tracing::info!("RSSD: {}", db_fs_path);
 
         Ok(DbConn {
             db_fs_path,
             conn,
             vebose_level,
+            auto_migrate: pragmas.auto_migrate,
         })
     }
 
@@ -65,18 +114,30 @@ impl DbConn {
             db_fs_path,
             conn,
             vebose_level,
+            auto_migrate: false,
         })
     }
 
-    pub fn init(&mut self, db_init_sql: Option<&[String]>) -> Result<rusqlite::Transaction> {
+    // the transaction only borrows the connection so that statements prepared on
+    // it outlive the transaction (e.g. `ingest files --commit-batch-size` begins
+    // a new one for each batch)
+    pub fn init(&self, db_init_sql: Option<&[String]>) -> Result<rusqlite::Transaction<'_>> {
         // putting everything inside a transaction improves performance significantly
-        let tx = self
-            .conn
-            .transaction()
+        let tx = begin_transaction(&self.conn)
             .with_context(|| format!("[DbConn::new] SQLite transaction in {}", self.db_fs_path))?;
 
+        if !self.auto_migrate {
+            if let Some(mismatch) = schema_mismatch(&tx, &self.db_fs_path)
+                .with_context(|| format!("[DbConn::new] schema_mismatch in {}", self.db_fs_path))?
+            {
+                return Err(mismatch.into());
+            }
+        }
         execute_migrations(&tx, "ingest")
             .with_context(|| format!("[DbConn::new] execute_migrations in {}", self.db_fs_path))?;
+        record_schema_version(&tx).with_context(|| {
+            format!("[DbConn::new] record_schema_version in {}", self.db_fs_path)
+        })?;
 
         if let Some(state_db_init_sql) = db_init_sql {
             // TODO: add the executed files into the behaviors or other activity log!?
@@ -181,7 +242,11 @@ impl DbConn {
     }
 }
 
-execute_sql_batch!(bootstrap_ddl, include_str!("bootstrap.sql"));
+/// The bootstrap DDL embedded in the binary so that no SQL files have to be
+/// shipped alongside it; `--state-db-init-sql` files are applied on top.
+pub const BOOTSTRAP_SQL: &str = include_str!("bootstrap.sql");
+
+execute_sql_batch!(bootstrap_ddl, BOOTSTRAP_SQL);
 
 query_sql_single!(
     select_notebook_cell_code_latest,
@@ -220,6 +285,22 @@ execute_sql!(
     transition_reason: &str
 );
 
+// a cell may fail more than once before it's fixed so only the latest failure is kept
+execute_sql!(
+    upsert_notebook_cell_failed_state,
+    r"INSERT INTO code_notebook_state (code_notebook_state_id, code_notebook_cell_id, from_state, to_state, transition_result, transition_reason)
+                               VALUES (ulid(), (SELECT code_notebook_cell_id FROM code_notebook_cell WHERE notebook_name = ?1 AND cell_name = ?2), ?3, 'FAILED', json_object('error', ?5), ?4)
+      ON CONFLICT (code_notebook_cell_id, from_state, to_state) DO UPDATE SET
+             transition_result = EXCLUDED.transition_result,
+             transition_reason = EXCLUDED.transition_reason,
+             transitioned_at = CURRENT_TIMESTAMP",
+    notebook_name: &str,
+    cell_name: &str,
+    from_state: &str,
+    transition_reason: &str,
+    error: &str
+);
+
 // Executes a query to select the most recently inserted cells for each all
 // rows in ConstructionSqlNotebook. Code notebook cells are unique for
 // notebook_name, cell_name and interpretable_code_hash which means there may
@@ -289,6 +370,47 @@ query_sql_rows_no_args!(
     code_notebook_cell_id: String
 );
 
+// same as migratable_notebook_cells_uniq_all, with whether each cell has been
+// executed by execute_migrations; cells without `_once_` are executed in every
+// session so their state isn't tracked.
+query_sql_rows_no_args!(
+    migratable_notebook_cells_status,
+    r#"   SELECT c.code_notebook_cell_id,
+                 c.notebook_name,
+                 c.cell_name,
+                 c.interpretable_code_hash,
+                 MAX(c.created_at) AS most_recent_created_at,
+                 CASE
+                   WHEN c.cell_name NOT LIKE '%\_once\_%' ESCAPE '\' THEN 'always'
+                   WHEN executed.transitioned_at IS NOT NULL THEN 'applied'
+                   WHEN failed.transitioned_at IS NOT NULL THEN 'failed'
+                   ELSE 'pending'
+                 END AS status,
+                 COALESCE(executed.transitioned_at, failed.transitioned_at) AS transitioned_at
+           FROM code_notebook_cell c
+      LEFT JOIN (SELECT sc.notebook_name, sc.cell_name, MAX(s.transitioned_at) AS transitioned_at
+                   FROM code_notebook_state s
+                   JOIN code_notebook_cell sc ON sc.code_notebook_cell_id = s.code_notebook_cell_id
+                  WHERE s.to_state = 'EXECUTED'
+               GROUP BY sc.notebook_name, sc.cell_name) executed
+             ON executed.notebook_name = c.notebook_name AND executed.cell_name = c.cell_name
+      LEFT JOIN (SELECT sc.notebook_name, sc.cell_name, MAX(s.transitioned_at) AS transitioned_at
+                   FROM code_notebook_state s
+                   JOIN code_notebook_cell sc ON sc.code_notebook_cell_id = s.code_notebook_cell_id
+                  WHERE s.to_state = 'FAILED'
+               GROUP BY sc.notebook_name, sc.cell_name) failed
+             ON failed.notebook_name = c.notebook_name AND failed.cell_name = c.cell_name
+          WHERE c.notebook_name = 'ConstructionSqlNotebook'
+       GROUP BY c.notebook_name, c.cell_name
+       ORDER BY c.cell_name"#;
+    notebook_name: String,
+    cell_name: String,
+    interpretable_code_hash: String,
+    status: String,
+    transitioned_at: Option<String>,
+    code_notebook_cell_id: String
+);
+
 query_sql_rows_no_args!(
     notebook_cells_versions,
     r"  SELECT notebook_name,
@@ -329,16 +451,25 @@ query_sql_rows_no_args!(
     transitioned_at: String
 );
 
+// the RSSD's own `--nature-bind`s, registered by inserting into `nature_bind`
+query_sql_rows_no_args!(
+    nature_binds,
+    r"SELECT nature, bound_nature FROM nature_bind ORDER BY nature";
+    nature: String,
+    bound_nature: String
+);
+
 // ulid() is not built into SQLite, be sure to register it with prepare_conn
 query_sql_single!(
     upsert_device,
-    r"INSERT INTO device (device_id, name, boundary, state, state_sysinfo) VALUES (ulid(), ?, ?, ?, ?)
-      ON CONFLICT(name, state, boundary) DO UPDATE SET updated_at = CURRENT_TIMESTAMP
+    r"INSERT INTO device (device_id, name, boundary, state, state_sysinfo, elaboration) VALUES (ulid(), ?, ?, ?, ?, ?)
+      ON CONFLICT(name, state, boundary) DO UPDATE SET updated_at = CURRENT_TIMESTAMP, elaboration = EXCLUDED.elaboration
       RETURNING device_id, name",
     name: &str,
     boundary: &str,
     state: &str,
-    state_sysinfo: &str;
+    state_sysinfo: &str,
+    elaboration: &str;
     device_id: String,
     name: String
 );
@@ -448,7 +579,7 @@ pub fn select_notebooks_and_cells(
     results
 }
 
-/**
+/*
  * IMPORTANT TODO: ensure all high performance loops are wrapped in prepare
  * statements along with BEGIN/END transactions in batches (prepared stmts
  * are faster than non-prepared, prepared stmts inside transactions are
@@ -530,24 +661,44 @@ pub fn execute_migrations(conn: &Connection, context: &str) -> RusqliteResult<()
                     "execute_migrations",
                 ) {
                     None => {
-                        println!(
-                            "[TODO: move this to Otel, {}] {} {} migration not required ({})",
-                            context, notebook_name, cell_name, id
+                        tracing::debug!(
+                            "[{}] {} {} migration not required ({})",
+                            context,
+                            notebook_name,
+                            cell_name,
+                            id
+                        );
+                        Ok(())
+                    }
+                    Some(Ok(_)) => {
+                        tracing::info!(
+                            "[{}] {} {} migrated ({})",
+                            context,
+                            notebook_name,
+                            cell_name,
+                            id
                         );
                         Ok(())
                     }
-                    Some(_) => {
-                        println!(
-                            "[TODO: move this to Otel, {}] {} {} migrated ({})",
-                            context, notebook_name, cell_name, id
+                    Some(Err(err)) => {
+                        tracing::warn!(
+                            "[{}] {} {} migration failed ({}): {}",
+                            context,
+                            notebook_name,
+                            cell_name,
+                            id,
+                            err
                         );
                         Ok(())
                     }
                 }
             } else {
-                println!(
-                    "[TODO: move this to Otel, {}] {} {} migrated ({})",
-                    context, notebook_name, cell_name, id
+                tracing::debug!(
+                    "[{}] {} {} migrated ({})",
+                    context,
+                    notebook_name,
+                    cell_name,
+                    id
                 );
                 conn.execute_batch(&sql)
             }
@@ -555,19 +706,143 @@ pub fn execute_migrations(conn: &Connection, context: &str) -> RusqliteResult<()
     )
 }
 
-pub fn execute_globs_batch(
+/// The schema version of this `surveilr`: the highest `vNNN` of the `_once_`
+/// migration cells in the embedded bootstrap SQL. An RSSD records the version
+/// it was migrated to as its `PRAGMA user_version`.
+pub fn schema_version() -> u32 {
+    let once_cell = regex::Regex::new(r"'v(\d+)_once_[^']*'").unwrap();
+    once_cell
+        .captures_iter(BOOTSTRAP_SQL)
+        .filter_map(|caps| caps[1].parse().ok())
+        .max()
+        .unwrap_or_default()
+}
+
+/// An existing RSSD which is missing migrations this `surveilr` expects; see
+/// `--auto-migrate` and `admin migrate`.
+#[derive(Debug)]
+pub struct SchemaMismatch {
+    pub db_fs_path: String,
+    pub db_version: u32,
+    pub expected_version: u32,
+    pub pending: Vec<String>,
+}
+
+impl std::fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[SchemaMismatch] {} has schema version {} but surveilr expects {}, pending migrations: {}; pass --auto-migrate or run `surveilr admin migrate -d {}`",
+            self.db_fs_path,
+            self.db_version,
+            self.expected_version,
+            self.pending.join(", "),
+            self.db_fs_path
+        )
+    }
+}
+
+impl std::error::Error for SchemaMismatch {}
+
+/// The `_once_` migrations this `surveilr` has which haven't been applied to
+/// `conn`; the (idempotent) bootstrap SQL is executed first so the notebook has
+/// the cells of this version.
+pub fn pending_migrations(conn: &Connection) -> RusqliteResult<Vec<String>> {
+    // bootstrap_ddl may partially fail on older schemas, see execute_migrations
+    let _ = bootstrap_ddl(conn);
+    let applied = once_migrations(conn, true)?;
+    Ok(once_migrations(conn, false)?
+        .into_iter()
+        .filter(|cell_name| !applied.contains(cell_name))
+        .collect())
+}
+
+/// `None` for new (empty) databases and RSSDs which are up to date, which is
+/// usually answered by `PRAGMA user_version` alone; RSSDs created before the
+/// version was recorded are judged by their applied migrations.
+pub fn schema_mismatch(conn: &Connection, db_fs_path: &str) -> Result<Option<SchemaMismatch>> {
+    let is_rssd: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'code_notebook_state')",
+        [],
+        |row| row.get(0),
+    )?;
+    let db_version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let expected_version = schema_version();
+    if !is_rssd || db_version >= expected_version {
+        return Ok(None);
+    }
+    let pending = pending_migrations(conn)?;
+    Ok((!pending.is_empty()).then(|| SchemaMismatch {
+        db_fs_path: db_fs_path.to_string(),
+        db_version,
+        expected_version,
+        pending,
+    }))
+}
+
+// only once every migration was applied, a failed one is retried next time
+fn record_schema_version(conn: &Connection) -> RusqliteResult<()> {
+    if once_migrations(conn, false)? == once_migrations(conn, true)? {
+        conn.pragma_update(None, "user_version", schema_version())?;
+    }
+    Ok(())
+}
+
+/// An in-memory database with all migrations applied, i.e. what a new RSSD
+/// would contain; unlike `execute_migrations` nothing is reported or recorded.
+pub fn migrated_in_memory_conn() -> Result<Connection> {
+    let conn = Connection::open_in_memory()
+        .with_context(|| "[migrated_in_memory_conn] in-memory SQLite database")?;
+    prepare_conn(&conn).with_context(|| "[migrated_in_memory_conn] prepare in-memory database")?;
+    bootstrap_ddl(&conn).with_context(|| "[migrated_in_memory_conn] bootstrap DDL")?;
+    migratable_notebook_cells_uniq_all(
+        &conn,
+        |_index, _notebook, _cell, sql, _hash, _id: String| conn.execute_batch(&sql),
+    )
+    .with_context(|| "[migrated_in_memory_conn] migrations")?;
+    Ok(conn)
+}
+
+/// The names of the `_once_` migrations in `conn`'s notebook, or with `applied`
+/// only those `execute_migrations` recorded as executed. Two RSSDs with the same
+/// applied migrations have tables which line up (e.g. for `admin merge`).
+pub fn once_migrations(
     conn: &Connection,
+    applied: bool,
+) -> RusqliteResult<std::collections::BTreeSet<String>> {
+    let sql = if applied {
+        r"SELECT DISTINCT c.cell_name
+            FROM code_notebook_state s
+            JOIN code_notebook_cell c ON c.code_notebook_cell_id = s.code_notebook_cell_id
+           WHERE c.notebook_name = 'ConstructionSqlNotebook'
+             AND c.cell_name LIKE '%\_once\_%' ESCAPE '\'
+             AND s.to_state = 'EXECUTED'"
+    } else {
+        r"SELECT DISTINCT cell_name
+            FROM code_notebook_cell
+           WHERE notebook_name = 'ConstructionSqlNotebook'
+             AND cell_name LIKE '%\_once\_%' ESCAPE '\'"
+    };
+    let mut stmt = conn.prepare(sql)?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<RusqliteResult<_>>()?;
+    Ok(names)
+}
+
+/// The files matching `candidates_globs` under each of `walk_paths`. Glob
+/// expansion order depends on the file system so the paths are sorted with
+/// `natural_cmp` (numbers by value, the rest by code point) which means SQL
+/// batches run in the same order everywhere and `file2.sql` runs before
+/// `file10.sql`.
+pub fn matched_sql_batch_paths(
     walk_paths: &[String],
     candidates_globs: &[String],
-    context: &str,
-    verbose_level: u8,
-) -> anyhow::Result<Vec<(String, Option<String>, bool)>> {
-    let mut executed: Vec<(String, Option<String>, bool)> = Vec::new();
-
+) -> Vec<std::path::PathBuf> {
     // prepare a single walker which will build a GlobWalker for each walk_path,
     // and iterate through only valid DirEntries;
     // TODO: this "eats" all errors without reporting
-    let entries = walk_paths
+    let mut paths: Vec<std::path::PathBuf> = walk_paths
         .iter()
         .map(|bp: &String| {
             globwalk::GlobWalkerBuilder::from_patterns(bp, candidates_globs)
@@ -576,58 +851,69 @@ pub fn execute_globs_batch(
         })
         .filter_map(Result::ok)
         .flatten()
-        .filter_map(Result::ok);
+        .filter_map(Result::ok)
+        .map(|entry| entry.into_path())
+        .collect();
+    paths.sort_by(|a, b| crate::format::natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    paths
+}
 
+/// SQL batch files named `*.sql.ts` or `*.sql.sh` are executed and their
+/// output is the SQL, when they're executable.
+pub fn is_capturable_sql_batch(path: &std::path::Path) -> anyhow::Result<bool> {
     let capturables_glob = Glob::new("*.sql.{ts,sh}")?.compile_matcher();
-    for entry in entries {
-        let path = entry.path();
+    Ok(capturables_glob.is_match(path) && path.is_executable())
+}
+
+pub fn execute_globs_batch(
+    conn: &Connection,
+    walk_paths: &[String],
+    candidates_globs: &[String],
+    context: &str,
+    verbose_level: u8,
+) -> anyhow::Result<Vec<(String, Option<String>, bool)>> {
+    let mut executed: Vec<(String, Option<String>, bool)> = Vec::new();
+
+    for path in matched_sql_batch_paths(walk_paths, candidates_globs) {
         let uri = path.to_string_lossy().to_string();
-        let (sql, is_captured_from_exec) =
-            if capturables_glob.is_match(path) && path.is_executable() {
-                let command = path.to_string_lossy().to_string();
-                let ce = CapturableExecutable::UriShellExecutive(
-                    Box::new(command.clone()), // `String` has ShellExecutive trait
-                    command,
-                    String::from("surveilr-SQL"), // arbitrary but useful "nature"
-                    true,
-                );
-                match ce.executed_result_as_sql(crate::shell::ShellStdIn::None) {
-                    Ok((sql_from_captured_exec, _nature)) => (sql_from_captured_exec, true),
-                    Err(err) => {
-                        eprintln!(
-                            "[execute_globs_batch({})] Unable to execute {}:\n{}",
-                            context, uri, err
-                        );
-                        continue;
-                    }
+        let (sql, is_captured_from_exec) = if is_capturable_sql_batch(&path)? {
+            let command = path.to_string_lossy().to_string();
+            let ce = CapturableExecutable::UriShellExecutive(
+                Box::new(command.clone()), // `String` has ShellExecutive trait
+                command,
+                String::from("surveilr-SQL"), // arbitrary but useful "nature"
+                true,
+                crate::shell::DEFAULT_EXECUTION_TIMEOUT,
+            );
+            match ce.executed_result_as_sql(crate::shell::ShellStdIn::None) {
+                Ok((sql_from_captured_exec, _nature)) => (sql_from_captured_exec, true),
+                Err(err) => {
+                    eprintln!(
+                        "[execute_globs_batch({})] Unable to execute {}:\n{}",
+                        context, uri, err
+                    );
+                    continue;
                 }
-            } else {
-                match std::fs::read_to_string(path) {
-                    Ok(sql_from_file) => (sql_from_file, false),
-                    Err(err) => {
-                        eprintln!(
-                            "[execute_globs_batch({})] Failed to read SQL file {}: {}",
-                            context, uri, err
-                        );
-                        continue;
-                    }
+            }
+        } else {
+            match std::fs::read_to_string(&path) {
+                Ok(sql_from_file) => (sql_from_file, false),
+                Err(err) => {
+                    eprintln!(
+                        "[execute_globs_batch({})] Failed to read SQL file {}: {}",
+                        context, uri, err
+                    );
+                    continue;
                 }
-            };
+            }
+        };
 
         match conn.execute_batch(&sql) {
             Ok(_) => {
-                executed.push((
-                    entry.path().to_string_lossy().to_string(),
-                    Some(sql),
-                    is_captured_from_exec,
-                ));
+                executed.push((uri.clone(), Some(sql), is_captured_from_exec));
             }
             Err(e) => {
-                executed.push((
-                    entry.path().to_string_lossy().to_string(),
-                    None,
-                    is_captured_from_exec,
-                ));
+                executed.push((uri.clone(), None, is_captured_from_exec));
                 eprintln!(
                     "[execute_globs_batch({})] Failed to execute SQL file: {}",
                     context, e
@@ -704,7 +990,19 @@ pub fn execute_batch_stateful(
                         Err(err) => Some(Err(err)),
                     }
                 }
-                Err(err) => Some(Err(err)),
+                Err(err) => {
+                    // the failure is recorded so it can be reported, the cell
+                    // isn't in `to_state` so it will be executed again next time
+                    let _ = upsert_notebook_cell_failed_state(
+                        conn,
+                        notebook_name,
+                        cell_name,
+                        from_state,
+                        transition_reason,
+                        &err.to_string(),
+                    );
+                    Some(Err(err))
+                }
             },
             Err(err) => Some(Err(err)),
         },
@@ -716,6 +1014,219 @@ pub fn execute_batch_stateful(
     }
 }
 
+/// Opens a (non-state) SQLite database read-only and returns each user table's
+/// name and row count, sorted by name; errors if the file isn't a database.
+pub fn sqlite_db_tables_row_counts(db_fs_path: &str) -> Result<Vec<(String, i64)>> {
+    let conn = Connection::open_with_flags(
+        db_fs_path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("[sqlite_db_tables_row_counts] opening {}", db_fs_path))?;
+
+    let mut stmt = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+        .with_context(|| format!("[sqlite_db_tables_row_counts] reading schema of {}", db_fs_path))?;
+    let table_names = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<RusqliteResult<Vec<_>>>()?;
+
+    let mut tables = Vec::with_capacity(table_names.len());
+    for table_name in table_names {
+        let row_count: i64 = conn
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM \"{}\"",
+                    table_name.replace('"', "\"\"")
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .with_context(|| {
+                format!(
+                    "[sqlite_db_tables_row_counts] counting rows of {} in {}",
+                    table_name, db_fs_path
+                )
+            })?;
+        tables.push((table_name, row_count));
+    }
+    Ok(tables)
+}
+
+/// A zstd dictionary trained by `admin train-dict`; content compressed with it
+/// records `zstd:{uniform_resource_compression_dict_id}` as its codec so it can
+/// be decompressed even after newer dictionaries are trained.
+pub struct CompressionDict {
+    pub id: String,
+    pub dictionary: Vec<u8>,
+}
+
+/// The most recently trained zstd dictionary, if any.
+pub fn latest_compression_dict(conn: &Connection) -> Result<Option<CompressionDict>> {
+    conn.query_row(
+        "SELECT uniform_resource_compression_dict_id, dictionary
+           FROM uniform_resource_compression_dict
+          WHERE algorithm = 'zstd'
+       ORDER BY created_at DESC, uniform_resource_compression_dict_id DESC
+          LIMIT 1",
+        [],
+        |row| {
+            Ok(CompressionDict {
+                id: row.get(0)?,
+                dictionary: row.get(1)?,
+            })
+        },
+    )
+    .optional()
+    .context("[latest_compression_dict] uniform_resource_compression_dict")
+}
+
+/// Compresses content before it's stored (with `dict` when there is one) and
+/// returns it along with the codec, which goes into `uniform_resource.content_codec`.
+pub fn compressed_content(
+    codec: crate::cmd::ContentCodec,
+    dict: Option<&CompressionDict>,
+    content: &[u8],
+) -> Result<(Vec<u8>, String)> {
+    match (codec, dict) {
+        (crate::cmd::ContentCodec::Zstd, Some(dict)) => Ok((
+            zstd::bulk::Compressor::with_dictionary(
+                zstd::DEFAULT_COMPRESSION_LEVEL,
+                &dict.dictionary,
+            )
+            .and_then(|mut compressor| compressor.compress(content))
+            .with_context(|| format!("[compressed_content] zstd with dictionary {}", dict.id))?,
+            format!("zstd:{}", dict.id),
+        )),
+        (crate::cmd::ContentCodec::Zstd, None) => Ok((
+            zstd::bulk::compress(content, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .context("[compressed_content] zstd")?,
+            String::from("zstd"),
+        )),
+    }
+}
+
+/// Reverses [`compressed_content`] given the stored `content_codec`, content
+/// without a codec was stored as-is.
+pub fn decompressed_content(
+    conn: &Connection,
+    content: &[u8],
+    content_codec: Option<&str>,
+) -> Result<Vec<u8>> {
+    match content_codec {
+        None => Ok(content.to_vec()),
+        Some("zstd") => zstd::stream::decode_all(content).context("[decompressed_content] zstd"),
+        Some(codec) => match codec.strip_prefix("zstd:") {
+            Some(dict_id) => {
+                let dictionary: Vec<u8> = conn
+                    .query_row(
+                        "SELECT dictionary FROM uniform_resource_compression_dict WHERE uniform_resource_compression_dict_id = ?",
+                        [dict_id],
+                        |row| row.get(0),
+                    )
+                    .with_context(|| format!("[decompressed_content] zstd dictionary {}", dict_id))?;
+                let mut decompressed = Vec::new();
+                zstd::stream::read::Decoder::with_dictionary(content, &dictionary)
+                    .and_then(|mut decoder| {
+                        std::io::Read::read_to_end(&mut decoder, &mut decompressed)
+                    })
+                    .with_context(|| {
+                        format!("[decompressed_content] zstd with dictionary {}", dict_id)
+                    })?;
+                Ok(decompressed)
+            }
+            None => anyhow::bail!("[decompressed_content] unknown content codec {}", codec),
+        },
+    }
+}
+
+/// `uniform_resource.content_codec` of content stored as a JSON array of
+/// `content_chunk` ids (see `--chunk-content`).
+pub const CHUNKED_CONTENT_CODEC: &str = "fastcdc";
+
+// FastCDC minimum, average and maximum chunk sizes
+const CONTENT_CHUNK_MIN_BYTES: u32 = 16 * 1024;
+const CONTENT_CHUNK_AVG_BYTES: u32 = 64 * 1024;
+const CONTENT_CHUNK_MAX_BYTES: u32 = 256 * 1024;
+
+/// Splits content into content-defined (FastCDC) chunks so that regions which
+/// don't change across versions of the content produce the same chunks.
+pub fn content_chunks(content: &[u8]) -> Vec<&[u8]> {
+    fastcdc::v2020::FastCDC::new(
+        content,
+        CONTENT_CHUNK_MIN_BYTES,
+        CONTENT_CHUNK_AVG_BYTES,
+        CONTENT_CHUNK_MAX_BYTES,
+    )
+    .map(|chunk| &content[chunk.offset..chunk.offset + chunk.length])
+    .collect()
+}
+
+/// Reassembles content stored with [`CHUNKED_CONTENT_CODEC`] from its ordered
+/// list of `content_chunk` ids, decompressing each chunk as needed.
+pub fn reassembled_content(conn: &Connection, chunk_ids_json: &[u8]) -> Result<Vec<u8>> {
+    let chunk_ids: Vec<String> = serde_json::from_slice(chunk_ids_json)
+        .context("[reassembled_content] content is not a JSON array of chunk ids")?;
+    let mut stmt = conn
+        .prepare("SELECT content, content_codec FROM content_chunk WHERE content_chunk_id = ?")
+        .context("[reassembled_content] preparing content_chunk query")?;
+    let mut content = Vec::new();
+    for chunk_id in chunk_ids {
+        let (chunk, chunk_codec): (Vec<u8>, Option<String>) = stmt
+            .query_row([&chunk_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .with_context(|| format!("[reassembled_content] reading chunk {}", chunk_id))?;
+        content.extend(
+            decompressed_content(conn, &chunk, chunk_codec.as_deref()).with_context(|| {
+                format!("[reassembled_content] decompressing chunk {}", chunk_id)
+            })?,
+        );
+    }
+    Ok(content)
+}
+
+/// Reads (and decompresses or reassembles) the stored content of a uniform
+/// resource, including content moved by `admin merge --dedupe-content`, `None`
+/// if the resource doesn't exist or its content wasn't stored.
+pub fn uniform_resource_content(
+    conn: &Connection,
+    uniform_resource_id: &str,
+) -> Result<Option<Vec<u8>>> {
+    let stored = conn
+        .query_row(
+            "SELECT content, content_codec FROM uniform_resource_deduped WHERE uniform_resource_id = ?",
+            [uniform_resource_id],
+            |row| {
+                Ok(match row.get_ref(0)? {
+                    ValueRef::Null => None,
+                    content => Some((
+                        content.as_bytes()?.to_vec(),
+                        row.get::<_, Option<String>>(1)?,
+                    )),
+                })
+            },
+        )
+        .optional()
+        .with_context(|| format!("[uniform_resource_content] reading {}", uniform_resource_id))?;
+    match stored.flatten() {
+        Some((content, Some(content_codec))) if content_codec == CHUNKED_CONTENT_CODEC => Ok(Some(
+            reassembled_content(conn, &content).with_context(|| {
+                format!(
+                    "[uniform_resource_content] reassembling {}",
+                    uniform_resource_id
+                )
+            })?,
+        )),
+        Some((content, content_codec)) => Ok(Some(
+            decompressed_content(conn, &content, content_codec.as_deref()).with_context(|| {
+                format!(
+                    "[uniform_resource_content] decompressing {}",
+                    uniform_resource_id
+                )
+            })?,
+        )),
+        None => Ok(None),
+    }
+}
+
 pub fn upserted_device(conn: &Connection, device: &Device) -> RusqliteResult<(String, String)> {
     upsert_device(
         conn,
@@ -727,5 +1238,225 @@ pub fn upserted_device(conn: &Connection, device: &Device) -> RusqliteResult<(St
         },
         &device.state_json(),
         &device.state_sysinfo_json(),
+        &device.elaboration_json(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    fn initialized(db_fs_path: &str, auto_migrate: bool) -> Result<DbConn> {
+        let pragmas = DbPragmas {
+            auto_migrate,
+            ..Default::default()
+        };
+        let dbc = DbConn::new(db_fs_path, 0, &pragmas)?;
+        dbc.init(None)?.commit()?;
+        Ok(dbc)
+    }
+
+    #[test]
+    fn test_schema_mismatch_fresh_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_fs_path = dir.path().join("fresh.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+
+        let dbc = initialized(&db_fs_path, false).unwrap();
+        assert_eq!(user_version(&dbc.conn), schema_version());
+        assert!(schema_mismatch(&dbc.conn, &db_fs_path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_schema_mismatch_pending_and_auto_migrate() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_fs_path = dir.path().join("outdated.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+
+        // pretend the RSSD predates `v014_once_natureBindDDL` (which is idempotent
+        // so applying it again works)
+        let dbc = initialized(&db_fs_path, false).unwrap();
+        dbc.conn
+            .execute_batch(
+                "DELETE FROM code_notebook_state
+                  WHERE code_notebook_cell_id IN (SELECT code_notebook_cell_id
+                                                    FROM code_notebook_cell
+                                                   WHERE cell_name = 'v014_once_natureBindDDL');
+                 PRAGMA user_version = 13;",
+            )
+            .unwrap();
+        let mismatch = schema_mismatch(&dbc.conn, &db_fs_path).unwrap().unwrap();
+        assert_eq!(mismatch.db_version, 13);
+        assert_eq!(mismatch.expected_version, schema_version());
+        assert_eq!(
+            mismatch.pending,
+            vec!["v014_once_natureBindDDL".to_string()]
+        );
+        drop(dbc);
+
+        let err = initialized(&db_fs_path, false).unwrap_err();
+        assert!(err.downcast_ref::<SchemaMismatch>().is_some());
+
+        let dbc = initialized(&db_fs_path, true).unwrap();
+        assert_eq!(user_version(&dbc.conn), schema_version());
+        assert!(schema_mismatch(&dbc.conn, &db_fs_path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_last_modified_at_rfc3339_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_fs_path = dir.path().join("timestamps.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+
+        let dbc = initialized(&db_fs_path, false).unwrap();
+        let migration: String = dbc
+            .conn
+            .query_row(
+                "SELECT interpretable_code FROM code_notebook_cell WHERE cell_name = 'v016_once_urLastModifiedAtRfc3339'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        dbc.conn
+            .execute_batch(
+                "INSERT INTO device (device_id, name, state, boundary) VALUES ('d', 'd', '{}', 'b');
+                 INSERT INTO uniform_resource (uniform_resource_id, device_id, ingest_session_id, uri, content_digest, nature, last_modified_at)
+                 VALUES ('1', 'd', 's', 'a.md', 'x', 'md', '2024-05-01 08:30:00.123456789 UTC'),
+                        ('2', 'd', 's', 'b.md', 'x', 'md', '2024-05-01 08:30:00 UTC'),
+                        ('3', 'd', 's', 'c.md', 'x', 'md', '2024-05-01T08:30:00.500000000Z');",
+            )
+            .unwrap();
+        dbc.conn.execute_batch(&migration).unwrap();
+
+        let mut stmt = dbc
+            .conn
+            .prepare("SELECT last_modified_at FROM uniform_resource ORDER BY uri")
+            .unwrap();
+        let migrated: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(
+            migrated,
+            vec![
+                "2024-05-01T08:30:00.123456789Z",
+                "2024-05-01T08:30:00.000000000Z",
+                "2024-05-01T08:30:00.500000000Z",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compressed_content_with_latest_dict() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_fs_path = dir.path().join("dict.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+        let dbc = initialized(&db_fs_path, false).unwrap();
+        let content = b"# Heading\n\nfrontmatter and markdown body text";
+
+        assert!(latest_compression_dict(&dbc.conn).unwrap().is_none());
+        let (compressed, codec) =
+            compressed_content(crate::cmd::ContentCodec::Zstd, None, content).unwrap();
+        assert_eq!(codec, "zstd");
+        assert_eq!(
+            decompressed_content(&dbc.conn, &compressed, Some(&codec)).unwrap(),
+            content
+        );
+
+        // zstd accepts any bytes as a raw content dictionary
+        dbc.conn
+            .execute_batch(
+                "INSERT INTO uniform_resource_compression_dict (uniform_resource_compression_dict_id, algorithm, dictionary, dictionary_digest, samples_count, samples_size_bytes, created_at)
+                 VALUES ('01OLD', 'zstd', X'00', 'old', 1, 1, '2024-01-01 00:00:00'),
+                        ('01NEW', 'zstd', CAST('frontmatter and markdown body' AS BLOB), 'new', 1, 29, '2024-02-01 00:00:00');",
+            )
+            .unwrap();
+        let dict = latest_compression_dict(&dbc.conn).unwrap().unwrap();
+        assert_eq!(dict.id, "01NEW");
+        let (compressed, codec) =
+            compressed_content(crate::cmd::ContentCodec::Zstd, Some(&dict), content).unwrap();
+        assert_eq!(codec, "zstd:01NEW");
+        assert_eq!(
+            decompressed_content(&dbc.conn, &compressed, Some(&codec)).unwrap(),
+            content
+        );
+        assert!(decompressed_content(&dbc.conn, &compressed, Some("zstd:missing")).is_err());
+    }
+
+    #[test]
+    fn test_uniform_resource_content_deduped() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_fs_path = dir.path().join("deduped.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+        let dbc = initialized(&db_fs_path, false).unwrap();
+
+        // as `admin merge --dedupe-content` leaves a compressed resource's content
+        let (compressed, codec) =
+            compressed_content(crate::cmd::ContentCodec::Zstd, None, b"shared").unwrap();
+        dbc.conn
+            .execute_batch(
+                "INSERT INTO device (device_id, name, state, boundary) VALUES ('d', 'd', '{}', 'b');
+                 INSERT INTO uniform_resource (uniform_resource_id, device_id, ingest_session_id, uri, content_digest, nature)
+                 VALUES ('1', 'd', 's', 'a.md', 'x', 'md');
+                 INSERT INTO uniform_resource_content_device (content_digest, device_id, uniform_resource_id)
+                 VALUES ('x', 'd', '1');",
+            )
+            .unwrap();
+        dbc.conn
+            .execute(
+                "INSERT INTO uniform_resource_content (content_digest, content, content_codec, size_bytes) VALUES ('x', ?, ?, 6)",
+                rusqlite::params![compressed, codec],
+            )
+            .unwrap();
+        assert_eq!(
+            uniform_resource_content(&dbc.conn, "1").unwrap(),
+            Some(b"shared".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_merge_dedupe_content_keeps_different_stored_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_fs_path = dir.path().join("dedupe.sqlite.db");
+        let db_fs_path = db_fs_path.to_string_lossy();
+        let dbc = initialized(&db_fs_path, false).unwrap();
+
+        // '2' has the same digest but stores other bytes (as `--content-text-trim`
+        // leaves them) and '4' stored no content at all
+        dbc.conn
+            .execute_batch(
+                "INSERT INTO device (device_id, name, state, boundary) VALUES ('d', 'd', '{}', 'b');
+                 INSERT INTO uniform_resource (uniform_resource_id, device_id, ingest_session_id, uri, content_digest, content, nature)
+                 VALUES ('1', 'd', 's', 'a.txt', 'x', CAST('raw' AS BLOB), 'txt'),
+                        ('2', 'd', 's', 'b.txt', 'x', CAST('trimmed' AS BLOB), 'txt'),
+                        ('3', 'd', 's', 'c.txt', 'x', CAST('raw' AS BLOB), 'txt'),
+                        ('4', 'd', 's', 'd.txt', 'x', NULL, 'txt');",
+            )
+            .unwrap();
+        dbc.conn
+            .execute_batch(crate::cmd::admin::MERGE_DEDUPE_CONTENT_SQL)
+            .unwrap();
+
+        let resolved = |id: &str| uniform_resource_content(&dbc.conn, id).unwrap();
+        assert_eq!(resolved("1"), Some(b"raw".to_vec()));
+        assert_eq!(resolved("2"), Some(b"trimmed".to_vec()));
+        assert_eq!(resolved("3"), Some(b"raw".to_vec()));
+        assert_eq!(resolved("4"), None);
+        let kept: Vec<String> = dbc
+            .conn
+            .prepare("SELECT uniform_resource_id FROM uniform_resource WHERE content IS NOT NULL")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(kept, vec!["2".to_string()]);
+    }
+}
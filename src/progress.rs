@@ -0,0 +1,50 @@
+/// Receives events while resources are being ingested so that callers decide
+/// how (or whether) to report them, e.g. log lines or a progress bar. Every
+/// method has a no-op default so observers only implement what they need.
+pub trait ProgressObserver {
+    fn on_behavior_saved(&self, _behavior_name: &str, _behavior_id: &str) {}
+    fn on_behavior(&self, _behavior_id: Option<&str>) {}
+    fn on_session_started(&self, _ingest_session_id: &str) {}
+    fn on_root_path(&self, _root_path: &str, _ingest_fs_path_id: &str) {}
+    fn on_resource_encountered(&self, _uri: &str) {}
+    fn on_walk_error(&self, _uri: &str, _reason: &str) {}
+    fn on_content_hashed(&self, _uri: &str, _size: Option<u64>) {}
+    fn on_db_upsert(&self, _uri: &str, _ur_status: Option<&str>) {}
+    fn on_error(&self, _message: &str) {}
+}
+
+/// Ignores all events.
+#[cfg_attr(not(test), allow(dead_code))] // only the tests ingest without reporting
+pub struct NoopProgress;
+
+impl ProgressObserver for NoopProgress {}
+
+/// Logs session details at `info` through `tracing` so that they follow
+/// `--log-level`, `--debug` and `--quiet`; errors are always written to STDERR.
+pub struct StderrProgress;
+
+impl ProgressObserver for StderrProgress {
+    fn on_behavior_saved(&self, behavior_name: &str, behavior_id: &str) {
+        tracing::info!("Saved behavior: {} ({})", behavior_name, behavior_id);
+    }
+
+    fn on_behavior(&self, behavior_id: Option<&str>) {
+        tracing::info!("Behavior: {}", behavior_id.unwrap_or("custom"));
+    }
+
+    fn on_session_started(&self, ingest_session_id: &str) {
+        tracing::info!("Walk Session: {ingest_session_id}");
+    }
+
+    fn on_root_path(&self, root_path: &str, ingest_fs_path_id: &str) {
+        tracing::info!("  Walk Session Path: {root_path} ({ingest_fs_path_id})");
+    }
+
+    fn on_walk_error(&self, uri: &str, reason: &str) {
+        tracing::info!("  Unable to walk {uri}: {reason}");
+    }
+
+    fn on_error(&self, message: &str) {
+        eprintln!("{message}");
+    }
+}
@@ -1,11 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fs;
 use std::fs::canonicalize;
 use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use anyhow::Context;
 use bitflags::bitflags;
 use chrono::{DateTime, Utc};
 use is_executable::IsExecutable;
@@ -17,6 +21,7 @@ use serde_json::Value as JsonValue;
 use sha1::{Digest, Sha1};
 
 use crate::frontmatter::frontmatter;
+use crate::git::{GitCommitMetadata, GitCommits};
 use crate::shell::*;
 
 // See src/resources.states.puml for PlantUML specification of the state machine
@@ -37,10 +42,20 @@ pub trait TextContent {
     fn content_digest_hash(&self) -> &str;
     fn content_text(&self) -> &str;
     fn frontmatter(&self) -> FrontmatterComponents;
+
+    // set when the text isn't exactly the acquired content (e.g. it was lossily decoded)
+    fn content_text_encoding(&self) -> Option<&str> {
+        None
+    }
 }
 
-pub type BinaryContentSupplier = Box<dyn Fn() -> Result<Box<dyn BinaryContent>, Box<dyn Error>>>;
-pub type TextContentSupplier = Box<dyn Fn() -> Result<Box<dyn TextContent>, Box<dyn Error>>>;
+pub type BinaryContentSupplier =
+    Box<dyn Fn() -> Result<Box<dyn BinaryContent>, Box<dyn Error>> + Send + Sync>;
+pub type TextContentSupplier =
+    Box<dyn Fn() -> Result<Box<dyn TextContent>, Box<dyn Error>> + Send + Sync>;
+pub type DigestContentSupplier = Box<dyn Fn() -> Result<String, Box<dyn Error>> + Send + Sync>;
+
+const DIGEST_CHUNK_BYTES: usize = 64 * 1024;
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -117,8 +132,10 @@ const PFRE_READ_NATURE_FROM_REGEX: &str = "?P<nature>";
 const PFRE_READ_NATURE_FROM_REGEX_CAPTURE: &str = "nature";
 
 const DEFAULT_IGNORE_PATHS_REGEX_PATTERNS: [&str; 1] = [r"/(\.git|node_modules)/"];
-const DEFAULT_ACQUIRE_CONTENT_EXTNS_REGEX_PATTERNS: [&str; 1] =
-    [r"\.(?P<nature>md|mdx|html|json|jsonc|puml|txt|toml|yml)$"];
+const DEFAULT_ACQUIRE_CONTENT_EXTNS_REGEX_PATTERNS: [&str; 2] = [
+    r"\.(?P<nature>md|mdx|html|json|jsonc|puml|txt|toml|yml)$",
+    r"\.(?P<nature>eml|msg)$",
+];
 const DEFAULT_CAPTURE_EXEC_REGEX_PATTERNS: [&str; 1] = [r"surveilr\[(?P<nature>[^\]]*)\]"];
 const DEFAULT_CAPTURE_SQL_EXEC_REGEX_PATTERNS: [&str; 1] = [r"surveilr-SQL"];
 
@@ -137,6 +154,119 @@ const DEFAULT_REWRITE_NATURE_PATTERNS: [(&str, &str); 3] = [
 // parent, it allows `surveilr` to ignore globs specified within it
 const SMART_IGNORE_CONF_FILES: [&str; 1] = [".surveilr_ignore"];
 
+// gitignore-style globs from conf files (e.g. `.surveilr_ignore`) in each
+// directory, for walkers which don't read them on their own; like `ignore`,
+// the closest conf file with a matching glob decides and `!glob` re-includes
+struct IgnoreConfFiles {
+    conf_files: Vec<String>,
+    by_dir: HashMap<PathBuf, Vec<ignore::gitignore::Gitignore>>,
+}
+
+impl IgnoreConfFiles {
+    fn new(conf_files: &[String]) -> IgnoreConfFiles {
+        IgnoreConfFiles {
+            conf_files: conf_files.to_vec(),
+            by_dir: HashMap::new(),
+        }
+    }
+
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        if self.conf_files.is_empty() {
+            return false;
+        }
+        for dir in path.ancestors().skip(1) {
+            let conf_files = &self.conf_files;
+            let globs = self.by_dir.entry(dir.to_path_buf()).or_insert_with(|| {
+                conf_files
+                    .iter()
+                    .map(|cf| dir.join(cf))
+                    .filter(|cf_path| cf_path.is_file())
+                    .map(|cf_path| ignore::gitignore::Gitignore::new(cf_path).0)
+                    .collect()
+            });
+            for gi in globs.iter() {
+                match gi.matched(path, is_dir) {
+                    ignore::Match::Ignore(_) => return true,
+                    ignore::Match::Whitelist(_) => return false,
+                    ignore::Match::None => {}
+                }
+            }
+        }
+        false
+    }
+}
+
+// the target of `fs_path` as read from the link (so possibly relative or
+// dangling) or None when it's not a symbolic link
+fn symlink_target(fs_path: &Path) -> Option<String> {
+    fs::read_link(fs_path)
+        .ok()
+        .map(|target| target.to_string_lossy().to_string())
+}
+
+// the path a symbolic link finally resolves to, None for other paths
+fn resolved_symlink(uri: &str) -> Option<String> {
+    let fs_path = Path::new(uri);
+    if !fs::symlink_metadata(fs_path).ok()?.is_symlink() {
+        return None;
+    }
+    fs::canonicalize(fs_path)
+        .ok()
+        .map(|resolved| resolved.to_string_lossy().to_string())
+}
+
+/// A Windows path as classifier regexes expect paths (like `--ignore-fs-entry`
+/// patterns, which assume `/` separators): without the verbatim (long path)
+/// prefix `canonicalize` adds, so `\\?\C:\src` is `C:/src` and
+/// `\\?\UNC\server\share` is `//server/share`, and with `/` separators.
+fn windows_path_with_slashes(path: &str) -> String {
+    let path = match path.strip_prefix(r"\\?\UNC\") {
+        Some(unc) => format!(r"\\{unc}"),
+        None => path.strip_prefix(r"\\?\").unwrap_or(path).to_string(),
+    };
+    path.replace('\\', "/")
+}
+
+/// Timestamps are persisted as RFC 3339 in UTC with nanoseconds (e.g.
+/// `2024-05-01T08:30:00.123456789Z`), always as wide so they sort as text.
+pub fn persistable_timestamp(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)
+}
+
+// `\` is a valid file name character elsewhere so only Windows paths are changed
+fn classifiable_path(path: &str) -> std::borrow::Cow<'_, str> {
+    if cfg!(windows) {
+        std::borrow::Cow::Owned(windows_path_with_slashes(path))
+    } else {
+        std::borrow::Cow::Borrowed(path)
+    }
+}
+
+// the `order` named capture of the capturable executable's rule (e.g.
+// `surveilr\[(?P<nature>[^,\]]*),order=(?P<order>\d+)\]`) or else the number its
+// file name starts with (e.g. `010-schema.surveilr-SQL.sh`)
+fn capture_order_key(uri: &str, captures: &HashMap<String, String>) -> Option<u64> {
+    if let Some(order) = captures.get("order") {
+        return order.trim().parse().ok();
+    }
+    let file_name = Path::new(uri).file_name()?.to_str()?;
+    let digits: String = file_name.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+// `ignore` wraps the path of an errored entry in the error itself
+fn ignore_error_path(err: &ignore::Error) -> Option<String> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path.to_string_lossy().to_string()),
+        ignore::Error::Loop { child, .. } => Some(child.to_string_lossy().to_string()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            ignore_error_path(err)
+        }
+        ignore::Error::Partial(errs) => errs.iter().find_map(ignore_error_path),
+        _ => None,
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct PersistableFlaggableRegEx {
     pub regex: String,          // untyped to make it easier to serialize/deserialize
@@ -144,11 +274,27 @@ pub struct PersistableFlaggableRegEx {
     pub nature: Option<String>, // if this is ?P<nature> then we read nature from reg-ex otherwise it's forced
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathRuleAction {
+    Include,
+    Exclude,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PersistablePathRule {
+    pub action: PathRuleAction,
+    pub regex: String, // untyped to make it easier to serialize/deserialize
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct EncounterableResourcePathRules {
     pub flaggables: Vec<PersistableFlaggableRegEx>,
     pub rewrite_nature_regexs: Vec<ResourcePathRewriteRule>,
     pub smart_ignore_conf_files: Vec<String>,
+    // include/exclude rules evaluated top-to-bottom, last match wins (like
+    // gitignore negation) before any of the flaggables are consulted
+    #[serde(default)]
+    pub path_rules: Vec<PersistablePathRule>,
 }
 
 query_sql_rows_no_args!(
@@ -216,6 +362,7 @@ impl Default for EncounterableResourcePathRules {
                 })
                 .to_vec(),
             smart_ignore_conf_files: SMART_IGNORE_CONF_FILES.map(|s| s.to_string()).to_vec(),
+            path_rules: vec![],
         }
     }
 }
@@ -245,22 +392,270 @@ impl EncounterableResourcePathRules {
             flaggables,
             rewrite_nature_regexs,
             smart_ignore_conf_files: SMART_IGNORE_CONF_FILES.map(|s| s.to_string()).to_vec(),
+            path_rules: vec![],
         })
     }
 
-    pub fn _from_json_text(json_text: &str) -> Result<Self, serde_json::Error> {
+    pub fn from_json_text(json_text: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(json_text)
     }
 
-    pub fn _persistable_json_text(&self) -> Result<String, serde_json::Error> {
+    pub fn persistable_json_text(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
     }
 }
 
+/// Which walker finds the files under `ingest files` root paths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Walker {
+    /// honors `.gitignore`, `.ignore` and `.surveilr_ignore` files (best for repositories)
+    #[default]
+    Smart,
+    /// every file except those excluded by `.surveilr_ignore` files
+    Walkdir,
+    /// every file through the virtual file system, ignoring `--follow-symlinks` and `--max-depth`
+    Vfs,
+}
+
+/// The algorithm used to compute content digests; anything other than SHA-1
+/// is prefixed with its name (e.g. `sha256:abcd...`) so that rows written by
+/// earlier versions (always unprefixed SHA-1) remain distinguishable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Blake3,
+}
+
+impl DigestAlgorithm {
+    /// The algorithm `digest` was computed with (judged by its prefix).
+    pub fn from_digest(digest: &str) -> DigestAlgorithm {
+        [DigestAlgorithm::Sha256, DigestAlgorithm::Blake3]
+            .into_iter()
+            .find(|algorithm| algorithm.is_digest_of(digest))
+            .unwrap_or(DigestAlgorithm::Sha1)
+    }
+
+    /// Whether `digest` was computed with this algorithm (judged by its prefix).
+    pub fn is_digest_of(&self, digest: &str) -> bool {
+        match self {
+            DigestAlgorithm::Sha1 => !digest.contains(':'),
+            DigestAlgorithm::Sha256 => digest.starts_with("sha256:"),
+            DigestAlgorithm::Blake3 => digest.starts_with("blake3:"),
+        }
+    }
+
+    /// Same as `digest` but reads `reader` in fixed-size chunks so that large
+    /// content is never held in memory all at once.
+    pub fn digest_reader(&self, mut reader: impl Read) -> std::io::Result<String> {
+        let mut chunk = vec![0u8; DIGEST_CHUNK_BYTES];
+        let mut read_chunks = |update: &mut dyn FnMut(&[u8])| -> std::io::Result<()> {
+            loop {
+                match reader.read(&mut chunk) {
+                    Ok(0) => return Ok(()),
+                    Ok(n) => update(&chunk[..n]),
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        };
+        Ok(match self {
+            DigestAlgorithm::Sha1 => {
+                let mut hasher = Sha1::new();
+                read_chunks(&mut |bytes| hasher.update(bytes))?;
+                format!("{:x}", hasher.finalize())
+            }
+            DigestAlgorithm::Sha256 => {
+                let mut hasher = sha2::Sha256::new();
+                read_chunks(&mut |bytes| hasher.update(bytes))?;
+                format!("sha256:{:x}", hasher.finalize())
+            }
+            DigestAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                read_chunks(&mut |bytes| {
+                    hasher.update(bytes);
+                })?;
+                format!("blake3:{}", hasher.finalize().to_hex())
+            }
+        })
+    }
+
+    pub fn digest(&self, content: impl AsRef<[u8]>) -> String {
+        // reading from a slice can't fail
+        self.digest_reader(content.as_ref())
+            .expect("[DigestAlgorithm::digest] reading in-memory content")
+    }
+}
+
+/// What text content suppliers do with content which isn't valid UTF-8.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TextEncodingFallback {
+    /// replace invalid sequences with U+FFFD and record `utf-8-lossy` as the resource's encoding
+    Lossy,
+    /// don't treat the content as text, store it as binary instead
+    Skip,
+    /// fail acquiring the content (the resource is recorded with an error)
+    #[default]
+    Error,
+}
+
+/// Which content suppliers are created for acquirable resources; with only one
+/// of them the content is never read (or decoded) the other way and with
+/// `ManifestOnly` it's not read (nor hashed) at all.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContentAcquisition {
+    #[default]
+    TextAndBinary,
+    TextOnly,
+    BinaryOnly,
+    ManifestOnly,
+}
+
+impl ContentAcquisition {
+    pub fn from_args(
+        text_only: bool,
+        binary_only: bool,
+        manifest_only: bool,
+    ) -> ContentAcquisition {
+        match (text_only, binary_only, manifest_only) {
+            (_, _, true) => ContentAcquisition::ManifestOnly,
+            (true, _, _) => ContentAcquisition::TextOnly,
+            (_, true, _) => ContentAcquisition::BinaryOnly,
+            _ => ContentAcquisition::TextAndBinary,
+        }
+    }
+
+    pub fn text(&self) -> bool {
+        matches!(
+            self,
+            ContentAcquisition::TextAndBinary | ContentAcquisition::TextOnly
+        )
+    }
+
+    pub fn binary(&self) -> bool {
+        matches!(
+            self,
+            ContentAcquisition::TextAndBinary | ContentAcquisition::BinaryOnly
+        )
+    }
+
+    pub fn digest(&self) -> bool {
+        *self != ContentAcquisition::ManifestOnly
+    }
+}
+
+/// Returned by text content suppliers for non-UTF-8 content when the fallback
+/// is [`TextEncodingFallback::Skip`] so writers can store it as binary instead.
+#[derive(Debug)]
+pub struct TextEncodingSkipped(pub std::string::FromUtf8Error);
+
+impl std::fmt::Display for TextEncodingSkipped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "content is not valid UTF-8 text: {}", self.0)
+    }
+}
+
+impl Error for TextEncodingSkipped {}
+
+impl TextEncodingFallback {
+    /// Decodes acquired content as UTF-8 text; the digest is always of the
+    /// raw content so it matches the binary and digest suppliers.
+    pub fn text_content(
+        &self,
+        content: Vec<u8>,
+        digest_algorithm: DigestAlgorithm,
+    ) -> Result<Box<dyn TextContent>, Box<dyn Error>> {
+        match String::from_utf8(content) {
+            Ok(text) => {
+                let hash = digest_algorithm.digest(&text);
+                Ok(Box::new(ResourceTextContent { hash, text }))
+            }
+            Err(err) => match self {
+                TextEncodingFallback::Lossy => {
+                    let hash = digest_algorithm.digest(err.as_bytes());
+                    let text = String::from_utf8_lossy(err.as_bytes()).into_owned();
+                    Ok(Box::new(LossyTextContent { hash, text }))
+                }
+                TextEncodingFallback::Skip => Err(Box::new(TextEncodingSkipped(err))),
+                TextEncodingFallback::Error => Err(Box::new(err)),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EncounterableResourceClass {
     pub flags: EncounterableResourceFlags,
     pub nature: Option<String>,
+    pub digest_algorithm: DigestAlgorithm,
+    pub sniff_content_nature: bool,
+    pub sniff_sqlite: bool,
+    pub max_content_bytes: Option<u64>,
+    pub text_encoding_fallback: TextEncodingFallback,
+    pub content_acquisition: ContentAcquisition,
+    pub capture_exec_timeout: Duration,
+    pub capture_exec_env: ShellEnvPolicy,
+    pub capture_exec_retry: RetryPolicy,
+    pub capture_nature_from_directive: bool,
+    pub nature_rewrite: Option<NatureRewrite>,
+    // named capture groups other than `nature` in the matching rule's regex
+    pub captures: HashMap<String, String>,
+    // only set when profiling so content isn't timed otherwise
+    pub hash_timing: Option<Arc<HashTiming>>,
+}
+
+/// Time spent reading and hashing content, summed across threads, and how many
+/// times it was done; see `ingest files --profile`.
+#[derive(Debug, Default)]
+pub struct HashTiming {
+    nanos: AtomicU64,
+    count: AtomicUsize,
+}
+
+impl HashTiming {
+    fn timed(&self) -> HashTimed<'_> {
+        HashTimed {
+            timing: self,
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+// records the time until it's dropped
+struct HashTimed<'a> {
+    timing: &'a HashTiming,
+    started_at: Instant,
+}
+
+impl Drop for HashTimed<'_> {
+    fn drop(&mut self) {
+        let nanos = self.started_at.elapsed().as_nanos() as u64;
+        self.timing.nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.timing.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A `rewrite_nature_regexs` rule which changed the nature a resource would
+/// have had, e.g. `.plantuml` classified as `puml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NatureRewrite {
+    pub original_nature: Option<String>,
+    pub rewritten_nature: Option<String>,
+    pub regex: String,
+    pub replace: String,
 }
 
 pub trait EncounterableResourceUriClassifier {
@@ -284,6 +679,99 @@ impl FlaggableRegEx {
             nature: pfre.nature.clone(),
         })
     }
+
+    /// The named capture groups other than `nature` which matched in `text`,
+    /// e.g. `priority` in `surveilr\[(?P<nature>[^,\]]*),priority=(?P<priority>[^\]]*)\]`.
+    pub fn named_captures(&self, text: &str) -> HashMap<String, String> {
+        let Some(caps) = self.regex.captures(text) else {
+            return HashMap::new();
+        };
+        self.regex
+            .capture_names()
+            .flatten()
+            .filter(|name| *name != PFRE_READ_NATURE_FROM_REGEX_CAPTURE)
+            .filter_map(|name| {
+                caps.name(name)
+                    .map(|value| (name.to_string(), value.as_str().to_string()))
+            })
+            .collect()
+    }
+
+    pub fn to_persistable(&self) -> PersistableFlaggableRegEx {
+        let mut flags = String::new();
+        bitflags::parser::to_writer(&self.flags, &mut flags).unwrap(); // writing to a String can't fail
+        PersistableFlaggableRegEx {
+            regex: self.regex.as_str().to_string(),
+            flags,
+            nature: self.nature.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathRule {
+    pub action: PathRuleAction,
+    #[serde(with = "serde_regex")]
+    pub regex: regex::Regex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathGlobAction {
+    Ignore,
+    Acquire,
+}
+
+/// A gitignore-style glob (`--ignore-glob`, `--acquire-glob`) matched against
+/// a path relative to its walk root: without a `/` (other than a trailing one)
+/// it matches at any depth, a leading `/` anchors it to the root and a trailing
+/// `/` matches everything beneath.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathGlob {
+    pub action: PathGlobAction,
+    #[serde(with = "serde_path_glob")]
+    pub glob: globset::GlobMatcher,
+}
+
+impl PathGlob {
+    pub fn matcher(glob: &str) -> Result<globset::GlobMatcher, globset::Error> {
+        let mut pattern = match glob.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if !glob.trim_end_matches('/').contains('/') => format!("**/{glob}"),
+            None => glob.to_string(),
+        };
+        if pattern.ends_with('/') {
+            pattern.push_str("**");
+        }
+        Self::compiled(&pattern)
+    }
+
+    // `*` and `?` don't match `/`, only `**` crosses directories
+    fn compiled(pattern: &str) -> Result<globset::GlobMatcher, globset::Error> {
+        Ok(globset::GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()?
+            .compile_matcher())
+    }
+}
+
+// persisted as the anchored pattern (e.g. `*.log` as `**/*.log`) so that it's
+// compiled as is when the behavior is loaded
+mod serde_path_glob {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        matcher: &globset::GlobMatcher,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(matcher.glob().glob())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<globset::GlobMatcher, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        super::PathGlob::compiled(&pattern).map_err(serde::de::Error::custom)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -291,6 +779,58 @@ pub struct EncounterableResourcePathClassifier {
     pub flaggables: Vec<FlaggableRegEx>,
     pub rewrite_path_regexs: Vec<ResourcePathRewriteRule>, // we need to capture `nature` so we loop through each one
     pub smart_ignore_conf_files: Vec<String>,
+    #[serde(default)]
+    pub digest_algorithm: DigestAlgorithm,
+    #[serde(default)]
+    pub sniff_content_nature: bool,
+    // only the 16 bytes SQLite header is checked so this is cheap enough to do
+    // for every file (unlike sniffing all natures)
+    #[serde(default)]
+    pub sniff_sqlite: bool,
+    // larger resources are only digested (streamed), their content isn't loaded
+    #[serde(default)]
+    pub max_content_bytes: Option<u64>,
+    #[serde(default)]
+    pub text_encoding_fallback: TextEncodingFallback,
+    #[serde(default)]
+    pub content_acquisition: ContentAcquisition,
+    // capturable executables' own content is acquired (and stored) when it's
+    // piped to them, otherwise only their output is
+    #[serde(default)]
+    pub acquire_capturable_content: bool,
+    #[serde(default = "default_capture_exec_timeout_secs")]
+    pub capture_exec_timeout_secs: u64,
+    // not persisted (or passed to executables as behavior) since injected
+    // values are often secrets such as API tokens
+    #[serde(skip)]
+    pub capture_exec_env: ShellEnvPolicy,
+    // transient capturable executable (and uniform resource upsert) failures
+    // are retried this many times with exponential backoff
+    #[serde(default)]
+    pub max_retries: u32,
+    #[serde(default)]
+    pub path_rules: Vec<PathRule>,
+    // `--ignore-glob`s and `--acquire-glob`s, applied by `ResourcesCollection`
+    // since they're relative to the walk root
+    #[serde(default)]
+    pub path_globs: Vec<PathGlob>,
+    // symbolic links are classified by the path they resolve to rather than
+    // their own name (they're still ignored by their own name)
+    #[serde(default)]
+    pub resolve_symlink_nature: bool,
+    // a `surveilr-nature:` directive at the top of a capturable executable
+    // overrides the nature from its file name
+    #[serde(default)]
+    pub capture_nature_from_directive: bool,
+    // when every path rule is an exclusion (the common case) a single set match
+    // answers "is this ignored?" without walking the rules; not persisted since
+    // the ordered rules remain authoritative
+    #[serde(skip)]
+    exclude_paths_regex_set: Option<regex::RegexSet>,
+}
+
+fn default_capture_exec_timeout_secs() -> u64 {
+    DEFAULT_EXECUTION_TIMEOUT.as_secs()
 }
 
 impl Default for EncounterableResourcePathClassifier {
@@ -308,11 +848,30 @@ impl EncounterableResourcePathClassifier {
         }
 
         let rewrite_nature_regexs = erpr.rewrite_nature_regexs.to_vec();
-        Ok(EncounterableResourcePathClassifier {
+        let mut classifier = EncounterableResourcePathClassifier {
             flaggables,
             rewrite_path_regexs: rewrite_nature_regexs,
             smart_ignore_conf_files: erpr.smart_ignore_conf_files.to_owned(),
-        })
+            digest_algorithm: DigestAlgorithm::default(),
+            sniff_content_nature: false,
+            sniff_sqlite: false,
+            max_content_bytes: None,
+            text_encoding_fallback: TextEncodingFallback::default(),
+            content_acquisition: ContentAcquisition::default(),
+            acquire_capturable_content: false,
+            capture_exec_timeout_secs: default_capture_exec_timeout_secs(),
+            capture_exec_env: ShellEnvPolicy::default(),
+            max_retries: 0,
+            path_rules: vec![],
+            path_globs: vec![],
+            resolve_symlink_nature: false,
+            capture_nature_from_directive: false,
+            exclude_paths_regex_set: None,
+        };
+        for pr in &erpr.path_rules {
+            classifier.add_path_rule(pr.action, &pr.regex)?;
+        }
+        Ok(classifier)
     }
 
     pub fn default_from_conn(conn: &Connection) -> anyhow::Result<Self> {
@@ -320,6 +879,34 @@ impl EncounterableResourcePathClassifier {
         Self::from_path_rules(rules)
     }
 
+    /// The rules this classifier would be built from by `from_path_rules`,
+    /// including those added afterwards (e.g. by CLI flags).
+    pub fn persistable_path_rules(&self) -> EncounterableResourcePathRules {
+        EncounterableResourcePathRules {
+            flaggables: self
+                .flaggables
+                .iter()
+                .map(FlaggableRegEx::to_persistable)
+                .collect(),
+            rewrite_nature_regexs: self.rewrite_path_regexs.clone(),
+            smart_ignore_conf_files: self.smart_ignore_conf_files.clone(),
+            path_rules: self
+                .path_rules
+                .iter()
+                .map(|pr| PersistablePathRule {
+                    action: pr.action,
+                    regex: pr.regex.as_str().to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Drops the rules which set any of `flags` (e.g. the default ignores) so
+    /// that only those added afterwards apply.
+    pub fn remove_flaggables(&mut self, flags: EncounterableResourceFlags) {
+        self.flaggables.retain(|f| !f.flags.intersects(flags));
+    }
+
     pub fn add_ignore_exact(&mut self, pattern: &str) {
         self.flaggables.push(FlaggableRegEx {
             regex: regex::Regex::new(format!("^{}$", regex::escape(pattern)).as_str()).unwrap(),
@@ -328,38 +915,121 @@ impl EncounterableResourcePathClassifier {
         });
     }
 
-    pub fn as_formatted_tables(&self) -> (comfy_table::Table, comfy_table::Table) {
-        let mut flaggables: comfy_table::Table =
-            crate::format::prepare_table(vec!["Regex", "Flags", "Nature"]);
-        for f in &self.flaggables {
-            flaggables.add_row(vec![
-                f.regex.to_string(),
-                format!("{:?}", f.flags),
-                f.nature.clone().unwrap_or("".to_string()),
-            ]);
+    /// Appends an include or exclude rule; rules are evaluated in the order
+    /// they were added and the last matching rule wins.
+    pub fn add_path_rule(&mut self, action: PathRuleAction, pattern: &str) -> anyhow::Result<()> {
+        self.path_rules.push(PathRule {
+            action,
+            regex: regex::Regex::new(pattern)?,
+        });
+        self.exclude_paths_regex_set = if self
+            .path_rules
+            .iter()
+            .all(|pr| pr.action == PathRuleAction::Exclude)
+        {
+            Some(regex::RegexSet::new(
+                self.path_rules.iter().map(|pr| pr.regex.as_str()),
+            )?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    pub fn add_path_glob(&mut self, action: PathGlobAction, glob: &str) -> anyhow::Result<()> {
+        self.path_globs.push(PathGlob {
+            action,
+            glob: PathGlob::matcher(glob)?,
+        });
+        Ok(())
+    }
+
+    // ignore globs take precedence over acquire globs
+    fn path_glob_action(&self, rel_path: &str) -> Option<PathGlobAction> {
+        let mut action = None;
+        for pg in self
+            .path_globs
+            .iter()
+            .filter(|pg| pg.glob.is_match(rel_path))
+        {
+            if pg.action == PathGlobAction::Ignore {
+                return Some(PathGlobAction::Ignore);
+            }
+            action = Some(pg.action);
         }
+        action
+    }
 
-        let mut rewrite_path_regexs: comfy_table::Table =
-            crate::format::prepare_table(vec!["Rewrite Regex", "Replace With"]);
-        for rprr in &self.rewrite_path_regexs {
-            rewrite_path_regexs.add_row(vec![rprr.regex.to_string(), rprr.replace.to_string()]);
+    fn path_rule_action(&self, text: &str) -> Option<PathRuleAction> {
+        if let Some(exclude_set) = &self.exclude_paths_regex_set {
+            return exclude_set
+                .is_match(text)
+                .then_some(PathRuleAction::Exclude);
         }
+        self.matching_path_rule(text).map(|pr| pr.action)
+    }
 
-        (flaggables, rewrite_path_regexs)
+    // the last matching rule wins
+    fn matching_path_rule(&self, text: &str) -> Option<&PathRule> {
+        self.path_rules
+            .iter()
+            .rev()
+            .find(|pr| pr.regex.is_match(text))
     }
-}
 
-impl EncounterableResourceUriClassifier for EncounterableResourcePathClassifier {
-    fn classify(&self, text: &str, class: &mut EncounterableResourceClass) -> bool {
+    // the built-in capturable executable patterns are matched against the
+    // basename only so that a parent directory like `surveilr[json]` doesn't
+    // flag all its descendants; users' own rules (e.g. `/bin/.*\.sh$`) may
+    // anchor on directories so they always see the whole path
+    fn flaggable_text<'a>(f: &FlaggableRegEx, text: &'a str) -> &'a str {
+        let pattern = f.regex.as_str();
+        if f.flags
+            .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
+            && (DEFAULT_CAPTURE_EXEC_REGEX_PATTERNS.contains(&pattern)
+                || DEFAULT_CAPTURE_SQL_EXEC_REGEX_PATTERNS.contains(&pattern))
+        {
+            text.rsplit(['/', '\\']).next().unwrap_or(text)
+        } else {
+            text
+        }
+    }
+
+    fn classify_flaggables(
+        &self,
+        text: &str,
+        class: &mut EncounterableResourceClass,
+        reincluded: bool,
+    ) -> bool {
         for rnr in &self.rewrite_path_regexs {
             if let Some(rewritten_text) = rnr.rewritten_text(text) {
                 // since we've rewritten the text, now recursively determine class
                 // using the new path/text
-                return self.classify(&rewritten_text, class);
+                let classified = self.classify_flaggables(&rewritten_text, class, reincluded);
+                // without the rewrite the nature would have been the extension
+                let original_nature = Path::new(text)
+                    .extension()
+                    .map(|extension| extension.to_string_lossy().to_string());
+                if original_nature != class.nature {
+                    class.nature_rewrite = Some(NatureRewrite {
+                        original_nature,
+                        rewritten_nature: class.nature.clone(),
+                        regex: rnr.regex.as_str().to_string(),
+                        replace: rnr.replace.clone(),
+                    });
+                }
+                return classified;
             }
         }
 
         for f in &self.flaggables {
+            // an explicitly re-included path is never ignored by a flaggable
+            if reincluded
+                && f.flags
+                    .contains(EncounterableResourceFlags::IGNORE_RESOURCE)
+            {
+                continue;
+            }
+            let text = Self::flaggable_text(f, text);
             if let Some(potential_nature) = &f.nature {
                 // if the nature is "?P<nature>" it means that we want to read nature from Regex
                 if potential_nature == PFRE_READ_NATURE_FROM_REGEX {
@@ -367,6 +1037,7 @@ impl EncounterableResourceUriClassifier for EncounterableResourcePathClassifier
                         if let Some(nature) = caps.name(PFRE_READ_NATURE_FROM_REGEX_CAPTURE) {
                             class.flags.insert(f.flags);
                             class.nature = Some(nature.as_str().to_string());
+                            class.captures = f.named_captures(text);
                             return true;
                         }
                     }
@@ -374,16 +1045,123 @@ impl EncounterableResourceUriClassifier for EncounterableResourcePathClassifier
                     // Since nature is NOT "?P<nature>", we take the nature value literally
                     class.flags.insert(f.flags);
                     class.nature = Some(potential_nature.clone());
+                    class.captures = f.named_captures(text);
                     return true;
                 }
             } else if f.regex.is_match(text) {
                 class.flags.insert(f.flags);
+                class.captures = f.named_captures(text);
                 return true;
             }
         }
 
         false
     }
+
+    pub fn as_formatted_tables(&self) -> (comfy_table::Table, comfy_table::Table) {
+        let mut flaggables: comfy_table::Table =
+            crate::format::prepare_table(vec!["Regex", "Flags", "Nature"]);
+        for f in &self.flaggables {
+            flaggables.add_row(vec![
+                f.regex.to_string(),
+                format!("{:?}", f.flags),
+                f.nature.clone().unwrap_or("".to_string()),
+            ]);
+        }
+
+        let mut rewrite_path_regexs: comfy_table::Table =
+            crate::format::prepare_table(vec!["Rewrite Regex", "Replace With"]);
+        for rprr in &self.rewrite_path_regexs {
+            rewrite_path_regexs.add_row(vec![rprr.regex.to_string(), rprr.replace.to_string()]);
+        }
+
+        (flaggables, rewrite_path_regexs)
+    }
+}
+
+/// A flaggable rule that matched a path, see `flaggable_matches`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FlaggableRegExMatch {
+    pub regex: String,
+    pub flags: EncounterableResourceFlags,
+    pub nature: Option<String>,
+    pub capturable_sql: bool,
+}
+
+/// Why a path was classified the way it was, step by step in the order
+/// `classify` decides; see `admin explain-path`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PathExplanation {
+    pub path: String,
+    // the include or exclude rule which decided the path (excluded paths are
+    // ignored without trying any flaggable, included ones are never ignored)
+    pub path_rule: Option<(PathRuleAction, String)>,
+    // the rewrite regex and the path the flaggables were matched against instead
+    pub path_rewrite: Option<(String, String)>,
+    pub flaggable_matches: Vec<FlaggableRegExMatch>,
+    // the first applicable match, which decided the class
+    pub decided_by: Option<FlaggableRegExMatch>,
+    pub ignored: bool,
+    pub acquire_content: bool,
+    pub capturable_executable: bool,
+    pub capturable_sql: bool,
+    pub flags: EncounterableResourceFlags,
+    pub nature: Option<String>,
+    pub nature_rewrite: Option<NatureRewrite>,
+    pub captures: HashMap<String, String>,
+}
+
+impl EncounterableResourcePathClassifier {
+    /// Every flaggable rule matching `text` (after any path rewrite), in
+    /// evaluation order; `classify` uses the first one so this explains why a
+    /// path was classified the way it was.
+    pub fn flaggable_matches(&self, text: &str) -> Vec<FlaggableRegExMatch> {
+        let text = &classifiable_path(text);
+        for rnr in &self.rewrite_path_regexs {
+            if let Some(rewritten_text) = rnr.rewritten_text(text) {
+                return self.flaggable_matches(&rewritten_text);
+            }
+        }
+
+        self.flaggables
+            .iter()
+            .filter_map(|f| {
+                let caps = f.regex.captures(Self::flaggable_text(f, text))?;
+                let nature = match &f.nature {
+                    Some(nature) if nature == PFRE_READ_NATURE_FROM_REGEX => Some(
+                        caps.name(PFRE_READ_NATURE_FROM_REGEX_CAPTURE)?
+                            .as_str()
+                            .to_string(),
+                    ),
+                    nature => nature.clone(),
+                };
+                Some(FlaggableRegExMatch {
+                    regex: f.regex.to_string(),
+                    flags: f.flags,
+                    nature,
+                    capturable_sql: f.flags.contains(EncounterableResourceFlags::CAPTURABLE_SQL),
+                })
+            })
+            .collect()
+    }
+}
+
+impl EncounterableResourceUriClassifier for EncounterableResourcePathClassifier {
+    fn classify(&self, text: &str, class: &mut EncounterableResourceClass) -> bool {
+        let text = &classifiable_path(text);
+        match self.path_rule_action(text) {
+            Some(PathRuleAction::Exclude) => {
+                class
+                    .flags
+                    .insert(EncounterableResourceFlags::IGNORE_RESOURCE);
+                true
+            }
+            Some(PathRuleAction::Include) => self.classify_flaggables(text, class, true),
+            None => self.classify_flaggables(text, class, false),
+        }
+    }
 }
 
 pub struct ContentResource {
@@ -395,17 +1173,37 @@ pub struct ContentResource {
     pub last_modified_at: Option<DateTime<Utc>>,
     pub content_binary_supplier: Option<BinaryContentSupplier>,
     pub content_text_supplier: Option<TextContentSupplier>,
+    pub content_digest_supplier: Option<DigestContentSupplier>,
+    pub declared_nature: Option<String>, // set when the sniffed nature replaced this one
+    pub git: Option<GitCommitMetadata>,  // set when --git-metadata found the file's last commit
+    pub symlink_target: Option<String>,  // set when the resource was encountered through a symlink
 }
 
 pub struct CapturableExecResource<Resource> {
     pub resource: Resource,
     pub executable: CapturableExecutable,
+    pub captures: HashMap<String, String>,
 }
 
 pub struct PlainTextResource<Resource> {
     pub resource: Resource,
 }
 
+pub enum EmailFormat {
+    Rfc822,
+    OutlookMsg,
+    Unknown,
+}
+
+pub struct EmailResource<Resource> {
+    pub resource: Resource,
+    pub format: EmailFormat,
+}
+
+pub struct SqliteDbResource<Resource> {
+    pub resource: Resource,
+}
+
 pub struct HtmlResource<Resource> {
     pub resource: Resource,
 }
@@ -422,6 +1220,7 @@ pub enum JsonFormat {
 
 pub struct JsonResource<Resource> {
     pub resource: Resource,
+    #[allow(dead_code)] // classified but JSON and JSONC are stored alike
     pub format: JsonFormat,
 }
 
@@ -437,10 +1236,53 @@ pub struct JsonableTextResource<Resource> {
     pub schema: JsonableTextSchema,
 }
 
+impl JsonableTextResource<ContentResource> {
+    /// Parses the text content into JSON according to `schema`; TAP becomes an
+    /// array of `{description, ok}` test points. Errors name the offending line.
+    pub fn as_json(&self) -> Result<JsonValue, Box<dyn Error>> {
+        let text_supplier = self
+            .resource
+            .content_text_supplier
+            .as_ref()
+            .ok_or_else(|| format!("no text content supplier for {}", self.resource.uri))?;
+        let text = text_supplier()?;
+        let text = text.content_text();
+        let uri = &self.resource.uri;
+        let line_error = |line_num: usize, err: &dyn std::fmt::Display| -> Box<dyn Error> {
+            let line = text
+                .lines()
+                .nth(line_num.saturating_sub(1))
+                .unwrap_or_default();
+            format!("{uri} line {line_num} `{line}`: {err}").into()
+        };
+        match self.schema {
+            JsonableTextSchema::Toml => toml::from_str(text).map_err(|err| {
+                let offset = err.span().map(|span| span.start).unwrap_or_default();
+                line_error(text[..offset].matches('\n').count() + 1, &err.message())
+            }),
+            JsonableTextSchema::Yaml => serde_yaml::from_str(text).map_err(|err| {
+                let line_num = err.location().map(|at| at.line()).unwrap_or(1);
+                line_error(line_num, &err)
+            }),
+            JsonableTextSchema::TestAnythingProtocol => {
+                crate::format::tap_test_points(text).map_err(|err| format!("{uri} {err}").into())
+            }
+            JsonableTextSchema::Unknown => {
+                Err(format!("{uri} has no known schema to parse as JSON").into())
+            }
+        }
+    }
+}
+
 pub struct MarkdownResource<Resource> {
     pub resource: Resource,
 }
 
+pub struct PdfResource<Resource> {
+    pub resource: Resource,
+    pub page_count: Option<usize>,
+}
+
 pub enum SourceCodeInterpreter {
     TypeScript,
     JavaScript,
@@ -451,9 +1293,16 @@ pub enum SourceCodeInterpreter {
 
 pub struct SourceCodeResource<Resource> {
     pub resource: Resource,
+    #[allow(dead_code)] // classified but source code isn't interpreted
     pub interpreter: SourceCodeInterpreter,
 }
 
+pub struct TabularResource<Resource> {
+    pub resource: Resource,
+    pub delimiter: u8,
+    pub header: Option<Vec<String>>,
+}
+
 pub enum XmlSchema {
     Svg,
     Unknown,
@@ -466,22 +1315,41 @@ pub struct XmlResource<Resource> {
 
 pub enum UniformResource<Resource> {
     CapturableExec(CapturableExecResource<Resource>),
+    Email(EmailResource<Resource>),
     Html(HtmlResource<Resource>),
     Image(ImageResource<Resource>),
     Json(JsonResource<Resource>),
     JsonableText(JsonableTextResource<Resource>),
     Markdown(MarkdownResource<Resource>),
+    Pdf(PdfResource<Resource>),
     PlainText(PlainTextResource<Resource>),
     SourceCode(SourceCodeResource<Resource>),
+    SqliteDb(SqliteDbResource<Resource>),
+    Tabular(TabularResource<Resource>),
     Xml(XmlResource<Resource>),
     Unknown(Resource, Option<String>),
 }
 
-pub trait UniformResourceSupplier<Resource> {
-    fn uniform_resource(
-        &self,
-        rs: Resource,
-    ) -> Result<Box<UniformResource<Resource>>, Box<dyn Error>>;
+impl UniformResource<ContentResource> {
+    /// The encountered resource itself, regardless of how it was classified.
+    pub fn content_resource(&self) -> &ContentResource {
+        match self {
+            UniformResource::CapturableExec(cer) => &cer.resource,
+            UniformResource::Email(email) => &email.resource,
+            UniformResource::Html(html) => &html.resource,
+            UniformResource::Image(img) => &img.resource,
+            UniformResource::Json(json) => &json.resource,
+            UniformResource::JsonableText(jsonable) => &jsonable.resource,
+            UniformResource::Markdown(md) => &md.resource,
+            UniformResource::Pdf(pdf) => &pdf.resource,
+            UniformResource::PlainText(txt) => &txt.resource,
+            UniformResource::SourceCode(sc) => &sc.resource,
+            UniformResource::SqliteDb(sqlite) => &sqlite.resource,
+            UniformResource::Tabular(tabular) => &tabular.resource,
+            UniformResource::Xml(xml) => &xml.resource,
+            UniformResource::Unknown(cr, _alternate) => cr,
+        }
+    }
 }
 
 pub trait UriNatureSupplier<Resource> {
@@ -493,13 +1361,17 @@ impl UriNatureSupplier<ContentResource> for UniformResource<ContentResource> {
     fn uri(&self) -> &String {
         match self {
             UniformResource::CapturableExec(cer) => &cer.resource.uri,
+            UniformResource::Email(email) => &email.resource.uri,
             UniformResource::Html(html) => &html.resource.uri,
             UniformResource::Image(img) => &img.resource.uri,
             UniformResource::Json(json) => &json.resource.uri,
             UniformResource::JsonableText(json) => &json.resource.uri,
             UniformResource::Markdown(md) => &md.resource.uri,
+            UniformResource::Pdf(pdf) => &pdf.resource.uri,
             UniformResource::PlainText(txt) => &txt.resource.uri,
             UniformResource::SourceCode(sc) => &sc.resource.uri,
+            UniformResource::SqliteDb(sqlite) => &sqlite.resource.uri,
+            UniformResource::Tabular(tabular) => &tabular.resource.uri,
             UniformResource::Xml(xml) => &xml.resource.uri,
             UniformResource::Unknown(cr, _alternate) => &cr.uri,
         }
@@ -508,13 +1380,17 @@ impl UriNatureSupplier<ContentResource> for UniformResource<ContentResource> {
     fn nature(&self) -> &Option<String> {
         match self {
             UniformResource::CapturableExec(cer) => &cer.resource.nature,
+            UniformResource::Email(email) => &email.resource.nature,
             UniformResource::Html(html) => &html.resource.nature,
             UniformResource::Image(img) => &img.resource.nature,
             UniformResource::Json(json) => &json.resource.nature,
             UniformResource::JsonableText(jsonable) => &jsonable.resource.nature,
             UniformResource::Markdown(md) => &md.resource.nature,
+            UniformResource::Pdf(pdf) => &pdf.resource.nature,
             UniformResource::PlainText(txt) => &txt.resource.nature,
             UniformResource::SourceCode(sc) => &sc.resource.nature,
+            UniformResource::SqliteDb(sqlite) => &sqlite.resource.nature,
+            UniformResource::Tabular(tabular) => &tabular.resource.nature,
             UniformResource::Xml(xml) => &xml.resource.nature,
             UniformResource::Unknown(_cr, _alternate) => &None::<String>,
         }
@@ -557,6 +1433,32 @@ impl TextContent for ResourceTextContent {
     }
 }
 
+/// Text decoded from content which wasn't valid UTF-8 (see
+/// [`TextEncodingFallback::Lossy`]), `hash` is the digest of the raw content.
+#[derive(Debug, Clone)]
+pub struct LossyTextContent {
+    pub hash: String,
+    pub text: String,
+}
+
+impl TextContent for LossyTextContent {
+    fn content_digest_hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn content_text(&self) -> &str {
+        &self.text
+    }
+
+    fn frontmatter(&self) -> FrontmatterComponents {
+        frontmatter(&self.text)
+    }
+
+    fn content_text_encoding(&self) -> Option<&str> {
+        Some("utf-8-lossy")
+    }
+}
+
 #[derive(Debug)]
 pub struct EncounteredResourceMetaData {
     pub flags: EncounteredResourceFlags,
@@ -564,6 +1466,7 @@ pub struct EncounteredResourceMetaData {
     pub file_size: u64,
     pub created_at: Option<chrono::prelude::DateTime<chrono::prelude::Utc>>,
     pub last_modified_at: Option<chrono::prelude::DateTime<chrono::prelude::Utc>>,
+    pub symlink_target: Option<String>, // as read from the link, not resolved
 }
 
 impl EncounteredResourceMetaData {
@@ -577,7 +1480,13 @@ impl EncounteredResourceMetaData {
             Ok(metadata) => {
                 flags.set(EncounteredResourceFlags::IS_FILE, metadata.is_file());
                 flags.set(EncounteredResourceFlags::IS_DIRECTORY, metadata.is_dir());
-                flags.set(EncounteredResourceFlags::IS_SYMLINK, metadata.is_symlink());
+                // `fs::metadata` traverses links so ask for the link itself
+                flags.set(
+                    EncounteredResourceFlags::IS_SYMLINK,
+                    fs::symlink_metadata(fs_path)
+                        .map(|link_metadata| link_metadata.is_symlink())
+                        .unwrap_or(false),
+                );
                 file_size = metadata.len();
                 created_at = metadata
                     .created()
@@ -604,10 +1513,66 @@ impl EncounteredResourceMetaData {
             file_size,
             created_at,
             last_modified_at,
+            symlink_target: symlink_target(fs_path),
+        })
+    }
+
+    /// Metadata from the headers of a `HEAD` request (or a `GET` if the server
+    /// doesn't answer `HEAD` successfully); non-2xx statuses are returned as
+    /// `HttpStatusError` once redirects have been followed.
+    pub fn from_url(url: &str) -> anyhow::Result<EncounteredResourceMetaData> {
+        let mut response = HTTP_CLIENT
+            .head(url)
+            .send()
+            .with_context(|| format!("ResourceContentMetaData::from_url({url})"))?;
+        if !response.status().is_success() {
+            response = HTTP_CLIENT
+                .get(url)
+                .send()
+                .with_context(|| format!("ResourceContentMetaData::from_url({url})"))?;
+        }
+        if !response.status().is_success() {
+            return Err(anyhow::Error::new(HttpStatusError {
+                url: url.to_string(),
+                status: response.status().as_u16(),
+            }));
+        }
+
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Ok(EncounteredResourceMetaData {
+            flags: EncounteredResourceFlags::IS_FILE,
+            nature: header(reqwest::header::CONTENT_TYPE)
+                .map(|content_type| nature_from_content_type(&content_type)),
+            file_size: header(reqwest::header::CONTENT_LENGTH)
+                .and_then(|content_length| content_length.parse().ok())
+                .unwrap_or(0),
+            created_at: None,
+            // like captured executables' output, content without a known
+            // modification time is considered modified when it's fetched
+            last_modified_at: Some(
+                header(reqwest::header::LAST_MODIFIED)
+                    .and_then(|last_modified| {
+                        chrono::DateTime::parse_from_rfc2822(&last_modified).ok()
+                    })
+                    .map(|last_modified| last_modified.with_timezone(&chrono::Utc))
+                    .unwrap_or_else(chrono::Utc::now),
+            ),
+            symlink_target: None,
         })
     }
 
-    pub fn from_vfs_path(vfs_path: &vfs::VfsPath) -> anyhow::Result<EncounteredResourceMetaData> {
+    /// vfs' metadata has no timestamps so they're read from `os_path`, the OS
+    /// path a physical VFS path maps to, when there's one.
+    pub fn from_vfs_path(
+        vfs_path: &vfs::VfsPath,
+        os_path: Option<&Path>,
+    ) -> anyhow::Result<EncounteredResourceMetaData> {
         let mut flags = EncounteredResourceFlags::empty();
 
         let metadata = match vfs_path.metadata() {
@@ -628,27 +1593,129 @@ impl EncounteredResourceMetaData {
             }
         };
 
-        let nature = vfs_path
-            .as_str()
-            .rsplit_once('.')
-            .map(|(_, ext)| ext.to_string());
+        // only the file name's own extension, a `.` in a parent (like an
+        // archive's `release.tar.gz/docs/LICENSE`) isn't a nature
+        let nature = vfs_path.extension();
 
+        let os_metadata = os_path.and_then(|os_path| fs::metadata(os_path).ok());
         Ok(EncounteredResourceMetaData {
             flags,
             nature,
             file_size: metadata.len,
-            created_at: None,
-            last_modified_at: None,
+            created_at: os_metadata
+                .as_ref()
+                .and_then(|os_metadata| os_metadata.created().ok())
+                .map(chrono::DateTime::<chrono::Utc>::from),
+            last_modified_at: os_metadata
+                .as_ref()
+                .and_then(|os_metadata| os_metadata.modified().ok())
+                .map(chrono::DateTime::<chrono::Utc>::from),
+            symlink_target: None,
         })
     }
 }
 
+// enough for the magic numbers of all the formats `infer` knows about
+const SNIFF_CONTENT_NATURE_BYTES: u64 = 8192;
+
+/// Detects the nature (as the usual file extension, e.g. `png` or `pdf`) of
+/// binary formats from their leading bytes. Text formats (HTML, XML, shell
+/// scripts) are not reported since their extensions are more specific than
+/// what can be guessed from a few bytes (e.g. `svg` vs. `xml`).
+pub fn detect_nature_from_bytes(bytes: &[u8]) -> Option<String> {
+    if is_sqlite_header(bytes) {
+        return Some(String::from("sqlite"));
+    }
+    infer::get(bytes)
+        .filter(|kind| kind.matcher_type() != infer::MatcherType::Text)
+        .map(|kind| kind.extension().to_string())
+}
+
+/// Every SQLite database file starts with this header, whatever its extension.
+pub const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+pub fn is_sqlite_header(bytes: &[u8]) -> bool {
+    bytes.starts_with(SQLITE_HEADER)
+}
+
+lazy_static::lazy_static! {
+    // redirects are followed (up to 10) by default
+    static ref HTTP_CLIENT: reqwest::blocking::Client = reqwest::blocking::Client::builder()
+        .user_agent(concat!("surveilr/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .expect("Failed to create HTTP client for URL resources");
+}
+
+/// A URL answered with a non-2xx status (after following redirects).
+#[derive(Debug, Clone)]
+pub struct HttpStatusError {
+    pub url: String,
+    pub status: u16,
+}
+
+impl std::fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} returned HTTP status {}", self.url, self.status)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// The nature of a `Content-Type` (e.g. `text/html; charset=utf-8` is `html`);
+/// the usual extension is used for common types so that URLs and files with
+/// the same content have the same nature, other types are kept as-is.
+pub fn nature_from_content_type(content_type: &str) -> String {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    match essence.as_str() {
+        "text/html" => "html",
+        "text/plain" => "txt",
+        "text/markdown" => "md",
+        "text/csv" => "csv",
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        "application/yaml" | "application/x-yaml" | "text/yaml" => "yaml",
+        "application/toml" => "toml",
+        "application/pdf" => "pdf",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        _ => return essence,
+    }
+    .to_string()
+}
+
+fn http_get(url: &str) -> Result<reqwest::blocking::Response, Box<dyn Error>> {
+    Ok(HTTP_CLIENT.get(url).send()?.error_for_status()?)
+}
+
 pub struct EncounteredResourceContentSuppliers {
     pub text: Option<TextContentSupplier>,
     pub binary: Option<BinaryContentSupplier>,
+    pub digest: Option<DigestContentSupplier>,
 }
 
 impl EncounteredResourceContentSuppliers {
+    // `--content-text-only` and `--content-binary-only` drop the other supplier,
+    // `--manifest-only` drops them all
+    fn acquiring(mut self, content_acquisition: ContentAcquisition) -> Self {
+        if !content_acquisition.text() {
+            self.text = None;
+        }
+        if !content_acquisition.binary() {
+            self.binary = None;
+        }
+        if !content_acquisition.digest() {
+            self.digest = None;
+        }
+        self
+    }
+
     pub fn from_fs_path(
         fs_path: &Path,
         erc: &EncounterableResourceClass,
@@ -656,41 +1723,59 @@ impl EncounteredResourceContentSuppliers {
         let binary: Option<BinaryContentSupplier>;
         let text: Option<TextContentSupplier>;
 
+        let digest: Option<DigestContentSupplier> = if erc
+            .flags
+            .contains(EncounterableResourceFlags::CONTENT_ACQUIRABLE)
+        {
+            let digest_algorithm = erc.digest_algorithm;
+            let path_cds = fs_path.to_string_lossy().to_string();
+            let hash_timing = erc.hash_timing.clone();
+            Some(Box::new(move || -> Result<String, Box<dyn Error>> {
+                let _span = tracing::info_span!("hash", path = %path_cds).entered();
+                let _timed = hash_timing.as_deref().map(HashTiming::timed);
+                Ok(digest_algorithm.digest_reader(fs::File::open(&path_cds)?)?)
+            }))
+        } else {
+            None
+        };
+
+        let exceeds_max_content_bytes = erc.max_content_bytes.is_some_and(|max_content_bytes| {
+            fs::metadata(fs_path).is_ok_and(|metadata| metadata.len() > max_content_bytes)
+        });
         if erc
             .flags
             .contains(EncounterableResourceFlags::CONTENT_ACQUIRABLE)
+            && !exceeds_max_content_bytes
         {
+            let digest_algorithm = erc.digest_algorithm;
             let path_cbs = fs_path.to_string_lossy().to_string(); // Clone for the first closure
+            let hash_timing = erc.hash_timing.clone();
             binary = Some(Box::new(
                 move || -> Result<Box<dyn BinaryContent>, Box<dyn Error>> {
+                    let _span = tracing::info_span!("hash", path = %path_cbs).entered();
+                    let _timed = hash_timing.as_deref().map(HashTiming::timed);
                     let mut binary = Vec::new();
                     let mut file = fs::File::open(&path_cbs)?;
                     file.read_to_end(&mut binary)?;
 
-                    let hash = {
-                        let mut hasher = Sha1::new();
-                        hasher.update(&binary);
-                        format!("{:x}", hasher.finalize())
-                    };
+                    let hash = digest_algorithm.digest(&binary);
 
                     Ok(Box::new(ResourceBinaryContent { hash, binary }) as Box<dyn BinaryContent>)
                 },
             ));
 
             let path_cts = fs_path.to_string_lossy().to_string(); // Clone for the second closure
+            let text_encoding_fallback = erc.text_encoding_fallback;
+            let hash_timing = erc.hash_timing.clone();
             text = Some(Box::new(
                 move || -> Result<Box<dyn TextContent>, Box<dyn Error>> {
-                    let mut text = String::new();
+                    let _span = tracing::info_span!("hash", path = %path_cts).entered();
+                    let _timed = hash_timing.as_deref().map(HashTiming::timed);
+                    let mut content = Vec::new();
                     let mut file = fs::File::open(&path_cts)?;
-                    file.read_to_string(&mut text)?;
-
-                    let hash = {
-                        let mut hasher = Sha1::new();
-                        hasher.update(&text);
-                        format!("{:x}", hasher.finalize())
-                    };
+                    file.read_to_end(&mut content)?;
 
-                    Ok(Box::new(ResourceTextContent { hash, text }) as Box<dyn TextContent>)
+                    text_encoding_fallback.text_content(content, digest_algorithm)
                 },
             ));
         } else {
@@ -698,7 +1783,90 @@ impl EncounteredResourceContentSuppliers {
             text = None;
         }
 
-        EncounteredResourceContentSuppliers { binary, text }
+        EncounteredResourceContentSuppliers {
+            binary,
+            text,
+            digest,
+        }
+        .acquiring(erc.content_acquisition)
+    }
+
+    /// Content is always acquired for URLs since they are explicitly requested
+    /// rather than discovered; each supplier makes its own `GET` request.
+    pub fn from_url(
+        url: &str,
+        erc: &EncounterableResourceClass,
+    ) -> EncounteredResourceContentSuppliers {
+        let digest_algorithm = erc.digest_algorithm;
+
+        let url_cds = url.to_string();
+        let digest: Option<DigestContentSupplier> =
+            Some(Box::new(move || -> Result<String, Box<dyn Error>> {
+                Ok(digest_algorithm.digest_reader(http_get(&url_cds)?)?)
+            }));
+
+        let url_cbs = url.to_string();
+        let binary: Option<BinaryContentSupplier> = Some(Box::new(
+            move || -> Result<Box<dyn BinaryContent>, Box<dyn Error>> {
+                let binary = http_get(&url_cbs)?.bytes()?.to_vec();
+                let hash = digest_algorithm.digest(&binary);
+                Ok(Box::new(ResourceBinaryContent { hash, binary }) as Box<dyn BinaryContent>)
+            },
+        ));
+
+        let url_cts = url.to_string();
+        let text: Option<TextContentSupplier> = Some(Box::new(
+            move || -> Result<Box<dyn TextContent>, Box<dyn Error>> {
+                let text = http_get(&url_cts)?.text()?;
+                let hash = digest_algorithm.digest(&text);
+                Ok(Box::new(ResourceTextContent { hash, text }) as Box<dyn TextContent>)
+            },
+        ));
+
+        EncounteredResourceContentSuppliers {
+            text,
+            binary,
+            digest,
+        }
+        .acquiring(erc.content_acquisition)
+    }
+
+    /// Content already in memory (e.g. fetched from a mailbox) is always
+    /// acquired, like URLs.
+    pub fn from_bytes(
+        content: Arc<Vec<u8>>,
+        erc: &EncounterableResourceClass,
+    ) -> EncounteredResourceContentSuppliers {
+        let digest_algorithm = erc.digest_algorithm;
+
+        let content_cds = content.clone();
+        let digest: Option<DigestContentSupplier> =
+            Some(Box::new(move || -> Result<String, Box<dyn Error>> {
+                Ok(digest_algorithm.digest(content_cds.as_slice()))
+            }));
+
+        let content_cbs = content.clone();
+        let binary: Option<BinaryContentSupplier> = Some(Box::new(
+            move || -> Result<Box<dyn BinaryContent>, Box<dyn Error>> {
+                let binary = content_cbs.to_vec();
+                let hash = digest_algorithm.digest(&binary);
+                Ok(Box::new(ResourceBinaryContent { hash, binary }) as Box<dyn BinaryContent>)
+            },
+        ));
+
+        let text_encoding_fallback = erc.text_encoding_fallback;
+        let text: Option<TextContentSupplier> = Some(Box::new(
+            move || -> Result<Box<dyn TextContent>, Box<dyn Error>> {
+                text_encoding_fallback.text_content(content.to_vec(), digest_algorithm)
+            },
+        ));
+
+        EncounteredResourceContentSuppliers {
+            text,
+            binary,
+            digest,
+        }
+        .acquiring(erc.content_acquisition)
     }
 
     pub fn from_vfs_path(
@@ -708,10 +1876,30 @@ impl EncounteredResourceContentSuppliers {
         let binary: Option<BinaryContentSupplier>;
         let text: Option<TextContentSupplier>;
 
+        let digest: Option<DigestContentSupplier> = if erc
+            .flags
+            .contains(EncounterableResourceFlags::CONTENT_ACQUIRABLE)
+        {
+            let digest_algorithm = erc.digest_algorithm;
+            let path_clone_cds = vfs_path.clone();
+            Some(Box::new(move || -> Result<String, Box<dyn Error>> {
+                Ok(digest_algorithm.digest_reader(path_clone_cds.open_file()?)?)
+            }))
+        } else {
+            None
+        };
+
+        let exceeds_max_content_bytes = erc.max_content_bytes.is_some_and(|max_content_bytes| {
+            vfs_path
+                .metadata()
+                .is_ok_and(|metadata| metadata.len > max_content_bytes)
+        });
         if erc
             .flags
             .contains(EncounterableResourceFlags::CONTENT_ACQUIRABLE)
+            && !exceeds_max_content_bytes
         {
+            let digest_algorithm = erc.digest_algorithm;
             let path_clone_cbs = vfs_path.clone();
             binary = Some(Box::new(
                 move || -> Result<Box<dyn BinaryContent>, Box<dyn Error>> {
@@ -719,30 +1907,21 @@ impl EncounteredResourceContentSuppliers {
                     let mut file = path_clone_cbs.open_file()?;
                     file.read_to_end(&mut binary)?;
 
-                    let hash = {
-                        let mut hasher = Sha1::new();
-                        hasher.update(&binary);
-                        format!("{:x}", hasher.finalize())
-                    };
+                    let hash = digest_algorithm.digest(&binary);
 
                     Ok(Box::new(ResourceBinaryContent { hash, binary }) as Box<dyn BinaryContent>)
                 },
             ));
 
             let path_clone_cts = vfs_path.clone();
+            let text_encoding_fallback = erc.text_encoding_fallback;
             text = Some(Box::new(
                 move || -> Result<Box<dyn TextContent>, Box<dyn Error>> {
-                    let mut text = String::new();
+                    let mut content = Vec::new();
                     let mut file = path_clone_cts.open_file()?;
-                    file.read_to_string(&mut text)?;
-
-                    let hash = {
-                        let mut hasher = Sha1::new();
-                        hasher.update(&text);
-                        format!("{:x}", hasher.finalize())
-                    };
+                    file.read_to_end(&mut content)?;
 
-                    Ok(Box::new(ResourceTextContent { hash, text }) as Box<dyn TextContent>)
+                    text_encoding_fallback.text_content(content, digest_algorithm)
                 },
             ));
         } else {
@@ -750,15 +1929,67 @@ impl EncounteredResourceContentSuppliers {
             binary = None;
         }
 
-        EncounteredResourceContentSuppliers { text, binary }
+        EncounteredResourceContentSuppliers {
+            text,
+            binary,
+            digest,
+        }
+        .acquiring(erc.content_acquisition)
     }
 }
 
 pub enum EncounterableResource {
     WalkDir(walkdir::DirEntry),
     SmartIgnore(ignore::DirEntry),
-    Vfs(vfs::VfsPath),
+    // with the OS path a physical VFS path maps to, since vfs' metadata has
+    // no timestamps (an archive's entries have none to begin with)
+    Vfs(vfs::VfsPath, Option<PathBuf>),
+    // a path the walker couldn't read (e.g. permission denied) and why
+    WalkError(String, String),
     DenoTaskShellLine(String, Option<String>, String),
+    Http(String),
+    Imap(ImapResource),
+}
+
+/// A message (or one of its attachments) already fetched from an IMAP mailbox.
+#[derive(Debug, Clone)]
+pub struct ImapResource {
+    pub uri: String,
+    pub nature: String,
+    pub content: Arc<Vec<u8>>,
+    pub internal_date: Option<DateTime<Utc>>,
+}
+
+impl ImapResource {
+    /// The message itself as `message/rfc822` followed by each of its
+    /// attachments, whose URIs are the message's URI plus `/` and their name.
+    pub fn from_message(uri: &str, message: crate::mailbox::ImapMessage) -> Vec<ImapResource> {
+        let mut resources: Vec<ImapResource> = crate::email::email_attachments(&message.raw)
+            .into_iter()
+            .map(|attachment| ImapResource {
+                uri: format!("{uri}/{}", attachment.name),
+                nature: match &attachment.content_type {
+                    Some(content_type) => nature_from_content_type(content_type),
+                    None => Path::new(&attachment.name)
+                        .extension()
+                        .map(|extn| extn.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                },
+                content: Arc::new(attachment.content),
+                internal_date: message.internal_date,
+            })
+            .collect();
+        resources.insert(
+            0,
+            ImapResource {
+                uri: uri.to_string(),
+                nature: String::from("message/rfc822"),
+                content: Arc::new(message.raw),
+                internal_date: message.internal_date,
+            },
+        );
+        resources
+    }
 }
 
 impl EncounterableResource {
@@ -786,7 +2017,12 @@ impl EncounterableResource {
     /// let result = dts_er(non_json_str);
     /// assert_eq!(result, ("Hello, world!".to_string(), None, "json".to_string()));
     /// ```
-    pub fn from_deno_task_shell_line(line: impl AsRef<str>) -> EncounterableResource {
+    /// Lines which are JSON objects carry their own `nature` (defaulting to `json`)
+    /// while plain shell command lines are given `plain_line_nature`.
+    pub fn from_deno_task_shell_line(
+        line: impl AsRef<str>,
+        plain_line_nature: &str,
+    ) -> EncounterableResource {
         let default_nature = "json".to_string();
         let (commands, identity, nature) = match serde_json::from_str::<JsonValue>(line.as_ref()) {
             Ok(parsed) => {
@@ -811,26 +2047,61 @@ impl EncounterableResource {
 
                     (task, identity, nature)
                 } else {
-                    (line.as_ref().to_owned(), None, default_nature)
+                    (
+                        line.as_ref().to_owned(),
+                        None,
+                        plain_line_nature.to_string(),
+                    )
                 }
             }
-            Err(_) => (line.as_ref().to_owned(), None, default_nature),
+            Err(_) => (
+                line.as_ref().to_owned(),
+                None,
+                plain_line_nature.to_string(),
+            ),
         };
         EncounterableResource::DenoTaskShellLine(commands, identity, nature)
     }
 }
 
 pub enum EncounteredResource<T> {
+    #[allow(dead_code)] // only counted, the URI and class aren't read
     Ignored(String, EncounterableResourceClass),
-    NotFound(String, EncounterableResourceClass),
+    // with the target when the path is a dangling symbolic link
+    #[allow(dead_code)] // the class isn't read
+    NotFound(String, Option<String>, EncounterableResourceClass),
+    // a URL that couldn't be fetched, with its HTTP status code
+    #[allow(dead_code)] // the class isn't read
+    HttpNotOk(String, u16, EncounterableResourceClass),
+    #[allow(dead_code)] // only skipped, the URI and class aren't read
     NotFile(String, EncounterableResourceClass),
+    // another path to a file (hard link or symlink) already encountered at the
+    // canonical URI, only reported when inodes are deduplicated
+    #[allow(dead_code)] // only counted, the URIs and class aren't read
+    Duplicate(String, String, EncounterableResourceClass),
+    // a zero-byte file, only reported when empty files are skipped
+    #[allow(dead_code)] // only counted, the URI and class aren't read
+    Empty(String, EncounterableResourceClass),
+    // a path the walker couldn't read and why
+    #[allow(dead_code)] // the class isn't read
+    WalkError(String, String, EncounterableResourceClass),
     Resource(T, EncounterableResourceClass),
     CapturableExec(T, CapturableExecutable, EncounterableResourceClass),
 }
 
 impl ShellExecutive for EncounterableResource {
-    fn execute(&self, std_in: ShellStdIn) -> anyhow::Result<ShellResult> {
-        execute_subprocess(self.uri(), std_in)
+    fn execute(
+        &self,
+        std_in: ShellStdIn,
+        timeout: Option<Duration>,
+    ) -> anyhow::Result<ShellResult> {
+        execute_subprocess(
+            self.uri(),
+            std_in,
+            timeout,
+            &ShellEnvPolicy::default(),
+            &RetryPolicy::default(),
+        )
     }
 }
 
@@ -839,10 +2110,41 @@ impl EncounterableResource {
         match self {
             EncounterableResource::WalkDir(de) => de.path().to_string_lossy().to_string(),
             EncounterableResource::SmartIgnore(de) => de.path().to_string_lossy().to_string(),
-            EncounterableResource::Vfs(path) => path.as_str().to_string(),
+            EncounterableResource::Vfs(path, _) => path.as_str().to_string(),
+            EncounterableResource::WalkError(path, _) => path.clone(),
             EncounterableResource::DenoTaskShellLine(line, identity, _) => {
                 identity.to_owned().unwrap_or(line.as_str().to_string())
             }
+            EncounterableResource::Http(url) => url.clone(),
+            EncounterableResource::Imap(ir) => ir.uri.clone(),
+        }
+    }
+
+    /// The `(dev, ino)` pair identifying the file behind this resource (after
+    /// following symlinks); always `None` on non-unix platforms and for
+    /// resources which aren't local files.
+    pub fn inode(&self) -> Option<(u64, u64)> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let path = match self {
+                EncounterableResource::WalkDir(de) => de.path(),
+                EncounterableResource::SmartIgnore(de) => de.path(),
+                _ => return None,
+            };
+            fs::metadata(path)
+                .ok()
+                .map(|metadata| (metadata.dev(), metadata.ino()))
+        }
+        #[cfg(not(unix))]
+        None
+    }
+
+    pub fn symlink_target(&self) -> Option<String> {
+        match self {
+            EncounterableResource::WalkDir(de) => symlink_target(de.path()),
+            EncounterableResource::SmartIgnore(de) => symlink_target(de.path()),
+            _ => None,
         }
     }
 
@@ -854,7 +2156,14 @@ impl EncounterableResource {
             EncounterableResource::SmartIgnore(de) => {
                 EncounteredResourceMetaData::from_fs_path(de.path())
             }
-            EncounterableResource::Vfs(path) => EncounteredResourceMetaData::from_vfs_path(path),
+            EncounterableResource::Vfs(path, os_path) => {
+                EncounteredResourceMetaData::from_vfs_path(path, os_path.as_deref())
+            }
+            EncounterableResource::WalkError(path, reason) => Err(anyhow::anyhow!(
+                "[EncounterableResource::meta_data] unable to walk {}: {}",
+                path,
+                reason
+            )),
             EncounterableResource::DenoTaskShellLine(_, _, nature) => {
                 Ok(EncounteredResourceMetaData {
                     flags: EncounteredResourceFlags::empty(),
@@ -862,8 +2171,19 @@ impl EncounterableResource {
                     file_size: 0,
                     created_at: None,
                     last_modified_at: None,
+                    symlink_target: None,
                 })
             }
+            EncounterableResource::Http(url) => EncounteredResourceMetaData::from_url(url),
+            EncounterableResource::Imap(ir) => Ok(EncounteredResourceMetaData {
+                flags: EncounteredResourceFlags::IS_FILE,
+                nature: Some(ir.nature.clone()),
+                file_size: ir.content.len() as u64,
+                created_at: ir.internal_date,
+                // writers expect a modification time, messages only have their arrival
+                last_modified_at: Some(ir.internal_date.unwrap_or_else(Utc::now)),
+                symlink_target: None,
+            }),
         }
     }
 
@@ -878,18 +2198,64 @@ impl EncounterableResource {
             EncounterableResource::SmartIgnore(de) => {
                 EncounteredResourceContentSuppliers::from_fs_path(de.path(), options)
             }
-            EncounterableResource::Vfs(path) => {
+            EncounterableResource::Vfs(path, _) => {
                 EncounteredResourceContentSuppliers::from_vfs_path(path, options)
             }
-            EncounterableResource::DenoTaskShellLine(_, _, _) => {
+            EncounterableResource::WalkError(_, _)
+            | EncounterableResource::DenoTaskShellLine(_, _, _) => {
                 EncounteredResourceContentSuppliers {
                     text: None,
                     binary: None,
+                    digest: None,
                 }
             }
+            EncounterableResource::Http(url) => {
+                EncounteredResourceContentSuppliers::from_url(url, options)
+            }
+            EncounterableResource::Imap(ir) => {
+                EncounteredResourceContentSuppliers::from_bytes(ir.content.clone(), options)
+            }
         }
     }
 
+    /// Reads up to `max_bytes` from the start of the resource regardless of
+    /// whether its content is acquirable; `None` if it can't be read.
+    pub fn content_prefix(&self, max_bytes: u64) -> Option<Vec<u8>> {
+        let mut prefix = Vec::new();
+        match self {
+            EncounterableResource::WalkDir(de) => fs::File::open(de.path())
+                .ok()?
+                .take(max_bytes)
+                .read_to_end(&mut prefix)
+                .ok()?,
+            EncounterableResource::SmartIgnore(de) => fs::File::open(de.path())
+                .ok()?
+                .take(max_bytes)
+                .read_to_end(&mut prefix)
+                .ok()?,
+            EncounterableResource::Vfs(path, _) => path
+                .open_file()
+                .ok()?
+                .take(max_bytes)
+                .read_to_end(&mut prefix)
+                .ok()?,
+            EncounterableResource::WalkError(_, _)
+            | EncounterableResource::DenoTaskShellLine(_, _, _) => return None,
+            EncounterableResource::Http(url) => http_get(url)
+                .ok()?
+                .take(max_bytes)
+                .read_to_end(&mut prefix)
+                .ok()?,
+            EncounterableResource::Imap(ir) => ir
+                .content
+                .as_slice()
+                .take(max_bytes)
+                .read_to_end(&mut prefix)
+                .ok()?,
+        };
+        Some(prefix)
+    }
+
     pub fn encountered(
         &self,
         erc: &EncounterableResourceClass,
@@ -902,12 +2268,18 @@ impl EncounterableResource {
         {
             return EncounteredResource::Ignored(uri, erc.to_owned());
         }
+        if let EncounterableResource::WalkError(_, reason) = self {
+            return EncounteredResource::WalkError(uri, reason.clone(), erc.to_owned());
+        }
 
         let metadata = match self.meta_data() {
             Ok(metadata) => match self {
                 EncounterableResource::WalkDir(_)
                 | EncounterableResource::SmartIgnore(_)
-                | EncounterableResource::Vfs(_) => {
+                | EncounterableResource::Vfs(_, _)
+                | EncounterableResource::WalkError(_, _)
+                | EncounterableResource::Http(_)
+                | EncounterableResource::Imap(_) => {
                     if !metadata.flags.contains(EncounteredResourceFlags::IS_FILE) {
                         return EncounteredResource::NotFile(uri, erc.to_owned());
                     }
@@ -915,11 +2287,20 @@ impl EncounterableResource {
                 }
                 EncounterableResource::DenoTaskShellLine(_, _, _) => metadata,
             },
-            Err(_) => return EncounteredResource::NotFound(uri, erc.to_owned()),
+            Err(err) => {
+                return match err.downcast_ref::<HttpStatusError>() {
+                    Some(http_status) => {
+                        EncounteredResource::HttpNotOk(uri, http_status.status, erc.to_owned())
+                    }
+                    None => {
+                        EncounteredResource::NotFound(uri, self.symlink_target(), erc.to_owned())
+                    }
+                }
+            }
         };
 
         let content_suppliers = self.content_suppliers(erc);
-        let nature: String;
+        let mut nature: String;
         match &erc.nature {
             Some(classification_nature) => nature = classification_nature.to_owned(),
             None => match &metadata.nature {
@@ -927,6 +2308,31 @@ impl EncounterableResource {
                 None => nature = "json".to_string(),
             },
         }
+        let mut declared_nature = None;
+        if erc.sniff_content_nature
+            && !erc
+                .flags
+                .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
+        {
+            if let Some(detected) = self
+                .content_prefix(SNIFF_CONTENT_NATURE_BYTES)
+                .and_then(|prefix| detect_nature_from_bytes(&prefix))
+            {
+                if detected != nature {
+                    declared_nature = Some(std::mem::replace(&mut nature, detected));
+                }
+            }
+        } else if erc.sniff_sqlite
+            && !erc
+                .flags
+                .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
+            && self
+                .content_prefix(SQLITE_HEADER.len() as u64)
+                .is_some_and(|prefix| is_sqlite_header(&prefix))
+            && !matches!(nature.as_str(), "db" | "sqlite" | "sqlite3")
+        {
+            declared_nature = Some(std::mem::replace(&mut nature, String::from("sqlite")));
+        }
         let cr: ContentResource = ContentResource {
             flags: ContentResourceFlags::from_bits_truncate(erc.flags.bits()),
             uri: uri.to_string(),
@@ -936,12 +2342,16 @@ impl EncounterableResource {
             last_modified_at: metadata.last_modified_at,
             content_binary_supplier: content_suppliers.binary,
             content_text_supplier: content_suppliers.text,
+            content_digest_supplier: content_suppliers.digest,
+            declared_nature,
+            git: None,
+            symlink_target: metadata.symlink_target,
         };
 
         match self {
             EncounterableResource::WalkDir(_)
             | EncounterableResource::SmartIgnore(_)
-            | EncounterableResource::Vfs(_) => {
+            | EncounterableResource::Vfs(_, _) => {
                 if erc
                     .flags
                     .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
@@ -961,13 +2371,39 @@ impl EncounterableResource {
                     CapturableExecutable::from_encountered_content(self, erc),
                     erc.to_owned(),
                 )
-            }
+            } // remote content is never executed
+            EncounterableResource::WalkError(_, _)
+            | EncounterableResource::Http(_)
+            | EncounterableResource::Imap(_) => EncounteredResource::Resource(cr, erc.to_owned()),
         }
     }
 }
 
+lazy_static::lazy_static! {
+    // a comment like `# surveilr-nature: json` (or `//`, `--`) on the first
+    // line, or on the second one after a shebang
+    static ref NATURE_DIRECTIVE_REGEX: Regex =
+        Regex::new(r"^\s*(?:#|//|--)\s*surveilr-nature:\s*(?P<nature>\S+)\s*$").unwrap();
+}
+
+/// The nature a capturable executable declares for its output, reading no
+/// more than its first two lines (and never more than a few KB).
+pub fn nature_directive(path: &Path) -> Option<String> {
+    let file = fs::File::open(path).ok()?;
+    let mut lines = std::io::BufRead::lines(std::io::BufReader::new(file.take(4096)));
+    let mut line = lines.next()?.ok()?;
+    if line.starts_with("#!") {
+        line = lines.next()?.ok()?;
+    }
+    NATURE_DIRECTIVE_REGEX
+        .captures(&line)
+        .map(|caps| caps["nature"].to_string())
+}
+
 pub enum CapturableExecutable {
-    UriShellExecutive(Box<dyn ShellExecutive>, String, String, bool),
+    // executive, interpretable code (URI), nature, is batched SQL, and how long
+    // the executive may run before it's killed
+    UriShellExecutive(Box<dyn ShellExecutive>, String, String, bool, Duration),
     RequestedButNotExecutable(String),
 }
 
@@ -983,21 +2419,31 @@ impl CapturableExecutable {
             EncounterableResource::SmartIgnore(de) => {
                 CapturableExecutable::from_executable_file_path(de.path(), erc)
             }
-            EncounterableResource::Vfs(path) => {
+            EncounterableResource::Vfs(path, _) => {
                 CapturableExecutable::from_executable_file_uri(path.as_str(), erc)
             }
             EncounterableResource::DenoTaskShellLine(line, identity, nature) => {
                 CapturableExecutable::UriShellExecutive(
-                    Box::new(DenoTaskShellExecutive::new(
-                        line.clone(),
-                        identity.to_owned(),
-                    )),
+                    Box::new(
+                        DenoTaskShellExecutive::new(line.clone(), identity.to_owned())
+                            .env_policy(&erc.capture_exec_env),
+                    ),
                     line.clone(),
                     nature.to_string(),
                     erc.flags
                         .contains(EncounterableResourceFlags::CAPTURABLE_SQL),
+                    erc.capture_exec_timeout,
                 )
             }
+            EncounterableResource::WalkError(path, _) => {
+                CapturableExecutable::RequestedButNotExecutable(path.clone())
+            }
+            EncounterableResource::Http(url) => {
+                CapturableExecutable::RequestedButNotExecutable(url.clone())
+            }
+            EncounterableResource::Imap(ir) => {
+                CapturableExecutable::RequestedButNotExecutable(ir.uri.clone())
+            }
         }
     }
 
@@ -1008,11 +2454,16 @@ impl CapturableExecutable {
     ) -> CapturableExecutable {
         let executable_file_uri = uri.to_string();
         CapturableExecutable::UriShellExecutive(
-            Box::new(executable_file_uri.clone()), // String has the `ShellExecutive` trait
+            Box::new(SubprocessExecutive {
+                command: executable_file_uri.clone(),
+                env_policy: erc.capture_exec_env.clone(),
+                retry_policy: erc.capture_exec_retry,
+            }),
             executable_file_uri,
             erc.nature.clone().unwrap_or("?nature".to_string()),
             erc.flags
                 .contains(EncounterableResourceFlags::CAPTURABLE_SQL),
+            erc.capture_exec_timeout,
         )
     }
 
@@ -1022,7 +2473,19 @@ impl CapturableExecutable {
         erc: &EncounterableResourceClass,
     ) -> CapturableExecutable {
         if path.is_executable() {
-            CapturableExecutable::from_executable_file_uri(path.to_str().unwrap(), erc)
+            // the class is only cloned when a directive overrides its nature
+            let directive_erc = erc
+                .capture_nature_from_directive
+                .then(|| nature_directive(path))
+                .flatten()
+                .map(|nature| EncounterableResourceClass {
+                    nature: Some(nature),
+                    ..erc.clone()
+                });
+            CapturableExecutable::from_executable_file_uri(
+                path.to_str().unwrap(),
+                directive_erc.as_ref().unwrap_or(erc),
+            )
         } else {
             CapturableExecutable::RequestedButNotExecutable(path.to_string_lossy().to_string())
         }
@@ -1030,11 +2493,27 @@ impl CapturableExecutable {
 
     pub fn uri(&self) -> &str {
         match self {
-            CapturableExecutable::UriShellExecutive(_, uri, _, _)
+            CapturableExecutable::UriShellExecutive(_, uri, _, _, _)
             | CapturableExecutable::RequestedButNotExecutable(uri) => uri.as_str(),
         }
     }
 
+    fn timed_out_json(
+        &self,
+        interpretable_code: &str,
+        nature: &str,
+        timeout: &Duration,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "src": self.uri(),
+            "interpretable-code": interpretable_code,
+            "issue": "timeout",
+            "remediation": "ensure that executable finishes (e.g. isn't waiting on input) or raise --capture-exec-timeout-secs",
+            "nature": nature,
+            "timeout-secs": timeout.as_secs(),
+        })
+    }
+
     pub fn executed_result_as_text(
         &self,
         std_in: ShellStdIn,
@@ -1045,7 +2524,8 @@ impl CapturableExecutable {
                 interpretable_code,
                 nature,
                 is_batched_sql,
-            ) => match executive.execute(std_in) {
+                timeout,
+            ) => match executive.execute(std_in, Some(*timeout)) {
                 Ok(shell_result) => {
                     if shell_result.success() {
                         Ok((shell_result.stdout, nature.clone(), *is_batched_sql))
@@ -1062,6 +2542,9 @@ impl CapturableExecutable {
                         }))
                     }
                 }
+                Err(err) if err.is::<ExecutionTimeout>() => {
+                    Err(self.timed_out_json(interpretable_code, nature, timeout))
+                }
                 Err(err) => Err(serde_json::json!({
                     "src": self.uri(),
                     "interpretable-code": interpretable_code,
@@ -1088,7 +2571,8 @@ impl CapturableExecutable {
                 interpretable_code,
                 nature,
                 is_batched_sql,
-            ) => match executive.execute(std_in) {
+                timeout,
+            ) => match executive.execute(std_in, Some(*timeout)) {
                 Ok(shell_result) => {
                     if shell_result.success() {
                         let captured_text = shell_result.stdout;
@@ -1121,6 +2605,9 @@ impl CapturableExecutable {
                         }))
                     }
                 }
+                Err(err) if err.is::<ExecutionTimeout>() => {
+                    Err(self.timed_out_json(interpretable_code, nature, timeout))
+                }
                 Err(err) => Err(serde_json::json!({
                     "src": self.uri(),
                     "issue": "[CapturableExecutable::TextFromExecutableUri.executed_result_as_json] execution error",
@@ -1147,9 +2634,10 @@ impl CapturableExecutable {
                 interpretable_code,
                 nature,
                 is_batched_sql,
+                timeout,
             ) => {
                 if *is_batched_sql {
-                    match executive.execute(std_in) {
+                    match executive.execute(std_in, Some(*timeout)) {
                         Ok(shell_result) => {
                             if shell_result.status.success() {
                                 Ok((shell_result.stdout, nature.clone()))
@@ -1166,6 +2654,9 @@ impl CapturableExecutable {
                                 }))
                             }
                         }
+                        Err(err) if err.is::<ExecutionTimeout>() => {
+                            Err(self.timed_out_json(interpretable_code, nature, timeout))
+                        }
                         Err(err) => Err(serde_json::json!({
                             "src": self.uri(),
                             "interpretable-code": interpretable_code,
@@ -1192,59 +2683,707 @@ impl CapturableExecutable {
     }
 }
 
+/// Extracts or transforms a resource's raw content into the text that should be
+/// stored for it (e.g. the structured output of a custom parser).
+pub type NatureContentTransformer =
+    Arc<dyn Fn(&[u8]) -> Result<String, Box<dyn Error>> + Send + Sync>;
+
+/// Returns the pattern of a `/regex/` nature alias key, or `None` if the key
+/// is an exact nature.
+pub fn nature_alias_regex(key: &str) -> Option<&str> {
+    key.strip_prefix('/')?.strip_suffix('/')
+}
+
+// digest and raw bytes read ahead of time by `par_uniform_resources` workers
+#[cfg(feature = "rayon")]
+type PrefetchedContent = Result<(String, Arc<Vec<u8>>), String>;
+
+/// Counts what `uniform_resources` (or its parallel variant) encountered,
+/// including the resources it filtered out; directories are not counted.
+#[derive(Debug, Default)]
+pub struct EncounterCounts {
+    encountered: AtomicUsize,
+    ignored: AtomicUsize,
+    not_found: AtomicUsize,
+    duplicates: AtomicUsize,
+    empty: AtomicUsize,
+    walk_errors: Mutex<Vec<(String, String)>>,
+    dangling_symlinks: Mutex<Vec<(String, String)>>,
+}
+
+impl EncounterCounts {
+    fn count(&self, er: &EncounteredResource<ContentResource>) {
+        let counter = match er {
+            EncounteredResource::NotFile(_, _) => return,
+            EncounteredResource::Ignored(_, _) => Some(&self.ignored),
+            EncounteredResource::NotFound(uri, symlink_target, _) => {
+                if let Some(symlink_target) = symlink_target {
+                    self.dangling_symlinks
+                        .lock()
+                        .unwrap()
+                        .push((uri.clone(), symlink_target.clone()));
+                }
+                Some(&self.not_found)
+            }
+            EncounteredResource::HttpNotOk(_, _, _) => Some(&self.not_found),
+            EncounteredResource::Duplicate(_, _, _) => Some(&self.duplicates),
+            EncounteredResource::Empty(_, _) => Some(&self.empty),
+            EncounteredResource::WalkError(uri, reason, _) => {
+                self.walk_errors
+                    .lock()
+                    .unwrap()
+                    .push((uri.clone(), reason.clone()));
+                None
+            }
+            EncounteredResource::Resource(_, _) | EncounteredResource::CapturableExec(_, _, _) => {
+                None
+            }
+        };
+        self.encountered.fetch_add(1, Ordering::Relaxed);
+        if let Some(counter) = counter {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn encountered(&self) -> usize {
+        self.encountered.load(Ordering::Relaxed)
+    }
+
+    pub fn ignored(&self) -> usize {
+        self.ignored.load(Ordering::Relaxed)
+    }
+
+    pub fn not_found(&self) -> usize {
+        self.not_found.load(Ordering::Relaxed)
+    }
+
+    pub fn duplicates(&self) -> usize {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+
+    pub fn empty(&self) -> usize {
+        self.empty.load(Ordering::Relaxed)
+    }
+
+    /// The `(uri, reason)` of the paths which couldn't be walked since the last call.
+    pub fn take_walk_errors(&self) -> Vec<(String, String)> {
+        std::mem::take(&mut *self.walk_errors.lock().unwrap())
+    }
+
+    /// The (uri, target) of each dangling symbolic link counted as not found.
+    pub fn take_dangling_symlinks(&self) -> Vec<(String, String)> {
+        std::mem::take(&mut *self.dangling_symlinks.lock().unwrap())
+    }
+
+    // resources dropped by a `NatureFilter` were already counted as encountered
+    fn count_filtered(&self) {
+        self.ignored.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 pub struct ResourcesCollection {
     pub encounterable: Vec<EncounterableResource>,
     pub classifier: EncounterableResourcePathClassifier,
     pub nature_aliases: Option<HashMap<String, String>>,
+    pub nature_regex_aliases: Vec<(Regex, String)>,
+    pub content_transformers: HashMap<String, NatureContentTransformer>,
+    pub encounter_counts: EncounterCounts,
+    pub git_commits: Option<GitCommits>,
+    pub seen_inodes: Option<SeenInodes>,
+    pub nature_rewrites: Option<Mutex<Vec<(String, NatureRewrite)>>>,
+    pub nature_filter: Option<NatureFilter>,
+    pub modified_since: Option<DateTime<Utc>>,
+    pub sample: Option<Sample>,
+    pub skip_empty_files: bool,
+    pub walk_roots: Vec<String>,
+    pub hash_timing: Option<Arc<HashTiming>>,
+}
+
+/// Keeps only the resources whose resolved nature (after rewrites and binds) is
+/// in the allowlist or not in the denylist; those without a nature are only
+/// kept by a denylist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NatureFilter {
+    Include(Vec<String>),
+    Exclude(Vec<String>),
+}
+
+impl NatureFilter {
+    pub fn from_args(include: &[String], exclude: &[String]) -> Option<NatureFilter> {
+        if !include.is_empty() {
+            Some(NatureFilter::Include(include.to_vec()))
+        } else if !exclude.is_empty() {
+            Some(NatureFilter::Exclude(exclude.to_vec()))
+        } else {
+            None
+        }
+    }
+
+    pub fn allows(&self, nature: Option<&str>) -> bool {
+        match (self, nature) {
+            (NatureFilter::Include(natures), Some(nature)) => natures.iter().any(|n| n == nature),
+            (NatureFilter::Include(_), None) => false,
+            (NatureFilter::Exclude(natures), Some(nature)) => !natures.iter().any(|n| n == nature),
+            (NatureFilter::Exclude(_), None) => true,
+        }
+    }
+}
+
+/// A random seed for sampling when none is given; kept below `i64::MAX` so it
+/// can be recorded as an SQLite integer and passed back as `--sample-seed`.
+pub fn resolved_sample_seed(sample_seed: Option<u64>) -> u64 {
+    sample_seed.unwrap_or_else(|| rand::random::<u64>() >> 1)
+}
+
+/// Keeps about `percent` of the resources; whether a URI is sampled depends only
+/// on it and the seed (not the walk order) so a sampled run can be reproduced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub percent: f64,
+    pub seed: u64,
+}
+
+impl Sample {
+    pub fn new(percent: f64, sample_seed: Option<u64>) -> Sample {
+        Sample {
+            percent,
+            seed: resolved_sample_seed(sample_seed),
+        }
+    }
+
+    pub fn includes(&self, uri: &str) -> bool {
+        let mut hasher = Sha1::new();
+        hasher.update(self.seed.to_le_bytes());
+        hasher.update(uri.as_bytes());
+        let hash = hasher.finalize();
+        let position = u64::from_be_bytes(hash[..8].try_into().unwrap());
+        self.percent >= 100.0 || (position as f64) < self.percent / 100.0 * u64::MAX as f64
+    }
 }
 
+/// The URI of the first resource encountered for each `(dev, ino)`; shared by
+/// the collections of all root paths so links across roots are also found.
+pub type SeenInodes = Arc<Mutex<HashMap<(u64, u64), String>>>;
+
 impl ResourcesCollection {
     pub fn new(
         encounterable: Vec<EncounterableResource>,
         classifier: &EncounterableResourcePathClassifier,
         nature_aliases: &Option<HashMap<String, String>>,
     ) -> ResourcesCollection {
+        // `/regex/` keys are matched against the URI of resources whose nature
+        // has no exact alias; an invalid regex never matches anything
+        let mut nature_regex_aliases: Vec<(Regex, String)> = nature_aliases
+            .iter()
+            .flatten()
+            .filter_map(|(key, nature)| {
+                let regex = Regex::new(nature_alias_regex(key)?).ok()?;
+                Some((regex, nature.clone()))
+            })
+            .collect();
+        nature_regex_aliases.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
         ResourcesCollection {
             encounterable,
             classifier: classifier.clone(),
             nature_aliases: nature_aliases.clone(),
+            nature_regex_aliases,
+            content_transformers: HashMap::new(),
+            encounter_counts: EncounterCounts::default(),
+            git_commits: None,
+            seen_inodes: None,
+            nature_rewrites: None,
+            nature_filter: None,
+            modified_since: None,
+            sample: None,
+            skip_empty_files: false,
+            walk_roots: Vec::new(),
+            hash_timing: None,
         }
     }
 
-    // create a physical file system mapped via VFS, mainly for testing and experimental use
-    pub fn from_vfs_physical_fs(
-        fs_root_paths: &[String],
-        classifier: &EncounterableResourcePathClassifier,
-        nature_aliases: &Option<HashMap<String, String>>,
+    /// Registers a content transformer for `nature` (after aliases are applied)
+    /// which takes precedence over the built-in handling of that nature; the
+    /// stored content is the transformer's output but the digest remains that
+    /// of the raw content so identity and deduplication are unaffected.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_content_transformer(
+        mut self,
+        nature: &str,
+        transformer: impl Fn(&[u8]) -> Result<String, Box<dyn Error>> + Send + Sync + 'static,
     ) -> ResourcesCollection {
-        let physical_fs = vfs::PhysicalFS::new("/");
-        let vfs_fs_root = vfs::VfsPath::new(physical_fs);
+        self.content_transformers
+            .insert(nature.to_string(), Arc::new(transformer));
+        self
+    }
 
-        let vfs_iter = fs_root_paths
-            .iter()
-            .flat_map(move |physical_fs_root_path_orig| {
-                let physical_fs_root_path: String;
-                if let Ok(canonical) = canonicalize(physical_fs_root_path_orig.clone()) {
-                    physical_fs_root_path = canonical.to_string_lossy().to_string();
-                } else {
-                    eprintln!(
-                        "Error canonicalizing {}, trying original",
-                        physical_fs_root_path_orig
-                    );
-                    physical_fs_root_path = physical_fs_root_path_orig.to_string();
-                }
+    /// Attach the last commit of each git-tracked file to its content resource.
+    pub fn with_git_commits(mut self, git_commits: Option<GitCommits>) -> ResourcesCollection {
+        self.git_commits = git_commits;
+        self
+    }
 
-                let path = vfs_fs_root.join(physical_fs_root_path).unwrap();
-                path.walk_dir().unwrap().flatten()
-            });
+    /// Report second and later paths to the same file as `Duplicate` instead of
+    /// resources (so they're neither hashed nor stored again).
+    pub fn with_seen_inodes(mut self, seen_inodes: Option<SeenInodes>) -> ResourcesCollection {
+        self.seen_inodes = seen_inodes;
+        self
+    }
 
-        ResourcesCollection::new(
-            vfs_iter.map(EncounterableResource::Vfs).collect(),
-            classifier,
-            nature_aliases,
+    /// Drop the resources whose resolved nature isn't allowed by `nature_filter`
+    /// before they become uniform resources (counting them as ignored).
+    pub fn with_nature_filter(
+        mut self,
+        nature_filter: Option<NatureFilter>,
+    ) -> ResourcesCollection {
+        self.nature_filter = nature_filter;
+        self
+    }
+
+    /// Report files last modified before `modified_since` as ignored; directories
+    /// are still walked so recently modified descendants are found.
+    pub fn with_modified_since(
+        mut self,
+        modified_since: Option<DateTime<Utc>>,
+    ) -> ResourcesCollection {
+        self.modified_since = modified_since;
+        self
+    }
+
+    /// Report the files (and capturable executables) which aren't in `sample`
+    /// as ignored.
+    pub fn with_sample(mut self, sample: Option<Sample>) -> ResourcesCollection {
+        self.sample = sample;
+        self
+    }
+
+    /// Report zero-byte files as `EncounteredResource::Empty` rather than
+    /// resources; directories and capturable executables are unaffected.
+    pub fn with_skip_empty_files(mut self, skip_empty_files: bool) -> ResourcesCollection {
+        self.skip_empty_files = skip_empty_files;
+        self
+    }
+
+    /// The roots the resources were walked from, so that each resource's own
+    /// root can be found with `walk_root` when several were walked together.
+    pub fn with_walk_roots(mut self, walk_roots: &[String]) -> ResourcesCollection {
+        self.walk_roots = walk_roots.to_vec();
+        self
+    }
+
+    /// The (deepest, when they're nested) walk root `uri` was encountered under.
+    pub fn walk_root(&self, uri: &str) -> Option<&str> {
+        self.walk_roots
+            .iter()
+            .filter(|walk_root| Path::new(uri).starts_with(walk_root))
+            .max_by_key(|walk_root| walk_root.len())
+            .map(String::as_str)
+    }
+
+    /// With `ordered` capturable executables are encountered in the order of
+    /// their order key (see `capture_order_key`), those without one last and
+    /// ties in walk order; other resources keep their place in the walk.
+    pub fn with_ordered_capture(mut self, ordered: bool) -> ResourcesCollection {
+        if !ordered {
+            return self;
+        }
+        let capturable: Vec<(usize, Option<u64>)> = self
+            .encounterable
+            .iter()
+            .enumerate()
+            .filter_map(|(index, er)| {
+                let uri = er.uri();
+                let erc = self.path_classified(&uri);
+                (erc.flags
+                    .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
+                    && !erc
+                        .flags
+                        .contains(EncounterableResourceFlags::IGNORE_RESOURCE))
+                .then(|| (index, capture_order_key(&uri, &erc.captures)))
+            })
+            .collect();
+        let mut ordered = capturable.clone();
+        ordered.sort_by_key(|(_, key)| (key.is_none(), *key));
+
+        // the capturable executables' slots are refilled in their sorted order
+        let mut slots: Vec<Option<EncounterableResource>> =
+            self.encounterable.drain(..).map(Some).collect();
+        let sorted: Vec<_> = ordered
+            .iter()
+            .filter_map(|(index, _)| slots[*index].take())
+            .collect();
+        for ((index, _), er) in capturable.iter().zip(sorted) {
+            slots[*index] = Some(er);
+        }
+        self.encounterable = slots.into_iter().flatten().collect();
+        self
+    }
+
+    /// Accumulate the time spent reading and hashing content in `hash_timing`.
+    pub fn with_hash_timing(mut self, hash_timing: Option<Arc<HashTiming>>) -> ResourcesCollection {
+        self.hash_timing = hash_timing;
+        self
+    }
+
+    // resources without a modification time (e.g. URLs) are never too old
+    fn recently_modified(
+        &self,
+        encountered: EncounteredResource<ContentResource>,
+    ) -> EncounteredResource<ContentResource> {
+        let Some(modified_since) = self.modified_since else {
+            return encountered;
+        };
+        match encountered {
+            EncounteredResource::Resource(cr, erc)
+            | EncounteredResource::CapturableExec(cr, _, erc)
+                if cr
+                    .last_modified_at
+                    .is_some_and(|modified_at| modified_at < modified_since) =>
+            {
+                EncounteredResource::Ignored(cr.uri, erc)
+            }
+            encountered => encountered,
+        }
+    }
+
+    fn sampled(
+        &self,
+        encountered: EncounteredResource<ContentResource>,
+    ) -> EncounteredResource<ContentResource> {
+        let Some(sample) = self.sample else {
+            return encountered;
+        };
+        match encountered {
+            EncounteredResource::Resource(cr, erc)
+            | EncounteredResource::CapturableExec(cr, _, erc)
+                if !sample.includes(&cr.uri) =>
+            {
+                EncounteredResource::Ignored(cr.uri, erc)
+            }
+            encountered => encountered,
+        }
+    }
+
+    fn non_empty(
+        &self,
+        encountered: EncounteredResource<ContentResource>,
+    ) -> EncounteredResource<ContentResource> {
+        match encountered {
+            EncounteredResource::Resource(cr, erc)
+                if self.skip_empty_files && cr.size == Some(0) =>
+            {
+                EncounteredResource::Empty(cr.uri, erc)
+            }
+            encountered => encountered,
+        }
+    }
+
+    fn nature_allowed(&self, cr: &ContentResource) -> bool {
+        match &self.nature_filter {
+            Some(nature_filter) => {
+                let allowed = nature_filter.allows(self.resolved_nature(cr));
+                if !allowed {
+                    self.encounter_counts.count_filtered();
+                }
+                allowed
+            }
+            None => true,
+        }
+    }
+
+    /// The nature which decides the kind of uniform resource: an exact nature
+    /// alias (of the nature as is, else lowercased), else the first URI regex
+    /// alias, else the resource's own nature.
+    pub fn resolved_nature<'n>(&'n self, cr: &'n ContentResource) -> Option<&'n str> {
+        let cr_nature = cr.nature.as_deref()?;
+        // exact nature aliases take precedence over URI regex aliases
+        Some(
+            match self.nature_aliases.as_ref().and_then(|aliases| {
+                aliases
+                    .get(cr_nature)
+                    .or_else(|| aliases.get(&cr_nature.to_lowercase()))
+            }) {
+                Some(alias) => alias.as_str(),
+                None => self
+                    .nature_regex_aliases
+                    .iter()
+                    .find(|(regex, _)| regex.is_match(&cr.uri))
+                    .map(|(_, alias)| alias.as_str())
+                    .unwrap_or(cr_nature),
+            },
         )
     }
 
+    /// Collect the nature rewrites of the resources which aren't ignored (see
+    /// `take_nature_rewrites`).
+    pub fn with_nature_rewrites_audit(mut self, audit: bool) -> ResourcesCollection {
+        self.nature_rewrites = audit.then(Default::default);
+        self
+    }
+
+    /// The `(uri, reason)` of the paths which couldn't be walked, except those
+    /// the classifier ignores anyway; known before any resource is encountered.
+    pub fn walk_errors(&self) -> Vec<(String, String)> {
+        self.encounterable
+            .iter()
+            .filter_map(|er| match er {
+                EncounterableResource::WalkError(uri, reason)
+                    if !self
+                        .path_classified(uri)
+                        .flags
+                        .contains(EncounterableResourceFlags::IGNORE_RESOURCE) =>
+                {
+                    Some((uri.clone(), reason.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The `(uri, rewrite)` pairs collected since the last call.
+    pub fn take_nature_rewrites(&self) -> Vec<(String, NatureRewrite)> {
+        match &self.nature_rewrites {
+            Some(nature_rewrites) => std::mem::take(&mut *nature_rewrites.lock().unwrap()),
+            None => vec![],
+        }
+    }
+
+    // must be called in walk order so the canonical URI is the first encountered
+    fn deduplicated(
+        &self,
+        er: &EncounterableResource,
+        encountered: EncounteredResource<ContentResource>,
+    ) -> EncounteredResource<ContentResource> {
+        let Some(seen_inodes) = &self.seen_inodes else {
+            return encountered;
+        };
+        let erc = match &encountered {
+            EncounteredResource::Resource(_, erc)
+            | EncounteredResource::CapturableExec(_, _, erc) => erc,
+            _ => return encountered,
+        };
+        let Some(inode) = er.inode() else {
+            return encountered;
+        };
+        let uri = er.uri();
+        let mut seen_inodes = seen_inodes.lock().unwrap();
+        match seen_inodes.get(&inode) {
+            Some(canonical_uri) if *canonical_uri != uri => {
+                EncounteredResource::Duplicate(uri, canonical_uri.clone(), erc.to_owned())
+            }
+            Some(_) => encountered,
+            None => {
+                seen_inodes.insert(inode, uri);
+                encountered
+            }
+        }
+    }
+
+    fn transformed_content_resource(
+        mut cr: ContentResource,
+        transformer: NatureContentTransformer,
+    ) -> ContentResource {
+        let binary = cr.content_binary_supplier.take();
+        let text = cr.content_text_supplier.take();
+        cr.content_text_supplier = Some(Box::new(
+            move || -> Result<Box<dyn TextContent>, Box<dyn Error>> {
+                let (raw, hash) = match (&binary, &text) {
+                    (Some(binary), _) => {
+                        let binary = binary()?;
+                        let hash = binary.content_digest_hash().to_string();
+                        (binary.content_binary().to_owned(), hash)
+                    }
+                    (None, Some(text)) => {
+                        let text = text()?;
+                        let hash = text.content_digest_hash().to_string();
+                        (text.content_text().as_bytes().to_owned(), hash)
+                    }
+                    (None, None) => return Err("no content supplier available to transform".into()),
+                };
+                Ok(Box::new(ResourceTextContent {
+                    hash,
+                    text: transformer(&raw)?,
+                }) as Box<dyn TextContent>)
+            },
+        ));
+        cr
+    }
+
+    // the header is parsed up front so the content is read once here and the
+    // text supplier is replaced with one which returns the already read text;
+    // "CSV" is often `;` or `|` delimited in practice so its delimiter is sniffed
+    fn tabular_resource(
+        mut cr: ContentResource,
+        default_delimiter: u8,
+        sniff_delimiter: bool,
+    ) -> TabularResource<ContentResource> {
+        let text = match cr.content_text_supplier.as_ref().map(|supplier| supplier()) {
+            Some(Ok(text)) => text,
+            _ => {
+                return TabularResource {
+                    resource: cr,
+                    delimiter: default_delimiter,
+                    header: None,
+                }
+            }
+        };
+        let delimiter = if sniff_delimiter {
+            crate::tabular::sniffed_delimiter(text.content_text(), default_delimiter)
+        } else {
+            default_delimiter
+        };
+        let header = crate::tabular::header_fields(text.content_text(), delimiter);
+        let (hash, text) = (
+            text.content_digest_hash().to_string(),
+            text.content_text().to_string(),
+        );
+        cr.content_text_supplier = Some(Box::new(
+            move || -> Result<Box<dyn TextContent>, Box<dyn Error>> {
+                Ok(Box::new(ResourceTextContent {
+                    hash: hash.clone(),
+                    text: text.clone(),
+                }) as Box<dyn TextContent>)
+            },
+        ));
+        TabularResource {
+            resource: cr,
+            delimiter,
+            header,
+        }
+    }
+
+    // PDFs are binary so the text supplier is replaced with one which returns the
+    // extracted page text (or why it could not be extracted); the digest remains
+    // that of the PDF itself
+    fn pdf_resource(mut cr: ContentResource) -> PdfResource<ContentResource> {
+        let extracted = match cr
+            .content_binary_supplier
+            .as_ref()
+            .map(|supplier| supplier())
+        {
+            Some(Ok(binary)) => crate::pdf::extracted_text(binary.content_binary())
+                .map(|pdf_text| (binary.content_digest_hash().to_string(), pdf_text)),
+            Some(Err(err)) => Err(crate::pdf::PdfExtractError::Unreadable(err.to_string())),
+            None => {
+                return PdfResource {
+                    resource: cr,
+                    page_count: None,
+                }
+            }
+        };
+        let page_count = match &extracted {
+            Ok((_, pdf_text)) => Some(pdf_text.page_count),
+            Err(crate::pdf::PdfExtractError::TextUnavailable { page_count, .. }) => {
+                Some(*page_count)
+            }
+            Err(_) => None,
+        };
+        cr.content_text_supplier = Some(Box::new(
+            move || -> Result<Box<dyn TextContent>, Box<dyn Error>> {
+                match &extracted {
+                    Ok((hash, pdf_text)) => Ok(Box::new(ResourceTextContent {
+                        hash: hash.clone(),
+                        text: pdf_text.text.clone(),
+                    }) as Box<dyn TextContent>),
+                    Err(err) => Err(Box::new(err.clone())),
+                }
+            },
+        ));
+        PdfResource {
+            resource: cr,
+            page_count,
+        }
+    }
+
+    // create a physical file system mapped via VFS, mainly for testing and experimental use
+    pub fn from_vfs_physical_fs(
+        fs_root_paths: &[String],
+        classifier: &EncounterableResourcePathClassifier,
+        nature_aliases: &Option<HashMap<String, String>>,
+    ) -> ResourcesCollection {
+        let physical_fs = vfs::PhysicalFS::new("/");
+        let vfs_fs_root = vfs::VfsPath::new(physical_fs);
+
+        let vfs_iter = fs_root_paths
+            .iter()
+            .flat_map(move |physical_fs_root_path_orig| {
+                let physical_fs_root_path: String;
+                if let Ok(canonical) = canonicalize(physical_fs_root_path_orig.clone()) {
+                    physical_fs_root_path = canonical.to_string_lossy().to_string();
+                } else {
+                    eprintln!(
+                        "Error canonicalizing {}, trying original",
+                        physical_fs_root_path_orig
+                    );
+                    physical_fs_root_path = physical_fs_root_path_orig.to_string();
+                }
+
+                // VFS paths are always `/` separated
+                let walk_dir = vfs_fs_root
+                    .join(classifiable_path(&physical_fs_root_path))
+                    .and_then(|root| Ok((root.walk_dir()?, root.as_str().to_string())));
+                match walk_dir {
+                    Ok((walk_dir, vfs_root)) => walk_dir
+                        .map(|entry| match entry {
+                            Ok(path) => {
+                                let os_path = path.as_str().strip_prefix(&vfs_root).map(|rel| {
+                                    Path::new(&physical_fs_root_path)
+                                        .join(rel.trim_start_matches('/'))
+                                });
+                                EncounterableResource::Vfs(path, os_path)
+                            }
+                            Err(err) => EncounterableResource::WalkError(
+                                physical_fs_root_path.clone(),
+                                err.to_string(),
+                            ),
+                        })
+                        .collect(),
+                    Err(err) => vec![EncounterableResource::WalkError(
+                        physical_fs_root_path,
+                        err.to_string(),
+                    )],
+                }
+            });
+
+        ResourcesCollection::new(vfs_iter.collect(), classifier, nature_aliases)
+    }
+
+    /// Each entry of the archive at `archive_fs_path` (see `crate::archive::mount`)
+    /// as an `EncounterableResource::Vfs` below the archive's own path, walked
+    /// exactly like a directory but without extracting anything to disk. Entries
+    /// which weren't loaded (see `limits`) are walk errors.
+    pub fn from_vfs_archive(
+        archive_fs_path: &str,
+        limits: crate::archive::MountLimits,
+        classifier: &EncounterableResourcePathClassifier,
+        nature_aliases: &Option<HashMap<String, String>>,
+    ) -> ResourcesCollection {
+        let encounterable = match crate::archive::mount(archive_fs_path, limits)
+            .and_then(|(mount, unmounted)| Ok((mount.walk_dir()?, unmounted)))
+        {
+            Ok((walk_dir, unmounted)) => walk_dir
+                .map(|entry| match entry {
+                    Ok(path) => EncounterableResource::Vfs(path, None),
+                    Err(err) => EncounterableResource::WalkError(
+                        archive_fs_path.to_string(),
+                        err.to_string(),
+                    ),
+                })
+                .chain(
+                    unmounted
+                        .into_iter()
+                        .map(|(path, reason)| EncounterableResource::WalkError(path, reason)),
+                )
+                .collect(),
+            Err(err) => vec![EncounterableResource::WalkError(
+                archive_fs_path.to_string(),
+                format!("{:#}", err),
+            )],
+        };
+
+        ResourcesCollection::new(encounterable, classifier, nature_aliases)
+    }
+
     // create a ignore::Walk instance which is a "smart" ignore because it honors .gitigore and .ignore
     // files in the walk path as well as the ignore and other directives passed in via options
     pub fn from_smart_ignore(
@@ -1252,42 +3391,126 @@ impl ResourcesCollection {
         classifier: &EncounterableResourcePathClassifier,
         nature_aliases: &Option<HashMap<String, String>>,
         ignore_hidden: bool,
+        follow_symlinks: bool,
+        max_depth: Option<usize>,
     ) -> ResourcesCollection {
+        // when following links both walkers detect cycles and report them as
+        // errors (walk errors, like unreadable directories) so a loop is never re-entered
         let vfs_iter = fs_root_paths.iter().flat_map(move |root_path| {
             let mut walk_builder = ignore::WalkBuilder::new(root_path);
             walk_builder.hidden(ignore_hidden);
+            walk_builder.follow_links(follow_symlinks);
+            walk_builder.max_depth(max_depth);
             for cf in &classifier.smart_ignore_conf_files {
                 walk_builder.add_custom_ignore_filename(cf);
             }
-            walk_builder.build().flatten()
+            let root_path = root_path.clone();
+            walk_builder.build().map(move |entry| match entry {
+                Ok(de) => EncounterableResource::SmartIgnore(de),
+                Err(err) => EncounterableResource::WalkError(
+                    ignore_error_path(&err).unwrap_or_else(|| root_path.clone()),
+                    err.to_string(),
+                ),
+            })
         });
 
-        ResourcesCollection::new(
-            vfs_iter.map(EncounterableResource::SmartIgnore).collect(),
-            classifier,
-            nature_aliases,
-        )
+        ResourcesCollection::new(vfs_iter.collect(), classifier, nature_aliases)
     }
 
     // create a traditional walkdir::WalkDir which only ignore files based on file names rules passed in
+    // and the globs in the classifier's ignore conf files (e.g. `.surveilr_ignore`), like the smart walker
     pub fn from_walk_dir(
         fs_root_paths: &[String],
         classifier: &EncounterableResourcePathClassifier,
         nature_aliases: &Option<HashMap<String, String>>,
+        follow_symlinks: bool,
+        max_depth: Option<usize>,
     ) -> ResourcesCollection {
-        let vfs_iter = fs_root_paths
+        let vfs_iter = fs_root_paths.iter().flat_map(move |root_path| {
+            let mut walk_dir = walkdir::WalkDir::new(root_path).follow_links(follow_symlinks);
+            if let Some(max_depth) = max_depth {
+                walk_dir = walk_dir.max_depth(max_depth);
+            }
+            let mut ignore_conf = IgnoreConfFiles::new(&classifier.smart_ignore_conf_files);
+            let root_path = root_path.clone();
+            walk_dir
+                .into_iter()
+                .filter_entry(move |de| {
+                    de.depth() == 0 || !ignore_conf.is_ignored(de.path(), de.file_type().is_dir())
+                })
+                .map(move |entry| match entry {
+                    Ok(de) => EncounterableResource::WalkDir(de),
+                    Err(err) => EncounterableResource::WalkError(
+                        err.path()
+                            .map(|path| path.to_string_lossy().to_string())
+                            .unwrap_or_else(|| root_path.clone()),
+                        err.to_string(),
+                    ),
+                })
+        });
+
+        ResourcesCollection::new(vfs_iter.collect(), classifier, nature_aliases)
+    }
+
+    pub fn from_walker(
+        walker: Walker,
+        fs_root_paths: &[String],
+        classifier: &EncounterableResourcePathClassifier,
+        nature_aliases: &Option<HashMap<String, String>>,
+        follow_symlinks: bool,
+        max_depth: Option<usize>,
+    ) -> ResourcesCollection {
+        match walker {
+            Walker::Smart => ResourcesCollection::from_smart_ignore(
+                fs_root_paths,
+                classifier,
+                nature_aliases,
+                false,
+                follow_symlinks,
+                max_depth,
+            ),
+            Walker::Walkdir => ResourcesCollection::from_walk_dir(
+                fs_root_paths,
+                classifier,
+                nature_aliases,
+                follow_symlinks,
+                max_depth,
+            ),
+            Walker::Vfs => {
+                ResourcesCollection::from_vfs_physical_fs(fs_root_paths, classifier, nature_aliases)
+            }
+        }
+    }
+
+    /// Each of `fs_paths` exactly as given (directories aren't walked), e.g. a
+    /// file list piped from `find`.
+    pub fn from_fs_paths(
+        fs_paths: &[String],
+        classifier: &EncounterableResourcePathClassifier,
+        nature_aliases: &Option<HashMap<String, String>>,
+        follow_symlinks: bool,
+    ) -> ResourcesCollection {
+        let encounterable = fs_paths
             .iter()
-            .flat_map(move |root_path| walkdir::WalkDir::new(root_path).into_iter().flatten());
+            .filter_map(|fs_path| {
+                let entry = walkdir::WalkDir::new(fs_path)
+                    .max_depth(0)
+                    .follow_links(follow_symlinks)
+                    .into_iter()
+                    .next()?;
+                Some(match entry {
+                    Ok(de) => EncounterableResource::WalkDir(de),
+                    Err(err) => EncounterableResource::WalkError(fs_path.clone(), err.to_string()),
+                })
+            })
+            .collect();
 
-        ResourcesCollection::new(
-            vfs_iter.map(EncounterableResource::WalkDir).collect(),
-            classifier,
-            nature_aliases,
-        )
+        ResourcesCollection::new(encounterable, classifier, nature_aliases)
     }
 
     pub fn from_tasks_lines(
         tasks: &[String],
+        plain_line_nature: &str,
         classifier: &EncounterableResourcePathClassifier,
         nature_aliases: &Option<HashMap<String, String>>,
     ) -> (Vec<String>, ResourcesCollection) {
@@ -1303,7 +3526,9 @@ impl ResourcesCollection {
             ResourcesCollection::new(
                 encounterable
                     .iter()
-                    .map(EncounterableResource::from_deno_task_shell_line)
+                    .map(|line| {
+                        EncounterableResource::from_deno_task_shell_line(line, plain_line_nature)
+                    })
                     .collect(),
                 classifier,
                 nature_aliases,
@@ -1311,6 +3536,37 @@ impl ResourcesCollection {
         )
     }
 
+    pub fn from_urls(
+        urls: &[String],
+        classifier: &EncounterableResourcePathClassifier,
+        nature_aliases: &Option<HashMap<String, String>>,
+    ) -> ResourcesCollection {
+        ResourcesCollection::new(
+            urls.iter()
+                .map(|url| url.trim())
+                .filter(|url| !url.is_empty() && !url.starts_with('#'))
+                .map(|url| EncounterableResource::Http(url.to_string()))
+                .collect(),
+            classifier,
+            nature_aliases,
+        )
+    }
+
+    pub fn from_imap(
+        resources: Vec<ImapResource>,
+        classifier: &EncounterableResourcePathClassifier,
+        nature_aliases: &Option<HashMap<String, String>>,
+    ) -> ResourcesCollection {
+        ResourcesCollection::new(
+            resources
+                .into_iter()
+                .map(EncounterableResource::Imap)
+                .collect(),
+            classifier,
+            nature_aliases,
+        )
+    }
+
     pub fn ignored(&self) -> impl Iterator<Item = EncounteredResource<ContentResource>> + '_ {
         self.encountered()
             .filter(|er| matches!(er, EncounteredResource::Ignored(_, _)))
@@ -1331,12 +3587,241 @@ impl ResourcesCollection {
     pub fn encountered(&self) -> impl Iterator<Item = EncounteredResource<ContentResource>> + '_ {
         self.encounterable.iter().map(move |er| {
             let uri = er.uri();
-            let mut ero = EncounterableResourceClass {
-                nature: None,
-                flags: EncounterableResourceFlags::empty(),
-            };
-            self.classifier.classify(&uri, &mut ero);
-            er.encountered(&ero)
+            let _span = tracing::info_span!("classify", uri = %uri).entered();
+            self.screened(er, er.encountered(&self.classified(&uri)))
+        })
+    }
+
+    // the filters shared by `encountered` and `par_uniform_resources` so both
+    // ignore, skip and deduplicate the same resources
+    fn screened(
+        &self,
+        er: &EncounterableResource,
+        encountered: EncounteredResource<ContentResource>,
+    ) -> EncounteredResource<ContentResource> {
+        self.deduplicated(
+            er,
+            self.non_empty(self.recently_modified(self.sampled(encountered))),
+        )
+    }
+
+    /// How `uri` is classified by the classifier's rules (see `PathExplanation`).
+    pub fn explain_path(&self, uri: &str) -> PathExplanation {
+        let text = classifiable_path(uri);
+        let path_rule = self
+            .classifier
+            .matching_path_rule(&text)
+            .map(|pr| (pr.action, pr.regex.as_str().to_string()));
+        let path_rewrite = self.classifier.rewrite_path_regexs.iter().find_map(|rnr| {
+            rnr.rewritten_text(&text)
+                .map(|rewritten| (rnr.regex.as_str().to_string(), rewritten))
+        });
+        let flaggable_matches = self.classifier.flaggable_matches(&text);
+        let decided_by = match &path_rule {
+            Some((PathRuleAction::Exclude, _)) => None,
+            Some((PathRuleAction::Include, _)) => flaggable_matches
+                .iter()
+                .find(|m| {
+                    !m.flags
+                        .contains(EncounterableResourceFlags::IGNORE_RESOURCE)
+                })
+                .cloned(),
+            None => flaggable_matches.first().cloned(),
+        };
+
+        let erc = self.path_classified(uri);
+        PathExplanation {
+            path: uri.to_string(),
+            path_rule,
+            path_rewrite,
+            flaggable_matches,
+            decided_by,
+            ignored: erc
+                .flags
+                .contains(EncounterableResourceFlags::IGNORE_RESOURCE),
+            acquire_content: erc
+                .flags
+                .contains(EncounterableResourceFlags::CONTENT_ACQUIRABLE),
+            capturable_executable: erc
+                .flags
+                .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE),
+            capturable_sql: erc
+                .flags
+                .contains(EncounterableResourceFlags::CAPTURABLE_SQL),
+            flags: erc.flags,
+            nature: erc.nature,
+            nature_rewrite: erc.nature_rewrite,
+            captures: erc.captures,
+        }
+    }
+
+    // the classifier's rules alone, without recording nature rewrites
+    fn path_classified(&self, uri: &str) -> EncounterableResourceClass {
+        let mut erc = self.unclassified();
+        self.classifier.classify(uri, &mut erc);
+        self.glob_classify(uri, &mut erc);
+        if self.classifier.resolve_symlink_nature
+            && !erc
+                .flags
+                .contains(EncounterableResourceFlags::IGNORE_RESOURCE)
+        {
+            if let Some(resolved) = resolved_symlink(uri) {
+                let mut resolved_erc = self.unclassified();
+                self.classifier.classify(&resolved, &mut resolved_erc);
+                if !resolved_erc
+                    .flags
+                    .contains(EncounterableResourceFlags::IGNORE_RESOURCE)
+                {
+                    erc = resolved_erc;
+                }
+            }
+        }
+        erc
+    }
+
+    // globs are matched against the path relative to its walk root, or the
+    // whole path when it wasn't walked from one
+    fn glob_classify(&self, uri: &str, erc: &mut EncounterableResourceClass) {
+        if self.classifier.path_globs.is_empty()
+            || erc
+                .flags
+                .contains(EncounterableResourceFlags::IGNORE_RESOURCE)
+        {
+            return;
+        }
+        let rel_path = match self
+            .walk_root(uri)
+            .and_then(|walk_root| Path::new(uri).strip_prefix(walk_root).ok())
+        {
+            Some(rel_path) => rel_path.to_string_lossy().replace('\\', "/"),
+            None => classifiable_path(uri).into_owned(),
+        };
+        match self.classifier.path_glob_action(&rel_path) {
+            Some(PathGlobAction::Ignore) => erc
+                .flags
+                .insert(EncounterableResourceFlags::IGNORE_RESOURCE),
+            Some(PathGlobAction::Acquire) => {
+                erc.flags
+                    .insert(EncounterableResourceFlags::CONTENT_ACQUIRABLE);
+                if erc.nature.is_none() {
+                    erc.nature = Path::new(uri)
+                        .extension()
+                        .map(|extension| extension.to_string_lossy().to_string());
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn unclassified(&self) -> EncounterableResourceClass {
+        EncounterableResourceClass {
+            nature: None,
+            flags: EncounterableResourceFlags::empty(),
+            digest_algorithm: self.classifier.digest_algorithm,
+            sniff_content_nature: self.classifier.sniff_content_nature,
+            sniff_sqlite: self.classifier.sniff_sqlite,
+            max_content_bytes: self.classifier.max_content_bytes,
+            text_encoding_fallback: self.classifier.text_encoding_fallback,
+            content_acquisition: self.classifier.content_acquisition,
+            capture_exec_timeout: Duration::from_secs(self.classifier.capture_exec_timeout_secs),
+            capture_exec_env: self.classifier.capture_exec_env.clone(),
+            capture_exec_retry: RetryPolicy::new(self.classifier.max_retries),
+            capture_nature_from_directive: self.classifier.capture_nature_from_directive,
+            nature_rewrite: None,
+            captures: HashMap::new(),
+            hash_timing: self.hash_timing.clone(),
+        }
+    }
+
+    // binding a URI to a nature is a request for its content so regex-aliased
+    // resources are acquirable even if no classifier rule says so
+    fn classified(&self, uri: &str) -> EncounterableResourceClass {
+        let erc = self.unrecorded_classified(uri);
+        if let (Some(nature_rewrites), Some(nature_rewrite)) =
+            (&self.nature_rewrites, &erc.nature_rewrite)
+        {
+            if !erc
+                .flags
+                .contains(EncounterableResourceFlags::IGNORE_RESOURCE)
+            {
+                nature_rewrites
+                    .lock()
+                    .unwrap()
+                    .push((uri.to_string(), nature_rewrite.clone()));
+            }
+        }
+        erc
+    }
+
+    // same as `classified` without recording nature rewrites
+    fn unrecorded_classified(&self, uri: &str) -> EncounterableResourceClass {
+        let mut erc = self.path_classified(uri);
+        if self.classifier.acquire_capturable_content
+            && erc
+                .flags
+                .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
+        {
+            erc.flags
+                .insert(EncounterableResourceFlags::CONTENT_ACQUIRABLE);
+        }
+        if !erc
+            .flags
+            .contains(EncounterableResourceFlags::IGNORE_RESOURCE)
+            && self
+                .nature_regex_aliases
+                .iter()
+                .any(|(regex, _)| regex.is_match(uri))
+        {
+            erc.flags
+                .insert(EncounterableResourceFlags::CONTENT_ACQUIRABLE);
+        }
+        erc
+    }
+
+    /// The capturable executables `uniform_resources` yields, in the same order,
+    /// without counting them or recording their nature rewrites so they can be
+    /// executed ahead of the walk (see `ingest files --capture-exec-concurrency`).
+    pub fn capturable_exec_resources(
+        &self,
+    ) -> impl Iterator<Item = CapturableExecResource<ContentResource>> + '_ {
+        let mut capturable_inodes = HashSet::new();
+        self.encounterable.iter().filter_map(move |er| {
+            let uri = er.uri();
+            let erc = self.unrecorded_classified(&uri);
+            if !erc
+                .flags
+                .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
+                || erc
+                    .flags
+                    .contains(EncounterableResourceFlags::IGNORE_RESOURCE)
+            {
+                return None;
+            }
+            // duplicates are never executed (see `deduplicated`)
+            if let (Some(seen_inodes), Some(inode)) = (&self.seen_inodes, er.inode()) {
+                let seen_elsewhere = seen_inodes
+                    .lock()
+                    .unwrap()
+                    .get(&inode)
+                    .is_some_and(|canonical_uri| *canonical_uri != uri);
+                if seen_elsewhere || !capturable_inodes.insert(inode) {
+                    return None;
+                }
+            }
+            match self.recently_modified(self.sampled(er.encountered(&erc))) {
+                EncounteredResource::CapturableExec(resource, executable, erc)
+                    if self.nature_filter.as_ref().map_or(true, |nature_filter| {
+                        nature_filter.allows(self.resolved_nature(&resource))
+                    }) =>
+                {
+                    Some(CapturableExecResource {
+                        resource,
+                        executable,
+                        captures: erc.captures,
+                    })
+                }
+                _ => None,
+            }
         })
     }
 
@@ -1345,53 +3830,208 @@ impl ResourcesCollection {
     ) -> impl Iterator<Item = anyhow::Result<UniformResource<ContentResource>, Box<dyn Error>>> + '_
     {
         self.encountered()
+            .inspect(move |er| self.encounter_counts.count(er))
             .filter_map(move |er: EncounteredResource<ContentResource>| match er {
+                EncounteredResource::Resource(resource, _) if !self.nature_allowed(&resource) => {
+                    None
+                }
+                EncounteredResource::CapturableExec(resource, _, _)
+                    if !self.nature_allowed(&resource) =>
+                {
+                    None
+                }
                 EncounteredResource::Resource(resource, _) => {
                     match self.uniform_resource(resource) {
                         Ok(uniform_resource) => Some(Ok(*uniform_resource)),
                         Err(e) => Some(Err(e)), // error will be returned
                     }
                 }
-                EncounteredResource::CapturableExec(resource, executable, _) => Some(Ok(
+                EncounteredResource::CapturableExec(resource, executable, erc) => Some(Ok(
                     UniformResource::CapturableExec(CapturableExecResource {
                         resource,
                         executable,
+                        captures: erc.captures,
                     }),
                 )),
                 EncounteredResource::Ignored(_, _)
                 | EncounteredResource::NotFile(_, _)
-                | EncounteredResource::NotFound(_, _) => None, // these will be filtered via `filter_map`
+                | EncounteredResource::NotFound(_, _, _)
+                | EncounteredResource::HttpNotOk(_, _, _)
+                | EncounteredResource::Duplicate(_, _, _)
+                | EncounteredResource::Empty(_, _)
+                | EncounteredResource::WalkError(_, _, _) => None, // these will be filtered via `filter_map`
             })
     }
 
+    /// Same as `uniform_resources` but classification and content hashing are
+    /// spread across `pool`'s threads. Resources are handled in chunks so that
+    /// only a bounded amount of prefetched content is held in memory and the
+    /// items are yielded in the same order as the single-threaded variant.
+    #[cfg(feature = "rayon")]
+    pub fn par_uniform_resources<'a>(
+        &'a self,
+        pool: &'a rayon::ThreadPool,
+    ) -> impl Iterator<Item = anyhow::Result<UniformResource<ContentResource>, Box<dyn Error>>> + 'a
+    {
+        use rayon::prelude::*;
+
+        let chunk_size = pool.current_num_threads().max(1) * 64;
+        self.encounterable
+            .chunks(chunk_size)
+            .flat_map(move |chunk| {
+                let prefetched: Vec<_> =
+                    pool.install(|| chunk.par_iter().map(|er| self.prefetched(er)).collect());
+                chunk
+                    .iter()
+                    .zip(prefetched)
+                    .map(move |(er, (erc, content))| {
+                        // duplicates were already hashed by the workers, only
+                        // storing them again is avoided
+                        let encountered = self.screened(er, er.encountered(&erc));
+                        self.encounter_counts.count(&encountered);
+                        (encountered, content)
+                    })
+                    .filter_map(move |(encountered, content)| match encountered {
+                        EncounteredResource::Resource(resource, _)
+                            if !self.nature_allowed(&resource) =>
+                        {
+                            None
+                        }
+                        EncounteredResource::CapturableExec(resource, _, _)
+                            if !self.nature_allowed(&resource) =>
+                        {
+                            None
+                        }
+                        EncounteredResource::Resource(mut resource, erc) => {
+                            if let Some(content) = content {
+                                Self::supply_prefetched_content(&mut resource, &erc, content);
+                            }
+                            match self.uniform_resource(resource) {
+                                Ok(uniform_resource) => Some(Ok(*uniform_resource)),
+                                Err(e) => Some(Err(e)),
+                            }
+                        }
+                        EncounteredResource::CapturableExec(resource, executable, erc) => Some(Ok(
+                            UniformResource::CapturableExec(CapturableExecResource {
+                                resource,
+                                executable,
+                                captures: erc.captures,
+                            }),
+                        )),
+                        EncounteredResource::Ignored(_, _)
+                        | EncounteredResource::NotFile(_, _)
+                        | EncounteredResource::NotFound(_, _, _)
+                        | EncounteredResource::HttpNotOk(_, _, _)
+                        | EncounteredResource::Duplicate(_, _, _)
+                        | EncounteredResource::Empty(_, _)
+                        | EncounteredResource::WalkError(_, _, _) => None,
+                    })
+            })
+    }
+
+    // classify and (when acquirable) read and hash the content on a worker thread;
+    // errors are carried as strings since `Box<dyn Error>` can't cross threads
+    #[cfg(feature = "rayon")]
+    fn prefetched(
+        &self,
+        er: &EncounterableResource,
+    ) -> (EncounterableResourceClass, Option<PrefetchedContent>) {
+        let uri = er.uri();
+        let _span = tracing::info_span!("classify", uri = %uri).entered();
+        let erc = self.classified(&uri);
+        if erc.flags.intersects(
+            EncounterableResourceFlags::IGNORE_RESOURCE
+                | EncounterableResourceFlags::CAPTURABLE_EXECUTABLE,
+        ) {
+            return (erc, None);
+        }
+
+        let content = er
+            .content_suppliers(&erc)
+            .binary
+            .map(|binary| match binary() {
+                Ok(binary) => Ok((
+                    binary.content_digest_hash().to_string(),
+                    Arc::new(binary.content_binary().to_owned()),
+                )),
+                Err(err) => Err(err.to_string()),
+            });
+        (erc, content)
+    }
+
+    // only the suppliers the resource was encountered with (see
+    // `EncounteredResourceContentSuppliers::acquiring`) are replaced
+    #[cfg(feature = "rayon")]
+    fn supply_prefetched_content(
+        cr: &mut ContentResource,
+        erc: &EncounterableResourceClass,
+        content: PrefetchedContent,
+    ) {
+        if cr.content_binary_supplier.is_some() {
+            let binary_content = content.clone();
+            cr.content_binary_supplier = Some(Box::new(
+                move || -> Result<Box<dyn BinaryContent>, Box<dyn Error>> {
+                    let (hash, binary) = binary_content.clone()?;
+                    Ok(Box::new(ResourceBinaryContent {
+                        hash,
+                        binary: binary.to_vec(),
+                    }) as Box<dyn BinaryContent>)
+                },
+            ));
+        }
+        if cr.content_text_supplier.is_some() {
+            let text_encoding_fallback = erc.text_encoding_fallback;
+            let digest_algorithm = erc.digest_algorithm;
+            cr.content_text_supplier = Some(Box::new(
+                move || -> Result<Box<dyn TextContent>, Box<dyn Error>> {
+                    let (_, binary) = content.clone()?;
+                    text_encoding_fallback.text_content(binary.to_vec(), digest_algorithm)
+                },
+            ));
+        }
+    }
+
     pub fn uniform_resource(
         &self,
-        cr: ContentResource,
+        mut cr: ContentResource,
     ) -> Result<Box<UniformResource<ContentResource>>, Box<dyn Error>> {
-        // Based on the nature of the resource, we determine the type of UniformResource
-        if let Some(cr_nature) = &cr.nature {
-            let candidate_nature = if let Some(aliases) = &self.nature_aliases {
-                if let Some(alias) = aliases.get(cr_nature.as_str()) {
-                    alias.as_str()
-                } else {
-                    cr_nature.as_str()
+        if let Some(git_commits) = &self.git_commits {
+            cr.git = git_commits.last_commit(Path::new(&cr.uri)).cloned();
+        }
+        // Based on the nature of the resource, we determine the type of UniformResource;
+        // natures from extensions keep their case (`README.MD`) so they're lowercased
+        if let Some(candidate_nature) = self.resolved_nature(&cr).map(str::to_lowercase) {
+            let candidate_nature = candidate_nature.as_str();
+
+            if let Some(transformer) = self.content_transformers.get(candidate_nature) {
+                // only content which was acquired can be transformed, otherwise use the built-ins
+                if cr.content_binary_supplier.is_some() || cr.content_text_supplier.is_some() {
+                    let transformed = PlainTextResource {
+                        resource: Self::transformed_content_resource(cr, transformer.clone()),
+                    };
+                    return Ok(Box::new(UniformResource::PlainText(transformed)));
                 }
-            } else {
-                cr_nature.as_str()
-            };
+            }
 
             match candidate_nature {
                 // Match different file extensions
                 "html" | "text/html" => {
-                    let html = HtmlResource {
+                    // `--html-select` selectors are run (see `crate::html`) when the
+                    // content is written, not here, since only then is it read
+                    let html = HtmlResource { resource: cr };
+                    Ok(Box::new(UniformResource::Html(html)))
+                }
+                "eml" | "message/rfc822" | "msg" | "application/vnd.ms-outlook" => {
+                    let format = match candidate_nature {
+                        "eml" | "message/rfc822" => EmailFormat::Rfc822,
+                        "msg" | "application/vnd.ms-outlook" => EmailFormat::OutlookMsg,
+                        _ => EmailFormat::Unknown,
+                    };
+                    let email = EmailResource {
                         resource: cr,
-                        // TODO parse using
-                        //      - https://github.com/y21/tl (performant but not spec compliant)
-                        //      - https://github.com/cloudflare/lol-html (more performant, spec compliant)
-                        //      - https://github.com/causal-agent/scraper or https://github.com/servo/html5ever directly
-                        // create HTML parser presets which can go through all stored HTML, running selectors and putting them into tables?
+                        format,
                     };
-                    Ok(Box::new(UniformResource::Html(html)))
+                    Ok(Box::new(UniformResource::Email(email)))
                 }
                 "json" | "jsonc" | "application/json" => {
                     let format = match candidate_nature {
@@ -1457,6 +4097,24 @@ impl ResourcesCollection {
                     };
                     Ok(Box::new(UniformResource::Xml(xml)))
                 }
+                "csv" | "tsv" | "text/csv" | "text/tab-separated-values" => {
+                    let (default_delimiter, sniff_delimiter) = match candidate_nature {
+                        "tsv" | "text/tab-separated-values" => (b'\t', false),
+                        _ => (b',', true),
+                    };
+                    Ok(Box::new(UniformResource::Tabular(Self::tabular_resource(
+                        cr,
+                        default_delimiter,
+                        sniff_delimiter,
+                    ))))
+                }
+                "pdf" | "application/pdf" => {
+                    Ok(Box::new(UniformResource::Pdf(Self::pdf_resource(cr))))
+                }
+                "db" | "sqlite" | "sqlite3" | "application/vnd.sqlite3" => {
+                    let sqlite = SqliteDbResource { resource: cr };
+                    Ok(Box::new(UniformResource::SqliteDb(sqlite)))
+                }
                 _ => Ok(Box::new(UniformResource::Unknown(cr, None))),
             }
         } else {
@@ -1492,7 +4150,15 @@ pub fn extract_path_info(
     root_path: &Path,
     root_path_entry: &Path,
 ) -> Option<(PathBuf, PathBuf, PathBuf, String, Option<String>)> {
-    let file_path_abs = root_path_entry.canonicalize().ok()?;
+    // dangling symlinks can't be canonicalized, their own path is used instead
+    // (as it is for the entries of an archive, which only exist within it)
+    let file_path_abs = match root_path_entry.canonicalize() {
+        Ok(file_path_abs) => file_path_abs,
+        Err(_) if root_path_entry.is_symlink() || root_path.is_file() => {
+            root_path_entry.to_path_buf()
+        }
+        Err(_) => return None,
+    };
     let file_path_rel_parent = root_path_entry.parent()?.to_path_buf();
     let file_path_rel = root_path_entry.strip_prefix(root_path).ok()?.to_path_buf();
     let file_basename = root_path_entry.file_name()?.to_str()?.to_string();
@@ -1509,3 +4175,367 @@ pub fn extract_path_info(
         file_extn,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_windows_path_with_slashes() {
+        assert_eq!(
+            windows_path_with_slashes(r"\\?\C:\Users\dev\notes.md"),
+            "C:/Users/dev/notes.md"
+        );
+        assert_eq!(
+            windows_path_with_slashes(r"\\?\UNC\server\share\docs\notes.md"),
+            "//server/share/docs/notes.md"
+        );
+        assert_eq!(
+            windows_path_with_slashes(r"\\server\share\notes.md"),
+            "//server/share/notes.md"
+        );
+        assert_eq!(
+            windows_path_with_slashes(r"C:\Users\dev\notes.md"),
+            "C:/Users/dev/notes.md"
+        );
+    }
+
+    #[test]
+    fn test_classify_windows_paths() {
+        let mut classifier = EncounterableResourcePathClassifier::default();
+        classifier
+            .add_path_rule(PathRuleAction::Exclude, r"/target/")
+            .unwrap();
+
+        let ignored = windows_path_with_slashes(r"\\?\C:\src\app\node_modules\index.json");
+        assert_eq!(
+            classifier.flaggable_matches(&ignored)[0].flags,
+            EncounterableResourceFlags::IGNORE_RESOURCE
+        );
+        let excluded = windows_path_with_slashes(r"\\?\UNC\server\share\app\target\out.md");
+        assert_eq!(
+            classifier.path_rule_action(&excluded),
+            Some(PathRuleAction::Exclude)
+        );
+
+        let acquirable = windows_path_with_slashes(r"\\?\UNC\server\share\app\README.md");
+        assert_eq!(classifier.path_rule_action(&acquirable), None);
+        let matches = classifier.flaggable_matches(&acquirable);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].nature.as_deref(), Some("md"));
+    }
+
+    #[test]
+    fn test_uniform_resource_uppercase_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let fs_paths: Vec<String> = ["README.MD", "data.JSON", "notes.Markdown", "logo.PNG"]
+            .iter()
+            .map(|file_name| {
+                let fs_path = dir.path().join(file_name);
+                std::fs::write(&fs_path, "{}").unwrap();
+                fs_path.to_string_lossy().to_string()
+            })
+            .collect();
+        let nature_aliases = Some(HashMap::from([("markdown".to_string(), "md".to_string())]));
+        let resources = ResourcesCollection::from_fs_paths(
+            &fs_paths,
+            &EncounterableResourcePathClassifier::default(),
+            &nature_aliases,
+            false,
+        );
+        let kinds: Vec<&str> = resources
+            .uniform_resources()
+            .map(|ur| match ur.unwrap() {
+                UniformResource::Markdown(_) => "markdown",
+                UniformResource::Json(_) => "json",
+                UniformResource::Image(_) => "image",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, vec!["markdown", "json", "markdown", "image"]);
+    }
+
+    #[test]
+    fn test_digest_algorithm() {
+        let content = "x".repeat(DIGEST_CHUNK_BYTES * 2 + 1);
+        for algorithm in [
+            DigestAlgorithm::Sha1,
+            DigestAlgorithm::Sha256,
+            DigestAlgorithm::Blake3,
+        ] {
+            let digest = algorithm.digest(&content);
+            assert_eq!(algorithm.digest_reader(content.as_bytes()).unwrap(), digest);
+            assert!(algorithm.is_digest_of(&digest));
+            assert_eq!(DigestAlgorithm::from_digest(&digest), algorithm);
+        }
+
+        assert_eq!(
+            DigestAlgorithm::Sha1.digest("abc"),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            DigestAlgorithm::Sha256.digest("abc"),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            DigestAlgorithm::Blake3.digest("abc"),
+            "blake3:6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert!(!DigestAlgorithm::Sha1.is_digest_of("sha256:ba78"));
+        assert!(!DigestAlgorithm::Sha256.is_digest_of("blake3:6437"));
+        assert!(!DigestAlgorithm::Blake3.is_digest_of("a9993e36"));
+    }
+
+    #[test]
+    fn test_content_transformer() {
+        let dir = tempfile::tempdir().unwrap();
+        let fs_path = dir.path().join("notes.md");
+        std::fs::write(&fs_path, "# Hello").unwrap();
+        let resources = ResourcesCollection::from_fs_paths(
+            &[fs_path.to_string_lossy().to_string()],
+            &EncounterableResourcePathClassifier::default(),
+            &None,
+            false,
+        )
+        .with_content_transformer("md", |raw| {
+            Ok(String::from_utf8(raw.to_vec())?.to_uppercase())
+        });
+
+        let ur = resources.uniform_resources().next().unwrap().unwrap();
+        let UniformResource::PlainText(transformed) = ur else {
+            panic!("transformed content should be stored as plain text");
+        };
+        let text = (transformed.resource.content_text_supplier.as_ref().unwrap())().unwrap();
+        assert_eq!(text.content_text(), "# HELLO");
+        assert_eq!(
+            text.content_digest_hash(),
+            DigestAlgorithm::Sha1.digest("# Hello")
+        );
+    }
+
+    #[test]
+    fn test_walk_root() {
+        let resources = ResourcesCollection::new(
+            vec![],
+            &EncounterableResourcePathClassifier::default(),
+            &None,
+        )
+        .with_walk_roots(&["/srv".to_string(), "/srv/app".to_string()]);
+        assert_eq!(resources.walk_root("/srv/app/README.md"), Some("/srv/app"));
+        assert_eq!(
+            resources.walk_root("/srv/application/README.md"),
+            Some("/srv")
+        );
+        assert_eq!(resources.walk_root("/etc/hosts"), None);
+    }
+
+    #[test]
+    fn test_vfs_physical_fs_meta_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let fs_path = dir.path().join("notes.md");
+        std::fs::write(&fs_path, "# Notes").unwrap();
+        let fs_path = canonicalize(fs_path).unwrap();
+
+        let resources = ResourcesCollection::from_vfs_physical_fs(
+            &[dir.path().to_string_lossy().to_string()],
+            &EncounterableResourcePathClassifier::default(),
+            &None,
+        );
+        let vfs_meta_data = resources
+            .encounterable
+            .iter()
+            .find(|er| er.uri().ends_with("/notes.md"))
+            .unwrap()
+            .meta_data()
+            .unwrap();
+        let fs_meta_data = EncounteredResourceMetaData::from_fs_path(&fs_path).unwrap();
+        assert_eq!(vfs_meta_data.file_size, fs_meta_data.file_size);
+        assert!(vfs_meta_data.last_modified_at.is_some());
+        assert_eq!(
+            vfs_meta_data.last_modified_at,
+            fs_meta_data.last_modified_at
+        );
+        assert_eq!(vfs_meta_data.created_at, fs_meta_data.created_at);
+    }
+
+    #[test]
+    fn test_sample() {
+        let uris: Vec<String> = (0..1000).map(|i| format!("/docs/{}.md", i)).collect();
+        let sampled = |sample: Sample| -> Vec<&String> {
+            uris.iter().filter(|uri| sample.includes(uri)).collect()
+        };
+
+        let quarter = sampled(Sample::new(25.0, Some(42)));
+        assert!((200..300).contains(&quarter.len()));
+        assert_eq!(quarter, sampled(Sample::new(25.0, Some(42))));
+        assert_ne!(quarter, sampled(Sample::new(25.0, Some(43))));
+        assert!(sampled(Sample::new(0.0, Some(42))).is_empty());
+        assert_eq!(sampled(Sample::new(100.0, Some(42))).len(), uris.len());
+        assert!(resolved_sample_seed(None) <= i64::MAX as u64);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_uniform_resources_parity() {
+        let dir = tempfile::tempdir().unwrap();
+        let fs_path = dir.path().join("latin1.txt");
+        std::fs::write(&fs_path, b"caf\xe9").unwrap();
+        let fs_paths = [fs_path.to_string_lossy().to_string()];
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+
+        let resources = |classifier: &EncounterableResourcePathClassifier| {
+            ResourcesCollection::from_fs_paths(&fs_paths, classifier, &None, false)
+        };
+
+        // `--since` ignores the file just like the single-threaded variant
+        let since = resources(&EncounterableResourcePathClassifier::default())
+            .with_modified_since(Some(Utc::now() + chrono::Duration::hours(1)));
+        assert_eq!(since.par_uniform_resources(&pool).count(), 0);
+        assert_eq!(since.uniform_resources().count(), 0);
+
+        // the text fallback is applied to the prefetched content
+        let lossy = resources(&EncounterableResourcePathClassifier {
+            text_encoding_fallback: TextEncodingFallback::Lossy,
+            ..Default::default()
+        });
+        let ur = lossy.par_uniform_resources(&pool).next().unwrap().unwrap();
+        let text_supplier = ur
+            .content_resource()
+            .content_text_supplier
+            .as_ref()
+            .unwrap();
+        assert_eq!(text_supplier().unwrap().content_text(), "caf\u{FFFD}");
+
+        // no text supplier is installed with `--content-binary-only`
+        let binary_only = resources(&EncounterableResourcePathClassifier {
+            content_acquisition: ContentAcquisition::BinaryOnly,
+            ..Default::default()
+        });
+        let ur = binary_only
+            .par_uniform_resources(&pool)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(ur.content_resource().content_text_supplier.is_none());
+        assert!(ur.content_resource().content_binary_supplier.is_some());
+    }
+
+    #[test]
+    fn test_persistable_timestamp() {
+        let at = DateTime::parse_from_rfc3339("2024-05-01T10:30:00.5+02:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(persistable_timestamp(&at), "2024-05-01T08:30:00.500000000Z");
+    }
+
+    #[test]
+    fn test_remove_flaggables() {
+        let mut classifier = EncounterableResourcePathClassifier::default();
+        classifier.remove_flaggables(EncounterableResourceFlags::IGNORE_RESOURCE);
+        let resources = ResourcesCollection::new(vec![], &classifier, &None);
+
+        assert!(!resources
+            .path_classified("/repo/.git/config")
+            .flags
+            .contains(EncounterableResourceFlags::IGNORE_RESOURCE));
+        assert!(resources
+            .path_classified("/repo/README.md")
+            .flags
+            .contains(EncounterableResourceFlags::CONTENT_ACQUIRABLE));
+    }
+
+    #[test]
+    fn test_capturable_executables_basename() {
+        let classifier = EncounterableResourcePathClassifier::default();
+        let resources = ResourcesCollection::new(vec![], &classifier, &None);
+        let capturable = |path: &str| {
+            resources
+                .path_classified(path)
+                .flags
+                .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE)
+        };
+        assert!(capturable("/repo/scripts/report.surveilr[json].sh"));
+        assert!(!capturable("/repo/surveilr[json]/notes.md"));
+        assert!(!capturable("/repo/surveilr[json]/build.sh"));
+
+        // a user's rule anchored on a directory still sees the whole path
+        let mut rules = EncounterableResourcePathRules::default();
+        rules.flaggables.push(PersistableFlaggableRegEx {
+            regex: r"/bin/.*\.sh$".to_string(),
+            flags: "CAPTURABLE_EXECUTABLE".to_string(),
+            nature: None,
+        });
+        let classifier = EncounterableResourcePathClassifier::from_path_rules(rules).unwrap();
+        let resources = ResourcesCollection::new(vec![], &classifier, &None);
+        assert!(resources
+            .path_classified("/repo/bin/report.sh")
+            .flags
+            .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE));
+        assert!(!resources
+            .path_classified("/repo/scripts/report.sh")
+            .flags
+            .contains(EncounterableResourceFlags::CAPTURABLE_EXECUTABLE));
+    }
+
+    #[test]
+    fn test_nature_directive() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            path
+        };
+        assert_eq!(
+            nature_directive(&script(
+                "a.sh",
+                "#!/bin/sh\n# surveilr-nature: application/json\necho '{}'\n"
+            )),
+            Some("application/json".to_string())
+        );
+        assert_eq!(
+            nature_directive(&script("b.ts", "// surveilr-nature: md\n")),
+            Some("md".to_string())
+        );
+        assert_eq!(
+            nature_directive(&script(
+                "c.sh",
+                "#!/bin/sh\necho\n# surveilr-nature: json\n"
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn test_content_acquisition() {
+        let manifest_only = ContentAcquisition::from_args(true, false, true);
+        assert_eq!(manifest_only, ContentAcquisition::ManifestOnly);
+        assert!(!manifest_only.text() && !manifest_only.binary() && !manifest_only.digest());
+
+        let text_only = ContentAcquisition::from_args(true, false, false);
+        assert!(text_only.text() && !text_only.binary() && text_only.digest());
+    }
+
+    #[test]
+    fn test_path_glob_matcher() {
+        let unanchored = PathGlob::matcher("*.log").unwrap();
+        assert!(unanchored.is_match("build.log"));
+        assert!(unanchored.is_match("logs/2024/build.log"));
+
+        let anchored = PathGlob::matcher("/*.log").unwrap();
+        assert!(anchored.is_match("build.log"));
+        assert!(!anchored.is_match("logs/build.log"));
+
+        let directory = PathGlob::matcher("target/").unwrap();
+        assert!(directory.is_match("target/debug/surveilr"));
+        assert!(directory.is_match("crates/x/target/release/x"));
+        assert!(!directory.is_match("targets.md"));
+
+        let nested = PathGlob::matcher("docs/*.md").unwrap();
+        assert!(nested.is_match("docs/README.md"));
+        assert!(!nested.is_match("docs/api/README.md"));
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
-use std::io::{Read, Write};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use deno_task_shell::execute_with_pipes;
 use deno_task_shell::parser::parse;
@@ -13,6 +13,9 @@ use subprocess::ExitStatus;
 use tokio::runtime::Runtime;
 use tokio::task::JoinHandle;
 
+/// How long a capturable executable may run before it's killed.
+pub const DEFAULT_EXECUTION_TIMEOUT: Duration = Duration::from_secs(60);
+
 lazy_static::lazy_static! {
     pub static ref RUNTIME: Runtime = Runtime::new().expect("Failed to create Tokio runtime for Capturable Executables");
 }
@@ -23,12 +26,14 @@ pub enum ShellStdIn {
     None,
     Text(String),
     Json(serde_json::Value),
+    // piped as-is, e.g. a resource's content which may not be UTF-8
+    Binary(Vec<u8>),
 }
 
 impl ShellStdIn {
     pub fn json(&self) -> Option<serde_json::Value> {
         match self {
-            ShellStdIn::None => None,
+            ShellStdIn::None | ShellStdIn::Binary(_) => None,
             ShellStdIn::Text(text) => Some(serde_json::from_str(text.as_str()).unwrap()),
             ShellStdIn::Json(value) => Some(value.clone()),
         }
@@ -39,11 +44,15 @@ impl ShellStdIn {
             ShellStdIn::None => None,
             ShellStdIn::Text(text) => Some(text.clone()),
             ShellStdIn::Json(value) => Some(serde_json::to_string_pretty(&value).unwrap()),
+            ShellStdIn::Binary(binary) => Some(String::from_utf8_lossy(binary).into_owned()),
         }
     }
 
     pub fn bytes(&self) -> Vec<u8> {
-        self.text().map(|s| s.into_bytes()).unwrap_or_default()
+        match self {
+            ShellStdIn::Binary(binary) => binary.clone(),
+            _ => self.text().map(|s| s.into_bytes()).unwrap_or_default(),
+        }
     }
 }
 
@@ -52,6 +61,8 @@ pub struct ShellResult {
     pub status: ExitStatus,
     pub stderr: String,
     pub stdout: String,
+    // how many times the execution was retried before this result
+    pub retries: u32,
 }
 
 #[allow(dead_code)]
@@ -113,56 +124,285 @@ impl ShellResult {
     }
 }
 
+/// Which environment variables an executable sees. Unless `clean` is set the
+/// child inherits the parent's environment; when it is, only the `allow`ed
+/// parent variables are passed through. `inject`ed variables are always set.
+#[derive(Debug, Clone, Default)]
+pub struct ShellEnvPolicy {
+    pub clean: bool,
+    pub allow: Vec<String>,
+    pub inject: Vec<(String, String)>,
+}
+
+impl ShellEnvPolicy {
+    /// Each of `env` is either `KEY=VAL` (injected) or just `KEY` (allowed
+    /// through from the parent's environment).
+    pub fn from_args(env: &[String], clean: bool) -> anyhow::Result<Self> {
+        let mut policy = ShellEnvPolicy {
+            clean,
+            ..Default::default()
+        };
+        for var in env {
+            match var.split_once('=') {
+                Some(("", _)) => {
+                    anyhow::bail!("[ShellEnvPolicy::from_args] empty variable name in `{var}`")
+                }
+                Some((key, value)) => policy.inject.push((key.to_string(), value.to_string())),
+                None if var.is_empty() => {
+                    anyhow::bail!("[ShellEnvPolicy::from_args] empty variable name")
+                }
+                None => policy.allow.push(var.to_string()),
+            }
+        }
+        Ok(policy)
+    }
+
+    pub fn is_inherited(&self) -> bool {
+        !self.clean && self.inject.is_empty()
+    }
+
+    /// The environment a child should see given the parent's `inherited` one.
+    pub fn applied(
+        &self,
+        inherited: impl IntoIterator<Item = (String, String)>,
+    ) -> HashMap<String, String> {
+        let mut env: HashMap<String, String> = inherited
+            .into_iter()
+            .filter(|(key, _)| !self.clean || self.allow.contains(key))
+            .collect();
+        env.extend(self.inject.iter().cloned());
+        env
+    }
+}
+
+/// Returned (wrapped in `anyhow::Error`) when an execution exceeded its time
+/// limit; the child has been killed and reaped by the time this is returned.
+#[derive(Debug, Clone)]
+pub struct ExecutionTimeout {
+    pub command: String,
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for ExecutionTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` did not finish within {} seconds and was killed",
+            self.command,
+            self.timeout.as_secs_f64()
+        )
+    }
+}
+
+impl std::error::Error for ExecutionTimeout {}
+
+/// How many times transient failures are retried; each retry waits twice as
+/// long as the previous one, starting with `initial_backoff`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(250),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32) -> Self {
+        RetryPolicy {
+            max_retries,
+            ..Default::default()
+        }
+    }
+
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+    }
+
+    /// Runs `attempt` until its outcome isn't `retryable` or the retries are
+    /// exhausted, returning the last outcome and how many retries it took.
+    pub fn retried<T, E>(
+        &self,
+        mut attempt: impl FnMut() -> Result<T, E>,
+        retryable: impl Fn(&Result<T, E>) -> bool,
+    ) -> (Result<T, E>, u32) {
+        let mut retries = 0;
+        loop {
+            let outcome = attempt();
+            if retries >= self.max_retries || !retryable(&outcome) {
+                return (outcome, retries);
+            }
+            std::thread::sleep(self.backoff(retries));
+            retries += 1;
+        }
+    }
+}
+
+// a non-zero exit or a timeout may be transient (e.g. a network blip) but a
+// command which isn't found or isn't executable won't be on the next attempt
+fn retryable_execution(outcome: &anyhow::Result<ShellResult>) -> bool {
+    match outcome {
+        Ok(shell_result) => {
+            !shell_result.success() && !matches!(shell_result.status, ExitStatus::Exited(126 | 127))
+        }
+        Err(err) => !err.chain().any(|cause| {
+            let io_err = match cause.downcast_ref::<subprocess::PopenError>() {
+                Some(subprocess::PopenError::IoError(io_err)) => Some(io_err),
+                _ => cause.downcast_ref::<std::io::Error>(),
+            };
+            io_err.is_some_and(|io_err| {
+                matches!(
+                    io_err.kind(),
+                    std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied
+                )
+            })
+        }),
+    }
+}
+
+/// Executes `command`, retrying (see `RetryPolicy`) non-zero exits, timeouts
+/// and other execution errors which may be transient; the result records how
+/// many retries it took and an error says how many were given up on.
 pub fn execute_subprocess(
     command: impl AsRef<std::ffi::OsStr>,
     std_in: ShellStdIn,
+    timeout: Option<Duration>,
+    env_policy: &ShellEnvPolicy,
+    retry_policy: &RetryPolicy,
+) -> anyhow::Result<ShellResult> {
+    let (outcome, retries) = retry_policy.retried(
+        || execute_subprocess_once(command.as_ref(), std_in.clone(), timeout, env_policy),
+        retryable_execution,
+    );
+    match outcome {
+        Ok(shell_result) => Ok(ShellResult {
+            retries,
+            ..shell_result
+        }),
+        Err(err) if retries > 0 => {
+            let message = format!("{} (gave up after {} retries)", err, retries);
+            Err(err.context(message))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn execute_subprocess_once(
+    command: &std::ffi::OsStr,
+    std_in: ShellStdIn,
+    timeout: Option<Duration>,
+    env_policy: &ShellEnvPolicy,
 ) -> anyhow::Result<ShellResult> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let timed_out = || ExecutionTimeout {
+        command: command.to_string_lossy().to_string(),
+        timeout: timeout.unwrap_or_default(),
+    };
+
     let mut exec = subprocess::Exec::cmd(command)
         .stdout(subprocess::Redirection::Pipe)
         .stderr(subprocess::Redirection::Pipe);
+    if !env_policy.is_inherited() {
+        let env: Vec<(String, String)> = env_policy.applied(std::env::vars()).into_iter().collect();
+        exec = exec.env_clear().env_extend(&env);
+    }
 
-    let stdin = std_in.text();
+    let stdin = match std_in {
+        ShellStdIn::None => None,
+        std_in => Some(std_in.bytes()),
+    };
     if stdin.is_some() {
         exec = exec.stdin(subprocess::Redirection::Pipe);
     }
 
     let mut popen = exec.popen()?;
 
-    if let Some(stdin_text) = stdin {
-        if let Some(mut stdin_pipe) = popen.stdin.take() {
-            stdin_pipe.write_all(stdin_text.as_bytes())?;
-            stdin_pipe.flush()?;
-            // `stdin_pipe` is dropped here when it goes out of scope, closing the stdin of the subprocess
-        } // else: no one is listening to the stdin of the subprocess, so we can't pipe anything to it
+    // stdin is written and stdout/stderr are read concurrently so that a chatty
+    // child can't block on a full pipe while we wait for it to exit
+    let mut communicator = popen.communicate_start(stdin);
+    if let Some(timeout) = timeout {
+        communicator = communicator.limit_time(timeout);
     }
-
-    let status = popen.wait()?;
-
-    let mut output = String::new();
-    popen.stdout.take().unwrap().read_to_string(&mut output)?;
-
-    let mut error_output = String::new();
-    match &mut popen.stderr.take() {
-        Some(stderr) => {
-            stderr.read_to_string(&mut error_output)?;
+    let (output, error_output) = match communicator.read_string() {
+        Ok((output, error_output)) => {
+            (output.unwrap_or_default(), error_output.unwrap_or_default())
         }
-        None => {}
-    }
+        Err(err) => {
+            // kill and then wait so that the child doesn't linger as a zombie
+            popen.kill()?;
+            popen.wait()?;
+            if err.kind() == std::io::ErrorKind::TimedOut {
+                return Err(timed_out().into());
+            }
+            return Err(err.into());
+        }
+    };
+
+    // the child may close its pipes and still keep running
+    let status = match deadline {
+        Some(deadline) => {
+            match popen.wait_timeout(deadline.saturating_duration_since(Instant::now()))? {
+                Some(status) => status,
+                None => {
+                    popen.kill()?;
+                    popen.wait()?;
+                    return Err(timed_out().into());
+                }
+            }
+        }
+        None => popen.wait()?,
+    };
 
     Ok(ShellResult {
         status,
         stdout: output,
         stderr: error_output,
+        retries: 0,
     })
 }
 
-pub trait ShellExecutive {
-    fn execute(&self, stdin: ShellStdIn) -> anyhow::Result<ShellResult>;
+// `Send + Sync` so executives can be run across threads with `--capture-exec-concurrency`
+pub trait ShellExecutive: Send + Sync {
+    /// Executes with the given stdin, killing the execution and returning
+    /// `ExecutionTimeout` if it runs longer than `timeout`.
+    fn execute(&self, stdin: ShellStdIn, timeout: Option<Duration>) -> anyhow::Result<ShellResult>;
 }
 
 impl ShellExecutive for String {
-    fn execute(&self, stdin: ShellStdIn) -> anyhow::Result<ShellResult> {
-        execute_subprocess(self, stdin)
+    fn execute(&self, stdin: ShellStdIn, timeout: Option<Duration>) -> anyhow::Result<ShellResult> {
+        execute_subprocess(
+            self,
+            stdin,
+            timeout,
+            &ShellEnvPolicy::default(),
+            &RetryPolicy::default(),
+        )
+    }
+}
+
+/// An executable (no arguments) run as a subprocess with its own environment.
+pub struct SubprocessExecutive {
+    pub command: String,
+    pub env_policy: ShellEnvPolicy,
+    pub retry_policy: RetryPolicy,
+}
+
+impl ShellExecutive for SubprocessExecutive {
+    fn execute(&self, stdin: ShellStdIn, timeout: Option<Duration>) -> anyhow::Result<ShellResult> {
+        execute_subprocess(
+            &self.command,
+            stdin,
+            timeout,
+            &self.env_policy,
+            &self.retry_policy,
+        )
     }
 }
 
@@ -181,6 +421,7 @@ pub struct DenoTaskShellExecutive {
     // An optional working directory to execute commands in (defaults to env::current_dir).
     pub cwd: PathBuf,
     // An optional identity if we need to persist the output
+    #[allow(dead_code)] // persisted outputs are identified by their resource instead
     pub identity: Option<String>,
 }
 
@@ -212,7 +453,14 @@ impl DenoTaskShellExecutive {
         }
     }
 
-    pub fn _cwd(&mut self, path: &std::path::Path) -> &mut Self {
+    pub fn env_policy(mut self, policy: &ShellEnvPolicy) -> Self {
+        if !policy.is_inherited() {
+            self.env_vars = policy.applied(std::mem::take(&mut self.env_vars));
+        }
+        self
+    }
+
+    pub fn cwd(mut self, path: &std::path::Path) -> Self {
         self.cwd = path.to_path_buf();
         self
     }
@@ -230,7 +478,7 @@ impl ShellExecutive for DenoTaskShellExecutive {
     ///
     /// * `command` - A string slice that holds the command to be executed.
     /// * `stdin_bytes` - A vector of bytes that will be written to the command's
-    ///    standard input.
+    ///   standard input.
     ///
     /// # Returns
     ///
@@ -244,7 +492,11 @@ impl ShellExecutive for DenoTaskShellExecutive {
     /// let (exit_code, stdout, stderr) = supplier.result("echo Hello", Default::default());
     /// assert_eq!(stdout, "Hello\n");
     /// ```
-    fn execute(&self, ce_stdin: ShellStdIn) -> anyhow::Result<ShellResult> {
+    fn execute(
+        &self,
+        ce_stdin: ShellStdIn,
+        timeout: Option<Duration>,
+    ) -> anyhow::Result<ShellResult> {
         fn get_output_writer_and_handle() -> (ShellPipeWriter, JoinHandle<String>) {
             let (reader, writer) = pipe();
             let handle = reader.pipe_to_string_handle();
@@ -267,43 +519,116 @@ impl ShellExecutive for DenoTaskShellExecutive {
                     state
                         .apply_env_var("INIT_CWD", self.cwd.to_string_lossy().to_string().as_str());
 
-                    let status = local_set
-                        .run_until(execute_with_pipes(list, state, stdin, stdout, stderr))
+                    // cancelling the shell's token kills (and waits for) its child processes
+                    let token = state.token().clone();
+                    let (status, timed_out) = local_set
+                        .run_until(async move {
+                            let execution = execute_with_pipes(list, state, stdin, stdout, stderr);
+                            tokio::pin!(execution);
+                            match timeout {
+                                Some(timeout) => tokio::select! {
+                                    status = &mut execution => (status, false),
+                                    _ = tokio::time::sleep(timeout) => {
+                                        token.cancel();
+                                        (execution.await, true)
+                                    }
+                                },
+                                None => (execution.await, false),
+                            }
+                        })
                         .await;
 
                     let stderr = stderr_handle.await.unwrap();
                     let stdout = stdout_handle.await.unwrap();
 
+                    if timed_out {
+                        return Err(ExecutionTimeout {
+                            command: self.command.clone(),
+                            timeout: timeout.unwrap_or_default(),
+                        }
+                        .into());
+                    }
+
                     Ok(ShellResult {
                         status: ExitStatus::Exited(status as u32),
                         stderr,
                         stdout,
+                        retries: 0,
                     })
                 }
                 Err(err) => Ok(ShellResult {
                     status: ExitStatus::Undetermined,
                     stderr: format!("{err:?}"),
                     stdout: String::new(),
+                    retries: 0,
                 }),
             }
         })
     }
 }
 
+/// Runs each `(executive, stdin, timeout)` across up to `concurrency` threads.
+/// The results are in the order of `executions`, whichever finished first;
+/// `None` for the executions of a worker which panicked.
+pub fn execute_concurrently(
+    executions: &[(&dyn ShellExecutive, ShellStdIn, Duration)],
+    concurrency: usize,
+) -> Vec<Option<anyhow::Result<ShellResult>>> {
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let next = &next;
+    let executed: Vec<(usize, anyhow::Result<ShellResult>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..concurrency.clamp(1, executions.len().max(1)))
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut executed = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        let Some((executive, stdin, timeout)) = executions.get(index) else {
+                            break;
+                        };
+                        executed.push((index, executive.execute(stdin.clone(), Some(*timeout))));
+                    }
+                    executed
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+
+    let mut results: Vec<Option<anyhow::Result<ShellResult>>> =
+        executions.iter().map(|_| None).collect();
+    for (index, result) in executed {
+        results[index] = Some(result);
+    }
+    results
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
 
     use crate::shell::ShellExecutive;
 
+    use std::time::Duration;
+
+    use super::execute_concurrently;
+    use super::execute_subprocess;
     use super::DenoTaskShellExecutive;
+    use super::ExecutionTimeout;
+    use super::RetryPolicy;
+    use super::ShellEnvPolicy;
     use super::ShellStdIn;
 
     #[test]
     fn test_command_execution() {
         let shell_result_supplier =
             DenoTaskShellExecutive::new(r#"echo "Hello, world!" | cat"#.to_string(), None);
-        let result = shell_result_supplier.execute(ShellStdIn::None).unwrap();
+        let result = shell_result_supplier
+            .execute(ShellStdIn::None, None)
+            .unwrap();
 
         assert_eq!(result.status, subprocess::ExitStatus::Exited(0)); // Assuming 0 is the success code
         assert_eq!(result.stderr, ""); // Assuming no error message for a successful command
@@ -319,10 +644,195 @@ mod tests {
             .env_vars
             .insert("TEST_VAR".to_string(), "123".to_string());
 
-        let result = shell_result_supplier.execute(ShellStdIn::None).unwrap();
+        let result = shell_result_supplier
+            .execute(ShellStdIn::None, None)
+            .unwrap();
         assert_eq!(result.stdout.trim(), "123");
     }
 
+    #[test]
+    fn test_sequential_commands_with_env_and_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().canonicalize().unwrap();
+        let policy = ShellEnvPolicy::from_args(&["GREETING=hi".to_string()], false).unwrap();
+        let result = DenoTaskShellExecutive::new(
+            "echo $GREETING && pwd && exit 3 && echo unreachable".to_string(),
+            None,
+        )
+        .env_policy(&policy)
+        .cwd(&cwd)
+        .execute(ShellStdIn::None, None)
+        .unwrap();
+        assert_eq!(result.status, subprocess::ExitStatus::Exited(3));
+        assert_eq!(result.stdout, format!("hi\n{}\n", cwd.display()));
+    }
+
+    #[test]
+    fn test_execution_timeout() {
+        let shell_result_supplier = DenoTaskShellExecutive::new("sleep 10".to_string(), None);
+        let err = shell_result_supplier
+            .execute(ShellStdIn::None, Some(Duration::from_millis(200)))
+            .unwrap_err();
+        assert!(err.is::<ExecutionTimeout>());
+    }
+
+    #[test]
+    fn test_execute_concurrently() {
+        // the slowest execution comes first so it finishes last
+        let executives = [
+            DenoTaskShellExecutive::new("sleep 0.3 && echo first".to_string(), None),
+            DenoTaskShellExecutive::new("echo second && exit 3".to_string(), None),
+            DenoTaskShellExecutive::new("sleep 10".to_string(), None),
+            DenoTaskShellExecutive::new("echo fourth".to_string(), None),
+        ];
+        let executions: Vec<_> = executives
+            .iter()
+            .map(|executive| {
+                (
+                    executive as &dyn ShellExecutive,
+                    ShellStdIn::None,
+                    Duration::from_millis(1000),
+                )
+            })
+            .collect();
+        let results = execute_concurrently(&executions, 3);
+        assert_eq!(results.len(), 4);
+
+        let outcomes: Vec<_> = results
+            .into_iter()
+            .map(|result| match result.unwrap() {
+                Ok(shell_result) => Ok((shell_result.status, shell_result.stdout)),
+                Err(err) => Err(err.is::<ExecutionTimeout>()),
+            })
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                Ok((subprocess::ExitStatus::Exited(0), "first\n".to_string())),
+                Ok((subprocess::ExitStatus::Exited(3), "second\n".to_string())),
+                Err(true),
+                Ok((subprocess::ExitStatus::Exited(0), "fourth\n".to_string())),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_subprocess_execution_timeout() {
+        // `yes` never exits on its own and fills its stdout pipe
+        let err = execute_subprocess(
+            "yes",
+            ShellStdIn::None,
+            Some(Duration::from_millis(200)),
+            &Default::default(),
+            &Default::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ExecutionTimeout>().unwrap().command,
+            "yes"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_subprocess_binary_stdin() {
+        // non-UTF-8 bytes must reach the child unchanged (not lossily decoded)
+        let result = execute_subprocess(
+            "wc",
+            ShellStdIn::Binary(vec![0xff, 0xfe, b'a']),
+            None,
+            &Default::default(),
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(result.stdout.split_whitespace().nth(2), Some("3"));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let retry_policy = RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(1),
+        };
+        assert_eq!(retry_policy.backoff(2), Duration::from_millis(4));
+
+        let mut attempts = 0;
+        let (outcome, retries) = retry_policy.retried(
+            || {
+                attempts += 1;
+                if attempts < 3 {
+                    Err("busy")
+                } else {
+                    Ok(attempts)
+                }
+            },
+            |outcome| outcome == &Err("busy"),
+        );
+        assert_eq!((outcome, retries), (Ok(3), 2));
+
+        // permanent errors aren't retried and retries are bounded
+        let (outcome, retries) =
+            retry_policy.retried(|| Err::<(), _>("denied"), |outcome| outcome == &Err("busy"));
+        assert_eq!((outcome, retries), (Err("denied"), 0));
+        let (_, retries) = retry_policy.retried(|| Err::<(), _>("busy"), |_| true);
+        assert_eq!(retries, 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_subprocess_retries() {
+        let retry_policy = RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+        };
+        let result = execute_subprocess(
+            "false",
+            ShellStdIn::None,
+            None,
+            &Default::default(),
+            &retry_policy,
+        )
+        .unwrap();
+        assert_eq!((result.success(), result.retries), (false, 2));
+
+        // a missing executable won't appear on the next attempt
+        let err = execute_subprocess(
+            "/nonexistent/surveilr-capture.sh",
+            ShellStdIn::None,
+            None,
+            &Default::default(),
+            &retry_policy,
+        )
+        .unwrap_err();
+        assert!(!err.to_string().contains("gave up"));
+    }
+
+    #[test]
+    fn test_env_policy() {
+        let policy =
+            ShellEnvPolicy::from_args(&["KEEP".to_string(), "TOKEN=a=b".to_string()], true)
+                .unwrap();
+        let inherited = [("KEEP", "1"), ("DROP", "2"), ("TOKEN", "old")]
+            .map(|(key, value)| (key.to_string(), value.to_string()));
+        let mut env: Vec<_> = policy.applied(inherited.clone()).into_iter().collect();
+        env.sort();
+        assert_eq!(
+            env,
+            vec![
+                ("KEEP".to_string(), "1".to_string()),
+                ("TOKEN".to_string(), "a=b".to_string())
+            ]
+        );
+
+        let policy = ShellEnvPolicy::from_args(&["TOKEN=new".to_string()], false).unwrap();
+        let env = policy.applied(inherited);
+        assert_eq!(env.len(), 3);
+        assert_eq!(env["TOKEN"], "new");
+
+        assert!(ShellEnvPolicy::from_args(&["=value".to_string()], false).is_err());
+    }
+
     #[test]
     fn test_custom_command_handling() {
         // Implement this test based on how you're using custom commands
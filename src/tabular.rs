@@ -0,0 +1,83 @@
+const CANDIDATE_DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+/// Guesses the delimiter of delimiter-separated text (CSV, TSV, etc.) by
+/// counting the candidate delimiters found outside of quotes in the first
+/// line; returns `default` if none of the candidates appear.
+pub fn sniffed_delimiter(text: &str, default: u8) -> u8 {
+    let first_line = text.lines().next().unwrap_or_default();
+    let mut counts = [0usize; CANDIDATE_DELIMITERS.len()];
+    let mut in_quotes = false;
+    for byte in first_line.bytes() {
+        if byte == b'"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes {
+            if let Some(index) = CANDIDATE_DELIMITERS.iter().position(|d| *d == byte) {
+                counts[index] += 1;
+            }
+        }
+    }
+    match counts.iter().enumerate().max_by_key(|(_, count)| **count) {
+        Some((index, count)) if *count > 0 => CANDIDATE_DELIMITERS[index],
+        _ => default,
+    }
+}
+
+/// Splits the first line of delimiter-separated text into its (unquoted)
+/// fields; returns `None` if the text is empty.
+pub fn header_fields(text: &str, delimiter: u8) -> Option<Vec<String>> {
+    let first_line = text.lines().next()?.trim_start_matches('\u{feff}');
+    if first_line.is_empty() {
+        return None;
+    }
+
+    let delimiter = delimiter as char;
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = first_line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_sniffed_delimiter() {
+        assert_eq!(sniffed_delimiter("a,b,c\n1,2,3\n", b','), b',');
+        assert_eq!(sniffed_delimiter("a;b;c\n1;2;3\n", b','), b';');
+        assert_eq!(sniffed_delimiter("a\tb\tc\n", b','), b'\t');
+        assert_eq!(sniffed_delimiter("\"x,y\";b;c\n", b','), b';');
+        assert_eq!(sniffed_delimiter("single\n", b'\t'), b'\t');
+    }
+
+    #[test]
+    fn test_header_fields() {
+        assert_eq!(
+            header_fields("\u{feff}id,\"name, full\",\"say \"\"hi\"\"\"\n1,2,3", b','),
+            Some(vec![
+                "id".to_string(),
+                "name, full".to_string(),
+                "say \"hi\"".to_string()
+            ])
+        );
+        assert_eq!(
+            header_fields("a\tb\r\n", b'\t'),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(header_fields("", b','), None);
+    }
+}
@@ -0,0 +1,192 @@
+use roxmltree::{Document, Node, ParsingOptions};
+use serde_json::{Map, Value as JsonValue};
+
+/// A named path from `--xml-select name=/root/elem`; each step is an element's
+/// local name (or `*` for any element) and a final `@name` step selects that
+/// attribute of the matching elements instead of their text.
+pub struct XmlSelect {
+    pub name: String,
+    steps: Vec<String>,
+    attr: Option<String>,
+}
+
+impl XmlSelect {
+    pub fn parse(name: &str, select: &str) -> Result<XmlSelect, String> {
+        let path = select.trim().strip_prefix('/').ok_or_else(|| {
+            format!("invalid XML path `{select}` for `{name}`: must start with `/`")
+        })?;
+        let mut steps: Vec<String> = path.split('/').map(String::from).collect();
+        let attr = match steps.last() {
+            Some(last) if last.starts_with('@') => steps.pop().map(|attr| attr[1..].to_string()),
+            _ => None,
+        };
+        if steps.is_empty()
+            || attr.as_ref().is_some_and(|attr| attr.is_empty())
+            || steps
+                .iter()
+                .any(|step| step.is_empty() || step.starts_with('@'))
+        {
+            return Err(format!("invalid XML path `{select}` for `{name}`"));
+        }
+        Ok(XmlSelect {
+            name: name.to_string(),
+            steps,
+            attr,
+        })
+    }
+
+    fn matches(step: &str, node: &Node) -> bool {
+        node.is_element() && (step == "*" || node.tag_name().name() == step)
+    }
+
+    // text is whitespace-collapsed like HTML selections since it's usually indented markup
+    fn values(&self, document: &Document) -> Vec<String> {
+        let root = document.root_element();
+        let mut nodes = match Self::matches(&self.steps[0], &root) {
+            true => vec![root],
+            false => Vec::new(),
+        };
+        for step in &self.steps[1..] {
+            nodes = nodes
+                .iter()
+                .flat_map(|node| node.children())
+                .filter(|child| Self::matches(step, child))
+                .collect();
+        }
+        nodes
+            .iter()
+            .filter_map(|node| match &self.attr {
+                Some(attr) => node.attribute(attr.as_str()).map(String::from),
+                None => Some(
+                    node.descendants()
+                        .filter(Node::is_text)
+                        .filter_map(|text| text.text())
+                        .flat_map(str::split_whitespace)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+            })
+            .collect()
+    }
+}
+
+/// Parses the XML and returns what should be merged into `elaboration`: the
+/// `width`, `height` and `viewBox` of the root element as `svg` for SVGs and
+/// each selection by name as `xml_select` (`null` if nothing matched, a string
+/// for a single match or an array of strings when several matched). Malformed
+/// XML is returned as `xml_parse_error` instead.
+pub fn elaboration(xml: &str, svg: bool, selects: &[XmlSelect]) -> Map<String, JsonValue> {
+    // SVGs commonly carry a DOCTYPE, entities are still limited by roxmltree
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let mut elaboration = Map::new();
+    let document = match Document::parse_with_options(xml, options) {
+        Ok(document) => document,
+        Err(err) => {
+            elaboration.insert("xml_parse_error".to_string(), err.to_string().into());
+            return elaboration;
+        }
+    };
+    if svg {
+        let root = document.root_element();
+        let attrs: Map<String, JsonValue> = ["width", "height", "viewBox"]
+            .iter()
+            .map(|attr| (attr.to_string(), root.attribute(*attr).into()))
+            .collect();
+        elaboration.insert("svg".to_string(), JsonValue::Object(attrs));
+    }
+    if !selects.is_empty() {
+        let selected: Map<String, JsonValue> = selects
+            .iter()
+            .map(|select| {
+                let mut values = select.values(&document);
+                let value = match values.len() {
+                    0 => JsonValue::Null,
+                    1 => JsonValue::String(values.remove(0)),
+                    _ => JsonValue::from(values),
+                };
+                (select.name.clone(), value)
+            })
+            .collect();
+        elaboration.insert("xml_select".to_string(), JsonValue::Object(selected));
+    }
+    elaboration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn selects(pairs: &[(&str, &str)]) -> Vec<XmlSelect> {
+        pairs
+            .iter()
+            .map(|(name, select)| XmlSelect::parse(name, select).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_svg_attributes() {
+        let svg = r#"<?xml version="1.0"?>
+            <!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
+            <svg xmlns="http://www.w3.org/2000/svg" width="24" viewBox="0 0 24 24">
+                <title>Icon</title>
+            </svg>"#;
+        assert_eq!(
+            JsonValue::Object(elaboration(svg, true, &selects(&[("title", "/svg/title")]))),
+            json!({
+                "svg": { "width": "24", "height": null, "viewBox": "0 0 24 24" },
+                "xml_select": { "title": "Icon" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_xml_select() {
+        let xml = r#"<catalog version="2">
+                <book id="a"><title>
+                    One   Title
+                </title></book>
+                <book id="b"><title>Two</title></book>
+            </catalog>"#;
+        let selected = elaboration(
+            xml,
+            false,
+            &selects(&[
+                ("version", "/catalog/@version"),
+                ("titles", "/catalog/book/title"),
+                ("ids", "/*/book/@id"),
+                ("missing", "/catalog/magazine"),
+            ]),
+        );
+        assert_eq!(
+            JsonValue::Object(selected),
+            json!({
+                "xml_select": {
+                    "version": "2",
+                    "titles": ["One Title", "Two"],
+                    "ids": ["a", "b"],
+                    "missing": null
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_malformed_xml() {
+        let parsed = elaboration("<catalog><book></catalog>", true, &[]);
+        assert!(parsed.get("xml_parse_error").is_some());
+        assert!(parsed.get("svg").is_none());
+    }
+
+    #[test]
+    fn test_invalid_path() {
+        assert!(XmlSelect::parse("bad", "catalog/book").is_err());
+        assert!(XmlSelect::parse("bad", "/catalog//book").is_err());
+        assert!(XmlSelect::parse("bad", "/catalog/@id/book").is_err());
+        assert!(XmlSelect::parse("bad", "/catalog/@").is_err());
+    }
+}
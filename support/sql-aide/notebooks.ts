@@ -433,6 +433,16 @@ export class ConstructionSqlNotebook<EmitContext extends SQLa.SqlEmitContext>
             "Ingest the content for md, mdx, html, json, jsonc, puml, txt, toml, and yml extensions. Assume the nature is the same as the extension.",
           created_at,
         }, options),
+        urIngestPathMatchRule.insertDML({
+          ur_ingest_resource_path_match_rule_id,
+          namespace,
+          regex: "\\.(?P<nature>eml|msg)$",
+          flags: "CONTENT_ACQUIRABLE",
+          nature: "?P<nature>", // should be same as src/resource.rs::PFRE_READ_NATURE_FROM_REGEX
+          description:
+            "Ingest the content for eml (RFC 822) and msg (Outlook) email messages. Assume the nature is the same as the extension.",
+          created_at,
+        }, options),
         urIngestPathMatchRule.insertDML({
           ur_ingest_resource_path_match_rule_id,
           namespace,
@@ -452,6 +462,26 @@ export class ConstructionSqlNotebook<EmitContext extends SQLa.SqlEmitContext>
             "Any entry with surveilr-SQL in the path will be treated as a capturable SQL executable and allow execution of the SQL",
           created_at,
         }, options),
+        urIngestPathMatchRule.insertDML({
+          ur_ingest_resource_path_match_rule_id,
+          namespace,
+          regex: "\\.(?P<nature>csv|tsv)$",
+          flags: "CONTENT_ACQUIRABLE",
+          nature: "?P<nature>", // should be same as src/resource.rs::PFRE_READ_NATURE_FROM_REGEX
+          description:
+            "Ingest the content for csv and tsv (delimited tabular) files. Assume the nature is the same as the extension.",
+          created_at,
+        }, options),
+        urIngestPathMatchRule.insertDML({
+          ur_ingest_resource_path_match_rule_id,
+          namespace,
+          regex: "\\.(?P<nature>pdf)$",
+          flags: "CONTENT_ACQUIRABLE",
+          nature: "?P<nature>", // should be same as src/resource.rs::PFRE_READ_NATURE_FROM_REGEX
+          description:
+            "Ingest the content for pdf files so that their page text can be extracted. Assume the nature is the same as the extension.",
+          created_at,
+        }, options),
       ];
     };
 
@@ -510,7 +540,7 @@ export class ConstructionSqlNotebook<EmitContext extends SQLa.SqlEmitContext>
               ur_ingest_session_fs_path.ur_ingest_session_fs_path_id as ingest_session_fs_path_id,
               ur_ingest_session_fs_path.root_path AS ingest_session_root_fs_path,
               COUNT(ur_ingest_session_fs_path_entry.uniform_resource_id) AS total_file_count,
-              SUM(CASE WHEN uniform_resource.content IS NOT NULL THEN 1 ELSE 0 END) AS file_count_with_content,
+              SUM(CASE WHEN uniform_resource_deduped.content IS NOT NULL THEN 1 ELSE 0 END) AS file_count_with_content,
               SUM(CASE WHEN uniform_resource.frontmatter IS NOT NULL THEN 1 ELSE 0 END) AS file_count_with_frontmatter,
               MIN(uniform_resource.size_bytes) AS min_file_size_bytes,
               AVG(uniform_resource.size_bytes) AS average_file_size_bytes,
@@ -527,6 +557,8 @@ export class ConstructionSqlNotebook<EmitContext extends SQLa.SqlEmitContext>
               ur_ingest_session_fs_path_entry ON ur_ingest_session_fs_path.ur_ingest_session_fs_path_id = ur_ingest_session_fs_path_entry.ingest_fs_path_id
           LEFT JOIN
               uniform_resource ON ur_ingest_session_fs_path_entry.uniform_resource_id = uniform_resource.uniform_resource_id
+          LEFT JOIN
+              uniform_resource_deduped ON uniform_resource.uniform_resource_id = uniform_resource_deduped.uniform_resource_id
           GROUP BY
               device.device_id,
               ur_ingest_session.ur_ingest_session_id,
@@ -585,7 +617,7 @@ export class ConstructionSqlNotebook<EmitContext extends SQLa.SqlEmitContext>
               COALESCE(ur_ingest_session_task.ur_status, 'Ok') AS ur_status,
               COALESCE(uniform_resource.nature, 'UNKNOWN') AS nature,
               COUNT(ur_ingest_session_task.uniform_resource_id) AS total_file_count,
-              SUM(CASE WHEN uniform_resource.content IS NOT NULL THEN 1 ELSE 0 END) AS file_count_with_content,
+              SUM(CASE WHEN uniform_resource_deduped.content IS NOT NULL THEN 1 ELSE 0 END) AS file_count_with_content,
               SUM(CASE WHEN uniform_resource.frontmatter IS NOT NULL THEN 1 ELSE 0 END) AS file_count_with_frontmatter,
               MIN(uniform_resource.size_bytes) AS min_file_size_bytes,
               AVG(uniform_resource.size_bytes) AS average_file_size_bytes,
@@ -600,6 +632,8 @@ export class ConstructionSqlNotebook<EmitContext extends SQLa.SqlEmitContext>
               ur_ingest_session_task ON ur_ingest_session.ur_ingest_session_id = ur_ingest_session_task.ingest_session_id
           LEFT JOIN
               uniform_resource ON ur_ingest_session_task.uniform_resource_id = uniform_resource.uniform_resource_id
+          LEFT JOIN
+              uniform_resource_deduped ON uniform_resource.uniform_resource_id = uniform_resource_deduped.uniform_resource_id
           GROUP BY
               device.device_id,
               ur_ingest_session.ur_ingest_session_id,
@@ -667,6 +701,275 @@ export class ConstructionSqlNotebook<EmitContext extends SQLa.SqlEmitContext>
                ufs.ur_status,
                ufs.ur_diagnostics;`
   }
+
+  // note `once_` pragma means it must only be run once in the database
+  v003_once_uniformResourceEmailDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "uniform_resource_email" (
+          "uniform_resource_email_id" VARCHAR PRIMARY KEY NOT NULL,
+          "uniform_resource_id" VARCHAR NOT NULL,
+          "message_id" TEXT,
+          "from_address" TEXT,
+          "to_addresses" TEXT CHECK(json_valid(to_addresses) OR to_addresses IS NULL),
+          "subject" TEXT,
+          "sent_at" TIMESTAMPTZ,
+          "body_text" TEXT,
+          "elaboration" TEXT CHECK(json_valid(elaboration) OR elaboration IS NULL),
+          "created_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+          "created_by" TEXT DEFAULT 'UNKNOWN',
+          "updated_at" TIMESTAMPTZ,
+          "updated_by" TEXT,
+          "deleted_at" TIMESTAMPTZ,
+          "deleted_by" TEXT,
+          "activity_log" TEXT,
+          FOREIGN KEY("uniform_resource_id") REFERENCES "uniform_resource"("uniform_resource_id"),
+          UNIQUE("uniform_resource_id")
+      );
+      CREATE INDEX IF NOT EXISTS "idx_uniform_resource_email__from_address__sent_at" ON "uniform_resource_email"("from_address", "sent_at");`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v004_once_uniformResourceCompressionDictDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "uniform_resource_compression_dict" (
+          "uniform_resource_compression_dict_id" VARCHAR PRIMARY KEY NOT NULL,
+          "algorithm" TEXT NOT NULL,
+          "dictionary" BLOB NOT NULL,
+          "dictionary_digest" TEXT NOT NULL,
+          "samples_count" INTEGER NOT NULL,
+          "samples_size_bytes" INTEGER NOT NULL,
+          "compressed_size_bytes" INTEGER,
+          "compressed_with_dict_size_bytes" INTEGER,
+          "elaboration" TEXT CHECK(json_valid(elaboration) OR elaboration IS NULL),
+          "created_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+          "created_by" TEXT DEFAULT 'UNKNOWN',
+          "updated_at" TIMESTAMPTZ,
+          "updated_by" TEXT,
+          "deleted_at" TIMESTAMPTZ,
+          "deleted_by" TEXT,
+          "activity_log" TEXT,
+          UNIQUE("algorithm", "dictionary_digest")
+      );`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v005_once_uniformResourceSqliteDbDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "uniform_resource_sqlite_db" (
+          "uniform_resource_sqlite_db_id" VARCHAR PRIMARY KEY NOT NULL,
+          "uniform_resource_id" VARCHAR NOT NULL,
+          "tables_count" INTEGER,
+          "tables" TEXT CHECK(json_valid(tables) OR tables IS NULL),
+          "inspection_error" TEXT,
+          "elaboration" TEXT CHECK(json_valid(elaboration) OR elaboration IS NULL),
+          "created_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+          "created_by" TEXT DEFAULT 'UNKNOWN',
+          "updated_at" TIMESTAMPTZ,
+          "updated_by" TEXT,
+          "deleted_at" TIMESTAMPTZ,
+          "deleted_by" TEXT,
+          "activity_log" TEXT,
+          FOREIGN KEY("uniform_resource_id") REFERENCES "uniform_resource"("uniform_resource_id"),
+          UNIQUE("uniform_resource_id")
+      );`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v006_once_uniformResourceFrontmatterErrorDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      ALTER TABLE "uniform_resource" ADD COLUMN "frontmatter_error" TEXT;`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v007_once_uniformResourceContentCodecDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      ALTER TABLE "uniform_resource" ADD COLUMN "content_codec" TEXT;`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v008_once_urIngestSessionNatureRewriteDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "ur_ingest_session_nature_rewrite" (
+          "ur_ingest_session_nature_rewrite_id" VARCHAR PRIMARY KEY NOT NULL,
+          "ingest_session_id" VARCHAR NOT NULL,
+          "uri" TEXT NOT NULL,
+          "original_nature" TEXT,
+          "rewritten_nature" TEXT,
+          "rewrite_regex" TEXT NOT NULL,
+          "rewrite_replace" TEXT NOT NULL,
+          "elaboration" TEXT CHECK(json_valid(elaboration) OR elaboration IS NULL),
+          "created_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+          "created_by" TEXT DEFAULT 'UNKNOWN',
+          FOREIGN KEY("ingest_session_id") REFERENCES "ur_ingest_session"("ur_ingest_session_id"),
+          UNIQUE("ingest_session_id", "uri")
+      );
+      CREATE INDEX IF NOT EXISTS "idx_ur_ingest_session_nature_rewrite__uri" ON "ur_ingest_session_nature_rewrite"("uri");`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v009_once_contentChunkDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "content_chunk" (
+          "content_chunk_id" VARCHAR PRIMARY KEY NOT NULL,
+          "size_bytes" INTEGER NOT NULL,
+          "content" BLOB,
+          "content_codec" TEXT,
+          "created_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+          "created_by" TEXT DEFAULT 'UNKNOWN'
+      );`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v010_once_validationIssueDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "validation_issue" (
+          "validation_issue_id" VARCHAR PRIMARY KEY NOT NULL,
+          "content_digest" TEXT NOT NULL,
+          "uri" TEXT,
+          "schema_name" TEXT NOT NULL,
+          "issue_type" TEXT NOT NULL,
+          "instance_path" TEXT NOT NULL,
+          "message" TEXT NOT NULL,
+          "created_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+          "created_by" TEXT DEFAULT 'UNKNOWN',
+          UNIQUE("content_digest", "schema_name", "issue_type", "instance_path", "message")
+      );
+      CREATE INDEX IF NOT EXISTS "idx_validation_issue__content_digest" ON "validation_issue"("content_digest");`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v011_once_capturableExecRunDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "capturable_exec_run" (
+          "capturable_exec_run_id" VARCHAR PRIMARY KEY NOT NULL,
+          "ingest_session_id" VARCHAR NOT NULL,
+          "uri" TEXT NOT NULL,
+          "exit_status" TEXT NOT NULL,
+          "exit_code" INTEGER,
+          "stderr" TEXT,
+          "stdout_bytes" INTEGER NOT NULL,
+          "created_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+          "created_by" TEXT DEFAULT 'UNKNOWN',
+          FOREIGN KEY("ingest_session_id") REFERENCES "ur_ingest_session"("ur_ingest_session_id")
+      );`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v012_once_urSymlinkTargetDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      ALTER TABLE "uniform_resource" ADD COLUMN "symlink_target" TEXT;`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v013_once_urPathDigestDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      ALTER TABLE "uniform_resource" ADD COLUMN "path_digest" TEXT;`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v014_once_natureBindDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "nature_bind" (
+          "nature" TEXT PRIMARY KEY NOT NULL,
+          "bound_nature" TEXT NOT NULL,
+          "created_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+          "created_by" TEXT DEFAULT 'UNKNOWN'
+      );`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database
+  v015_once_urWalkRootDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      ALTER TABLE "uniform_resource" ADD COLUMN "walk_root" TEXT;
+      ALTER TABLE "uniform_resource" ADD COLUMN "path_rel" TEXT;`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database;
+  // `last_modified_at` was stored as `2024-05-01 08:30:00.500 UTC` before it
+  // became RFC 3339 with nanoseconds, rows which would then collide with ones
+  // already stored in the new format are left as they are
+  v016_once_urLastModifiedAtRfc3339() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      UPDATE OR IGNORE "uniform_resource"
+         SET "last_modified_at" = substr("last_modified_at", 1, 10) || 'T' || substr("last_modified_at", 12, 8) || '.' ||
+             substr(CASE WHEN substr("last_modified_at", 20, 1) = '.' THEN substr("last_modified_at", 21, length("last_modified_at") - 24) ELSE '' END || '000000000', 1, 9) || 'Z'
+       WHERE "last_modified_at" LIKE '____-__-__ __:__:__% UTC';`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database;
+  // `admin merge --dedupe-content` moves content into a single row per digest
+  // (along with its codec) and keeps which devices' resources share it
+  v017_once_urContentDedupeDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "uniform_resource_content" (
+          "content_digest" TEXT PRIMARY KEY NOT NULL,
+          "content" BLOB,
+          "content_codec" TEXT,
+          "size_bytes" INTEGER
+      );
+      CREATE TABLE IF NOT EXISTS "uniform_resource_content_device" (
+          "content_digest" TEXT NOT NULL,
+          "device_id" VARCHAR NOT NULL,
+          "uniform_resource_id" VARCHAR NOT NULL,
+          FOREIGN KEY("content_digest") REFERENCES "uniform_resource_content"("content_digest"),
+          FOREIGN KEY("device_id") REFERENCES "device"("device_id"),
+          FOREIGN KEY("uniform_resource_id") REFERENCES "uniform_resource"("uniform_resource_id"),
+          UNIQUE("content_digest", "device_id", "uniform_resource_id")
+      );
+      CREATE VIEW IF NOT EXISTS "uniform_resource_deduped" AS
+          SELECT ur.uniform_resource_id, ur.device_id, ur.uri, ur.nature, ur.content_digest,
+                 COALESCE(ur.content, urc.content) AS content,
+                 CASE WHEN ur.content IS NULL THEN urc.content_codec ELSE ur.content_codec END AS content_codec,
+                 ur.size_bytes
+            FROM uniform_resource ur
+       LEFT JOIN uniform_resource_content urc ON urc.content_digest = ur.content_digest;`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database;
+  // `admin merge` records each merged candidate by the digest of its file so
+  // `--append` can skip it however it's named the next time
+  v018_once_mergedSourceDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      CREATE TABLE IF NOT EXISTS "merged_source" (
+          "source_digest" TEXT PRIMARY KEY NOT NULL,
+          "source_fs_path" TEXT NOT NULL,
+          "merged_at" TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+      );`;
+  }
+
+  // note `once_` pragma means it must only be run once in the database;
+  // a resource only shares the deduped content `admin merge --dedupe-content`
+  // moved out of it, not whatever content has the same digest (which isn't
+  // always of the stored bytes, e.g. with `--content-text-trim`)
+  v019_once_urContentDedupedViewDDL() {
+    // deno-fmt-ignore
+    return this.nbh.SQL`
+      DROP VIEW IF EXISTS "uniform_resource_deduped";
+      CREATE VIEW IF NOT EXISTS "uniform_resource_deduped" AS
+          SELECT ur.uniform_resource_id, ur.device_id, ur.uri, ur.nature, ur.content_digest,
+                 COALESCE(ur.content, urc.content) AS content,
+                 CASE WHEN ur.content IS NULL THEN urc.content_codec ELSE ur.content_codec END AS content_codec,
+                 ur.size_bytes
+            FROM uniform_resource ur
+       LEFT JOIN uniform_resource_content_device urcd ON urcd.uniform_resource_id = ur.uniform_resource_id
+       LEFT JOIN uniform_resource_content urc ON urc.content_digest = urcd.content_digest;`;
+  }
 }
 
 /**